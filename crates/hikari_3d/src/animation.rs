@@ -0,0 +1,557 @@
+use hikari_asset::{Asset, AssetPool, Handle, LoadContext, Loader, Saver};
+use hikari_math::{Mat4, Quat, Transform, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::{processing, Scene, SceneNode};
+
+/// How values are computed between two keyframes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    Linear,
+    /// Holds the value of the previous keyframe
+    Step,
+    /// Hermite spline through the keyframes, using the tangents stored alongside every value
+    CubicSpline,
+}
+
+/// Values of an animated property, one per keyframe.
+/// With [`Interpolation::CubicSpline`] every keyframe has three values: the in tangent, the value and the out tangent
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Keyframes {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Channel {
+    /// Index of the animated node in [`Scene::nodes`]
+    pub node: usize,
+    pub interpolation: Interpolation,
+    /// Keyframe times in seconds, in ascending order
+    pub times: Vec<f32>,
+    pub keyframes: Keyframes,
+}
+
+/// Animation of the nodes of a [`Scene`], imported from glTF
+#[derive(Clone, Debug, Default, Serialize, Deserialize, type_uuid::TypeUuid)]
+#[uuid = "5c0b6f0e-8f3a-4d7e-b2a9-3e61d4c8a7f5"]
+#[serde(default)]
+pub struct AnimationClip {
+    pub name: String,
+    /// Length of the clip in seconds
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+impl Asset for AnimationClip {
+    type Settings = ();
+}
+
+pub const SUPPORTED_ANIMATION_EXTENSIONS: [&'static str; 1] = ["hanim"];
+
+pub struct AnimationClipLoader;
+
+impl Loader for AnimationClipLoader {
+    fn load(&self, context: &mut LoadContext) -> anyhow::Result<()> {
+        let clip: AnimationClip = serde_yaml::from_reader(context.reader())?;
+
+        context.set_asset(clip);
+        Ok(())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &SUPPORTED_ANIMATION_EXTENSIONS
+    }
+}
+
+impl Saver for AnimationClipLoader {
+    fn extensions(&self) -> &[&str] {
+        &SUPPORTED_ANIMATION_EXTENSIONS
+    }
+
+    fn save(&self, context: &mut hikari_asset::SaveContext, writer: &mut dyn std::io::Write) -> anyhow::Result<()> {
+        serde_yaml::to_writer(writer, context.get_asset::<AnimationClip>())?;
+
+        Ok(())
+    }
+}
+
+trait Interpolate: Copy + std::ops::Add<Output = Self> + std::ops::Mul<f32, Output = Self> {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+    /// Spline results aren't guaranteed to be valid values, e.g. rotations need to be renormalized
+    fn fix_up(self) -> Self {
+        self
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a.lerp(b, t)
+    }
+}
+
+impl Interpolate for Quat {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a.slerp(b, t)
+    }
+    fn fix_up(self) -> Self {
+        self.normalize()
+    }
+}
+
+fn sample_keyframes<T: Interpolate>(times: &[f32], values: &[T], interpolation: Interpolation, time: f32) -> Option<T> {
+    let stride = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+    let offset = if interpolation == Interpolation::CubicSpline { 1 } else { 0 };
+    let value = |keyframe: usize| values.get(keyframe * stride + offset).copied();
+
+    let next = times.partition_point(|&keyframe_time| keyframe_time <= time);
+    if next == 0 {
+        return value(0);
+    }
+    if next == times.len() {
+        return value(times.len() - 1);
+    }
+
+    let prev = next - 1;
+    let dt = times[next] - times[prev];
+    let t = (time - times[prev]) / dt;
+
+    match interpolation {
+        Interpolation::Step => value(prev),
+        Interpolation::Linear => Some(T::lerp(value(prev)?, value(next)?, t)),
+        Interpolation::CubicSpline => {
+            let v0 = value(prev)?;
+            let out0 = *values.get(prev * 3 + 2)?;
+            let in1 = *values.get(next * 3)?;
+            let v1 = value(next)?;
+
+            let t2 = t * t;
+            let t3 = t2 * t;
+            let value = v0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+                + out0 * ((t3 - 2.0 * t2 + t) * dt)
+                + v1 * (-2.0 * t3 + 3.0 * t2)
+                + in1 * ((t3 - t2) * dt);
+
+            Some(value.fix_up())
+        }
+    }
+}
+
+/// Local transforms of every node of a [`Scene`]
+#[derive(Clone, Debug, Default)]
+pub struct Pose {
+    pub locals: Vec<Transform>,
+}
+
+impl Pose {
+    /// The pose the nodes were authored in
+    pub fn rest(nodes: &[SceneNode]) -> Self {
+        Self {
+            locals: nodes.iter().map(|node| node.transform).collect(),
+        }
+    }
+    /// Moves every node `weight` of the way towards `other`, 0 keeps this pose and 1 copies `other`
+    pub fn blend(&mut self, other: &Pose, weight: f32) {
+        for (local, other) in self.locals.iter_mut().zip(&other.locals) {
+            local.position = local.position.lerp(other.position, weight);
+            local.rotation = local.rotation.slerp(other.rotation, weight);
+            local.scale = local.scale.lerp(other.scale, weight);
+        }
+    }
+    /// Computes the transform of every node relative to the scene root
+    pub fn global_matrices(&self, nodes: &[SceneNode], roots: &[usize]) -> Vec<Mat4> {
+        let mut globals = vec![Mat4::IDENTITY; self.locals.len()];
+
+        let mut stack: Vec<_> = roots.iter().map(|&root| (root, Mat4::IDENTITY)).collect();
+        while let Some((node, parent)) = stack.pop() {
+            let global = parent * self.locals[node].get_matrix();
+            globals[node] = global;
+
+            stack.extend(nodes[node].children.iter().map(|&child| (child, global)));
+        }
+
+        globals
+    }
+    /// Computes the skinning matrix of every joint of `skin`, relative to the mesh of `mesh_node`.
+    /// The instance transform of the mesh is applied after skinning, so the palette undoes it
+    pub fn joint_palette(&self, scene: &Scene, skin: &Skin, mesh_node: usize, palette: &mut Vec<Mat4>) {
+        let globals = self.global_matrices(&scene.nodes, &scene.roots);
+        let mesh_correction = processing::left_handed_mesh_correction().get_matrix();
+        let to_mesh = (globals[mesh_node] * mesh_correction).inverse();
+
+        palette.clear();
+        palette.extend(
+            skin.joints
+                .iter()
+                .zip(&skin.inverse_bind_matrices)
+                .map(|(&joint, inverse_bind)| to_mesh * globals[joint] * mesh_correction * *inverse_bind),
+        );
+    }
+}
+
+impl AnimationClip {
+    /// Overwrites the animated properties of `pose` with their values at `time`
+    pub fn sample(&self, time: f32, pose: &mut Pose) {
+        let time = time.clamp(0.0, self.duration);
+
+        for channel in &self.channels {
+            let Some(local) = pose.locals.get_mut(channel.node) else { continue };
+            let times = &channel.times;
+            let interpolation = channel.interpolation;
+
+            match &channel.keyframes {
+                Keyframes::Translation(values) => {
+                    if let Some(position) = sample_keyframes(times, values, interpolation, time) {
+                        local.position = position;
+                    }
+                }
+                Keyframes::Rotation(values) => {
+                    if let Some(rotation) = sample_keyframes(times, values, interpolation, time) {
+                        local.rotation = rotation;
+                    }
+                }
+                Keyframes::Scale(values) => {
+                    if let Some(scale) = sample_keyframes(times, values, interpolation, time) {
+                        local.scale = scale;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Joints deforming a skinned mesh
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Skin {
+    pub name: String,
+    /// Indices of the joint nodes in [`Scene::nodes`], vertices refer to joints by their position in this list
+    pub joints: Vec<usize>,
+    /// Transforms from the space of the mesh to the space of each joint when the mesh was bound
+    pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+/// A clip played by an [`Animator`]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimationLayer {
+    pub clip: Handle<AnimationClip>,
+    /// Playback position in seconds
+    pub time: f32,
+    pub speed: f32,
+    pub looping: bool,
+    /// Influence of this layer relative to the other layers
+    pub weight: f32,
+    /// Change of weight per second, used for cross fades
+    pub fade: f32,
+}
+
+impl AnimationLayer {
+    pub fn new(clip: Handle<AnimationClip>) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+            weight: 1.0,
+            fade: 0.0,
+        }
+    }
+    fn advance(&mut self, dt: f32, duration: f32) {
+        self.time += dt * self.speed;
+        if self.looping && duration > 0.0 {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+        }
+
+        self.weight = (self.weight + self.fade * dt).clamp(0.0, 1.0);
+    }
+    fn faded_out(&self) -> bool {
+        self.fade < 0.0 && self.weight == 0.0
+    }
+}
+
+/// Samples [`Pose`]s of every layer and blends them by weight into `pose`, which starts out as the rest pose
+fn blend_layers<'a>(layers: impl IntoIterator<Item = (&'a AnimationClip, f32, f32)>, pose: &mut Pose) {
+    let rest = pose.clone();
+    let mut sampled = rest.clone();
+    let mut total_weight = 0.0;
+
+    for (clip, time, weight) in layers {
+        if weight <= 0.0 {
+            continue;
+        }
+
+        sampled.locals.clone_from(&rest.locals);
+        clip.sample(time, &mut sampled);
+
+        // Blending every layer by its share of the weight so far averages all of them
+        total_weight += weight;
+        pose.blend(&sampled, weight / total_weight);
+    }
+}
+
+/// Plays [`AnimationClip`]s on the skin of a mesh imported from a [`Scene`] and computes its joint palette for GPU skinning
+#[derive(Clone, type_uuid::TypeUuid)]
+#[uuid = "b5a9f2d4-7c1e-4f0a-9d63-2e8c5b1f7a30"]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Animator {
+    pub scene: Handle<Scene>,
+    /// Index in [`Scene::skins`]
+    pub skin: usize,
+    /// Index in [`Scene::nodes`] of the node the skinned mesh belongs to
+    pub node: usize,
+    layers: Vec<AnimationLayer>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pose: Pose,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    palette: Vec<Mat4>,
+}
+
+impl Animator {
+    pub fn new(scene: Handle<Scene>, skin: usize, node: usize) -> Self {
+        Self {
+            scene,
+            skin,
+            node,
+            layers: Vec::new(),
+            pose: Pose::default(),
+            palette: Vec::new(),
+        }
+    }
+    /// Stops all layers and plays `clip` from the start
+    pub fn play(&mut self, clip: Handle<AnimationClip>) {
+        self.layers.clear();
+        self.layers.push(AnimationLayer::new(clip));
+    }
+    /// Starts playing `clip` while fading out all other layers over `duration` seconds
+    pub fn cross_fade(&mut self, clip: Handle<AnimationClip>, duration: f32) {
+        if duration <= 0.0 {
+            return self.play(clip);
+        }
+
+        for layer in &mut self.layers {
+            layer.fade = -1.0 / duration;
+        }
+        self.layers.push(AnimationLayer {
+            weight: 0.0,
+            fade: 1.0 / duration,
+            ..AnimationLayer::new(clip)
+        });
+    }
+    pub fn stop(&mut self) {
+        self.layers.clear();
+    }
+    pub fn layers(&self) -> &[AnimationLayer] {
+        &self.layers
+    }
+    /// Layers can be added or adjusted directly to blend clips by fixed weights
+    pub fn layers_mut(&mut self) -> &mut Vec<AnimationLayer> {
+        &mut self.layers
+    }
+    /// Skinning matrices of every joint of the skin, in the order of [`Skin::joints`]
+    pub fn palette(&self) -> &[Mat4] {
+        &self.palette
+    }
+    /// Advances all layers by `dt` and recomputes the joint palette.
+    /// Does nothing until the scene is loaded, layers with clips which aren't loaded yet are skipped
+    pub fn update(&mut self, dt: f32, scenes: &AssetPool<Scene>, clips: &AssetPool<AnimationClip>) {
+        let Some(scene) = scenes.get(&self.scene) else { return };
+        let Some(skin) = scene.skins.get(self.skin) else { return };
+
+        for layer in &mut self.layers {
+            if let Some(clip) = clips.get(&layer.clip) {
+                layer.advance(dt, clip.duration);
+            }
+        }
+        self.layers.retain(|layer| !layer.faded_out());
+
+        self.pose = Pose::rest(&scene.nodes);
+        let layers = self
+            .layers
+            .iter()
+            .filter_map(|layer| Some((clips.get(&layer.clip)?, layer.time, layer.weight)));
+        blend_layers(layers, &mut self.pose);
+
+        self.pose.joint_palette(scene, skin, self.node, &mut self.palette);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hikari_math::Vec3;
+
+    use super::*;
+
+    fn clip(interpolation: Interpolation, keyframes: Keyframes) -> AnimationClip {
+        let times = match interpolation {
+            Interpolation::CubicSpline => vec![0.0, 2.0],
+            _ => vec![0.0, 1.0, 2.0],
+        };
+        AnimationClip {
+            name: "Test".into(),
+            duration: 2.0,
+            channels: vec![Channel {
+                node: 0,
+                interpolation,
+                times,
+                keyframes,
+            }],
+        }
+    }
+
+    fn sample_position(clip: &AnimationClip, time: f32) -> Vec3 {
+        let mut pose = Pose {
+            locals: vec![Transform::default()],
+        };
+        clip.sample(time, &mut pose);
+        pose.locals[0].position
+    }
+
+    #[test]
+    fn interpolation_modes() {
+        let positions = vec![Vec3::ZERO, Vec3::X, Vec3::new(3.0, 0.0, 0.0)];
+
+        let linear = clip(Interpolation::Linear, Keyframes::Translation(positions.clone()));
+        assert_eq!(sample_position(&linear, 0.5), Vec3::new(0.5, 0.0, 0.0));
+        assert_eq!(sample_position(&linear, 1.5), Vec3::new(2.0, 0.0, 0.0));
+        // Clamped to the duration of the clip
+        assert_eq!(sample_position(&linear, 5.0), Vec3::new(3.0, 0.0, 0.0));
+
+        let step = clip(Interpolation::Step, Keyframes::Translation(positions));
+        assert_eq!(sample_position(&step, 0.99), Vec3::ZERO);
+        assert_eq!(sample_position(&step, 1.0), Vec3::X);
+
+        // With tangents matching the slope the spline is a straight line
+        let slope = Vec3::new(0.5, 0.0, 0.0);
+        let cubic = clip(
+            Interpolation::CubicSpline,
+            Keyframes::Translation(vec![slope, Vec3::ZERO, slope, slope, Vec3::X, slope]),
+        );
+        assert!(sample_position(&cubic, 1.0).abs_diff_eq(Vec3::new(0.5, 0.0, 0.0), 1e-6));
+        assert!(sample_position(&cubic, 2.0).abs_diff_eq(Vec3::X, 1e-6));
+    }
+
+    #[test]
+    fn rotations_stay_normalized() {
+        let rotations = vec![Quat::IDENTITY, Quat::from_rotation_y(1.0), Quat::from_rotation_y(2.0)];
+        let clip = clip(Interpolation::Linear, Keyframes::Rotation(rotations));
+
+        let mut pose = Pose {
+            locals: vec![Transform::default()],
+        };
+        clip.sample(0.5, &mut pose);
+        assert!(pose.locals[0].rotation.abs_diff_eq(Quat::from_rotation_y(0.5), 1e-5));
+        assert!(pose.locals[0].rotation.is_normalized());
+    }
+
+    #[test]
+    fn blends_by_weight() {
+        let walk = clip(Interpolation::Linear, Keyframes::Translation(vec![Vec3::X; 3]));
+        let run = clip(Interpolation::Linear, Keyframes::Translation(vec![Vec3::Y; 3]));
+
+        let rest = Pose {
+            locals: vec![Transform::default()],
+        };
+
+        let mut pose = rest.clone();
+        blend_layers([(&walk, 0.0, 1.0), (&run, 0.0, 1.0)], &mut pose);
+        assert!(pose.locals[0].position.abs_diff_eq(Vec3::new(0.5, 0.5, 0.0), 1e-6));
+
+        let mut pose = rest.clone();
+        blend_layers([(&walk, 0.0, 3.0), (&run, 0.0, 1.0)], &mut pose);
+        assert!(pose.locals[0].position.abs_diff_eq(Vec3::new(0.75, 0.25, 0.0), 1e-6));
+
+        // Layers without weight don't contribute
+        let mut pose = rest;
+        blend_layers([(&walk, 0.0, 0.0)], &mut pose);
+        assert_eq!(pose.locals[0].position, Vec3::ZERO);
+    }
+
+    #[test]
+    fn layers_loop_and_fade() {
+        let mut clips = AssetPool::new();
+        let mut layer = AnimationLayer {
+            fade: -2.0,
+            ..AnimationLayer::new(clips.insert(AnimationClip::default()))
+        };
+
+        layer.advance(0.25, 1.0);
+        assert_eq!(layer.time, 0.25);
+        assert_eq!(layer.weight, 0.5);
+
+        layer.advance(1.0, 1.0);
+        assert_eq!(layer.time, 0.25);
+        assert!(layer.faded_out());
+
+        layer.looping = false;
+        layer.advance(2.0, 1.0);
+        assert_eq!(layer.time, 1.0);
+    }
+
+    fn node(name: &str, position: Vec3, children: Vec<usize>) -> SceneNode {
+        SceneNode {
+            name: name.into(),
+            transform: processing::left_handed_correction(Transform::from_position(position)),
+            children,
+            mesh: None,
+            camera: None,
+            light: None,
+            skin: None,
+        }
+    }
+
+    #[test]
+    fn joint_palette() {
+        // A mesh next to a two joint arm, positioned in right handed glTF space
+        let skin = Skin {
+            name: "Arm".into(),
+            joints: vec![1, 2],
+            inverse_bind_matrices: vec![
+                Mat4::from_translation(Vec3::new(0.0, 0.0, -1.0)),
+                Mat4::from_translation(Vec3::new(0.0, -1.0, -1.0)),
+            ],
+        };
+        let scene = Scene {
+            meshes: vec![],
+            cameras: vec![],
+            lights: vec![],
+            skins: vec![skin],
+            animations: vec![],
+            nodes: vec![
+                node("Mesh", Vec3::ZERO, vec![]),
+                node("Shoulder", Vec3::new(0.0, 0.0, 1.0), vec![2]),
+                node("Elbow", Vec3::new(0.0, 1.0, 0.0), vec![]),
+            ],
+            roots: vec![0, 1],
+        };
+
+        let mut scenes = AssetPool::new();
+        let mut clips = AssetPool::new();
+        let mut animator = Animator::new(scenes.insert(scene), 0, 0);
+
+        // Joints in their bind pose don't deform the mesh
+        animator.update(0.0, &scenes, &clips);
+        assert_eq!(animator.palette().len(), 2);
+        assert!(animator.palette().iter().all(|joint| joint.abs_diff_eq(Mat4::IDENTITY, 1e-6)));
+
+        // Moving the shoulder moves both joints
+        let raise = clips.insert(AnimationClip {
+            name: "Raise".into(),
+            duration: 1.0,
+            channels: vec![Channel {
+                node: 1,
+                interpolation: Interpolation::Linear,
+                times: vec![0.0, 1.0],
+                keyframes: Keyframes::Translation(vec![Vec3::new(0.0, 0.0, -1.0), Vec3::new(2.0, 0.0, -1.0)]),
+            }],
+        });
+        animator.play(raise);
+        animator.update(0.5, &scenes, &clips);
+
+        let moved = Mat4::from_translation(Vec3::X);
+        assert!(animator.palette().iter().all(|joint| joint.abs_diff_eq(moved, 1e-6)));
+    }
+}
@@ -0,0 +1,407 @@
+//! CPU encoders for the BC block compressed texture formats.
+//! Quality is traded for speed: endpoints are fit along the principal axis of each block and aren't refined further
+
+use rayon::prelude::*;
+
+use crate::config::Format;
+
+type Texel = [u8; 4];
+
+/// Compresses RGBA8 texels, row by row, into blocks of `format`.
+/// Texels of partial blocks at the right and bottom edge are filled by repeating the last column and row
+pub fn compress(format: Format, data: &[u8], width: u32, height: u32) -> anyhow::Result<Vec<u8>> {
+    let block_size = match format {
+        Format::BC1 | Format::BC1Srgb | Format::BC4 => 8,
+        Format::BC3 | Format::BC3Srgb | Format::BC5 | Format::BC7 | Format::BC7Srgb => 16,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "{:?} is not a block compressed format",
+                format
+            ))
+        }
+    };
+    if data.len() != (width * height * 4) as usize {
+        return Err(anyhow::anyhow!(
+            "Expected {} bytes of RGBA8 texels for a {}x{} image, got {}",
+            width * height * 4,
+            width,
+            height,
+            data.len()
+        ));
+    }
+
+    let blocks_x = width.div_ceil(4) as usize;
+    let blocks_y = height.div_ceil(4) as usize;
+    let mut output = vec![0; blocks_x * blocks_y * block_size];
+
+    output
+        .par_chunks_mut(blocks_x * block_size)
+        .enumerate()
+        .for_each(|(block_y, row)| {
+            for (block_x, block) in row.chunks_exact_mut(block_size).enumerate() {
+                let texels = fetch_block(data, width, height, block_x as u32, block_y as u32);
+                match format {
+                    Format::BC1 | Format::BC1Srgb => block.copy_from_slice(&encode_color(&texels)),
+                    Format::BC3 | Format::BC3Srgb => {
+                        block[..8].copy_from_slice(&encode_channel(&texels, 3));
+                        block[8..].copy_from_slice(&encode_color(&texels));
+                    }
+                    Format::BC4 => block.copy_from_slice(&encode_channel(&texels, 0)),
+                    Format::BC5 => {
+                        block[..8].copy_from_slice(&encode_channel(&texels, 0));
+                        block[8..].copy_from_slice(&encode_channel(&texels, 1));
+                    }
+                    _ => block.copy_from_slice(&encode_bc7(&texels)),
+                }
+            }
+        });
+
+    Ok(output)
+}
+
+fn fetch_block(data: &[u8], width: u32, height: u32, block_x: u32, block_y: u32) -> [Texel; 16] {
+    let mut texels = [[0; 4]; 16];
+    for (ix, texel) in texels.iter_mut().enumerate() {
+        let x = (block_x * 4 + ix as u32 % 4).min(width - 1);
+        let y = (block_y * 4 + ix as u32 / 4).min(height - 1);
+        let offset = ((y * width + x) * 4) as usize;
+        texel.copy_from_slice(&data[offset..offset + 4]);
+    }
+    texels
+}
+
+/// Mean and principal axis of the texels, considering the first `N` channels
+fn principal_axis<const N: usize>(texels: &[Texel; 16]) -> ([f32; N], [f32; N]) {
+    let mut mean = [0.0; N];
+    for texel in texels {
+        for c in 0..N {
+            mean[c] += texel[c] as f32 / 16.0;
+        }
+    }
+
+    let mut covariance = [[0.0; N]; N];
+    for texel in texels {
+        for i in 0..N {
+            for j in 0..N {
+                covariance[i][j] += (texel[i] as f32 - mean[i]) * (texel[j] as f32 - mean[j]);
+            }
+        }
+    }
+
+    // Power iteration, starting from the diagonal of the bounding box converges quickly for most blocks
+    let mut axis = [1.0; N];
+    for _ in 0..8 {
+        let mut next = [0.0; N];
+        for i in 0..N {
+            for j in 0..N {
+                next[i] += covariance[i][j] * axis[j];
+            }
+        }
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+
+    (mean, axis)
+}
+
+/// End points of the texels projected on their principal axis, from the lowest to the highest projection
+fn fit_endpoints<const N: usize>(texels: &[Texel; 16]) -> ([f32; N], [f32; N]) {
+    let (mean, axis) = principal_axis::<N>(texels);
+
+    let project = |texel: &Texel| {
+        (0..N)
+            .map(|c| (texel[c] as f32 - mean[c]) * axis[c])
+            .sum::<f32>()
+    };
+    let (min, max) = texels
+        .iter()
+        .map(project)
+        .fold((f32::MAX, f32::MIN), |(min, max), t| {
+            (min.min(t), max.max(t))
+        });
+
+    let point = |t: f32| std::array::from_fn(|c| (mean[c] + axis[c] * t).clamp(0.0, 255.0));
+    (point(min), point(max))
+}
+
+fn nearest<const N: usize>(texel: &Texel, palette: &[[f32; N]]) -> usize {
+    let distance = |color: &[f32; N]| {
+        (0..N)
+            .map(|c| (texel[c] as f32 - color[c]).powi(2))
+            .sum::<f32>()
+    };
+    (0..palette.len())
+        .min_by(|&a, &b| distance(&palette[a]).total_cmp(&distance(&palette[b])))
+        .unwrap()
+}
+
+fn pack_565(color: [f32; 3]) -> u16 {
+    let r = (color[0] * 31.0 / 255.0).round() as u16;
+    let g = (color[1] * 63.0 / 255.0).round() as u16;
+    let b = (color[2] * 31.0 / 255.0).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn unpack_565(color: u16) -> [f32; 3] {
+    let r = (color >> 11) & 31;
+    let g = (color >> 5) & 63;
+    let b = color & 31;
+    [
+        (r << 3 | r >> 2) as f32,
+        (g << 2 | g >> 4) as f32,
+        (b << 3 | b >> 2) as f32,
+    ]
+}
+
+/// BC1 color block in four color mode, which is also the color part of BC3
+fn encode_color(texels: &[Texel; 16]) -> [u8; 8] {
+    let (low, high) = fit_endpoints::<3>(texels);
+    let mut color0 = pack_565(high);
+    let mut color1 = pack_565(low);
+    if color0 < color1 {
+        std::mem::swap(&mut color0, &mut color1);
+    }
+
+    let mut indices = 0u32;
+    if color0 != color1 {
+        let c0 = unpack_565(color0);
+        let c1 = unpack_565(color1);
+        let palette = [
+            c0,
+            c1,
+            std::array::from_fn(|c| (2.0 * c0[c] + c1[c]) / 3.0),
+            std::array::from_fn(|c| (c0[c] + 2.0 * c1[c]) / 3.0),
+        ];
+        for (ix, texel) in texels.iter().enumerate() {
+            indices |= (nearest(texel, &palette) as u32) << (ix * 2);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[0..2].copy_from_slice(&color0.to_le_bytes());
+    block[2..4].copy_from_slice(&color1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+/// BC4 block of a single channel in eight value mode, used for BC3 alpha and both channels of BC5
+fn encode_channel(texels: &[Texel; 16], channel: usize) -> [u8; 8] {
+    let values = texels.map(|texel| [texel[channel], 0, 0, 0]);
+    let max = values.iter().map(|value| value[0]).max().unwrap();
+    let min = values.iter().map(|value| value[0]).min().unwrap();
+
+    let mut indices = 0u64;
+    if max != min {
+        let palette: [[f32; 1]; 8] = std::array::from_fn(|ix| match ix {
+            0 => [max as f32],
+            1 => [min as f32],
+            _ => [((8 - ix) as f32 * max as f32 + (ix - 1) as f32 * min as f32) / 7.0],
+        });
+        for (ix, value) in values.iter().enumerate() {
+            indices |= (nearest(value, &palette) as u64) << (ix * 3);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[0] = max;
+    block[1] = min;
+    block[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+const BC7_WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Quantizes an endpoint to 7 bits per channel plus a shared lowest bit, as used by BC7 mode 6
+fn quantize_bc7_endpoint(endpoint: [f32; 4]) -> ([u8; 4], u8) {
+    (0..2)
+        .map(|p_bit| {
+            let quantized =
+                endpoint.map(|v| ((v - p_bit as f32) / 2.0).round().clamp(0.0, 127.0) as u8);
+            let error: f32 = (0..4)
+                .map(|c| ((quantized[c] << 1 | p_bit) as f32 - endpoint[c]).powi(2))
+                .sum();
+            ((quantized, p_bit), error)
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap()
+        .0
+}
+
+/// BC7 mode 6, a single subset with RGBA endpoints and 4 bit indices
+fn encode_bc7(texels: &[Texel; 16]) -> [u8; 16] {
+    let (low, high) = fit_endpoints::<4>(texels);
+    let mut endpoints = [quantize_bc7_endpoint(low), quantize_bc7_endpoint(high)];
+
+    let expand = |(endpoint, p_bit): ([u8; 4], u8)| endpoint.map(|v| (v << 1 | p_bit) as u32);
+    let e0 = expand(endpoints[0]);
+    let e1 = expand(endpoints[1]);
+    let palette: [[f32; 4]; 16] = std::array::from_fn(|ix| {
+        let w = BC7_WEIGHTS[ix];
+        std::array::from_fn(|c| (((64 - w) * e0[c] + w * e1[c] + 32) >> 6) as f32)
+    });
+    let mut indices = texels.map(|texel| nearest(&texel, &palette) as u8);
+
+    // The highest bit of the first index is implicitly zero
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        indices = indices.map(|ix| 15 - ix);
+    }
+
+    let mut bits = 0u128;
+    let mut offset = 0;
+    let mut write = |value: u128, count: u32| {
+        bits |= value << offset;
+        offset += count;
+    };
+
+    write(1 << 6, 7);
+    for c in 0..4 {
+        write(endpoints[0].0[c] as u128, 7);
+        write(endpoints[1].0[c] as u128, 7);
+    }
+    write(endpoints[0].1 as u128, 1);
+    write(endpoints[1].1 as u128, 1);
+    for (ix, &index) in indices.iter().enumerate() {
+        write(index as u128, if ix == 0 { 3 } else { 4 });
+    }
+
+    bits.to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_color(block: &[u8]) -> [[u8; 3]; 16] {
+        let c0 = unpack_565(u16::from_le_bytes([block[0], block[1]]));
+        let c1 = unpack_565(u16::from_le_bytes([block[2], block[3]]));
+        let palette: [[f32; 3]; 4] = [
+            c0,
+            c1,
+            std::array::from_fn(|c| (2.0 * c0[c] + c1[c]) / 3.0),
+            std::array::from_fn(|c| (c0[c] + 2.0 * c1[c]) / 3.0),
+        ];
+        let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+        std::array::from_fn(|ix| {
+            palette[(indices >> (ix * 2) & 3) as usize].map(|v| v.round() as u8)
+        })
+    }
+
+    fn decode_channel(block: &[u8]) -> [u8; 16] {
+        let (r0, r1) = (block[0] as u32, block[1] as u32);
+        let mut bytes = [0; 8];
+        bytes[..6].copy_from_slice(&block[2..8]);
+        let indices = u64::from_le_bytes(bytes);
+        std::array::from_fn(|ix| match indices >> (ix * 3) & 7 {
+            0 => r0 as u8,
+            1 => r1 as u8,
+            i => (((8 - i as u32) * r0 + (i as u32 - 1) * r1 + 3) / 7) as u8,
+        })
+    }
+
+    fn decode_bc7_mode6(block: &[u8]) -> [Texel; 16] {
+        let bits = u128::from_le_bytes(block.try_into().unwrap());
+        let mut offset = 0;
+        let mut read = |count: u32| {
+            let value = (bits >> offset) & ((1 << count) - 1);
+            offset += count;
+            value as u32
+        };
+
+        assert_eq!(read(7), 1 << 6, "Expected mode 6");
+        let mut endpoints = [[0; 4]; 2];
+        for c in 0..4 {
+            for endpoint in &mut endpoints {
+                endpoint[c] = read(7);
+            }
+        }
+        let p_bits = [read(1), read(1)];
+        let endpoints = [0, 1].map(|e| endpoints[e].map(|v| v << 1 | p_bits[e]));
+
+        std::array::from_fn(|ix| {
+            let w = BC7_WEIGHTS[read(if ix == 0 { 3 } else { 4 }) as usize];
+            std::array::from_fn(|c| {
+                (((64 - w) * endpoints[0][c] + w * endpoints[1][c] + 32) >> 6) as u8
+            })
+        })
+    }
+
+    /// Blocks of a gradient along x have four distinct texels on a line, which the palettes can represent closely
+    fn gradient(width: u32, height: u32) -> Vec<u8> {
+        (0..height)
+            .flat_map(|_| {
+                (0..width).flat_map(|x| [(x * 50) as u8, (x * 25) as u8, 128, 255 - (x * 20) as u8])
+            })
+            .collect()
+    }
+
+    fn max_error(a: impl IntoIterator<Item = u8>, b: impl IntoIterator<Item = u8>) -> u8 {
+        a.into_iter()
+            .zip(b)
+            .map(|(a, b)| a.abs_diff(b))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn block_counts() {
+        let data = gradient(6, 5);
+        assert_eq!(compress(Format::BC1, &data, 6, 5).unwrap().len(), 2 * 2 * 8);
+        assert_eq!(
+            compress(Format::BC7Srgb, &data, 6, 5).unwrap().len(),
+            2 * 2 * 16
+        );
+        assert!(compress(Format::RGBA8, &data, 6, 5).is_err());
+        assert!(compress(Format::BC1, &data, 4, 4).is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        let data = gradient(4, 4);
+        let texels = fetch_block(&data, 4, 4, 0, 0);
+
+        let bc1 = compress(Format::BC1, &data, 4, 4).unwrap();
+        let decoded = decode_color(&bc1);
+        assert!(
+            max_error(
+                texels.iter().flat_map(|t| [t[0], t[1], t[2]]),
+                decoded.iter().flatten().copied()
+            ) <= 8
+        );
+
+        // Within half of the distance between two of the eight values
+        let bc5 = compress(Format::BC5, &data, 4, 4).unwrap();
+        assert!(max_error(texels.map(|t| t[0]), decode_channel(&bc5[..8])) <= 150 / 14 + 1);
+        assert!(max_error(texels.map(|t| t[1]), decode_channel(&bc5[8..])) <= 75 / 14 + 1);
+
+        let bc7 = compress(Format::BC7, &data, 4, 4).unwrap();
+        let decoded = decode_bc7_mode6(&bc7);
+        assert!(
+            max_error(
+                texels.iter().flatten().copied(),
+                decoded.iter().flatten().copied()
+            ) <= 3
+        );
+    }
+
+    #[test]
+    fn solid_blocks_are_exact() {
+        let data = [200, 100, 50, 255].repeat(16);
+
+        let bc1 = compress(Format::BC1, &data, 4, 4).unwrap();
+        assert!(decode_color(&bc1)
+            .iter()
+            .all(|&texel| max_error(texel, [200, 100, 50]) <= 4));
+
+        let bc4 = compress(Format::BC4, &data, 4, 4).unwrap();
+        assert_eq!(decode_channel(&bc4), [200; 16]);
+
+        let bc7 = compress(Format::BC7, &data, 4, 4).unwrap();
+        assert!(decode_bc7_mode6(&bc7)
+            .iter()
+            .all(|&texel| max_error(texel, [200, 100, 50, 255]) <= 1));
+    }
+}
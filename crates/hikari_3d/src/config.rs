@@ -38,7 +38,7 @@ impl Default for FilterMode {
     }
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Format {
     RGBA8,
 
@@ -46,6 +46,91 @@ pub enum Format {
     RGBAFloat32,
 
     SRGBA,
+
+    /// Block compressed RGB without alpha, 4 bits per texel
+    BC1,
+    BC1Srgb,
+    /// Block compressed RGBA, 8 bits per texel
+    BC3,
+    BC3Srgb,
+    /// Block compressed single channel, 4 bits per texel
+    BC4,
+    /// Block compressed two channels, 8 bits per texel
+    BC5,
+    /// Block compressed RGBA with higher quality than BC3, 8 bits per texel
+    BC7,
+    BC7Srgb,
+}
+
+impl Format {
+    pub fn into_vk(self) -> vk::Format {
+        match self {
+            Format::RGBA8 => vk::Format::R8G8B8A8_UNORM,
+            Format::SRGBA => vk::Format::R8G8B8A8_SRGB,
+            Format::RGBAFloat16 => vk::Format::R16G16B16A16_SFLOAT,
+            Format::RGBAFloat32 => vk::Format::R32G32B32A32_SFLOAT,
+            Format::BC1 => vk::Format::BC1_RGB_UNORM_BLOCK,
+            Format::BC1Srgb => vk::Format::BC1_RGB_SRGB_BLOCK,
+            Format::BC3 => vk::Format::BC3_UNORM_BLOCK,
+            Format::BC3Srgb => vk::Format::BC3_SRGB_BLOCK,
+            Format::BC4 => vk::Format::BC4_UNORM_BLOCK,
+            Format::BC5 => vk::Format::BC5_UNORM_BLOCK,
+            Format::BC7 => vk::Format::BC7_UNORM_BLOCK,
+            Format::BC7Srgb => vk::Format::BC7_SRGB_BLOCK,
+        }
+    }
+    /// BC1 with an alpha channel is read as [`Format::BC1`]
+    pub fn from_vk(format: vk::Format) -> Option<Self> {
+        Some(match format {
+            vk::Format::R8G8B8A8_UNORM => Format::RGBA8,
+            vk::Format::R8G8B8A8_SRGB => Format::SRGBA,
+            vk::Format::R16G16B16A16_SFLOAT => Format::RGBAFloat16,
+            vk::Format::R32G32B32A32_SFLOAT => Format::RGBAFloat32,
+            vk::Format::BC1_RGB_UNORM_BLOCK | vk::Format::BC1_RGBA_UNORM_BLOCK => Format::BC1,
+            vk::Format::BC1_RGB_SRGB_BLOCK | vk::Format::BC1_RGBA_SRGB_BLOCK => Format::BC1Srgb,
+            vk::Format::BC3_UNORM_BLOCK => Format::BC3,
+            vk::Format::BC3_SRGB_BLOCK => Format::BC3Srgb,
+            vk::Format::BC4_UNORM_BLOCK => Format::BC4,
+            vk::Format::BC5_UNORM_BLOCK => Format::BC5,
+            vk::Format::BC7_UNORM_BLOCK => Format::BC7,
+            vk::Format::BC7_SRGB_BLOCK => Format::BC7Srgb,
+            _ => return None,
+        })
+    }
+    pub fn is_block_compressed(self) -> bool {
+        !matches!(
+            self,
+            Format::RGBA8 | Format::SRGBA | Format::RGBAFloat16 | Format::RGBAFloat32
+        )
+    }
+    pub fn is_srgb(self) -> bool {
+        matches!(
+            self,
+            Format::SRGBA | Format::BC1Srgb | Format::BC3Srgb | Format::BC7Srgb
+        )
+    }
+    /// Uncompressed format with the same encoding, for devices without block compression support
+    pub fn uncompressed(self) -> Self {
+        if self.is_block_compressed() {
+            Format::RGBA8.with_srgb(self.is_srgb())
+        } else {
+            self
+        }
+    }
+    /// The same format with sRGB or linear encoding, formats without an sRGB variant are returned as is
+    pub fn with_srgb(self, srgb: bool) -> Self {
+        match (self, srgb) {
+            (Format::RGBA8, true) => Format::SRGBA,
+            (Format::SRGBA, false) => Format::RGBA8,
+            (Format::BC1, true) => Format::BC1Srgb,
+            (Format::BC1Srgb, false) => Format::BC1,
+            (Format::BC3, true) => Format::BC3Srgb,
+            (Format::BC3Srgb, false) => Format::BC3,
+            (Format::BC7, true) => Format::BC7Srgb,
+            (Format::BC7Srgb, false) => Format::BC7,
+            (format, _) => format,
+        }
+    }
 }
 
 impl Default for Format {
@@ -93,15 +178,19 @@ impl Default for TextureConfig {
     }
 }
 impl TextureConfig {
+    /// Number of mip levels of a texture with this config
+    pub fn mip_levels(&self, width: u32, height: u32) -> anyhow::Result<u32> {
+        if !self.generate_mips {
+            return Ok(1);
+        }
+        if self.max_mip_levels == 0 {
+            return Err(anyhow::anyhow!("Max mip levels must be greater than 0"));
+        }
+
+        Ok(TextureConfig::get_mip_count(width, height).min(self.max_mip_levels))
+    }
     pub fn into_image_config_2d(&self, width: u32, height: u32) -> anyhow::Result<ImageConfig> {
-        let format = match self.format {
-            //Format::RGB8 => vk::Format::R8G8B8_SNORM,
-            Format::RGBA8 => vk::Format::R8G8B8A8_UNORM,
-            //Format::SRGB => vk::Format::R8G8B8_SRGB,
-            Format::SRGBA => vk::Format::R8G8B8A8_SRGB,
-            Format::RGBAFloat16 => vk::Format::R16G16B16A16_SFLOAT,
-            Format::RGBAFloat32 => vk::Format::R32G32B32A32_SFLOAT,
-        };
+        let format = self.format.into_vk();
         let filtering = match self.filtering {
             FilterMode::Closest => vk::Filter::NEAREST,
             FilterMode::Linear => vk::Filter::LINEAR,
@@ -121,10 +210,6 @@ impl TextureConfig {
             FilterMode::Linear => vk::SamplerMipmapMode::LINEAR,
         };
 
-        if self.generate_mips && self.max_mip_levels == 0 {
-            return Err(anyhow::anyhow!("Max mip levels must be greater than 0"));
-        }
-
         Ok(ImageConfig {
             format,
             filtering,
@@ -133,11 +218,7 @@ impl TextureConfig {
             wrap_z,
             sampler_reduction_mode: None,
             aniso_level: self.aniso_level,
-            mip_levels: if self.generate_mips {
-                TextureConfig::get_mip_count(width, height).min(self.max_mip_levels)
-            } else {
-                1
-            },
+            mip_levels: self.mip_levels(width, height)?,
             mip_filtering,
             usage: vk::ImageUsageFlags::SAMPLED,
             flags: vk::ImageCreateFlags::empty(),
@@ -149,15 +230,7 @@ impl TextureConfig {
         })
     }
     pub fn into_image_config_cube(&self, width: u32, height: u32) -> anyhow::Result<ImageConfig> {
-        let format = match self.format {
-            //Format::RGB8 => vk::Format::R8G8B8_SNORM,
-            Format::RGBA8 => vk::Format::R8G8B8A8_UNORM,
-            //Format::SRGB => vk::Format::R8G8B8_SRGB,
-            Format::SRGBA => vk::Format::R8G8B8A8_SRGB,
-
-            Format::RGBAFloat16 => vk::Format::R16G16B16A16_SFLOAT,
-            Format::RGBAFloat32 => vk::Format::R32G32B32A32_SFLOAT,
-        };
+        let format = self.format.into_vk();
         let filtering = match self.filtering {
             FilterMode::Closest => vk::Filter::NEAREST,
             FilterMode::Linear => vk::Filter::LINEAR,
@@ -177,10 +250,6 @@ impl TextureConfig {
             FilterMode::Linear => vk::SamplerMipmapMode::LINEAR,
         };
 
-        if self.generate_mips && self.max_mip_levels == 0 {
-            return Err(anyhow::anyhow!("Max mip levels must be greater than 0"));
-        }
-
         Ok(ImageConfig {
             format,
             filtering,
@@ -189,11 +258,7 @@ impl TextureConfig {
             wrap_z,
             sampler_reduction_mode: None,
             aniso_level: self.aniso_level,
-            mip_levels: if self.generate_mips {
-                TextureConfig::get_mip_count(width, height).min(self.max_mip_levels)
-            } else {
-                1
-            },
+            mip_levels: self.mip_levels(width, height)?,
             mip_filtering,
             usage: vk::ImageUsageFlags::SAMPLED,
             flags: vk::ImageCreateFlags::CUBE_COMPATIBLE,
@@ -1,7 +1,12 @@
+use std::io::Read;
+use std::io::Write;
 use std::sync::Arc;
 
+use half::f16;
 use hikari_asset::Asset;
 use hikari_asset::Handle;
+use hikari_asset::ImportContext;
+use hikari_asset::Importer;
 use hikari_asset::Loader;
 use hikari_render::n_workgroups;
 use hikari_render::vk;
@@ -47,6 +52,69 @@ impl Asset for EnvironmentTexture {
 }
 
 pub const SUPPORTED_ENV_TEXTURE_EXTENSIONS: [&'static str; 1] = ["hdr"];
+/// Extension of the artifacts produced by [`EnvironmentTextureImporter`]
+pub const ENV_TEXTURE_ARTIFACT_EXTENSION: &str = "henv";
+
+/// Decodes HDR maps ahead of time into raw RGBA half float texels, preceded by the width and height.
+/// Convolving the cubemaps needs the GPU, so that still happens on load
+pub struct EnvironmentTextureImporter;
+
+impl Importer for EnvironmentTextureImporter {
+    fn extensions(&self) -> &[&str] {
+        &SUPPORTED_ENV_TEXTURE_EXTENSIONS
+    }
+    fn artifact_extension(&self) -> &str {
+        ENV_TEXTURE_ARTIFACT_EXTENSION
+    }
+    fn version(&self) -> u32 {
+        2
+    }
+    fn import(&self, ctx: &mut ImportContext, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let (data, width, height) = crate::image::open_hdr(ctx.reader())?;
+
+        write_env_artifact(writer, &data, width, height)
+    }
+}
+
+fn write_env_artifact(writer: &mut dyn Write, data: &[f32], width: u32, height: u32) -> anyhow::Result<()> {
+    writer.write_all(&width.to_le_bytes())?;
+    writer.write_all(&height.to_le_bytes())?;
+
+    let mut bytes = Vec::with_capacity(data.len() * std::mem::size_of::<f16>());
+    for &component in data {
+        bytes.extend_from_slice(&f16::from_f32(component).to_le_bytes());
+    }
+    writer.write_all(&bytes)?;
+
+    Ok(())
+}
+
+fn read_env_artifact(reader: &mut impl Read) -> anyhow::Result<(Vec<f16>, u32, u32)> {
+    let mut size = [0; 4];
+    reader.read_exact(&mut size)?;
+    let width = u32::from_le_bytes(size);
+    reader.read_exact(&mut size)?;
+    let height = u32::from_le_bytes(size);
+
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() != width as usize * height as usize * 4 * std::mem::size_of::<f16>() {
+        return Err(anyhow::anyhow!(
+            "Environment texture artifact has {} bytes of texels, expected {}x{} RGBA half float texels",
+            bytes.len(),
+            width,
+            height
+        ));
+    }
+
+    let data = bytes
+        .chunks_exact(2)
+        .map(|component| f16::from_le_bytes(component.try_into().unwrap()))
+        .collect();
+
+    Ok((data, width, height))
+}
+
 pub struct EnvironmentTextureLoader {
     loader: HDRLoader,
 }
@@ -59,7 +127,7 @@ impl EnvironmentTextureLoader {
 }
 impl Loader for EnvironmentTextureLoader {
     fn extensions(&self) -> &[&str] {
-        &SUPPORTED_ENV_TEXTURE_EXTENSIONS
+        &["hdr", ENV_TEXTURE_ARTIFACT_EXTENSION]
     }
 
     fn load(&self, ctx: &mut hikari_asset::LoadContext) -> anyhow::Result<()> {
@@ -76,9 +144,18 @@ impl Loader for EnvironmentTextureLoader {
             ..Default::default()
         };
 
-        let (data, width, height) = crate::image::open_hdr(ctx.reader())?;
-
-        let texture = Texture2D::new(&self.loader.device, &data, width, height, config)?;
+        let texture = if ctx.extension() == Some(ENV_TEXTURE_ARTIFACT_EXTENSION) {
+            // Artifacts already hold half floats, which are uploaded as is
+            let (data, width, height) = read_env_artifact(ctx.reader())?;
+            let config = TextureConfig {
+                format: Format::RGBAFloat16,
+                ..config
+            };
+            Texture2D::new(&self.loader.device, &data, width, height, config)?
+        } else {
+            let (data, width, height) = crate::image::open_hdr(ctx.reader())?;
+            Texture2D::new(&self.loader.device, &data, width, height, config)?
+        };
 
         ctx.set_asset(self.loader.load_from_hdr(texture)?);
 
@@ -411,3 +488,25 @@ impl HDRLoader {
         Ok(graph.build()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use half::f16;
+
+    use super::{read_env_artifact, write_env_artifact};
+
+    #[test]
+    fn artifact_round_trip() {
+        let data = [0.0, 1.0, 0.5, 0.0, 1024.0, 0.25, 3.0, 0.0];
+
+        let mut artifact = Vec::new();
+        write_env_artifact(&mut artifact, &data, 2, 1).unwrap();
+        assert_eq!(artifact.len(), 8 + data.len() * 2);
+
+        let (texels, width, height) = read_env_artifact(&mut artifact.as_slice()).unwrap();
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(texels, data.map(f16::from_f32));
+
+        assert!(read_env_artifact(&mut &artifact[..artifact.len() - 2]).is_err());
+    }
+}
@@ -1,13 +1,59 @@
 use std::{
     ffi::OsString,
     path::{Path, PathBuf},
-    sync::Arc,
 };
 
-use hikari_asset::{Handle, LoadContext, Mode};
-use hikari_math::{Quat, Vec2, Vec3, Vec4};
-
-use crate::{material::Material, processing, texture::Texture2D, SubMesh, TextureConfig};
+use gltf::animation::util::ReadOutputs;
+use hikari_asset::{AssetManager, Handle, ImportContext, LoadContext, Mode, IO};
+use hikari_math::{Mat4, Quat, UVec4, Vec2, Vec3, Vec4};
+use hikari_render::{Device, Features};
+
+use crate::{material::Material, processing, texture::Texture2D, MeshData, SceneData, SubMeshData, TextureConfig};
+
+/// What extracting the textures, materials and animations of a glTF file needs, shared by loading and importing it
+pub(crate) struct ExtractContext<'a> {
+    io: &'a dyn IO,
+    asset_dir: &'a Path,
+    asset_manager: &'a AssetManager,
+    reload: bool,
+    /// Importers save created assets right away, as their artifacts only refer to them by path
+    save_created: bool,
+    block_compression: bool,
+}
+impl<'a> ExtractContext<'a> {
+    pub fn from_load(context: &'a LoadContext, device: &Device) -> Self {
+        Self {
+            io: context.io(),
+            asset_dir: context.asset_dir(),
+            asset_manager: context.asset_manager(),
+            reload: context.is_reload(),
+            save_created: false,
+            block_compression: device.is_feature_supported(Features::TEXTURE_COMPRESSION_BC),
+        }
+    }
+    pub fn from_import(context: &'a ImportContext, device: &Device) -> Self {
+        Self {
+            io: context.io(),
+            asset_dir: context.asset_dir(),
+            asset_manager: context.asset_manager(),
+            reload: context.is_reload(),
+            save_created: true,
+            block_compression: device.is_feature_supported(Features::TEXTURE_COMPRESSION_BC),
+        }
+    }
+    fn io(&self) -> &dyn IO {
+        self.io
+    }
+    fn asset_dir(&self) -> &Path {
+        self.asset_dir
+    }
+    fn asset_manager(&self) -> &AssetManager {
+        self.asset_manager
+    }
+    fn is_reload(&self) -> bool {
+        self.reload
+    }
+}
 #[allow(unused)]
 struct ImportData {
     path: PathBuf,
@@ -59,7 +105,7 @@ impl ImportData {
 fn load_texture(
     import_data: &ImportData,
     texture: &gltf::Texture,
-    load_context: &mut LoadContext,
+    load_context: &ExtractContext,
     is_srgb: bool,
 ) -> Result<Handle<Texture2D>, anyhow::Error> {
     // let is_albedo = import_data.document().materials().find(|mat| {
@@ -114,11 +160,13 @@ fn load_texture(
     // }).is_some();
 
     //Albedo textures are treated as SRGB
-    let format = if is_srgb {
-        crate::config::Format::SRGBA
+    //Block compressed to save VRAM where supported, see TextureImporter
+    let format = if load_context.block_compression {
+        crate::config::Format::BC7
     } else {
         crate::config::Format::RGBA8
-    };
+    }
+    .with_srgb(is_srgb);
 
     let config = TextureConfig {
         format,
@@ -136,7 +184,7 @@ fn parse_texture_data(
     texture: &gltf::Texture,
     gltf: &ImportData,
     config: TextureConfig,
-    load_context: &mut LoadContext,
+    load_context: &ExtractContext,
 ) -> Result<Handle<Texture2D>, anyhow::Error> {
     let asset_manager = load_context.asset_manager();
     match texture.source().source() {
@@ -190,7 +238,7 @@ fn create_and_load_image_with_data(
     config: TextureConfig,
     mime_type: &str,
     import_data: &ImportData,
-    load_context: &mut LoadContext,
+    load_context: &ExtractContext,
 ) -> anyhow::Result<Handle<Texture2D>> {
     let base_path = import_data.parent_path();
 
@@ -209,7 +257,7 @@ fn create_and_load_image_with_data(
     }
 
     let asset_dir = load_context.asset_dir();
-    if !new_texture_path.exists() {
+    if !load_context.io().exists(&asset_dir.join(&new_texture_path)) {
         let mut file = load_context.io().write_file(
             &asset_dir.join(&new_texture_path),
             &Mode::create_and_write(),
@@ -227,23 +275,20 @@ fn create_and_load_image_with_data(
 }
 fn load_materials(
     import_data: &ImportData,
-    load_context: &mut LoadContext,
-) -> Result<Vec<Handle<crate::Material>>, anyhow::Error> {
+    load_context: &ExtractContext,
+) -> Result<(Vec<PathBuf>, Vec<Handle<crate::Material>>), anyhow::Error> {
+    let mut paths = Vec::new();
     let mut materials = Vec::new();
     for (ix, material) in import_data.document().materials().enumerate() {
+        paths.push(material_path(ix, import_data, &material));
         let material = load_material(ix, import_data, &material, load_context)?;
 
         materials.push(material);
     }
 
-    Ok(materials)
+    Ok((paths, materials))
 }
-fn load_material(
-    ix: usize,
-    import_data: &ImportData,
-    material: &gltf::Material,
-    load_context: &mut LoadContext,
-) -> Result<Handle<crate::Material>, anyhow::Error> {
+fn material_path(ix: usize, import_data: &ImportData, material: &gltf::Material) -> PathBuf {
     let material_id = material.index().unwrap_or(ix).to_string();
     let material_name = material.name().unwrap_or(&material_id);
 
@@ -251,8 +296,19 @@ fn load_material(
 
     file_name.push_str(".hmat");
 
-    let material_path = import_data.parent_path().join(file_name);
-    if !material_path.exists() || load_context.is_reload() {
+    import_data.parent_path().join(file_name)
+}
+fn load_material(
+    ix: usize,
+    import_data: &ImportData,
+    material: &gltf::Material,
+    load_context: &ExtractContext,
+) -> Result<Handle<crate::Material>, anyhow::Error> {
+    let material_path = material_path(ix, import_data, material);
+    let material_exists = load_context
+        .io()
+        .exists(&load_context.asset_dir().join(&material_path));
+    if !material_exists || load_context.is_reload() {
         let pbr = material.pbr_metallic_roughness();
 
         let uv_set = pbr
@@ -341,6 +397,9 @@ fn load_material(
         let handle = load_context
             .asset_manager()
             .create(&material_path, material, None)?;
+        if load_context.save_created {
+            load_context.asset_manager().save(&handle)?;
+        }
         // let material_text = serde_yaml::to_string(&material)?;
 
         // let mut file = load_context.io().write_file(&material_path, &Mode::create_and_write())?;
@@ -357,31 +416,9 @@ fn load_material(
     }
 }
 
-fn load_mesh(
-    import_data: &ImportData,
-    device: &Arc<hikari_render::Device>,
-    mesh: &gltf::Mesh<'_>,
-    materials: &[Handle<crate::Material>],
-) -> Result<crate::mesh::Mesh, anyhow::Error> {
+fn load_mesh(import_data: &ImportData, mesh: &gltf::Mesh<'_>) -> MeshData {
     let mut sub_meshes = Vec::new();
 
-    let node = import_data
-        .document()
-        .nodes()
-        .find(|node| node.mesh().map(|mesh| mesh.index()) == Some(mesh.index()));
-
-    let transform =
-        node.map(|node| node.transform())
-            .unwrap_or(gltf::scene::Transform::Decomposed {
-                translation: [0.0, 0.0, 0.0],
-                rotation: Quat::IDENTITY.to_array(),
-                scale: [1.0, 1.0, 1.0],
-            });
-
-    let transform_matrix = hikari_math::Mat4::from_cols_array_2d(&transform.matrix());
-    let mut transform = hikari_math::Transform::from_matrix(transform_matrix);
-    transform = processing::left_handed_correction(transform);
-
     //println!("Loading model {}", name);
     for primitive in mesh.primitives() {
         let reader = primitive.reader(|buffer| Some(&import_data.buffers()[buffer.index()]));
@@ -396,11 +433,6 @@ fn load_mesh(
             continue;
         };
 
-        let normals = if let Some(normals) = reader.read_normals() {
-            normals.map(|normal| Vec3::from(normal)).collect()
-        } else {
-            crate::mesh::default_normals(positions.len())
-        };
 
         let texcoord0 = if let Some(texcoord) = reader.read_tex_coords(0) {
             let texcoord = texcoord.into_f32();
@@ -416,6 +448,21 @@ fn load_mesh(
             vec![Vec2::ZERO; positions.len()]
         };
 
+        let joints = if let Some(joints) = reader.read_joints(0) {
+            joints
+                .into_u16()
+                .map(|joint| UVec4::from(joint.map(u32::from)))
+                .collect()
+        } else {
+            vec![UVec4::ZERO; positions.len()]
+        };
+
+        let weights = if let Some(weights) = reader.read_weights(0) {
+            weights.into_f32().map(|weight| Vec4::from(weight)).collect()
+        } else {
+            vec![Vec4::ZERO; positions.len()]
+        };
+
         let mut indices = if let Some(iter) = reader.read_indices() {
             let iter = iter.into_u32();
             iter.collect::<Vec<_>>()
@@ -423,93 +470,344 @@ fn load_mesh(
             (0..positions.len()).map(|x| x as u32).collect::<Vec<_>>()
         };
 
+        let normals = if let Some(normals) = reader.read_normals() {
+            normals.map(|normal| Vec3::from(normal)).collect()
+        } else {
+            processing::smooth_normals(&positions, &indices)
+        };
+
+        //Tangents are generated from the UV set normal maps are sampled with
+        let tangents = if let Some(tangents) = reader.read_tangents() {
+            tangents.map(|tangent| Vec4::from(tangent)).collect()
+        } else {
+            let uv_set = primitive
+                .material()
+                .normal_texture()
+                .map(|normal| normal.tex_coord())
+                .unwrap_or(0);
+            let texcoord = if uv_set == 1 { &texcoord1 } else { &texcoord0 };
+            processing::generate_tangents(&positions, &normals, texcoord, &indices)
+        };
+
         //GLTF winding order is CCW
         //Change winding order to CW
         processing::ccw_to_cw(&mut indices);
 
-        //let vertices = pack_for_gpu(positions, normals, texcoord0, texcoord1);
-        let mut positions_buffer = hikari_render::create_vertex_buffer(device, positions.len())?;
-        positions_buffer.upload(&positions, 0)?;
-
-        let mut normals_buffer = hikari_render::create_vertex_buffer(device, normals.len())?;
-        normals_buffer.upload(&normals, 0)?;
-
-        let mut tc0_buffer = hikari_render::create_vertex_buffer(device, texcoord0.len())?;
-        tc0_buffer.upload(&texcoord0, 0)?;
-
-        let mut tc1_buffer = hikari_render::create_vertex_buffer(device, texcoord1.len())?;
-        tc1_buffer.upload(&texcoord1, 0)?;
-
-        let mut ibuffer = hikari_render::create_index_buffer(device, indices.len())?;
-        ibuffer.upload(&indices, 0)?;
-
-        let submesh = SubMesh {
-            position: positions_buffer,
-            normals: normals_buffer,
-            tc0: tc0_buffer,
-            tc1: tc1_buffer,
-            indices: ibuffer,
-            material: materials[primitive
+        let bounds = hikari_math::Aabb::from_points(&positions);
+
+        let submesh = SubMeshData {
+            positions,
+            normals,
+            tangents,
+            tc0: texcoord0,
+            tc1: texcoord1,
+            joints,
+            weights,
+            indices,
+            material: primitive
                 .material()
                 .index()
-                .expect("TODO: Handle default material")]
-            .clone(),
+                .expect("TODO: Handle default material"),
+            bounds,
         };
 
         sub_meshes.push(submesh);
     }
 
-    Ok(crate::Mesh {
+    MeshData {
         sub_meshes,
-        transform,
-    })
+        transform: processing::left_handed_mesh_correction(),
+    }
 }
 
-pub fn load_scene(
-    device: &Arc<hikari_render::Device>,
-    path: &Path,
-    load_context: &mut LoadContext,
-) -> Result<crate::Scene, anyhow::Error> {
+type ParsedScene = (SceneData, Vec<Handle<crate::Material>>, Vec<Handle<crate::AnimationClip>>);
+
+/// Buffers and images the glTF file at `path` keeps in files of their own, relative to the asset directory
+pub(crate) fn external_files(path: &Path, data: &[u8]) -> anyhow::Result<Vec<PathBuf>> {
+    let gltf = gltf::Gltf::from_slice(data)?;
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let buffer_uris = gltf.buffers().filter_map(|buffer| match buffer.source() {
+        gltf::buffer::Source::Uri(uri) => Some(uri),
+        gltf::buffer::Source::Bin => None,
+    });
+    let image_uris = gltf.images().filter_map(|image| match image.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri),
+        gltf::image::Source::View { .. } => None,
+    });
+
+    Ok(buffer_uris
+        .chain(image_uris)
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| parent.join(uri))
+        .collect())
+}
+
+/// Reads everything but the GPU resources of a scene, extracting its textures, materials and animations into their own assets.
+/// Returns the handles of the materials and animations listed in the [`SceneData`] as well, to keep them alive
+pub(crate) fn parse_scene(path: &Path, load_context: &ExtractContext) -> Result<ParsedScene, anyhow::Error> {
     let import_data = ImportData::new(path)
         .map_err(|err| crate::Error::FailedToParse(path.into(), err.to_string()))?;
 
     //let textures = load_textures(&import_data, load_context)?;
-    let materials = load_materials(&import_data, load_context)?;
+    let (material_paths, materials) = load_materials(&import_data, load_context)?;
+
+    let meshes = import_data
+        .document()
+        .meshes()
+        .map(|mesh| load_mesh(&import_data, &mesh))
+        .collect();
+
+    let cameras = import_data.document().cameras().map(|camera| load_camera(&camera)).collect();
+    let lights = import_data
+        .document()
+        .lights()
+        .map(|lights| lights.map(|light| load_light(&light)).collect())
+        .unwrap_or_default();
+    let skins = import_data.document().skins().map(|skin| load_skin(&import_data, &skin)).collect();
+    let (animation_paths, animations) = load_animations(&import_data, load_context)?;
+    let (nodes, roots) = load_nodes(import_data.document());
+
+    let data = SceneData {
+        meshes,
+        cameras,
+        lights,
+        skins,
+        nodes,
+        roots,
+        materials: material_paths,
+        animations: animation_paths,
+    };
 
-    let mut meshes = Vec::new();
+    Ok((data, materials, animations))
+}
+fn load_skin(import_data: &ImportData, skin: &gltf::Skin) -> crate::Skin {
+    let reader = skin.reader(|buffer| Some(&import_data.buffers()[buffer.index()]));
+
+    let joints: Vec<_> = skin.joints().map(|joint| joint.index()).collect();
+    //Inverse bind matrices stay right handed like the mesh vertices they apply to
+    let inverse_bind_matrices = if let Some(matrices) = reader.read_inverse_bind_matrices() {
+        matrices.map(|matrix| Mat4::from_cols_array_2d(&matrix)).collect()
+    } else {
+        vec![Mat4::IDENTITY; joints.len()]
+    };
 
-    for mesh in import_data.document().meshes() {
-        let mesh = load_mesh(&import_data, device, &mesh, &materials)?;
-        meshes.push(mesh);
+    crate::Skin {
+        name: skin.name().unwrap_or_default().to_owned(),
+        joints,
+        inverse_bind_matrices,
     }
+}
+fn load_animation(import_data: &ImportData, animation: &gltf::Animation) -> crate::AnimationClip {
+    let mut channels = Vec::new();
+    let mut duration = 0.0f32;
 
-    let camera = import_data
-        .document()
-        .cameras()
-        .next()
-        .map(|camera| match camera.projection() {
-            gltf::camera::Projection::Orthographic(ortho) => crate::Camera {
-                near: ortho.znear(),
-                far: ortho.zfar(),
-                exposure: 1.0,
-                projection: crate::Projection::Orthographic,
-                is_primary: false,
-            },
-            gltf::camera::Projection::Perspective(persp) => crate::Camera {
-                near: persp.znear(),
-                far: persp.zfar().unwrap_or(1000.0),
-                exposure: 1.0,
-                projection: crate::Projection::Perspective(persp.yfov()),
-                is_primary: false,
-            },
-        })
-        .unwrap_or(crate::Camera {
-            near: 0.1,
-            far: 1000.0,
+    for channel in animation.channels() {
+        let reader = channel.reader(|buffer| Some(&import_data.buffers()[buffer.index()]));
+        let (Some(times), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+            continue;
+        };
+
+        //Keyframes are mirrored into the left handed convention like the node transforms they replace
+        let keyframes = match outputs {
+            ReadOutputs::Translations(translations) => crate::Keyframes::Translation(
+                translations
+                    .map(|translation| processing::left_handed_position(Vec3::from(translation)))
+                    .collect(),
+            ),
+            ReadOutputs::Rotations(rotations) => crate::Keyframes::Rotation(
+                rotations
+                    .into_f32()
+                    .map(|rotation| processing::left_handed_rotation(Quat::from_array(rotation)))
+                    .collect(),
+            ),
+            ReadOutputs::Scales(scales) => crate::Keyframes::Scale(scales.map(Vec3::from).collect()),
+            ReadOutputs::MorphTargetWeights(_) => {
+                log::warn!("Skipping morph target animation of node {}", channel.target().node().index());
+                continue;
+            }
+        };
+
+        let interpolation = match channel.sampler().interpolation() {
+            gltf::animation::Interpolation::Linear => crate::Interpolation::Linear,
+            gltf::animation::Interpolation::Step => crate::Interpolation::Step,
+            gltf::animation::Interpolation::CubicSpline => crate::Interpolation::CubicSpline,
+        };
+
+        let times: Vec<_> = times.collect();
+        duration = duration.max(times.last().copied().unwrap_or_default());
+
+        channels.push(crate::Channel {
+            node: channel.target().node().index(),
+            interpolation,
+            times,
+            keyframes,
+        });
+    }
+
+    let animation_id = animation.index().to_string();
+    crate::AnimationClip {
+        name: animation.name().unwrap_or(&animation_id).to_owned(),
+        duration,
+        channels,
+    }
+}
+fn load_animations(
+    import_data: &ImportData,
+    load_context: &ExtractContext,
+) -> Result<(Vec<PathBuf>, Vec<Handle<crate::AnimationClip>>), anyhow::Error> {
+    let mut paths = Vec::new();
+    let mut animations = Vec::new();
+    for animation in import_data.document().animations() {
+        let animation_id = animation.index().to_string();
+        let animation_name = animation.name().unwrap_or(&animation_id);
+
+        let file_name = format!("{}_animation_{}.hanim", import_data.filename(), animation_name);
+        let animation_path = import_data.parent_path().join(file_name);
+        let animation_exists = load_context
+            .io()
+            .exists(&load_context.asset_dir().join(&animation_path));
+
+        let handle = if !animation_exists || load_context.is_reload() {
+            let clip = load_animation(import_data, &animation);
+            let handle = load_context.asset_manager().create(&animation_path, clip, None)?;
+            if load_context.save_created {
+                load_context.asset_manager().save(&handle)?;
+            }
+            handle
+        } else {
+            load_context
+                .asset_manager()
+                .load::<crate::AnimationClip>(&animation_path, None, false)?
+        };
+
+        paths.push(animation_path);
+        animations.push(handle);
+    }
+
+    Ok((paths, animations))
+}
+fn load_camera(camera: &gltf::Camera) -> crate::Camera {
+    match camera.projection() {
+        gltf::camera::Projection::Orthographic(ortho) => crate::Camera {
+            near: ortho.znear(),
+            far: ortho.zfar(),
             exposure: 1.0,
-            projection: crate::Projection::Perspective(45.0),
+            projection: crate::Projection::Orthographic,
             is_primary: false,
-        });
+        },
+        gltf::camera::Projection::Perspective(persp) => crate::Camera {
+            near: persp.znear(),
+            far: persp.zfar().unwrap_or(1000.0),
+            exposure: 1.0,
+            projection: crate::Projection::Perspective(persp.yfov().to_degrees()),
+            is_primary: false,
+        },
+    }
+}
+fn load_light(light: &gltf::khr_lights_punctual::Light) -> crate::Light {
+    let default = crate::Light::default();
+    let (kind, inner_cone_angle, outer_cone_angle) = match light.kind() {
+        gltf::khr_lights_punctual::Kind::Directional => (crate::LightKind::Directional, default.inner_cone_angle, default.outer_cone_angle),
+        gltf::khr_lights_punctual::Kind::Point => (crate::LightKind::Point, default.inner_cone_angle, default.outer_cone_angle),
+        gltf::khr_lights_punctual::Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => (crate::LightKind::Spot, inner_cone_angle, outer_cone_angle),
+    };
 
-    Ok(crate::Scene { meshes, camera })
+    crate::Light {
+        color: Vec4::from((Vec3::from(light.color()), 1.0)),
+        intensity: light.intensity(),
+        kind,
+        range: light.range().unwrap_or(default.range),
+        inner_cone_angle,
+        outer_cone_angle,
+        ..default
+    }
+}
+fn load_node(node: &gltf::Node) -> crate::SceneNode {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    let transform = hikari_math::Transform {
+        position: Vec3::from(translation),
+        rotation: Quat::from_array(rotation),
+        scale: Vec3::from(scale),
+    };
+
+    crate::SceneNode {
+        name: node
+            .name()
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("Node {}", node.index())),
+        transform: processing::left_handed_correction(transform),
+        children: node.children().map(|child| child.index()).collect(),
+        mesh: node.mesh().map(|mesh| mesh.index()),
+        camera: node.camera().map(|camera| camera.index()),
+        light: node.light().map(|light| light.index()),
+        skin: node.skin().map(|skin| skin.index()),
+    }
+}
+/// Returns every node of the document and the root nodes of its default scene
+fn load_nodes(document: &gltf::Document) -> (Vec<crate::SceneNode>, Vec<usize>) {
+    let nodes: Vec<_> = document.nodes().map(|node| load_node(&node)).collect();
+
+    let roots = if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+        scene.nodes().map(|node| node.index()).collect()
+    } else {
+        //Without any scenes every node which isn't a child is a root
+        let mut is_child = vec![false; nodes.len()];
+        for child in nodes.iter().flat_map(|node| &node.children) {
+            is_child[*child] = true;
+        }
+        (0..nodes.len()).filter(|&ix| !is_child[ix]).collect()
+    };
+
+    (nodes, roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use hikari_math::{Quat, Vec3};
+
+    use super::load_nodes;
+
+    // A building with two instances of the same window mesh, nested below a translated floor node
+    const BUILDING: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "name": "Building", "translation": [10.0, 0.0, 5.0], "children": [1] },
+            { "name": "Floor", "translation": [0.0, 3.0, 0.0], "children": [2, 3] },
+            { "name": "Window", "mesh": 0, "translation": [1.0, 0.0, 0.0] },
+            { "mesh": 0, "translation": [-1.0, 0.0, 0.0], "rotation": [0.0, 0.7071068, 0.0, 0.7071068] }
+        ],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+        "accessors": [{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0.0, 0.0, 0.0], "max": [0.0, 0.0, 0.0] }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "buffers": [{ "byteLength": 36 }]
+    }"#;
+
+    #[test]
+    fn keeps_node_hierarchy() {
+        let gltf = gltf::Gltf::from_slice(BUILDING.as_bytes()).unwrap();
+        let (nodes, roots) = load_nodes(&gltf.document);
+
+        assert_eq!(roots, [0]);
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(nodes[0].children, [1]);
+        assert_eq!(nodes[1].children, [2, 3]);
+        assert_eq!(nodes[2].name, "Window");
+        assert_eq!(nodes[3].name, "Node 3");
+        assert_eq!(nodes[2].mesh, Some(0));
+        assert_eq!(nodes[3].mesh, Some(0));
+
+        // The z axis is mirrored into the left handed convention
+        assert_eq!(nodes[0].transform.position, Vec3::new(10.0, 0.0, -5.0));
+        assert_eq!(nodes[1].transform.position, Vec3::new(0.0, 3.0, 0.0));
+        assert!(nodes[3]
+            .transform
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_y(-90.0f32.to_radians()), 1e-5));
+    }
 }
@@ -1,3 +1,4 @@
+mod bc;
 mod gltf;
 mod processing;
 mod shader;
@@ -5,6 +6,7 @@ mod shader;
 #[cfg(feature = "serde")]
 mod serialize;
 
+pub mod animation;
 pub mod camera;
 pub mod config;
 pub mod cubemap;
@@ -15,21 +17,25 @@ pub mod image;
 pub mod light;
 pub mod material;
 pub mod mesh;
+pub mod mip_chain;
 pub mod primitives;
 pub mod scene;
 pub mod texture;
 
+pub use animation::*;
 pub use camera::*;
 pub use config::*;
 pub use cubemap::*;
 pub use effects::*;
 pub use environment::*;
 pub use error::Error;
-use hikari_core::Plugin;
+use hikari_asset::AssetManager;
+use hikari_core::{Plugin, Task, Time, World};
 use hikari_render::Gfx;
 pub use light::*;
 pub use material::*;
 pub use mesh::*;
+pub use mip_chain::MipChain;
 pub use scene::*;
 pub use shader::*;
 pub use texture::*;
@@ -43,6 +49,7 @@ impl Plugin for Plugin3D {
         game.create_asset::<Texture2D>();
         game.create_asset::<Material>();
         game.create_asset::<Scene>();
+        game.create_asset::<AnimationClip>();
         game.create_asset::<EnvironmentTexture>();
 
         let device = {
@@ -77,15 +84,41 @@ impl Plugin for Plugin3D {
         game.register_asset_loader::<Texture2D, TextureLoader>(TextureLoader {
             device: device.clone(),
         });
+        game.register_asset_importer::<Texture2D, TextureImporter>(TextureImporter {
+            device: device.clone(),
+        });
         
         #[cfg(feature = "serde")] {
         game.register_asset_loader::<Material, MaterialLoader>(MaterialLoader);
         game.register_asset_saver::<Material, MaterialLoader>(MaterialLoader);
+        game.register_asset_loader::<AnimationClip, AnimationClipLoader>(AnimationClipLoader);
+        game.register_asset_saver::<AnimationClip, AnimationClipLoader>(AnimationClipLoader);
+        game.register_asset_importer::<Scene, GLTFImporter>(GLTFImporter {
+            device: device.clone(),
+        });
         }
 
         game.register_asset_loader::<Scene, GLTFLoader>(GLTFLoader {
             device: device.clone(),
         });
         game.register_asset_loader::<EnvironmentTexture, EnvironmentTextureLoader>(env_loader);
+        game.register_asset_importer::<EnvironmentTexture, EnvironmentTextureImporter>(
+            EnvironmentTextureImporter,
+        );
+
+        game.add_task(
+            hikari_core::POST_UPDATE,
+            Task::new(
+                "Update Animators",
+                |world: &mut World, time: &Time, asset_manager: &AssetManager| {
+                    let scenes = asset_manager.read_assets::<Scene>().unwrap();
+                    let clips = asset_manager.read_assets::<AnimationClip>().unwrap();
+
+                    for (_, animator) in world.query_mut::<&mut Animator>() {
+                        animator.update(time.dt(), &scenes, &clips);
+                    }
+                },
+            ),
+        );
     }
 }
@@ -13,6 +13,12 @@ pub struct Light {
     pub size: f32,
     pub shadow: ShadowInfo,
     pub kind: LightKind,
+    /// Distance at which point and spot lights stop contributing any light
+    pub range: f32,
+    /// Angle in radians from the spot direction inside of which the light is at full intensity
+    pub inner_cone_angle: f32,
+    /// Angle in radians from the spot direction outside of which the light has no effect
+    pub outer_cone_angle: f32,
 }
 
 impl Default for Light {
@@ -23,6 +29,9 @@ impl Default for Light {
             size: 1.0,
             shadow: ShadowInfo::default(),
             kind: LightKind::Directional,
+            range: 10.0,
+            inner_cone_angle: 20.0f32.to_radians(),
+            outer_cone_angle: 30.0f32.to_radians(),
         }
     }
 }
@@ -59,4 +68,5 @@ impl Default for ShadowInfo {
 pub enum LightKind {
     Point,
     Directional,
+    Spot,
 }
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use hikari_asset::{Handle, AssetPool};
 use hikari_math::*;
 use hikari_render::GpuBuffer;
@@ -15,17 +17,27 @@ pub struct Vertex {
 pub struct SubMesh {
     pub position: GpuBuffer<Vec3>,
     pub normals: GpuBuffer<Vec3>,
+    /// Tangent in xyz and the handedness of the bitangent in w, `bitangent = cross(normal, tangent.xyz) * tangent.w`
+    pub tangents: GpuBuffer<Vec4>,
     pub tc0: GpuBuffer<Vec2>,
     pub tc1: GpuBuffer<Vec2>,
+    /// Indices into the joints of the skin, zero weighted for meshes without a skin
+    pub joints: GpuBuffer<UVec4>,
+    pub weights: GpuBuffer<Vec4>,
     pub indices: GpuBuffer<u32>,
     pub material: Handle<Material>,
+    /// Bounds of the vertex positions, in the space of the parent mesh
+    pub bounds: Aabb,
 }
 
 pub struct SubMeshNew {
     pub position: GpuBuffer<Vec3>,
     pub normals: GpuBuffer<Vec3>,
+    pub tangents: GpuBuffer<Vec4>,
     pub tc0: GpuBuffer<Vec2>,
     pub tc1: GpuBuffer<Vec2>,
+    pub joints: GpuBuffer<UVec4>,
+    pub weights: GpuBuffer<Vec4>,
     pub indices: GpuBuffer<u32>,
 }
 
@@ -39,9 +51,87 @@ pub struct MeshNew {
     pub transform: Transform,
 }
 
-pub fn default_normals(n: usize) -> Vec<Vec3> {
-    //Flat normals
-    vec![Vec3::ZERO; n]
+/// Vertex data of a [`SubMesh`] before it is uploaded to the GPU
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubMeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub tangents: Vec<Vec4>,
+    pub tc0: Vec<Vec2>,
+    pub tc1: Vec<Vec2>,
+    pub joints: Vec<UVec4>,
+    pub weights: Vec<Vec4>,
+    pub indices: Vec<u32>,
+    /// Index into the materials of the scene
+    pub material: usize,
+    pub bounds: Aabb,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshData {
+    pub sub_meshes: Vec<SubMeshData>,
+    pub transform: Transform,
+}
+
+impl Mesh {
+    /// Uploads the vertex data to the GPU, `materials` are indexed by [`SubMeshData::material`]
+    pub fn upload(
+        device: &Arc<hikari_render::Device>,
+        data: &MeshData,
+        materials: &[Handle<Material>],
+    ) -> anyhow::Result<Self> {
+        let mut sub_meshes = Vec::with_capacity(data.sub_meshes.len());
+
+        for submesh in &data.sub_meshes {
+            let mut positions_buffer = hikari_render::create_vertex_buffer(device, submesh.positions.len())?;
+            positions_buffer.upload(&submesh.positions, 0)?;
+
+            let mut normals_buffer = hikari_render::create_vertex_buffer(device, submesh.normals.len())?;
+            normals_buffer.upload(&submesh.normals, 0)?;
+
+            let mut tangents_buffer = hikari_render::create_vertex_buffer(device, submesh.tangents.len())?;
+            tangents_buffer.upload(&submesh.tangents, 0)?;
+
+            let mut tc0_buffer = hikari_render::create_vertex_buffer(device, submesh.tc0.len())?;
+            tc0_buffer.upload(&submesh.tc0, 0)?;
+
+            let mut tc1_buffer = hikari_render::create_vertex_buffer(device, submesh.tc1.len())?;
+            tc1_buffer.upload(&submesh.tc1, 0)?;
+
+            let mut joints_buffer = hikari_render::create_vertex_buffer(device, submesh.joints.len())?;
+            joints_buffer.upload(&submesh.joints, 0)?;
+
+            let mut weights_buffer = hikari_render::create_vertex_buffer(device, submesh.weights.len())?;
+            weights_buffer.upload(&submesh.weights, 0)?;
+
+            let mut ibuffer = hikari_render::create_index_buffer(device, submesh.indices.len())?;
+            ibuffer.upload(&submesh.indices, 0)?;
+
+            let material = materials.get(submesh.material).ok_or_else(|| {
+                anyhow::anyhow!("Submesh refers to missing material {}", submesh.material)
+            })?;
+
+            sub_meshes.push(SubMesh {
+                position: positions_buffer,
+                normals: normals_buffer,
+                tangents: tangents_buffer,
+                tc0: tc0_buffer,
+                tc1: tc1_buffer,
+                joints: joints_buffer,
+                weights: weights_buffer,
+                indices: ibuffer,
+                material: material.clone(),
+                bounds: submesh.bounds,
+            });
+        }
+
+        Ok(Self {
+            sub_meshes,
+            transform: data.transform,
+        })
+    }
 }
 
 #[derive(Clone, Default)]
@@ -82,10 +172,39 @@ impl MaterialTable {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct MeshRender {
     pub source: MeshSource,
-    pub material_table: MaterialTable
+    pub material_table: MaterialTable,
+    /// Set on components saved before scenes kept their node hierarchy, see [`MeshRender::migrate_v0`]
+    pub legacy_node_transform: bool,
 }
 
 impl MeshRender {
+    pub const VERSION: u32 = 1;
+
+    /// Version 0 rendered meshes with the transform of the first node using them baked in.
+    /// Saved entities don't carry that transform themselves, so they keep being rendered that way
+    pub fn migrate_v0(mut value: serde_yaml::Value) -> anyhow::Result<serde_yaml::Value> {
+        if let serde_yaml::Value::Mapping(mapping) = &mut value {
+            mapping.insert("legacy_node_transform".into(), true.into());
+        }
+
+        Ok(value)
+    }
+    /// Transform of the mesh relative to the entity
+    pub fn get_mesh_transform(&self, scenes: &AssetPool<Scene>) -> Option<Transform> {
+        let MeshSource::Scene(handle, mesh_ix) = &self.source else {
+            return None;
+        };
+
+        let Some(scene) = scenes.get(handle) else {
+            return None;
+        };
+
+        if self.legacy_node_transform {
+            Some(scene.legacy_mesh_transform(*mesh_ix))
+        } else {
+            Some(scene.meshes[*mesh_ix].transform)
+        }
+    }
     pub fn get_mesh<'a>(&'a self, scenes: &'a AssetPool<Scene>) -> Option<&'a Mesh> {
         let MeshSource::Scene(handle, mesh_ix) = &self.source else {
             return None;
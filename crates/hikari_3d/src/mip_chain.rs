@@ -0,0 +1,369 @@
+use std::io::{Read, Write};
+
+use ::image::{imageops::FilterType, RgbaImage};
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use hikari_render::{mip_level_size, vk};
+
+use crate::{
+    bc,
+    config::{Format, TextureConfig},
+};
+
+/// A 2D texture with all of its mip levels, which can be uploaded without any further processing.
+/// Read from KTX2 and DDS files and cached as KTX2 files
+pub struct MipChain {
+    pub format: Format,
+    pub width: u32,
+    pub height: u32,
+    /// Mip levels starting with the full resolution image
+    pub levels: Vec<Vec<u8>>,
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+impl MipChain {
+    /// Generates the mip levels requested by `config` on the CPU and compresses them if `config.format` is block compressed
+    pub fn from_rgba8(image: &RgbaImage, config: &TextureConfig) -> anyhow::Result<Self> {
+        if matches!(config.format, Format::RGBAFloat16 | Format::RGBAFloat32) {
+            return Err(anyhow::anyhow!(
+                "Cannot build a {:?} mip chain from RGBA8 texels",
+                config.format
+            ));
+        }
+
+        let (width, height) = image.dimensions();
+        let level_count = config.mip_levels(width, height)?;
+
+        let mut levels = Vec::with_capacity(level_count as usize);
+        let mut level = image.clone();
+        for ix in 0..level_count {
+            if ix > 0 {
+                level = ::image::imageops::resize(
+                    &level,
+                    (width >> ix).max(1),
+                    (height >> ix).max(1),
+                    FilterType::Triangle,
+                );
+            }
+
+            if config.format.is_block_compressed() {
+                levels.push(bc::compress(
+                    config.format,
+                    level.as_raw(),
+                    level.width(),
+                    level.height(),
+                )?);
+            } else {
+                levels.push(level.as_raw().clone());
+            }
+        }
+
+        Ok(Self {
+            format: config.format,
+            width,
+            height,
+            levels,
+        })
+    }
+    pub fn read_ktx2(data: &[u8]) -> anyhow::Result<Self> {
+        let reader = ktx2::Reader::new(data)
+            .map_err(|err| anyhow::anyhow!("Invalid KTX2 file: {:?}", err))?;
+        let header = reader.header();
+
+        if header.supercompression_scheme.is_some() {
+            return Err(anyhow::anyhow!(
+                "Supercompressed KTX2 files are not supported"
+            ));
+        }
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count != 1 {
+            return Err(anyhow::anyhow!("Only 2D KTX2 textures are supported"));
+        }
+
+        let vk_format = header
+            .format
+            .map(|format| vk::Format::from_raw(format.0.get() as i32))
+            .ok_or_else(|| anyhow::anyhow!("KTX2 file has no Vulkan format"))?;
+        let format = Format::from_vk(vk_format)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported KTX2 format {:?}", vk_format))?;
+
+        Ok(Self {
+            format,
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            levels: reader.levels().map(|level| level.to_vec()).collect(),
+        })
+    }
+    /// Writes a KTX2 file without supercompression, only 8 bit and block compressed formats are supported
+    pub fn write_ktx2(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let dfd = data_format_descriptor(self.format).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Writing {:?} textures to KTX2 is not supported",
+                self.format
+            )
+        })?;
+
+        let level_count = self.levels.len();
+        let dfd_offset = 80 + 24 * level_count;
+
+        // Levels are stored from the smallest to the largest, each aligned to the size of a block
+        let alignment = if self.format.is_block_compressed() {
+            16
+        } else {
+            4
+        };
+        let mut level_offsets = vec![0; level_count];
+        let mut end = dfd_offset + dfd.len();
+        for (level, data) in self.levels.iter().enumerate().rev() {
+            end = end.next_multiple_of(alignment);
+            level_offsets[level] = end;
+            end += data.len();
+        }
+
+        let mut file = Vec::with_capacity(end);
+        file.extend_from_slice(&KTX2_IDENTIFIER);
+        for value in [
+            self.format.into_vk().as_raw() as u32,
+            1, // typeSize
+            self.width,
+            self.height,
+            0, // pixelDepth
+            0, // layerCount
+            1, // faceCount
+            level_count as u32,
+            0, // supercompressionScheme
+            dfd_offset as u32,
+            dfd.len() as u32,
+            0, // kvdByteOffset
+            0, // kvdByteLength
+        ] {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        // sgdByteOffset and sgdByteLength
+        file.extend_from_slice(&[0; 16]);
+
+        for (data, &offset) in self.levels.iter().zip(&level_offsets) {
+            for value in [offset as u64, data.len() as u64, data.len() as u64] {
+                file.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        file.extend_from_slice(&dfd);
+
+        for (data, &offset) in self.levels.iter().zip(&level_offsets).rev() {
+            file.resize(offset, 0);
+            file.extend_from_slice(data);
+        }
+
+        writer.write_all(&file)?;
+        Ok(())
+    }
+    pub fn read_dds(reader: impl Read) -> anyhow::Result<Self> {
+        let dds = Dds::read(reader)?;
+
+        let format = match (dds.get_dxgi_format(), dds.get_d3d_format()) {
+            (Some(format), _) => match format {
+                DxgiFormat::R8G8B8A8_UNorm => Format::RGBA8,
+                DxgiFormat::R8G8B8A8_UNorm_sRGB => Format::SRGBA,
+                DxgiFormat::R16G16B16A16_Float => Format::RGBAFloat16,
+                DxgiFormat::R32G32B32A32_Float => Format::RGBAFloat32,
+                DxgiFormat::BC1_UNorm => Format::BC1,
+                DxgiFormat::BC1_UNorm_sRGB => Format::BC1Srgb,
+                DxgiFormat::BC3_UNorm => Format::BC3,
+                DxgiFormat::BC3_UNorm_sRGB => Format::BC3Srgb,
+                DxgiFormat::BC4_UNorm => Format::BC4,
+                DxgiFormat::BC5_UNorm => Format::BC5,
+                DxgiFormat::BC7_UNorm => Format::BC7,
+                DxgiFormat::BC7_UNorm_sRGB => Format::BC7Srgb,
+                format => return Err(anyhow::anyhow!("Unsupported DDS format {:?}", format)),
+            },
+            (None, Some(D3DFormat::A8B8G8R8)) => Format::RGBA8,
+            (None, format) => return Err(anyhow::anyhow!("Unsupported DDS format {:?}", format)),
+        };
+        if dds.get_num_array_layers() > 1 || dds.get_depth() > 1 {
+            return Err(anyhow::anyhow!("Only 2D DDS textures are supported"));
+        }
+
+        let (width, height) = (dds.get_width(), dds.get_height());
+        let mut data = dds.get_data(0)?;
+        let mut levels = Vec::new();
+        for level in 0..dds.get_num_mipmap_levels().max(1) {
+            let size = mip_level_size(format.into_vk(), width >> level, height >> level, 1);
+            if data.len() < size {
+                return Err(anyhow::anyhow!(
+                    "DDS file is missing data of mip level {}",
+                    level
+                ));
+            }
+            let (level, rest) = data.split_at(size);
+            levels.push(level.to_vec());
+            data = rest;
+        }
+
+        Ok(Self {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+}
+
+/// Bit offset, bit length, channel, lower and upper value of a sample of a data format descriptor
+type Sample = (u32, u32, u32, u32, u32);
+
+/// Basic data format descriptor of a KTX2 file, as defined by the Khronos Data Format Specification
+fn data_format_descriptor(format: Format) -> Option<Vec<u8>> {
+    const CHANNEL_ALPHA: u32 = 15;
+    const QUALIFIER_LINEAR: u32 = 1 << 4;
+    let (color_model, block_size, samples): (u32, u32, &[Sample]) = match format {
+        Format::RGBA8 | Format::SRGBA => (
+            1,
+            4,
+            &[
+                (0, 8, 0, 0, 255),
+                (8, 8, 1, 0, 255),
+                (16, 8, 2, 0, 255),
+                (24, 8, CHANNEL_ALPHA, 0, 255),
+            ],
+        ),
+        Format::BC1 | Format::BC1Srgb => (128, 8, &[(0, 64, 0, 0, u32::MAX)]),
+        Format::BC3 | Format::BC3Srgb => (
+            130,
+            16,
+            &[
+                (0, 64, CHANNEL_ALPHA, 0, u32::MAX),
+                (64, 64, 0, 0, u32::MAX),
+            ],
+        ),
+        Format::BC4 => (131, 8, &[(0, 64, 0, 0, u32::MAX)]),
+        Format::BC5 => (
+            132,
+            16,
+            &[(0, 64, 0, 0, u32::MAX), (64, 64, 1, 0, u32::MAX)],
+        ),
+        Format::BC7 | Format::BC7Srgb => (134, 16, &[(0, 128, 0, 0, u32::MAX)]),
+        Format::RGBAFloat16 | Format::RGBAFloat32 => return None,
+    };
+
+    let transfer_function = if format.is_srgb() { 2 } else { 1 };
+    let texel_block_dimension = if format.is_block_compressed() {
+        0x0303
+    } else {
+        0
+    };
+    let block_byte_size = 24 + 16 * samples.len() as u32;
+
+    let mut words = vec![
+        4 + block_byte_size,
+        0, // vendorId and descriptorType
+        2 | (block_byte_size << 16),
+        color_model | (1 << 8) | (transfer_function << 16),
+        texel_block_dimension,
+        block_size,
+        0,
+    ];
+    for &(bit_offset, bit_length, channel, lower, upper) in samples {
+        // Alpha is never sRGB encoded
+        let qualifiers = if format.is_srgb() && channel == CHANNEL_ALPHA {
+            QUALIFIER_LINEAR
+        } else {
+            0
+        };
+        words.push(bit_offset | ((bit_length - 1) << 16) | ((channel | qualifiers) << 24));
+        words.extend([0, lower, upper]);
+    }
+
+    Some(words.into_iter().flat_map(u32::to_le_bytes).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(format: Format) -> TextureConfig {
+        TextureConfig {
+            format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builds_mips() {
+        let image = RgbaImage::from_pixel(10, 4, ::image::Rgba([255, 0, 0, 255]));
+
+        let chain = MipChain::from_rgba8(&image, &config(Format::BC7Srgb)).unwrap();
+        let sizes: Vec<_> = chain.levels.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [3 * 16, 2 * 16, 16, 16]);
+
+        let chain = MipChain::from_rgba8(
+            &image,
+            &TextureConfig {
+                generate_mips: false,
+                ..config(Format::SRGBA)
+            },
+        )
+        .unwrap();
+        assert_eq!(chain.levels.len(), 1);
+        assert_eq!(chain.levels[0], image.as_raw().as_slice());
+
+        assert!(MipChain::from_rgba8(&image, &config(Format::RGBAFloat16)).is_err());
+    }
+
+    #[test]
+    fn ktx2_round_trip() {
+        let image = RgbaImage::from_fn(16, 8, |x, y| {
+            ::image::Rgba([x as u8 * 16, y as u8 * 32, 0, 255])
+        });
+        let chain = MipChain::from_rgba8(&image, &config(Format::BC3Srgb)).unwrap();
+
+        let mut file = Vec::new();
+        chain.write_ktx2(&mut file).unwrap();
+
+        // Checked against the parser of the ktx2 crate as well
+        let reader = ktx2::Reader::new(file.as_slice()).unwrap();
+        let header = reader.header();
+        assert_eq!(header.format, Some(ktx2::Format::BC3_SRGB_BLOCK));
+        assert_eq!(header.level_count, 5);
+        let dfd = reader.data_format_descriptors().next().unwrap();
+        assert_eq!(dfd.header, ktx2::DataFormatDescriptorHeader::BASIC);
+        let basic = ktx2::BasicDataFormatDescriptor::parse(dfd.data).unwrap();
+        assert_eq!(basic.texel_block_dimensions, [4, 4, 1, 1]);
+        assert_eq!(basic.sample_information().count(), 2);
+
+        let read = MipChain::read_ktx2(&file).unwrap();
+        assert_eq!(read.format, Format::BC3Srgb);
+        assert_eq!((read.width, read.height), (16, 8));
+        assert_eq!(read.levels, chain.levels);
+    }
+
+    #[test]
+    fn reads_dds() {
+        let mut dds = Dds::new_dxgi(ddsfile::NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: DxgiFormat::BC1_UNorm,
+            mipmap_levels: Some(4),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: ddsfile::D3D10ResourceDimension::Texture2D,
+            alpha_mode: ddsfile::AlphaMode::Unknown,
+        })
+        .unwrap();
+        let data = dds.get_mut_data(0).unwrap();
+        data.iter_mut()
+            .enumerate()
+            .for_each(|(ix, byte)| *byte = ix as u8);
+
+        let mut file = Vec::new();
+        dds.write(&mut file).unwrap();
+
+        let chain = MipChain::read_dds(file.as_slice()).unwrap();
+        assert_eq!(chain.format, Format::BC1);
+        let sizes: Vec<_> = chain.levels.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [32, 8, 8, 8]);
+        assert_eq!(chain.levels[1][0], 32);
+    }
+}
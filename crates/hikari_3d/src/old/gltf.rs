@@ -341,7 +341,7 @@ fn load_model_data(import_data: &ImportData, mesh: &gltf::Mesh<'_>) -> super::Mo
             let normals = iter.collect::<Vec<_>>();
             normals.iter().map(|&normal| Vec3::from(normal)).collect()
         } else {
-            crate::mesh::default_normals(positions.len())
+            super::mesh::default_normals(positions.len())
         };
 
         let texcoord0 = if let Some(iter) = reader.read_tex_coords(0) {
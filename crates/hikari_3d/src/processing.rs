@@ -1,22 +1,162 @@
-use hikari_math::{Quat, Transform, Vec3};
+use hikari_math::{Quat, Transform, Vec2, Vec3, Vec4};
 
-//Creates a correction matrix for right handed transformation to left handed transformation assuming the following convention:
+//Converts a node transform from the right handed glTF convention to the left handed convention by mirroring the z axis:
 // Right Handed: +x = right, +y = up, +z = forward
 // Left Handed:  +x = right, +y = up, -z = forward
+//Mesh vertices are kept as is and are mirrored by left_handed_mesh_correction instead
 pub fn left_handed_correction(transform: Transform) -> Transform {
-    let position = transform.position;
-
-    let scale = Vec3::new(transform.scale.x, transform.scale.y, -transform.scale.z);
-
-    let (axis, angle) = transform.rotation.to_axis_angle();
-    let rotation = Quat::from_axis_angle(Vec3::new(axis.x, axis.y, -axis.z), -angle);
-
     Transform {
-        position,
-        scale,
-        rotation,
+        position: left_handed_position(transform.position),
+        scale: transform.scale,
+        rotation: left_handed_rotation(transform.rotation),
+    }
+}
+pub fn left_handed_position(position: Vec3) -> Vec3 {
+    Vec3::new(position.x, position.y, -position.z)
+}
+//Mirrors the rotation axis and reverses the rotation. This is linear, so it applies to spline tangents as well
+pub fn left_handed_rotation(rotation: Quat) -> Quat {
+    Quat::from_xyzw(-rotation.x, -rotation.y, rotation.z, rotation.w)
+}
+//Mirrors the z axis of right handed mesh vertices
+pub fn left_handed_mesh_correction() -> Transform {
+    Transform {
+        scale: Vec3::new(1.0, 1.0, -1.0),
+        ..Default::default()
+    }
+}
+//Transform meshes were rendered with before scenes kept their node hierarchy, given the corrected local transform of their node.
+//The node was mirrored as a whole back then, keeping its position and flipping its z scale
+pub fn legacy_mesh_correction(node_transform: Transform) -> Transform {
+    Transform {
+        position: left_handed_position(node_transform.position),
+        scale: node_transform.scale * Vec3::new(1.0, 1.0, -1.0),
+        rotation: node_transform.rotation,
     }
 }
 pub fn ccw_to_cw<T>(arr: &mut [T]) {
     arr.chunks_exact_mut(3).for_each(|tri| tri.reverse())
 }
+
+/// Smooth vertex normals, averaging the normals of the triangles sharing each vertex weighted by their area.
+/// Expects CCW winding. Unindexed meshes don't share vertices, so their normals end up flat
+pub fn smooth_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+        // Length is twice the area of the triangle
+        let normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+
+        normals[a] += normal;
+        normals[b] += normal;
+        normals[c] += normal;
+    }
+
+    normals
+        .into_iter()
+        .map(|normal| normal.try_normalize().unwrap_or(Vec3::Y))
+        .collect()
+}
+
+struct TangentGeometry<'a> {
+    positions: &'a [Vec3],
+    normals: &'a [Vec3],
+    tex_coords: &'a [Vec2],
+    indices: &'a [u32],
+    tangents: Vec<Vec4>,
+}
+
+impl TangentGeometry<'_> {
+    fn index(&self, face: usize, vert: usize) -> usize {
+        self.indices[face * 3 + vert] as usize
+    }
+}
+
+impl bevy_mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.positions[self.index(face, vert)].to_array()
+    }
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.normals[self.index(face, vert)].to_array()
+    }
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.tex_coords[self.index(face, vert)].to_array()
+    }
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        let ix = self.index(face, vert);
+        self.tangents[ix] = Vec4::from(tangent);
+    }
+}
+
+/// Any tangent perpendicular to `normal`, for vertices without usable texture coordinates
+fn fallback_tangent(normal: Vec3) -> Vec4 {
+    let tangent = normal.any_orthonormal_vector();
+    Vec4::from((tangent, 1.0))
+}
+
+/// Generates MikkTSpace tangents, matching the tangent space normal maps are baked in by most tools.
+/// The w component is the handedness of the bitangent, `bitangent = cross(normal, tangent.xyz) * tangent.w`.
+/// Expects CCW winding
+pub fn generate_tangents(positions: &[Vec3], normals: &[Vec3], tex_coords: &[Vec2], indices: &[u32]) -> Vec<Vec4> {
+    let mut geometry = TangentGeometry {
+        positions,
+        normals,
+        tex_coords,
+        indices,
+        tangents: normals.iter().copied().map(fallback_tangent).collect(),
+    };
+
+    if !bevy_mikktspace::generate_tangents(&mut geometry) {
+        log::warn!("Failed to generate tangents, falling back to arbitrary tangents");
+        return normals.iter().copied().map(fallback_tangent).collect();
+    }
+
+    geometry.tangents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit quad in the XY plane facing +Z, with texture coordinates following the position
+    fn quad() -> (Vec<Vec3>, Vec<Vec2>, Vec<u32>) {
+        let positions = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        ];
+        let tex_coords = positions.iter().map(|position| position.truncate()).collect();
+
+        (positions, tex_coords, vec![0, 1, 2, 0, 2, 3])
+    }
+
+    #[test]
+    fn smooth_normals_of_quad() {
+        let (positions, _, indices) = quad();
+
+        let normals = smooth_normals(&positions, &indices);
+        assert!(normals.iter().all(|normal| normal.abs_diff_eq(Vec3::Z, 1e-6)));
+    }
+
+    #[test]
+    fn tangents_follow_tex_coords() {
+        let (positions, mut tex_coords, indices) = quad();
+        let normals = smooth_normals(&positions, &indices);
+
+        let tangents = generate_tangents(&positions, &normals, &tex_coords, &indices);
+        assert!(tangents.iter().all(|tangent| tangent.abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, 1.0), 1e-5)));
+
+        // Mirrored texture coordinates flip the handedness
+        tex_coords.iter_mut().for_each(|tc| tc.y = -tc.y);
+        let tangents = generate_tangents(&positions, &normals, &tex_coords, &indices);
+        assert!(tangents.iter().all(|tangent| tangent.abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, -1.0), 1e-5)));
+    }
+}
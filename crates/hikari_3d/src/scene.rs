@@ -1,14 +1,132 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
-use hikari_asset::{Asset, LoadContext, Loader};
+use hikari_asset::{Asset, Handle, LoadContext, Loader};
+use hikari_core::{Entity, World};
+use hikari_math::Transform;
 
-use crate::{Camera, Mesh};
+use crate::{
+    gltf::ExtractContext, AnimationClip, Animator, Camera, Light, Material, Mesh, MeshData, MeshRender,
+    MeshSource, Skin,
+};
+
+/// Extension of the artifacts produced by [`GLTFImporter`]
+pub const SCENE_ARTIFACT_EXTENSION: &str = "hscene";
+/// Scenes which are imported into artifacts by [`GLTFImporter`]
+pub const IMPORTED_SCENE_EXTENSIONS: [&str; 2] = ["gltf", "glb"];
+
+/// A node of an imported scene, referencing its children and attachments by index into the [`Scene`]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneNode {
+    pub name: String,
+    /// Transform relative to the parent node
+    pub transform: Transform,
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub camera: Option<usize>,
+    pub light: Option<usize>,
+    /// Skin deforming the mesh of this node
+    pub skin: Option<usize>,
+}
 
 #[derive(type_uuid::TypeUuid)]
 #[uuid = "90eff7a8-4a6b-444f-bc09-dbc441bda057"]
 pub struct Scene {
     pub meshes: Vec<Mesh>,
-    pub camera: Camera,
+    pub cameras: Vec<Camera>,
+    pub lights: Vec<Light>,
+    pub skins: Vec<Skin>,
+    pub animations: Vec<Handle<AnimationClip>>,
+    pub nodes: Vec<SceneNode>,
+    /// Nodes without a parent, in the order they are listed in the file
+    pub roots: Vec<usize>,
+}
+
+/// A [`Scene`] before its meshes are uploaded to the GPU, with materials and animations referred to by path
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneData {
+    pub meshes: Vec<MeshData>,
+    pub cameras: Vec<Camera>,
+    pub lights: Vec<Light>,
+    pub skins: Vec<Skin>,
+    pub nodes: Vec<SceneNode>,
+    pub roots: Vec<usize>,
+    pub materials: Vec<PathBuf>,
+    pub animations: Vec<PathBuf>,
+}
+
+impl Scene {
+    /// Uploads the meshes of `data`, `materials` and `animations` are the handles of the paths listed in it
+    pub fn from_data(
+        device: &Arc<hikari_render::Device>,
+        data: SceneData,
+        materials: &[Handle<Material>],
+        animations: Vec<Handle<AnimationClip>>,
+    ) -> anyhow::Result<Self> {
+        let meshes = data
+            .meshes
+            .iter()
+            .map(|mesh| Mesh::upload(device, mesh, materials))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            meshes,
+            cameras: data.cameras,
+            lights: data.lights,
+            skins: data.skins,
+            animations,
+            nodes: data.nodes,
+            roots: data.roots,
+        })
+    }
+    /// Transform of the mesh `mesh_ix` for [`MeshRender`]s saved before scenes kept their node hierarchy,
+    /// which is the local transform of the first node using the mesh
+    pub fn legacy_mesh_transform(&self, mesh_ix: usize) -> Transform {
+        self.nodes
+            .iter()
+            .find(|node| node.mesh == Some(mesh_ix))
+            .map(|node| crate::processing::legacy_mesh_correction(node.transform))
+            .unwrap_or_else(crate::processing::left_handed_mesh_correction)
+    }
+    /// Creates an entity named `name` with an entity for every node of the scene below it,
+    /// keeping the node hierarchy and local transforms. Nodes with a mesh get a [`MeshRender`]
+    /// referencing `handle`, which must be the handle this scene was loaded from, and skinned meshes get an [`Animator`]
+    pub fn spawn(&self, handle: &Handle<Scene>, world: &mut World, name: impl AsRef<str>) -> Entity {
+        let root = world.create_entity_with_name(name);
+
+        let mut stack: Vec<_> = self.roots.iter().rev().map(|&node| (node, root)).collect();
+        while let Some((node_ix, parent)) = stack.pop() {
+            let node = &self.nodes[node_ix];
+            let entity = world.create_entity_with_name(&node.name);
+
+            // set_parent keeps the world transform, so the local transform is applied afterwards
+            world.set_parent(entity, parent).unwrap();
+            *world.get_component::<&mut Transform>(entity).unwrap() = node.transform;
+
+            if let Some(mesh_ix) = node.mesh {
+                let mesh_render = MeshRender {
+                    source: MeshSource::Scene(handle.clone(), mesh_ix),
+                    ..Default::default()
+                };
+                world.add_component(entity, mesh_render).unwrap();
+
+                if let Some(skin) = node.skin {
+                    world.add_component(entity, Animator::new(handle.clone(), skin, node_ix)).unwrap();
+                }
+            }
+            if let Some(camera) = node.camera.and_then(|ix| self.cameras.get(ix)) {
+                world.add_component(entity, *camera).unwrap();
+            }
+            if let Some(light) = node.light.and_then(|ix| self.lights.get(ix)) {
+                world.add_component(entity, *light).unwrap();
+            }
+
+            stack.extend(node.children.iter().rev().map(|&child| (child, entity)));
+        }
+
+        root
+    }
 }
 pub struct GLTFLoader {
     pub device: Arc<hikari_render::Device>,
@@ -17,8 +135,32 @@ impl Loader for GLTFLoader {
     fn load(&self, context: &mut LoadContext) -> anyhow::Result<()> {
         // let mut data = vec![];
         // context.reader().read_to_end(&mut data)?;
+        #[cfg(feature = "serde")]
+        if context.extension() == Some(SCENE_ARTIFACT_EXTENSION) {
+            let data: SceneData = bincode::deserialize_from(context.reader())?;
+
+            let asset_manager = context.asset_manager();
+            let materials = data
+                .materials
+                .iter()
+                .map(|path| asset_manager.load::<Material>(path, None, false))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let animations = data
+                .animations
+                .iter()
+                .map(|path| asset_manager.load::<AnimationClip>(path, None, false))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let scene = Scene::from_data(&self.device, data, &materials, animations)?;
+            context.set_asset(scene);
+
+            return Ok(());
+        }
+
         let path = context.path().to_owned();
-        let scene = crate::gltf::load_scene(&self.device, &path, context)?;
+        let (data, materials, animations) =
+            crate::gltf::parse_scene(&path, &ExtractContext::from_load(context, &self.device))?;
+        let scene = Scene::from_data(&self.device, data, &materials, animations)?;
 
         context.set_asset(scene);
 
@@ -26,15 +168,133 @@ impl Loader for GLTFLoader {
     }
 
     fn extensions(&self) -> &[&str] {
-        &["gltf", "glb"]
+        &["gltf", "glb", SCENE_ARTIFACT_EXTENSION]
+    }
+}
+
+/// Imports glTF files into [`SceneData`], so their meshes don't need to be parsed and processed on every load.
+/// Textures, materials and animations are extracted into assets of their own
+#[cfg(feature = "serde")]
+pub struct GLTFImporter {
+    pub device: Arc<hikari_render::Device>,
+}
+
+#[cfg(feature = "serde")]
+impl hikari_asset::Importer for GLTFImporter {
+    fn extensions(&self) -> &[&str] {
+        &IMPORTED_SCENE_EXTENSIONS
+    }
+    fn artifact_extension(&self) -> &str {
+        SCENE_ARTIFACT_EXTENSION
+    }
+    fn version(&self) -> u32 {
+        1
+    }
+    fn import(
+        &self,
+        context: &mut hikari_asset::ImportContext,
+        writer: &mut dyn std::io::Write,
+    ) -> anyhow::Result<()> {
+        use std::io::Read;
+
+        let path = context.path().to_owned();
+
+        // External buffers and images change the scene as much as the glTF file itself
+        let mut source = Vec::new();
+        context.reader().read_to_end(&mut source)?;
+        for file in crate::gltf::external_files(&path, &source)? {
+            context.add_source_dependency(file);
+        }
+
+        let (data, _materials, _animations) =
+            crate::gltf::parse_scene(&path, &ExtractContext::from_import(context, &self.device))?;
+
+        bincode::serialize_into(writer, &data)?;
+
+        Ok(())
     }
 }
 #[cfg(test)]
 mod tests {
     use hikari_asset::*;
+    use hikari_core::World;
+    use hikari_math::{Transform, Vec3};
     use simple_logger::SimpleLogger;
 
-    use crate::{texture::TextureLoader, Material, MaterialLoader, Scene, Texture2D};
+    use crate::{texture::TextureLoader, Material, MaterialLoader, MeshRender, MeshSource, Scene, SceneNode, Texture2D};
+
+    fn node(name: &str, position: Vec3, children: Vec<usize>, mesh: Option<usize>) -> SceneNode {
+        SceneNode {
+            name: name.into(),
+            transform: Transform::from_position(position),
+            children,
+            mesh,
+            camera: None,
+            light: None,
+            skin: None,
+        }
+    }
+
+    // A building with two instances of the same window mesh
+    fn building() -> Scene {
+        Scene {
+            meshes: vec![],
+            cameras: vec![],
+            lights: vec![],
+            skins: vec![],
+            animations: vec![],
+            nodes: vec![
+                node("Building", Vec3::new(10.0, 0.0, -5.0), vec![1, 2], None),
+                node("Window", Vec3::new(1.0, 0.0, -2.0), vec![], Some(0)),
+                node("Window", Vec3::new(-1.0, 0.0, 0.0), vec![], Some(0)),
+            ],
+            roots: vec![0],
+        }
+    }
+
+    #[test]
+    fn spawn_keeps_hierarchy() {
+        let mut scenes = AssetPool::new();
+        let handle = scenes.insert(building());
+
+        let mut world = World::new();
+        let root = scenes.get(&handle).unwrap().spawn(&handle, &mut world, "building.gltf");
+
+        assert_eq!(world.len(), 4);
+        assert_eq!(world.entity_id(root).unwrap().name, "building.gltf");
+
+        let building = world.children(root);
+        assert_eq!(building.len(), 1);
+        assert!(world.get_component::<&MeshRender>(building[0]).is_err());
+
+        let windows = world.children(building[0]);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(
+            world.world_matrix(windows[1]).unwrap().w_axis.truncate(),
+            Vec3::new(9.0, 0.0, -5.0)
+        );
+        for window in windows {
+            assert_eq!(world.entity_id(window).unwrap().name, "Window");
+
+            let mesh_render = world.get_component::<&MeshRender>(window).unwrap();
+            assert!(!mesh_render.legacy_node_transform);
+            assert!(matches!(&mesh_render.source, MeshSource::Scene(scene, 0) if *scene == handle));
+        }
+    }
+
+    #[test]
+    fn legacy_mesh_transform() {
+        let value = MeshRender::migrate_v0(serde_yaml::from_str("source: None").unwrap()).unwrap();
+        assert_eq!(value["legacy_node_transform"], true);
+
+        // The first node using the mesh was baked in, mirrored as a whole
+        let scene = building();
+        let transform = scene.legacy_mesh_transform(0);
+        assert_eq!(transform.position, Vec3::new(1.0, 0.0, 2.0));
+        assert_eq!(transform.scale, Vec3::new(1.0, 1.0, -1.0));
+
+        assert_eq!(scene.legacy_mesh_transform(1).scale, Vec3::new(1.0, 1.0, -1.0));
+    }
     #[test]
     fn sponza() {
         use hikari_render::GfxConfig;
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use hikari_math::{Vec2, Vec3, Transform};
+use hikari_math::{UVec4, Vec2, Vec3, Vec4, Transform};
 use hikari_render::{GpuBuffer, Device, vk};
 use rkyv::Archived;
 
@@ -12,8 +12,11 @@ const MAGIC_NUM: &[u8; 8] = b"hkmesh\0\0";
 pub struct SubMeshFile {
     pub positions: Vec<Vec3>,
     pub normals: Vec<Vec3>,
+    pub tangents: Vec<Vec4>,
     pub tc0: Vec<Vec2>,
     pub tc1: Vec<Vec2>,
+    pub joints: Vec<UVec4>,
+    pub weights: Vec<Vec4>,
     pub indices: Vec<u32>,
 }
 #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
@@ -51,12 +54,21 @@ impl MeshNew {
             let normals = submesh.normals.download(0..submesh.normals.capacity())?;
             let normals = normals.mapped_slice().to_owned();
 
+            let tangents = submesh.tangents.download(0..submesh.tangents.capacity())?;
+            let tangents = tangents.mapped_slice().to_owned();
+
             let tc0 = submesh.tc0.download(0..submesh.tc0.capacity())?;
             let tc0 = tc0.mapped_slice().to_owned();
 
             let tc1 = submesh.tc1.download(0..submesh.tc1.capacity())?;
             let tc1 = tc1.mapped_slice().to_owned();
 
+            let joints = submesh.joints.download(0..submesh.joints.capacity())?;
+            let joints = joints.mapped_slice().to_owned();
+
+            let weights = submesh.weights.download(0..submesh.weights.capacity())?;
+            let weights = weights.mapped_slice().to_owned();
+
             let indices = submesh.indices.download(0..submesh.indices.capacity())?;
             let indices = indices.mapped_slice().to_owned();
 
@@ -64,8 +76,11 @@ impl MeshNew {
                 SubMeshFile {
                     positions,
                     normals,
+                    tangents,
                     tc0,
                     tc1,
+                    joints,
+                    weights,
                     indices,
                 }
             );
@@ -83,21 +98,30 @@ impl SubMeshNew {
     pub fn from_archive(device: &Arc<Device>, archive: &Archived<SubMeshFile>) -> anyhow::Result<Self> {
         let mut position = GpuBuffer::new(device, archive.positions.len(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
         let mut normals = GpuBuffer::new(device, archive.normals.len(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
+        let mut tangents = GpuBuffer::new(device, archive.tangents.len(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
         let mut tc0 =  GpuBuffer::new(device, archive.tc0.len(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
         let mut tc1 =  GpuBuffer::new(device, archive.tc1.len(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
+        let mut joints = GpuBuffer::new(device, archive.joints.len(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
+        let mut weights = GpuBuffer::new(device, archive.weights.len(), vk::BufferUsageFlags::VERTEX_BUFFER)?;
         let mut indices = GpuBuffer::new(device, archive.indices.len(), vk::BufferUsageFlags::INDEX_BUFFER)?;
 
         position.upload(&archive.positions, 0)?;
         normals.upload(&archive.normals, 0)?;
+        tangents.upload(&archive.tangents, 0)?;
         tc0.upload(&archive.tc0, 0)?;
         tc1.upload(&archive.tc1, 0)?;
+        joints.upload(&archive.joints, 0)?;
+        weights.upload(&archive.weights, 0)?;
         indices.upload(&archive.indices, 0)?;
 
         Ok(Self {
             position,
             normals,
+            tangents,
             tc0,
             tc1,
+            joints,
+            weights,
             indices
         })
     }
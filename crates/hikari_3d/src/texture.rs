@@ -1,9 +1,12 @@
 use ::image::EncodableLayout;
 
-use crate::config::*;
-use std::sync::Arc;
+use crate::{config::*, MipChain};
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+};
 
-use hikari_asset::{Asset, LoadContext, Loader};
+use hikari_asset::{Asset, ImportContext, Importer, LoadContext, Loader};
 use hikari_render::*;
 
 #[derive(type_uuid::TypeUuid)]
@@ -32,6 +35,34 @@ impl Texture2D {
             config,
         })
     }
+    /// Creates a texture from pre-built mip levels, e.g. read from a KTX2 or DDS file.
+    /// The format of `mips` is used, except that `config` decides whether texels are read as sRGB.
+    /// Levels beyond what `config` asks for are dropped.
+    pub fn with_mips(
+        device: &Arc<hikari_render::Device>,
+        mips: &MipChain,
+        mut config: TextureConfig,
+    ) -> Result<Texture2D, anyhow::Error> {
+        config.format = mips.format.with_srgb(config.format.is_srgb());
+
+        let level_count =
+            (config.mip_levels(mips.width, mips.height)? as usize).min(mips.levels.len());
+        let levels: Vec<&[u8]> = mips.levels[..level_count]
+            .iter()
+            .map(Vec::as_slice)
+            .collect();
+
+        Ok(Self {
+            image: SampledImage::with_mips(
+                device,
+                &levels,
+                mips.width,
+                mips.height,
+                config.into_image_config_2d(mips.width, mips.height)?,
+            )?,
+            config,
+        })
+    }
     pub fn with_dimensions(
         device: &Arc<hikari_render::Device>,
         width: u32,
@@ -67,33 +98,74 @@ impl Texture2D {
     }
 }
 
-pub const SUPPORTED_TEXTURE_EXTENSIONS: [&'static str; 7] =
-    ["png", "jpg", "jpeg", "dds", "bmp", "gif", "tga"];
+pub const SUPPORTED_TEXTURE_EXTENSIONS: [&'static str; 8] =
+    ["png", "jpg", "jpeg", "dds", "ktx2", "bmp", "gif", "tga"];
+
+/// Source images which are imported into KTX2 artifacts by [`TextureImporter`]
+pub const IMPORTED_TEXTURE_EXTENSIONS: [&'static str; 6] =
+    ["png", "jpg", "jpeg", "bmp", "gif", "tga"];
+
 pub struct TextureLoader {
     pub device: Arc<Device>,
 }
 
+/// `config` with block compressed formats replaced if `device` can't sample them
+fn supported_config(device: &Device, mut config: TextureConfig) -> TextureConfig {
+    if !device.is_feature_supported(Features::TEXTURE_COMPRESSION_BC) {
+        config.format = config.format.uncompressed();
+    }
+    config
+}
+
 impl Asset for Texture2D {
     type Settings = TextureConfig;
 }
 impl Loader for TextureLoader {
     fn load(&self, context: &mut LoadContext) -> anyhow::Result<()> {
-        //let mut raw_data = vec![];
-        //context.reader().read_to_end(&mut raw_data)?;
-
-        let format = ::image::ImageFormat::from_path(context.path())?;
-
-        let image = ::image::load(context.reader(), format)?;
-        //let image = image::load_from_memory(&buf_reader)?;
-        let width = image.width();
-        let height = image.height();
-
-        let config = *context.settings::<Texture2D>();
-
-        let image = image.to_rgba8();
-        let data = image.as_bytes();
-
-        let texture = Texture2D::new(&self.device, data, width, height, config)?;
+        let config = supported_config(&self.device, *context.settings::<Texture2D>());
+        let extension = context.extension().map(str::to_ascii_lowercase);
+
+        let mips = match extension.as_deref() {
+            Some("ktx2") => {
+                let mut data = vec![];
+                context.reader().read_to_end(&mut data)?;
+                MipChain::read_ktx2(&data)?
+            }
+            Some("dds") => MipChain::read_dds(context.reader())?,
+            _ => {
+                let format = ::image::ImageFormat::from_path(context.path())?;
+
+                let image = ::image::load(context.reader(), format)?;
+                let width = image.width();
+                let height = image.height();
+
+                let image = image.to_rgba8();
+                if config.format.is_block_compressed() {
+                    let mips = MipChain::from_rgba8(&image, &config)?;
+                    context.set_asset(Texture2D::with_mips(&self.device, &mips, config)?);
+
+                    return Ok(());
+                }
+                let data = image.as_bytes();
+
+                let texture = Texture2D::new(&self.device, data, width, height, config)?;
+                context.set_asset(texture);
+
+                return Ok(());
+            }
+        };
+
+        if mips.format.is_block_compressed()
+            && !self.device.is_feature_supported(Features::TEXTURE_COMPRESSION_BC)
+        {
+            return Err(anyhow::anyhow!(
+                "{:?} is block compressed as {:?}, which this device doesn't support",
+                context.path(),
+                mips.format
+            ));
+        }
+
+        let texture = Texture2D::with_mips(&self.device, &mips, config)?;
         context.set_asset(texture);
 
         Ok(())
@@ -104,6 +176,31 @@ impl Loader for TextureLoader {
     }
 }
 
+/// Generates and block compresses the mips of source images ahead of time, which is too slow to do on every load
+pub struct TextureImporter {
+    pub device: Arc<Device>,
+}
+
+impl Importer for TextureImporter {
+    fn extensions(&self) -> &[&str] {
+        &IMPORTED_TEXTURE_EXTENSIONS
+    }
+    fn artifact_extension(&self) -> &str {
+        "ktx2"
+    }
+    fn version(&self) -> u32 {
+        1
+    }
+    fn import(&self, context: &mut ImportContext, writer: &mut dyn Write) -> anyhow::Result<()> {
+        let config = supported_config(&self.device, *context.settings::<Texture2D>());
+
+        let format = ::image::ImageFormat::from_path(context.path())?;
+        let image = ::image::load(context.reader(), format)?.to_rgba8();
+
+        MipChain::from_rgba8(&image, &config)?.write_ktx2(writer)
+    }
+}
+
 #[test]
 fn parallel_load() -> Result<(), Box<dyn std::error::Error>> {
     use crate::*;
@@ -0,0 +1,494 @@
+use std::{
+    any::Any,
+    hash::Hasher,
+    io::{BufWriter, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::{Asset, AssetManager, BufReadSeek, Mode, IO};
+
+/// Directory, relative to the asset directory, where artifacts of imported assets are stored
+pub const ARTIFACT_DIR: &str = ".cache/artifacts";
+
+const ARTIFACT_MAGIC: [u8; 4] = *b"HKAR";
+/// Size of the header without its dependencies
+const ARTIFACT_HEADER_SIZE: usize = 4 + 4 + 8 + 8 + 4;
+
+/// Turns a source file into an artifact which is cheaper to load, e.g. compressing textures or flattening a glTF file.
+/// Artifacts are cached and only re-imported when the source file, its settings or the importer version change.
+/// The artifact is read back by the [`Loader`](crate::Loader) registered for [`Importer::artifact_extension`]
+pub trait Importer: Send + Sync + 'static {
+    /// Extensions of the source files handled by this importer
+    fn extensions(&self) -> &[&str];
+    /// Extension of the produced artifacts
+    fn artifact_extension(&self) -> &str;
+    /// Bump whenever the artifacts produced for the same source and settings change, so existing ones get re-imported
+    fn version(&self) -> u32;
+    fn import(&self, ctx: &mut ImportContext, writer: &mut dyn Write) -> anyhow::Result<()>;
+}
+
+pub struct ImportContext {
+    asset_dir: PathBuf,
+    rel_path: PathBuf,
+    io: Arc<dyn IO>,
+    reader: Box<dyn BufReadSeek + Send + Sync + 'static>,
+    settings: Box<dyn Any + Send + Sync + 'static>,
+    reload: bool,
+    ass_man: AssetManager,
+    dependencies: Vec<PathBuf>,
+}
+impl ImportContext {
+    pub(crate) fn new<T: Asset>(
+        asset_dir: PathBuf,
+        rel_path: PathBuf,
+        io: Arc<dyn IO>,
+        reader: Box<dyn BufReadSeek + Send + Sync + 'static>,
+        settings: T::Settings,
+        reload: bool,
+        ass_man: AssetManager,
+    ) -> Self {
+        Self {
+            asset_dir,
+            rel_path,
+            io,
+            reader,
+            settings: Box::new(settings),
+            reload,
+            ass_man,
+            dependencies: Vec::new(),
+        }
+    }
+    pub(crate) fn into_dependencies(self) -> Vec<PathBuf> {
+        self.dependencies
+    }
+    pub fn io(&self) -> &dyn IO {
+        &*self.io
+    }
+    /// Returns absolute path of asset directory
+    pub fn asset_dir(&self) -> &Path {
+        &self.asset_dir
+    }
+    /// Return path of the source file relative to asset directory
+    pub fn path(&self) -> &Path {
+        &self.rel_path
+    }
+    /// Reads the source file
+    pub fn reader(&mut self) -> &mut impl BufReadSeek {
+        &mut self.reader
+    }
+    pub fn settings<T: Asset>(&self) -> &T::Settings {
+        self.settings.downcast_ref().unwrap()
+    }
+    pub fn is_reload(&self) -> bool {
+        self.reload
+    }
+    pub fn asset_manager(&self) -> &AssetManager {
+        &self.ass_man
+    }
+    /// Declares another file the artifact is made from, e.g. the buffers of a glTF file.
+    /// `path` is relative to the asset directory, the asset is re-imported when the file changes
+    pub fn add_source_dependency(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        if !self.dependencies.contains(&path) {
+            self.dependencies.push(path);
+        }
+    }
+}
+
+/// Header stored in front of every artifact, describing what it was imported from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    pub version: u32,
+    /// Hash of the source file and its dependencies, see [`hash_sources`]
+    pub source_hash: u64,
+    pub settings_hash: u64,
+    /// Other files the artifact was imported from, relative to the asset directory
+    pub dependencies: Vec<PathBuf>,
+}
+impl ArtifactHeader {
+    pub fn read(reader: &mut dyn Read) -> anyhow::Result<Self> {
+        let mut bytes = [0; ARTIFACT_HEADER_SIZE];
+        reader.read_exact(&mut bytes)?;
+
+        if bytes[0..4] != ARTIFACT_MAGIC {
+            return Err(anyhow!("Not an artifact, magic number doesn't match"));
+        }
+
+        let dependency_count = u32::from_le_bytes(bytes[24..28].try_into().unwrap());
+        let mut dependencies = Vec::new();
+        for _ in 0..dependency_count {
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            let mut path = vec![0; u32::from_le_bytes(len) as usize];
+            reader.read_exact(&mut path)?;
+
+            dependencies.push(PathBuf::from(String::from_utf8(path)?));
+        }
+
+        Ok(Self {
+            version: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            source_hash: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            settings_hash: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            dependencies,
+        })
+    }
+    pub fn write(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+        writer.write_all(&ARTIFACT_MAGIC)?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        writer.write_all(&self.source_hash.to_le_bytes())?;
+        writer.write_all(&self.settings_hash.to_le_bytes())?;
+
+        writer.write_all(&(self.dependencies.len() as u32).to_le_bytes())?;
+        for dependency in &self.dependencies {
+            let path = dependency
+                .to_str()
+                .ok_or_else(|| anyhow!("Dependency {:?} is not valid UTF-8", dependency))?;
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Hash of the contents of a source file
+pub fn hash_source(data: &[u8]) -> u64 {
+    fxhash::hash64(data)
+}
+/// Combines the [`hash_source`] of a source file with the contents of its dependencies, as stored in [`ArtifactHeader::source_hash`]
+pub fn hash_sources(
+    io: &dyn IO,
+    asset_dir: &Path,
+    source_hash: u64,
+    dependencies: &[PathBuf],
+) -> anyhow::Result<u64> {
+    let mut hasher = fxhash::FxHasher64::default();
+    hasher.write_u64(source_hash);
+
+    for dependency in dependencies {
+        let mut data = Vec::new();
+        io.read_file(&asset_dir.join(dependency), &Mode::read_only())?
+            .read_to_end(&mut data)?;
+
+        hasher.write(dependency.to_string_lossy().as_bytes());
+        hasher.write(&data);
+    }
+
+    Ok(hasher.finish())
+}
+
+/// Whether `artifact` was imported from the current contents of `source` and its dependencies.
+/// The importer version and settings are not compared. Paths are relative to `asset_dir`
+pub fn is_artifact_current(
+    io: &dyn IO,
+    asset_dir: &Path,
+    source: &Path,
+    artifact: &Path,
+) -> anyhow::Result<bool> {
+    let mut reader = io.read_file(&asset_dir.join(artifact), &Mode::read_only())?;
+    let header = ArtifactHeader::read(&mut reader)?;
+
+    let mut source_data = Vec::new();
+    io.read_file(&asset_dir.join(source), &Mode::read_only())?
+        .read_to_end(&mut source_data)?;
+
+    // Missing dependencies make the artifact stale
+    let source_hash =
+        hash_sources(io, asset_dir, hash_source(&source_data), &header.dependencies).ok();
+    Ok(source_hash == Some(header.source_hash))
+}
+/// Hash of import settings, as stored in [`ArtifactHeader::settings_hash`].
+/// The YAML representation is hashed, so settings read back from the asset database as a `serde_yaml::Value` hash the same
+pub fn hash_settings(settings: &impl Serialize) -> anyhow::Result<u64> {
+    Ok(fxhash::hash64(&serde_yaml::to_string(settings)?))
+}
+
+/// Path of the artifact imported from `source` with the given settings, both relative to the asset directory.
+/// A source imported with different settings gets one artifact per settings
+pub fn artifact_path(source: &Path, settings_hash: u64, artifact_extension: &str) -> PathBuf {
+    let mut file_name = source.file_name().unwrap_or_default().to_owned();
+    file_name.push(format!(".{:016x}.", settings_hash));
+    file_name.push(artifact_extension);
+
+    Path::new(ARTIFACT_DIR).join(source.with_file_name(file_name))
+}
+
+/// Imports `rel_path` using `import` unless an up to date artifact already exists.
+/// `import` returns the dependencies of the source file, see [`ImportContext::add_source_dependency`].
+/// Without the source file, as in shipped builds, an existing artifact is used as is.
+/// Returns the path of the artifact relative to the asset directory
+pub(crate) fn import_artifact(
+    io: &dyn IO,
+    asset_dir: &Path,
+    rel_path: &Path,
+    importer: &dyn Importer,
+    settings_hash: u64,
+    import: impl FnOnce(Vec<u8>, &mut dyn Write) -> anyhow::Result<Vec<PathBuf>>,
+) -> anyhow::Result<PathBuf> {
+    let artifact = artifact_path(rel_path, settings_hash, importer.artifact_extension());
+    let artifact_abs = asset_dir.join(&artifact);
+    let source_abs = asset_dir.join(rel_path);
+
+    if !io.exists(&source_abs) {
+        return if io.exists(&artifact_abs) {
+            Ok(artifact)
+        } else {
+            Err(anyhow!("Neither {:?} nor its artifact exist", rel_path))
+        };
+    }
+
+    let mut source = Vec::new();
+    io.read_file(&source_abs, &Mode::read_only())?
+        .read_to_end(&mut source)?;
+
+    let source_hash = hash_source(&source);
+
+    if io.exists(&artifact_abs) {
+        let existing = io
+            .read_file(&artifact_abs, &Mode::read_only())
+            .map_err(anyhow::Error::from)
+            .and_then(|mut reader| ArtifactHeader::read(&mut reader));
+
+        if let Ok(existing) = existing {
+            let current = existing.version == importer.version()
+                && existing.settings_hash == settings_hash
+                && hash_sources(io, asset_dir, source_hash, &existing.dependencies).ok()
+                    == Some(existing.source_hash);
+            if current {
+                return Ok(artifact);
+            }
+        }
+    }
+
+    log::info!("Importing {:?}", rel_path);
+    if let Some(parent) = artifact_abs.parent() {
+        io.create_dir_all(parent)?;
+    }
+
+    // The header depends on what the importer read, so the artifact is buffered until the import is done
+    let mut data = Vec::new();
+    let dependencies = import(source, &mut data)?;
+    let header = ArtifactHeader {
+        version: importer.version(),
+        source_hash: hash_sources(io, asset_dir, source_hash, &dependencies)?,
+        settings_hash,
+        dependencies,
+    };
+
+    let (temp_path, temp_file) = io.create_temp_file(&artifact_abs, &Mode::create_and_write())?;
+    let result = (|| {
+        let mut writer = BufWriter::new(temp_file);
+        header.write(&mut writer)?;
+        writer.write_all(&data)?;
+        writer.flush()?;
+
+        anyhow::Ok(())
+    })();
+
+    if let Err(err) = result {
+        let _ = io.remove_file(&temp_path);
+        return Err(err);
+    }
+    io.rename_file(&temp_path, &artifact_abs)?;
+
+    Ok(artifact)
+}
+
+/// Reads an artifact, skipping its header. Fails if it wasn't imported by this version of `importer` with the given settings
+pub(crate) fn read_artifact(
+    io: &dyn IO,
+    path: &Path,
+    importer: &dyn Importer,
+    settings_hash: u64,
+) -> anyhow::Result<Cursor<Vec<u8>>> {
+    let mut data = Vec::new();
+    io.read_file(path, &Mode::read_only())?
+        .read_to_end(&mut data)?;
+
+    let mut reader = data.as_slice();
+    let header = ArtifactHeader::read(&mut reader)?;
+    if header.version != importer.version() || header.settings_hash != settings_hash {
+        return Err(anyhow!(
+            "Artifact {:?} was imported by another importer version or with other settings",
+            path
+        ));
+    }
+    let header_size = data.len() - reader.len();
+    data.drain(..header_size);
+
+    Ok(Cursor::new(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::MemoryIO;
+
+    struct UppercaseImporter;
+
+    impl Importer for UppercaseImporter {
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+        fn artifact_extension(&self) -> &str {
+            "upper"
+        }
+        fn version(&self) -> u32 {
+            1
+        }
+        fn import(&self, ctx: &mut ImportContext, writer: &mut dyn Write) -> anyhow::Result<()> {
+            let mut text = String::new();
+            ctx.reader().read_to_string(&mut text)?;
+            writer.write_all(text.to_uppercase().as_bytes())?;
+
+            Ok(())
+        }
+    }
+
+    fn uppercase(source: Vec<u8>, writer: &mut dyn Write) -> anyhow::Result<Vec<PathBuf>> {
+        writer.write_all(&source.to_ascii_uppercase())?;
+        Ok(Vec::new())
+    }
+
+    #[test]
+    fn artifact_paths() {
+        assert_eq!(
+            artifact_path(Path::new("models/house.gltf"), 0xabc, "hscene"),
+            Path::new(".cache/artifacts/models/house.gltf.0000000000000abc.hscene")
+        );
+    }
+
+    #[test]
+    fn imports_only_when_changed() {
+        let io = MemoryIO::new();
+        let asset_dir = Path::new("/assets");
+        let source = Path::new("notes/a.txt");
+        io.insert(asset_dir.join(source), "hello");
+
+        let imports = Cell::new(0);
+        let import = |settings_hash| {
+            import_artifact(
+                &io,
+                asset_dir,
+                source,
+                &UppercaseImporter,
+                settings_hash,
+                |data, writer| {
+                    imports.set(imports.get() + 1);
+                    uppercase(data, writer)
+                },
+            )
+            .unwrap()
+        };
+
+        let artifact = import(0);
+        assert_eq!(
+            artifact,
+            Path::new(".cache/artifacts/notes/a.txt.0000000000000000.upper")
+        );
+        let mut data = read_artifact(&io, &asset_dir.join(&artifact), &UppercaseImporter, 0)
+            .unwrap()
+            .into_inner();
+        assert_eq!(data, b"HELLO");
+
+        import(0);
+        assert_eq!(imports.get(), 1);
+
+        // Other settings get an artifact of their own, without invalidating the first one
+        let other = import(1);
+        assert_ne!(other, artifact);
+        assert_eq!(imports.get(), 2);
+        import(0);
+        assert_eq!(imports.get(), 2);
+
+        io.insert(asset_dir.join(source), "bye");
+        import(1);
+        assert_eq!(imports.get(), 3);
+        data = read_artifact(&io, &asset_dir.join(&other), &UppercaseImporter, 1)
+            .unwrap()
+            .into_inner();
+        assert_eq!(data, b"BYE");
+
+        assert!(read_artifact(&io, &asset_dir.join(&other), &UppercaseImporter, 0).is_err());
+    }
+
+    #[test]
+    fn reimports_when_dependency_changes() {
+        let io = MemoryIO::new();
+        let asset_dir = Path::new("/assets");
+        let source = Path::new("a.txt");
+        io.insert(asset_dir.join(source), "hello");
+        io.insert(asset_dir.join("b.txt"), "world");
+
+        let imports = Cell::new(0);
+        let import = || {
+            import_artifact(&io, asset_dir, source, &UppercaseImporter, 0, |data, writer| {
+                imports.set(imports.get() + 1);
+                uppercase(data, writer)?;
+                Ok(vec![PathBuf::from("b.txt")])
+            })
+            .unwrap()
+        };
+
+        let artifact = import();
+        import();
+        assert_eq!(imports.get(), 1);
+        assert!(is_artifact_current(&io, asset_dir, source, &artifact).unwrap());
+
+        io.insert(asset_dir.join("b.txt"), "moon");
+        assert!(!is_artifact_current(&io, asset_dir, source, &artifact).unwrap());
+        import();
+        assert_eq!(imports.get(), 2);
+
+        io.remove_file(&asset_dir.join("b.txt")).unwrap();
+        assert!(!is_artifact_current(&io, asset_dir, source, &artifact).unwrap());
+    }
+
+    #[test]
+    fn uses_artifact_without_source() {
+        let io = MemoryIO::new();
+        let asset_dir = Path::new("/assets");
+        let source = Path::new("a.txt");
+
+        let result = import_artifact(&io, asset_dir, source, &UppercaseImporter, 0, uppercase);
+        assert!(result.is_err());
+
+        io.insert(asset_dir.join(source), "shipped");
+        let artifact =
+            import_artifact(&io, asset_dir, source, &UppercaseImporter, 0, uppercase).unwrap();
+        io.remove_file(&asset_dir.join(source)).unwrap();
+
+        let result = import_artifact(&io, asset_dir, source, &UppercaseImporter, 0, |_, _| {
+            panic!("Source is gone, nothing to import")
+        });
+        assert_eq!(result.unwrap(), artifact);
+    }
+
+    #[test]
+    fn failed_import_leaves_no_artifact() {
+        let io = MemoryIO::new();
+        let asset_dir = Path::new("/assets");
+        io.insert(asset_dir.join("a.txt"), "broken");
+
+        let result = import_artifact(
+            &io,
+            asset_dir,
+            Path::new("a.txt"),
+            &UppercaseImporter,
+            0,
+            |_, _| Err(anyhow!("Corrupt source")),
+        );
+
+        assert!(result.is_err());
+        assert!(io
+            .files()
+            .iter()
+            .all(|file| !file.starts_with(asset_dir.join(ARTIFACT_DIR))));
+    }
+}
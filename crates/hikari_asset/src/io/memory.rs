@@ -0,0 +1,263 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use parking_lot::RwLock;
+
+use super::{BufReadSeek, Mode, IO};
+
+#[derive(Default)]
+struct MemoryFs {
+    files: HashMap<PathBuf, Arc<[u8]>>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl MemoryFs {
+    fn is_dir(&self, path: &Path) -> bool {
+        self.dirs.contains(path) || self.files.keys().any(|file| file.starts_with(path) && file != path)
+    }
+}
+
+/// A virtual filesystem held entirely in memory.
+/// Useful for tests and for tools that need to produce assets without touching the disk.
+/// Cloning a `MemoryIO` yields another view into the same filesystem
+#[derive(Clone, Default)]
+pub struct MemoryIO {
+    fs: Arc<RwLock<MemoryFs>>,
+}
+
+impl MemoryIO {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Inserts a file, replacing its contents if it already exists
+    pub fn insert(&self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) {
+        let data: Vec<u8> = data.into();
+        self.fs
+            .write()
+            .files
+            .insert(path.as_ref().to_owned(), data.into());
+    }
+    /// Returns the current contents of a file
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<Arc<[u8]>> {
+        self.fs.read().files.get(path.as_ref()).cloned()
+    }
+    /// Registers `path` and all of its ancestors as directories
+    pub fn create_dir_all(&self, path: impl AsRef<Path>) {
+        let mut fs = self.fs.write();
+        for ancestor in path.as_ref().ancestors() {
+            fs.dirs.insert(ancestor.to_owned());
+        }
+    }
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.fs.read().files.keys().cloned().collect()
+    }
+}
+
+struct MemoryWriter {
+    fs: Arc<RwLock<MemoryFs>>,
+    path: PathBuf,
+    buffer: Cursor<Vec<u8>>,
+}
+
+impl MemoryWriter {
+    fn commit(&self) {
+        let data: Arc<[u8]> = self.buffer.get_ref().as_slice().into();
+        self.fs.write().files.insert(self.path.clone(), data);
+    }
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.commit();
+        Ok(())
+    }
+}
+
+impl Drop for MemoryWriter {
+    fn drop(&mut self) {
+        self.commit();
+    }
+}
+
+fn not_found(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("File not found: {:?}", path),
+    )
+}
+
+impl IO for MemoryIO {
+    fn read_file(
+        &self,
+        path: &Path,
+        mode: &Mode,
+    ) -> Result<Box<dyn BufReadSeek + Send + Sync + 'static>, std::io::Error> {
+        let mut fs = self.fs.write();
+
+        if mode.create_new && fs.files.contains_key(path) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("File already exists: {:?}", path),
+            ));
+        }
+
+        let data = match fs.files.get(path) {
+            Some(data) if !mode.truncate => data.clone(),
+            Some(_) => {
+                let empty: Arc<[u8]> = Arc::new([]);
+                fs.files.insert(path.to_owned(), empty.clone());
+                empty
+            }
+            None if mode.create || mode.create_new => {
+                let empty: Arc<[u8]> = Arc::new([]);
+                fs.files.insert(path.to_owned(), empty.clone());
+                empty
+            }
+            None => return Err(not_found(path)),
+        };
+
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn write_file(
+        &self,
+        path: &Path,
+        mode: &Mode,
+    ) -> Result<Box<dyn Write + Send + Sync + 'static>, std::io::Error> {
+        let fs = self.fs.read();
+
+        let existing = fs.files.get(path);
+        if mode.create_new && existing.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("File already exists: {:?}", path),
+            ));
+        }
+        if existing.is_none() && !(mode.create || mode.create_new) {
+            return Err(not_found(path));
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        if let Some(data) = existing {
+            if !mode.truncate {
+                buffer.get_mut().extend_from_slice(data);
+            }
+            if mode.append {
+                buffer.set_position(buffer.get_ref().len() as u64);
+            }
+        }
+        drop(fs);
+
+        let writer = MemoryWriter {
+            fs: self.fs.clone(),
+            path: path.to_owned(),
+            buffer,
+        };
+        // Make the file visible immediately, like creating it on disk would
+        writer.commit();
+
+        Ok(Box::new(writer))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), std::io::Error> {
+        self.fs
+            .write()
+            .files
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn rename_file(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+        let mut fs = self.fs.write();
+        let data = fs.files.remove(from).ok_or_else(|| not_found(from))?;
+        fs.files.insert(to.to_owned(), data);
+
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let fs = self.fs.read();
+        fs.files.contains_key(path) || fs.is_dir(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.fs.read().is_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), std::io::Error> {
+        MemoryIO::create_dir_all(self, path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn write_then_read() {
+        let io = MemoryIO::new();
+        let path = Path::new("/assets/hello.txt");
+
+        {
+            let mut file = io.write_file(path, &Mode::create_and_write()).unwrap();
+            file.write_all(b"Hello").unwrap();
+        }
+
+        let mut contents = String::new();
+        io.read_file(path, &Mode::read_only())
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+
+        assert_eq!(contents, "Hello");
+        assert!(io.exists(path));
+        assert!(io.is_dir(Path::new("/assets")));
+        assert!(!io.is_dir(path));
+    }
+
+    #[test]
+    fn modes() {
+        let io = MemoryIO::new();
+        let path = Path::new("/a.txt");
+
+        assert_eq!(
+            io.read_file(path, &Mode::read_only()).err().unwrap().kind(),
+            std::io::ErrorKind::NotFound
+        );
+
+        io.insert(path, "abc");
+        let mut append = Mode::create_and_write();
+        append.append = true;
+        io.write_file(path, &append).unwrap().write_all(b"def").unwrap();
+        assert_eq!(&*io.get(path).unwrap(), b"abcdef");
+
+        io.write_file(path, &Mode::create_and_write_and_truncate())
+            .unwrap()
+            .write_all(b"x")
+            .unwrap();
+        assert_eq!(&*io.get(path).unwrap(), b"x");
+
+        let (temp_path, mut temp) = io.create_temp_file(path, &Mode::create_and_write()).unwrap();
+        temp.write_all(b"temp").unwrap();
+        drop(temp);
+
+        io.rename_file(&temp_path, path).unwrap();
+        assert_eq!(&*io.get(path).unwrap(), b"temp");
+        assert!(!io.exists(&temp_path));
+
+        io.remove_file(path).unwrap();
+        assert!(!io.exists(path));
+    }
+}
@@ -3,6 +3,12 @@ use std::{
     path::{Path, PathBuf},
 };
 
+mod memory;
+mod pack;
+
+pub use memory::*;
+pub use pack::*;
+
 pub struct Mode {
     pub create: bool,
     pub create_new: bool,
@@ -61,6 +67,11 @@ pub trait IO: Send + Sync + 'static {
     fn remove_file(&self, path: &Path) -> Result<(), std::io::Error>;
     fn rename_file(&self, old: &Path, new: &Path) -> Result<(), std::io::Error>;
 
+    fn exists(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    /// Creates `path` and all of its missing ancestors
+    fn create_dir_all(&self, path: &Path) -> Result<(), std::io::Error>;
+
     fn create_temp_file(
         &self,
         path: &Path,
@@ -140,4 +151,16 @@ impl IO for PhysicalIO {
     fn rename_file(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
         std::fs::rename(from, to)
     }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(path)
+    }
 }
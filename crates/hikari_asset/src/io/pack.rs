@@ -0,0 +1,285 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use super::{BufReadSeek, Mode, IO};
+
+const PACK_MAGIC: &[u8; 8] = b"HKPACK\0\0";
+const PACK_VERSION: u32 = 1;
+
+struct PackEntry {
+    offset: u64,
+    size: u64,
+}
+
+/// Converts a relative path into the form used for pack entries, components separated by '/'
+fn normalize(path: &Path) -> Option<String> {
+    let mut normalized = String::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => {
+                if !normalized.is_empty() {
+                    normalized.push('/');
+                }
+                normalized.push_str(part.to_str()?);
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    Some(normalized)
+}
+
+fn read_only_error(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        format!("Cannot modify {:?}, pack archives are read only", path),
+    )
+}
+
+/// Serves files out of a single pack archive produced by [`PackBuilder`].
+/// Files are addressed as if the archive was extracted at `mount_point`, so the asset directory
+/// of the manager should be set to the same path. The archive is read only, all attempts to modify it fail
+pub struct PackIO {
+    pack_path: PathBuf,
+    mount_point: PathBuf,
+    entries: HashMap<String, PackEntry>,
+}
+
+impl PackIO {
+    pub fn open(pack_path: impl AsRef<Path>, mount_point: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let pack_path = pack_path.as_ref().to_owned();
+        let mut file = std::io::BufReader::new(std::fs::File::open(&pack_path)?);
+
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != PACK_MAGIC {
+            return Err(anyhow::anyhow!("{:?} is not a pack archive", pack_path));
+        }
+
+        let version = read_u32(&mut file)?;
+        if version != PACK_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported pack version {}, expected {}",
+                version,
+                PACK_VERSION
+            ));
+        }
+
+        let count = read_u32(&mut file)?;
+        let mut entries = HashMap::with_capacity(count as usize);
+        for _ in 0..count {
+            let len = read_u32(&mut file)?;
+            let mut path = vec![0; len as usize];
+            file.read_exact(&mut path)?;
+            let path = String::from_utf8(path)?;
+
+            let offset = read_u64(&mut file)?;
+            let size = read_u64(&mut file)?;
+
+            entries.insert(path, PackEntry { offset, size });
+        }
+
+        Ok(Self {
+            pack_path,
+            mount_point: mount_point.as_ref().to_owned(),
+            entries,
+        })
+    }
+    fn entry_name(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.mount_point).ok()?;
+        normalize(relative)
+    }
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entry_name(path)
+            .map(|name| self.entries.contains_key(&name))
+            .unwrap_or(false)
+    }
+}
+
+impl IO for PackIO {
+    fn read_file(
+        &self,
+        path: &Path,
+        mode: &Mode,
+    ) -> Result<Box<dyn BufReadSeek + Send + Sync + 'static>, std::io::Error> {
+        if mode.write || mode.append || mode.truncate || mode.create || mode.create_new {
+            return Err(read_only_error(path));
+        }
+
+        let entry = self
+            .entry_name(path)
+            .and_then(|name| self.entries.get(&name))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("{:?} is not present in pack {:?}", path, self.pack_path),
+                )
+            })?;
+
+        let mut file = std::fs::File::open(&self.pack_path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+
+        let mut data = vec![0; entry.size as usize];
+        file.read_exact(&mut data)?;
+
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn write_file(
+        &self,
+        path: &Path,
+        _mode: &Mode,
+    ) -> Result<Box<dyn Write + Send + Sync + 'static>, std::io::Error> {
+        Err(read_only_error(path))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), std::io::Error> {
+        Err(read_only_error(path))
+    }
+
+    fn rename_file(&self, from: &Path, _to: &Path) -> Result<(), std::io::Error> {
+        Err(read_only_error(from))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.contains(path) || self.is_dir(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let Some(name) = self.entry_name(path) else {
+            return false;
+        };
+        if name.is_empty() {
+            return true;
+        }
+
+        let prefix = format!("{}/", name);
+        self.entries.keys().any(|entry| entry.starts_with(&prefix))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), std::io::Error> {
+        Err(read_only_error(path))
+    }
+}
+
+/// Builds a pack archive readable by [`PackIO`]
+#[derive(Default)]
+pub struct PackBuilder {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl PackBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds the file at `source` to the archive under the relative path `path`
+    pub fn add_file(&mut self, path: impl AsRef<Path>, source: impl AsRef<Path>) -> anyhow::Result<()> {
+        let data = std::fs::read(source)?;
+        self.add_bytes(path, data)
+    }
+    /// Adds `data` to the archive under the relative path `path`, replacing any previous entry
+    pub fn add_bytes(&mut self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let name = normalize(path)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Invalid pack entry path: {:?}", path))?;
+
+        let data = data.into();
+        match self.files.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, existing)) => *existing = data,
+            None => self.files.push((name, data)),
+        }
+
+        Ok(())
+    }
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+    pub fn write(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let header_size = PACK_MAGIC.len() as u64
+            + 4
+            + 4
+            + self
+                .files
+                .iter()
+                .map(|(name, _)| 4 + name.len() as u64 + 8 + 8)
+                .sum::<u64>();
+
+        writer.write_all(PACK_MAGIC)?;
+        writer.write_all(&PACK_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.files.len() as u32).to_le_bytes())?;
+
+        let mut offset = header_size;
+        for (name, data) in &self.files {
+            writer.write_all(&(name.len() as u32).to_le_bytes())?;
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(data.len() as u64).to_le_bytes())?;
+
+            offset += data.len() as u64;
+        }
+
+        for (_, data) in &self.files {
+            writer.write_all(data)?;
+        }
+
+        writer.flush()
+    }
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut file)
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_round_trip() {
+        let mut builder = PackBuilder::new();
+        builder.add_bytes("textures/a.png", b"png data".to_vec()).unwrap();
+        builder.add_bytes("world.hworld", b"entities: []".to_vec()).unwrap();
+
+        let pack_path = std::env::temp_dir().join(format!("hikari_pack_{}.hpack", rand::random::<u32>()));
+        builder.write_to_file(&pack_path).unwrap();
+
+        let mount = Path::new("/game/data");
+        let io = PackIO::open(&pack_path, mount).unwrap();
+
+        let mut contents = String::new();
+        io.read_file(&mount.join("textures/a.png"), &Mode::read_only())
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "png data");
+
+        assert!(io.exists(&mount.join("world.hworld")));
+        assert!(io.is_dir(&mount.join("textures")));
+        assert!(io.is_dir(mount));
+        assert!(!io.exists(&mount.join("missing.txt")));
+        assert!(io
+            .write_file(&mount.join("world.hworld"), &Mode::create_and_write())
+            .is_err());
+
+        std::fs::remove_file(pack_path).unwrap();
+    }
+}
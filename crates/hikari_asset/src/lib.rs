@@ -1,5 +1,6 @@
 mod asset;
 mod handle;
+mod import;
 mod io;
 mod load;
 mod manager;
@@ -7,12 +8,14 @@ mod pool;
 mod record;
 mod save;
 mod status;
+mod watcher;
 
 #[cfg(feature = "serialize")]
 mod serialize;
 
 pub use asset::*;
 pub use handle::*;
+pub use import::*;
 pub use io::*;
 pub use load::*;
 pub use manager::*;
@@ -1,5 +1,6 @@
 use std::{
     any::Any,
+    cell::RefCell,
     collections::HashSet,
     path::{Path, PathBuf},
     sync::Arc,
@@ -16,6 +17,7 @@ pub struct LoadContext {
     asset: Option<Box<dyn Any + Send + Sync + 'static>>,
     reload: bool,
     ass_man: AssetManager,
+    artifact_extension: Option<String>,
     pub(crate) dependencies: Dependencies,
 }
 impl LoadContext {
@@ -37,9 +39,14 @@ impl LoadContext {
             asset: None,
             reload,
             ass_man,
+            artifact_extension: None,
             dependencies: Dependencies::default(),
         }
     }
+    /// Marks the reader as reading an artifact imported from the asset, see [`crate::Importer`]
+    pub(crate) fn set_artifact_extension(&mut self, extension: &str) {
+        self.artifact_extension = Some(extension.to_owned());
+    }
     pub fn io(&self) -> &dyn IO {
         &*self.io
     }
@@ -51,6 +58,14 @@ impl LoadContext {
     pub fn path(&self) -> &Path {
         &self.rel_path
     }
+    /// Extension of the data read by [`LoadContext::reader`].
+    /// For imported assets this is the extension of the artifact rather than of the source file
+    pub fn extension(&self) -> Option<&str> {
+        match &self.artifact_extension {
+            Some(extension) => Some(extension),
+            None => self.rel_path.extension().and_then(|ext| ext.to_str()),
+        }
+    }
     pub fn reader(&mut self) -> &mut impl BufReadSeek {
         &mut self.reader
     }
@@ -71,6 +86,11 @@ impl LoadContext {
     pub fn depends_on<T: Asset>(&mut self, handle: &Handle<T>) {
         self.dependencies.add_dependency(handle);
     }
+    /// Records that the asset uses `handle` without waiting for it to load, see [`AssetManager::references`].
+    /// Handles deserialized while loading are recorded automatically
+    pub fn references<T: Asset>(&mut self, handle: &Handle<T>) {
+        self.dependencies.references.insert(handle.clone_erased_as_weak());
+    }
     pub(crate) fn take_asset<T: Asset>(&mut self) -> Option<T> {
         self.asset
             .take()
@@ -81,6 +101,7 @@ impl LoadContext {
 #[derive(Default)]
 pub(crate) struct Dependencies {
     inner: HashSet<ErasedHandle>,
+    pub(crate) references: HashSet<ErasedHandle>,
 }
 impl Dependencies {
     pub fn add_dependency<T: Asset>(&mut self, handle: &Handle<T>) {
@@ -90,6 +111,28 @@ impl Dependencies {
         self.inner.iter()
     }
 }
+thread_local! {
+    /// Handles loaded by the loader running on this thread
+    static REFERENCES: RefCell<Option<HashSet<ErasedHandle>>> = RefCell::new(None);
+}
+
+/// Runs `f` and returns the handles which were loaded by it on this thread
+pub(crate) fn track_references<R>(f: impl FnOnce() -> R) -> (R, HashSet<ErasedHandle>) {
+    let outer = REFERENCES.with(|references| references.replace(Some(HashSet::new())));
+    let result = f();
+    let references = REFERENCES.with(|references| references.replace(outer));
+
+    (result, references.unwrap_or_default())
+}
+
+pub(crate) fn record_reference(handle: &ErasedHandle) {
+    REFERENCES.with(|references| {
+        if let Some(references) = references.borrow_mut().as_mut() {
+            references.insert(handle.clone_weak());
+        }
+    });
+}
+
 pub trait Loader: Send + Sync + 'static {
     fn extensions(&self) -> &[&str];
     fn load(&self, ctx: &mut LoadContext) -> anyhow::Result<()>;
@@ -1,10 +1,11 @@
 #[cfg(feature = "serialize")]
 use crate::serialize::AnySerde;
 use crate::{
-    record::Record, AsPath, Asset, AssetDB, Dependencies, DynAssetPool, ErasedHandle, Handle, LoadContext, Loader, Mode, PhysicalIO, PoolMut, PoolRef, SaveContext, Saver, Unsaved, IO
+    record::Record, AsPath, Asset, AssetDB, BufReadSeek, Dependencies, DynAssetPool, ErasedHandle, Handle, ImportContext, Importer, LoadContext, Loader, Mode, PhysicalIO, PoolMut, PoolRef, SaveContext, Saver, Unsaved, IO
 };
 
 use crate::status::*;
+use crate::watcher::HotReload;
 
 use anyhow::anyhow;
 use once_cell::sync::OnceCell;
@@ -54,6 +55,7 @@ struct AssetManagerInner {
     load_queue: Arc<LoadQueue>,
     loaders: HashMap<TypeId, Vec<Arc<dyn Loader>>>,
     savers: HashMap<TypeId, Vec<Arc<dyn Saver>>>,
+    importers: HashMap<TypeId, Vec<Arc<dyn Importer>>>,
     // load_statuses: LoadStatuses,
     thread_pool: Arc<ThreadPool>,
     //untyped_loaders: HashMap<TypeId, fn() -> ErasedHandle>,
@@ -62,13 +64,17 @@ struct AssetManagerInner {
     any_serde: AnySerde,
     asset_dir: RwLock<PathBuf>,
     asset_pools: HashMap<TypeId, DynAssetPool>,
+    hot_reload: Mutex<HotReload>,
+    references: Mutex<HashMap<(TypeId, usize), Vec<ErasedHandle>>>,
 }
 impl AssetManagerInner {
     fn get_loader<T: Asset>(&self, path: &Path) -> anyhow::Result<&Arc<dyn Loader>> {
         let file_ext = path
             .extension()
             .ok_or_else(|| anyhow!("Couldn't determine file extension: {:#?}", path))?;
-        let file_ext = file_ext.to_str().unwrap();
+        self.get_loader_for_extension::<T>(file_ext.to_str().unwrap())
+    }
+    fn get_loader_for_extension<T: Asset>(&self, file_ext: &str) -> anyhow::Result<&Arc<dyn Loader>> {
         let loaders = self
             .loaders
             .get(&TypeId::of::<T>())
@@ -87,6 +93,16 @@ impl AssetManagerInner {
             file_ext
         ))
     }
+    fn get_importer<T: Asset>(&self, path: &Path) -> Option<&Arc<dyn Importer>> {
+        let file_ext = path.extension()?.to_str()?.to_lowercase();
+
+        self.importers.get(&TypeId::of::<T>())?.iter().find(|importer| {
+            importer
+                .extensions()
+                .iter()
+                .any(|extension| extension.to_lowercase() == file_ext)
+        })
+    }
     pub fn read_assets<T: Asset>(&self) -> Option<PoolRef<T>> {
         self.asset_pools
             .get(&TypeId::of::<T>())
@@ -107,9 +123,16 @@ impl AssetManagerInner {
         reload: bool,
         io: Arc<dyn IO>,
         loader: Arc<dyn Loader>,
+        importer: Option<Arc<dyn Importer>>,
     ) -> anyhow::Result<(T, Dependencies)> {
-        let abs_path = asset_dir.join(&rel_path);
-        let reader = io.read_file(&abs_path, &Mode::read_only())?;
+        let reader: Box<dyn BufReadSeek + Send + Sync> = match &importer {
+            Some(importer) => {
+                let settings_hash = crate::import::hash_settings(&settings)?;
+                let artifact = Self::import_task::<T>(&asset_dir, &rel_path, &settings, settings_hash, reload, &io, importer)?;
+                Box::new(crate::import::read_artifact(&*io, &asset_dir.join(artifact), &**importer, settings_hash)?)
+            }
+            None => io.read_file(&asset_dir.join(&rel_path), &Mode::read_only())?,
+        };
         let mut ctx = LoadContext::new::<T>(
             asset_dir,
             rel_path,
@@ -119,12 +142,42 @@ impl AssetManagerInner {
             reload,
             get_asset_manager().clone(),
         );
-        loader.load(&mut ctx)?;
+        if let Some(importer) = &importer {
+            ctx.set_artifact_extension(importer.artifact_extension());
+        }
+        let (result, references) = crate::load::track_references(|| loader.load(&mut ctx));
+        result?;
+        ctx.dependencies.references.extend(references);
 
         let asset = ctx.take_asset::<T>().expect("Asset not set during loading");
 
         Ok((asset, ctx.dependencies))
     }
+    /// Imports the asset unless its artifact is up to date, returns the path of the artifact
+    fn import_task<T: Asset>(
+        asset_dir: &Path,
+        rel_path: &Path,
+        settings: &T::Settings,
+        settings_hash: u64,
+        reload: bool,
+        io: &Arc<dyn IO>,
+        importer: &Arc<dyn Importer>,
+    ) -> anyhow::Result<PathBuf> {
+        crate::import::import_artifact(&**io, asset_dir, rel_path, &**importer, settings_hash, |source, writer| {
+            let mut ctx = ImportContext::new::<T>(
+                asset_dir.to_owned(),
+                rel_path.to_owned(),
+                io.clone(),
+                Box::new(std::io::Cursor::new(source)),
+                settings.clone(),
+                reload,
+                get_asset_manager(),
+            );
+            importer.import(&mut ctx, writer)?;
+
+            Ok(ctx.into_dependencies())
+        })
+    }
     fn trigger_load<T: Asset>(
         &self,
         handle: &ErasedHandle,
@@ -132,7 +185,12 @@ impl AssetManagerInner {
         settings: &T::Settings,
         reload: bool,
     ) -> anyhow::Result<()> {
-        let loader = self.get_loader::<T>(path)?.clone();
+        let importer = self.get_importer::<T>(path).cloned();
+        let loader = match &importer {
+            Some(importer) => self.get_loader_for_extension::<T>(importer.artifact_extension())?,
+            None => self.get_loader::<T>(path)?,
+        }
+        .clone();
 
         let io = self.io.clone();
         let asset_dir = self.asset_dir.read().clone();
@@ -141,7 +199,7 @@ impl AssetManagerInner {
         let load_queue = self.load_queue.clone();
         let handle = handle.clone();
         self.thread_pool.spawn(move || {
-            let result = Self::load_task::<T>(asset_dir, rel_path, settings, reload, io, loader);
+            let result = Self::load_task::<T>(asset_dir, rel_path, settings, reload, io, loader, importer);
 
             let load_result = LoadResult { result, handle };
             load_queue
@@ -368,6 +426,7 @@ impl AssetManagerInner {
             writer.flush()?;
         }
         self.io.rename_file(&temp_path, &path)?;
+        self.hot_reload.lock().mark_saved(&path);
 
         self.unsaved.lock().save(&handle.clone_erased_as_internal());
 
@@ -442,9 +501,19 @@ impl AssetManagerInner {
                         let mut pool = self.write_assets::<T>().unwrap();
                         pool.insert_with_handle(handle.index(), data);
 
-                        log::info!(
-                            "Loaded {:?}",
-                            asset_db.handle_to_path(&handle).unwrap()
+                        let path = asset_db.handle_to_path(&handle).unwrap();
+                        log::info!("Loaded {:?}", path);
+
+                        let mut hot_reload = self.hot_reload.lock();
+                        for dependency in dependencies.iter() {
+                            hot_reload.add_dependent(dependency, TypeId::of::<T>(), &path);
+                        }
+                        hot_reload.finish_load(&handle, &path, true);
+                        drop(hot_reload);
+
+                        self.references.lock().insert(
+                            (TypeId::of::<T>(), handle.index()),
+                            dependencies.references.into_iter().collect(),
                         );
 
                         let load_status = asset_db.status_mut(&handle.into()).unwrap();
@@ -462,6 +531,12 @@ impl AssetManagerInner {
                 }
                 Err(err) => {
                     log::error!("{}", err);
+                    if let Some(path) = asset_db.handle_to_path(&result.handle) {
+                        self.hot_reload
+                            .lock()
+                            .finish_load(&result.handle, &path, false);
+                    }
+
                     let load_status = asset_db.status_mut(&result.handle.into()).unwrap();
                     *load_status = LoadStatus::Failed;
                 }
@@ -476,20 +551,81 @@ impl AssetManagerInner {
         .garbage_collect(|index| {
             let mut asset_db = self.asset_db().write();
             remove_unused_handle(&mut asset_db, &(TypeId::of::<T>(), index));
+            self.hot_reload.lock().remove_handle(&(TypeId::of::<T>(), index));
+            self.references.lock().remove(&(TypeId::of::<T>(), index));
         });
         
-        self.queue_update::<T>()
+        self.queue_update::<T>();
+        self.reload_changed::<T>();
+    }
+    /// Reloads assets of type `T` whose files changed on disk, or whose dependencies were reloaded
+    fn reload_changed<T: Asset>(&self) {
+        let pending = {
+            let asset_db = self.asset_db.read();
+            let mut hot_reload = self.hot_reload.lock();
+            if !hot_reload.is_enabled() {
+                return;
+            }
+            hot_reload.collect_changes(&self.asset_dir.read(), &asset_db);
+            hot_reload.take_pending(TypeId::of::<T>())
+        };
+
+        for path in pending {
+            {
+                let asset_db = self.asset_db.read();
+                let Some(handle) = asset_db.path_to_handle(&path) else { continue };
+
+                // Nothing uses the asset anymore, no point in reloading it
+                if handle.strong_count() == 0 {
+                    continue;
+                }
+                // Reloading now could race with the load in progress, try again on the next update
+                if asset_db.status(handle) == Some(LoadStatus::Loading) {
+                    self.hot_reload.lock().requeue(TypeId::of::<T>(), path);
+                    continue;
+                }
+            }
+
+            log::info!("Reloading {:?}", path);
+            self.hot_reload.lock().begin_reload(&path);
+
+            if let Err(err) = self.load::<T>(&path, None, true) {
+                log::error!("Failed to reload {:?}: {}", path, err);
+                self.hot_reload.lock().finish_load_failed(&path);
+            }
+        }
+    }
+    pub fn set_hot_reload(&self, enabled: bool) -> anyhow::Result<()> {
+        let mut hot_reload = self.hot_reload.lock();
+        if enabled {
+            if !hot_reload.is_enabled() {
+                hot_reload.watch(&self.asset_dir.read())?;
+            }
+        } else {
+            hot_reload.unwatch();
+        }
+
+        Ok(())
+    }
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.hot_reload.lock().is_enabled()
     }
     #[cfg(feature = "serialize")]
     pub fn set_asset_dir(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
         let path = path.as_ref();
 
-        assert!(path.is_dir());
+        assert!(self.io.is_dir(path));
         assert!(path.is_absolute());
 
         let old_path = self.asset_dir.upgradable_read();
         if path != &*old_path {
             *parking_lot::RwLockUpgradableReadGuard::upgrade(old_path) = path.to_owned();
+
+            let mut hot_reload = self.hot_reload.lock();
+            if hot_reload.is_enabled() {
+                hot_reload.watch(path)?;
+            }
+            drop(hot_reload);
             
             self.load_db()
         } else {
@@ -505,7 +641,7 @@ impl AssetManagerInner {
         let path = self.asset_dir.read().join("assets.db");
         let io = &self.io;
 
-        if path.exists() {
+        if io.exists(&path) {
             let mut path_olded = path.clone();
             path_olded.set_extension("db.old");
 
@@ -528,7 +664,7 @@ impl AssetManagerInner {
         let path = self.asset_dir.read().join("assets.db");
         let io = &self.io;
 
-        if path.exists() {
+        if io.exists(&path) {
             let reader = io.read_file(&path, &Mode::read_only())?;
             let deserializer = serde_yaml::Deserializer::from_reader(reader);
             let asset_db = AssetDB::deserialize(deserializer, &self.any_serde)?;
@@ -540,6 +676,13 @@ impl AssetManagerInner {
     pub fn status(&self, handle: &ErasedHandle) -> Option<LoadStatus> {
         self.asset_db.read().status(handle)
     }
+    pub fn references(&self, handle: &ErasedHandle) -> Vec<ErasedHandle> {
+        self.references
+            .lock()
+            .get(&(handle.type_id_asset(), handle.index()))
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 impl Drop for AssetManagerInner {
     fn drop(&mut self) {
@@ -552,10 +695,12 @@ pub struct AssetManagerBuilder {
     asset_db: RwLock<AssetDB>,
     loaders: HashMap<TypeId, Vec<Arc<dyn Loader>>>,
     savers: HashMap<TypeId, Vec<Arc<dyn Saver>>>,
+    importers: HashMap<TypeId, Vec<Arc<dyn Importer>>>,
     load_queue: LoadQueue,
     //untyped_loaders: HashMap<TypeId, fn() -> ErasedHandle>,
     io: Option<Arc<dyn IO>>,
     asset_dir: Option<PathBuf>,
+    hot_reload: bool,
     #[cfg(feature = "serialize")]
     any_serde: AnySerde,
 }
@@ -567,10 +712,12 @@ impl AssetManagerBuilder {
             asset_db: RwLock::new(AssetDB::new()),
             loaders: HashMap::new(),
             savers: HashMap::new(),
+            importers: HashMap::new(),
             load_queue: LoadQueue::new(),
             //untyped_loaders: HashMap::new(),
             io: None,
             asset_dir: None,
+            hot_reload: false,
             #[cfg(feature = "serialize")]
             any_serde: AnySerde::new(),
         }
@@ -585,6 +732,13 @@ impl AssetManagerBuilder {
 
         self
     }
+    /// Watches the asset directory for changes and reloads modified assets along with the assets depending on them.
+    /// Only meaningful with [`PhysicalIO`]
+    pub fn hot_reload(&mut self, enabled: bool) -> &mut Self {
+        self.hot_reload = enabled;
+
+        self
+    }
     pub fn register_asset_type<T: Asset>(&mut self) -> &mut Self {
         let existing = self
             .asset_pools
@@ -596,6 +750,7 @@ impl AssetManagerBuilder {
 
         self.loaders.insert(TypeId::of::<T>(), Vec::new());
         self.savers.insert(TypeId::of::<T>(), Vec::new());
+        self.importers.insert(TypeId::of::<T>(), Vec::new());
 
         #[cfg(feature = "serialize")]
         self.any_serde.register_type::<T::Settings>();
@@ -621,6 +776,17 @@ impl AssetManagerBuilder {
 
         self
     }
+    /// Source files handled by `importer` are imported into artifacts before loading, see [`Importer`].
+    /// A loader for the artifacts must be registered as well
+    pub fn register_importer<T: Asset, I: Importer>(&mut self, importer: I) -> &mut Self {
+        let importers = self
+            .importers
+            .get_mut(&TypeId::of::<T>())
+            .expect("Asset type not registered");
+        importers.push(Arc::new(importer));
+
+        self
+    }
     #[cfg(feature = "serialize")]
     pub fn set_asset_dir(&mut self, path: impl AsRef<Path>) -> &mut Self {
         self.asset_dir = Some(path.as_ref().to_owned());
@@ -636,6 +802,7 @@ impl AssetManagerBuilder {
         let asset_pools = self.asset_pools;
         let loaders = self.loaders;
         let savers = self.savers;
+        let importers = self.importers;
         let load_queue = self.load_queue;
 
         #[cfg(feature = "serialize")]
@@ -647,11 +814,14 @@ impl AssetManagerBuilder {
             asset_pools,
             loaders,
             savers,
+            importers,
             #[cfg(feature = "serialize")]
             any_serde,
             load_queue: Arc::new(load_queue),
             asset_dir: RwLock::new(PathBuf::new()),
             unsaved: Mutex::new(Unsaved::default()),
+            hot_reload: Mutex::new(HotReload::default()),
+            references: Mutex::new(HashMap::new()),
         };
         let asset_manager = AssetManager::new(asset_manager);
               
@@ -660,6 +830,10 @@ impl AssetManagerBuilder {
         #[cfg(feature = "serialize")]
         asset_manager.set_asset_dir(asset_dir)?;
 
+        if self.hot_reload {
+            asset_manager.set_hot_reload(true)?;
+        }
+
         Ok(asset_manager)
     }
 }
@@ -741,6 +915,13 @@ impl AssetManager {
     pub fn update<T: Asset>(&self) {
         self.inner.update::<T>()
     }
+    /// Starts or stops watching the asset directory for changes, see [`AssetManagerBuilder::hot_reload`]
+    pub fn set_hot_reload(&self, enabled: bool) -> anyhow::Result<()> {
+        self.inner.set_hot_reload(enabled)
+    }
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.inner.is_hot_reload_enabled()
+    }
     #[cfg(feature = "serialize")]
     pub fn set_asset_dir(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
         self.inner.set_asset_dir(path)
@@ -760,6 +941,11 @@ impl AssetManager {
     pub fn status(&self, handle: &ErasedHandle) -> Option<LoadStatus> {
         self.inner.status(handle)
     }
+    /// Weak handles of the assets which were loaded while loading `handle`, like the meshes and materials used by a world.
+    /// Unlike dependencies they don't hold back the asset, so they may still be loading once it is loaded
+    pub fn references(&self, handle: &ErasedHandle) -> Vec<ErasedHandle> {
+        self.inner.references(handle)
+    }
 }
 
 static ASSET_MANAGER: OnceCell<Weak<AssetManagerInner>> = OnceCell::new();
@@ -97,18 +97,41 @@ impl<'de, T: Asset> Visitor<'de> for HandleVisitor<T> {
         }
 
         let uuid = uuid.ok_or_else(|| de::Error::missing_field("uuid"))?;
+        self.load(uuid)
+    }
+    /// Binary formats encode the fields in order without their names
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        use std::path::PathBuf;
+        use uuid::Uuid;
+
+        let uuid: Uuid = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let _path: PathBuf = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        self.load(uuid)
+    }
+}
+impl<T: Asset> HandleVisitor<T> {
+    fn load<E: de::Error>(&self, uuid: uuid::Uuid) -> Result<Handle<T>, E> {
         let asset_manager = crate::manager::get_asset_manager();
 
         let handle = if self.lazy {
             asset_manager.load_lazy(uuid, None)
         } else {
-            asset_manager.load(uuid, None, false)
+            let handle = asset_manager.load(uuid, None, false);
+            if let Ok(handle) = &handle {
+                crate::load::record_reference(&handle.clone_erased());
+            }
+            handle
         };
 
-        let handle =
-            handle.map_err(|err| de::Error::custom(&format!("Failed to load asset: {}", err)))?;
-
-        Ok(handle)
+        handle.map_err(|err| de::Error::custom(&format!("Failed to load asset: {}", err)))
     }
 }
 
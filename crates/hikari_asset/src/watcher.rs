@@ -0,0 +1,279 @@
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::{Duration, SystemTime},
+};
+
+use crate::{AssetDB, ErasedHandle};
+
+/// Watches a directory on the physical filesystem by periodically polling modification times.
+/// Paths of files which were modified or created since the previous poll are sent through `changed`, along with their modification time
+pub(crate) struct FileWatcher {
+    changed: flume::Receiver<(PathBuf, SystemTime)>,
+    stop: Option<flume::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FileWatcher {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(root: impl AsRef<Path>, interval: Duration) -> std::io::Result<Self> {
+        let root = root.as_ref().to_owned();
+
+        let mut snapshot = HashMap::new();
+        scan(&root, &mut snapshot)?;
+
+        let (changed_send, changed) = flume::unbounded();
+        let (stop, stop_recv) = flume::bounded::<()>(1);
+
+        let thread_root = root;
+        let thread = std::thread::Builder::new()
+            .name(String::from("Hikari Asset Watcher"))
+            .spawn(move || loop {
+                match stop_recv.recv_timeout(interval) {
+                    Err(flume::RecvTimeoutError::Timeout) => {}
+                    _ => return,
+                }
+
+                let mut current = HashMap::with_capacity(snapshot.len());
+                if let Err(err) = scan(&thread_root, &mut current) {
+                    log::warn!("Failed to scan {:?} for changes: {}", thread_root, err);
+                    continue;
+                }
+
+                for (path, modified) in &current {
+                    if snapshot.get(path) != Some(modified) && changed_send.send((path.clone(), *modified)).is_err() {
+                        return;
+                    }
+                }
+
+                snapshot = current;
+            })?;
+
+        Ok(Self {
+            changed,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+    /// Returns paths of files that have changed since the last call, without blocking
+    pub fn changed(&self) -> flume::TryIter<'_, (PathBuf, SystemTime)> {
+        self.changed.try_iter()
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        // Disconnecting the stop channel wakes up the watcher thread immediately
+        self.stop.take();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Bookkeeping for hot reloading.
+/// Changed files are mapped to the type of asset they hold, so that each `AssetManager::update::<T>` only reloads its own assets
+#[derive(Default)]
+pub(crate) struct HotReload {
+    watcher: Option<FileWatcher>,
+    pending: HashMap<TypeId, HashSet<PathBuf>>,
+    /// Assets being reloaded because they changed, their dependents are reloaded once they finish loading
+    in_flight: HashSet<PathBuf>,
+    /// Files written by the manager itself along with the modification time of the write, only that exact change doesn't need to be reloaded
+    saved: HashMap<PathBuf, SystemTime>,
+    /// Maps an asset to the assets which depend on it
+    dependents: HashMap<(TypeId, usize), HashSet<(TypeId, PathBuf)>>,
+}
+
+impl HotReload {
+    pub fn is_enabled(&self) -> bool {
+        self.watcher.is_some()
+    }
+    pub fn watch(&mut self, asset_dir: &Path) -> std::io::Result<()> {
+        // Drop the old watcher first so that two threads don't scan at the same time
+        self.watcher = None;
+        self.watcher = Some(FileWatcher::new(asset_dir, FileWatcher::DEFAULT_INTERVAL)?);
+        self.saved.clear();
+
+        Ok(())
+    }
+    pub fn unwatch(&mut self) {
+        self.watcher = None;
+        self.saved.clear();
+    }
+    /// Drains the watcher, skipping the writes recorded by [`HotReload::mark_saved`]
+    fn external_changes(&mut self) -> Vec<PathBuf> {
+        let Some(watcher) = &self.watcher else { return Vec::new() };
+
+        watcher
+            .changed()
+            .filter(|(abs_path, modified)| {
+                // A save which didn't change the modification time is never reported, so the entry must not swallow a later change
+                self.saved.remove(abs_path) != Some(*modified)
+            })
+            .map(|(abs_path, _)| abs_path)
+            .collect()
+    }
+    /// Drains the watcher and queues all changed files which have a loaded asset associated with them
+    pub fn collect_changes(&mut self, asset_dir: &Path, asset_db: &AssetDB) {
+        for abs_path in self.external_changes() {
+            let Ok(path) = abs_path.strip_prefix(asset_dir) else { continue };
+            let Some(handle) = asset_db.path_to_handle(path) else { continue };
+
+            log::debug!("Detected change in {:?}", path);
+            self.pending
+                .entry(handle.type_id_asset())
+                .or_default()
+                .insert(path.to_owned());
+        }
+    }
+    pub fn take_pending(&mut self, type_id: TypeId) -> HashSet<PathBuf> {
+        self.pending.remove(&type_id).unwrap_or_default()
+    }
+    pub fn requeue(&mut self, type_id: TypeId, path: PathBuf) {
+        self.pending.entry(type_id).or_default().insert(path);
+    }
+    pub fn begin_reload(&mut self, path: &Path) {
+        self.in_flight.insert(path.to_owned());
+    }
+    /// Called when a load finishes, queues dependents of the asset if it was reloaded because of a change
+    pub fn finish_load(&mut self, handle: &ErasedHandle, path: &Path, success: bool) {
+        if !self.in_flight.remove(path) || !success {
+            return;
+        }
+
+        if let Some(dependents) = self.dependents.get(&(handle.type_id_asset(), handle.index())) {
+            for (type_id, dependent) in dependents {
+                self.pending
+                    .entry(*type_id)
+                    .or_default()
+                    .insert(dependent.clone());
+            }
+        }
+    }
+    pub fn finish_load_failed(&mut self, path: &Path) {
+        self.in_flight.remove(path);
+    }
+    pub fn add_dependent(&mut self, dependency: &ErasedHandle, type_id: TypeId, path: &Path) {
+        self.dependents
+            .entry((dependency.type_id_asset(), dependency.index()))
+            .or_default()
+            .insert((type_id, path.to_owned()));
+    }
+    pub fn mark_saved(&mut self, abs_path: &Path) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        match std::fs::metadata(abs_path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => {
+                self.saved.insert(abs_path.to_owned(), modified);
+            }
+            Err(_) => {
+                self.saved.remove(abs_path);
+            }
+        }
+    }
+    pub fn remove_handle(&mut self, handle: &(TypeId, usize)) {
+        self.dependents.remove(handle);
+    }
+}
+
+fn scan(dir: &Path, files: &mut HashMap<PathBuf, SystemTime>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            // Directories can vanish in between listing and scanning them
+            let _ = scan(&entry.path(), files);
+        } else if file_type.is_file() {
+            if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+                files.insert(entry.path(), modified);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_modification() {
+        let dir = std::env::temp_dir().join(format!("hikari_watcher_{}", rand::random::<u32>()));
+        std::fs::create_dir_all(dir.join("textures")).unwrap();
+
+        let file = dir.join("textures/albedo.png");
+        std::fs::write(&file, b"old").unwrap();
+        std::fs::write(dir.join("untouched.txt"), b"untouched").unwrap();
+
+        let watcher = FileWatcher::new(&dir, Duration::from_millis(10)).unwrap();
+
+        // Make sure the modification time differs even on filesystems with coarse timestamps
+        std::thread::sleep(Duration::from_millis(50));
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(2))
+            .unwrap();
+
+        let changed = watcher.changed.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changed.0, file);
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(watcher.changed().all(|(changed, _)| changed == file));
+
+        drop(watcher);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn skips_only_saved_changes() {
+        let dir = std::env::temp_dir().join(format!("hikari_watcher_{}", rand::random::<u32>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("material.hmat");
+        std::fs::write(&file, b"old").unwrap();
+
+        let mut hot_reload = HotReload {
+            watcher: Some(FileWatcher::new(&dir, Duration::from_millis(10)).unwrap()),
+            ..Default::default()
+        };
+        let set_modified = |path: &Path, offset: u64| {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(SystemTime::now() + Duration::from_secs(offset))
+                .unwrap();
+        };
+        let wait_for_changes = |hot_reload: &mut HotReload| {
+            std::thread::sleep(Duration::from_millis(100));
+            hot_reload.external_changes()
+        };
+
+        // Saves are written to a temporary file first, so the watcher only sees the final modification time
+        let temp_file = dir.with_extension("tmp");
+        std::fs::write(&temp_file, b"saved").unwrap();
+        set_modified(&temp_file, 2);
+        std::fs::rename(&temp_file, &file).unwrap();
+        hot_reload.mark_saved(&file);
+        assert!(wait_for_changes(&mut hot_reload).is_empty());
+
+        // A save which leaves the modification time as is doesn't hide the next change
+        hot_reload.mark_saved(&file);
+        assert!(wait_for_changes(&mut hot_reload).is_empty());
+        set_modified(&file, 4);
+        assert_eq!(wait_for_changes(&mut hot_reload), vec![file.clone()]);
+
+        drop(hot_reload);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+use std::any::TypeId;
+
+use bincode::Options;
+use rkyv::AlignedVec;
+use uuid::Uuid;
+
+use crate::{
+    serialize::{ComponentSerialize, UnknownComponent, UnknownComponents},
+    Entity, EntityId, Registry, World,
+};
+
+const MAGIC_NUM: [u8; 8] = *b"hkworld\0";
+const FORMAT_VERSION: u32 = 2;
+
+/// The entity table of a binary world, read in place without parsing.
+/// Component data is encoded with bincode through the serde implementations registered in the [`Registry`],
+/// keyed by the same component uuids as the YAML format
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+struct WorldFile {
+    magic: [u8; 8],
+    version: u32,
+    entities: Vec<EntityRecord>,
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+struct EntityRecord {
+    entity: u64,
+    uuid: [u8; 16],
+    name: String,
+    components: Vec<ComponentRecord>,
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive(check_bytes)]
+struct ComponentRecord {
+    uuid: [u8; 16],
+    /// Schema version of the component, see [`crate::RegistryBuilder::register_serde_versioned`]
+    version: u32,
+    data: Vec<u8>,
+}
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+impl World {
+    /// Encodes the world in the binary world format
+    pub fn to_binary(&self, registry: &Registry) -> anyhow::Result<AlignedVec> {
+        hikari_dev::profile_function!();
+
+        let mut entities = Vec::with_capacity(self.len());
+        for entity_ref in self.entities() {
+            let entity = entity_ref.entity();
+            let id = self.entity_id(entity)?;
+
+            let mut components = Vec::new();
+            for type_id in entity_ref.component_types() {
+                if let Some(uuid) = registry.type_id_to_uuid(type_id) {
+                    let data = bincode_options().serialize(&ComponentSerialize(registry, entity_ref, *uuid))?;
+                    components.push(ComponentRecord {
+                        uuid: *uuid.as_bytes(),
+                        version: registry.component_version(uuid),
+                        data,
+                    });
+                } else if type_id == TypeId::of::<UnknownComponents>() {
+                    let unknown = entity_ref.get::<&UnknownComponents>().unwrap();
+                    for entry in &unknown.entries {
+                        match entry {
+                            UnknownComponent::Binary { uuid, version, data } => components.push(ComponentRecord {
+                                uuid: *uuid.as_bytes(),
+                                version: *version,
+                                data: data.clone(),
+                            }),
+                            UnknownComponent::Yaml { key, .. } => {
+                                log::warn!("Dropping component {} which was loaded from a YAML world", key)
+                            }
+                        }
+                    }
+                } else if !crate::serialize::is_derived_component(type_id) {
+                    log::warn!("Skipping serializing typeid: {:#?}", type_id);
+                }
+            }
+
+            entities.push(EntityRecord {
+                entity: entity.to_bits().get(),
+                uuid: *id.uuid.as_bytes(),
+                name: id.name.clone(),
+                components,
+            });
+        }
+
+        let file = WorldFile {
+            magic: MAGIC_NUM,
+            version: FORMAT_VERSION,
+            entities,
+        };
+
+        rkyv::to_bytes::<_, 4096>(&file).map_err(|err| anyhow::anyhow!("Failed to archive world: {}", err))
+    }
+    /// Decodes a world written by [`World::to_binary`]
+    pub fn from_binary(bytes: &[u8], registry: &Registry) -> anyhow::Result<World> {
+        hikari_dev::profile_function!();
+
+        // Archives must be read from aligned memory
+        let aligned;
+        let bytes = if bytes.as_ptr() as usize % AlignedVec::ALIGNMENT == 0 {
+            bytes
+        } else {
+            let mut copy = AlignedVec::with_capacity(bytes.len());
+            copy.extend_from_slice(bytes);
+            aligned = copy;
+            aligned.as_slice()
+        };
+
+        let file = rkyv::check_archived_root::<WorldFile>(bytes)
+            .map_err(|err| anyhow::anyhow!("Invalid binary world: {}", err))?;
+
+        if file.magic != MAGIC_NUM {
+            return Err(anyhow::anyhow!("Not a binary world"));
+        }
+        if file.version != FORMAT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported binary world version {}, expected {}",
+                file.version,
+                FORMAT_VERSION
+            ));
+        }
+
+        let mut world = World::new();
+        for record in file.entities.iter() {
+            let entity = Entity::from_bits(record.entity)
+                .ok_or_else(|| anyhow::anyhow!("Invalid entity handle: {}", record.entity))?;
+            let id = EntityId {
+                name: record.name.as_str().to_owned(),
+                uuid: Uuid::from_bytes(record.uuid),
+            };
+            world.create_entity_at(entity, (id,));
+
+            for component in record.components.iter() {
+                let uuid = Uuid::from_bytes(component.uuid);
+
+                // Bincode isn't self describing, so components can't be migrated from binary worlds.
+                // They are kept as is until the world is converted again from its YAML source
+                if !registry.has_serde(&uuid) || component.version != registry.component_version(&uuid) {
+                    log::warn!("Preserving component {} with version {}", uuid, component.version);
+                    UnknownComponents::push(
+                        &mut world,
+                        entity,
+                        UnknownComponent::Binary {
+                            uuid,
+                            version: component.version,
+                            data: component.data.to_vec(),
+                        },
+                    );
+                    continue;
+                }
+
+                let mut deserializer = bincode::Deserializer::from_slice(&component.data, bincode_options());
+                registry.deserialize_component(&uuid, entity, &mut world, &mut deserializer)?;
+            }
+        }
+
+        world.relink_hierarchy();
+        world.resolve_entity_links(registry);
+
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use type_uuid::TypeUuid;
+
+    use crate::{Registry, World};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize, TypeUuid)]
+    #[uuid = "6f3c2a9e-1d4b-4c8a-9e7f-5b2d8c1a0e43"]
+    enum Shape {
+        Sphere { radius: f32 },
+        Box([f32; 3]),
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mut registry = Registry::builder();
+        registry.register_serde::<Shape>();
+        let registry = registry.build();
+
+        let mut world = World::new();
+        let parent = world.create_entity_with_name("parent");
+        let child = world.create_entity_with((Shape::Sphere { radius: 2.0 },));
+        world.add_component(parent, Shape::Box([1.0, 2.0, 3.0])).unwrap();
+        world.set_parent(child, parent).unwrap();
+
+        let bytes = world.to_binary(&registry).unwrap();
+        let loaded = World::from_binary(&bytes, &registry).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.entity_id(parent).unwrap().name, "parent");
+        assert_eq!(
+            loaded.entity_uuid(child).unwrap(),
+            world.entity_uuid(child).unwrap()
+        );
+        assert_eq!(
+            *loaded.get_component::<&Shape>(child).unwrap(),
+            Shape::Sphere { radius: 2.0 }
+        );
+        assert_eq!(
+            *loaded.get_component::<&Shape>(parent).unwrap(),
+            Shape::Box([1.0, 2.0, 3.0])
+        );
+        assert_eq!(loaded.parent(child), Some(parent));
+        assert_eq!(loaded.children(parent), vec![child]);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        let registry = Registry::builder().build();
+
+        assert!(World::from_binary(&[0; 64], &registry).is_err());
+    }
+}
@@ -0,0 +1,615 @@
+use std::collections::{HashMap, HashSet};
+
+use hecs::{ComponentError, MissingComponent, NoSuchEntity};
+use hikari_math::{Mat4, Quat, Transform, Vec3};
+use uuid::Uuid;
+
+use crate::{Entity, EntityId, World};
+
+/// Links an entity to its parent.
+/// The parent is persisted by its [`EntityId`] uuid, so that the link survives save/load
+#[derive(Clone, Copy, Debug, PartialEq, Eq, type_uuid::TypeUuid)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[uuid = "fe80b6fa-e1fc-4573-aff4-e70abb02a57a"]
+pub struct Parent {
+    #[cfg_attr(feature = "serde", serde(skip, default = "dangling_entity"))]
+    entity: Entity,
+    uuid: Uuid,
+}
+
+#[cfg(feature = "serde")]
+fn dangling_entity() -> Entity {
+    Entity::DANGLING
+}
+
+impl Parent {
+    pub(crate) fn new(entity: Entity, uuid: Uuid) -> Self {
+        Self { entity, uuid }
+    }
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+}
+
+/// Children of an entity in the order they were attached.
+/// This is derived from [`Parent`] and is never serialized
+#[derive(Clone, Debug, Default)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn iter(&self) -> std::slice::Iter<'_, Entity> {
+        self.0.iter()
+    }
+    pub fn as_slice(&self) -> &[Entity] {
+        &self.0
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.0.contains(&entity)
+    }
+}
+
+/// World space transform of an entity, computed every frame from the [`Transform`]s of the entity and its ancestors
+#[derive(Clone, Copy, Debug)]
+pub struct GlobalTransform {
+    matrix: Mat4,
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self {
+            matrix: Mat4::IDENTITY,
+        }
+    }
+}
+
+impl GlobalTransform {
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        Self { matrix }
+    }
+    #[inline]
+    pub fn matrix(&self) -> Mat4 {
+        self.matrix
+    }
+    #[inline]
+    pub fn position(&self) -> Vec3 {
+        self.matrix.w_axis.truncate()
+    }
+    pub fn rotation(&self) -> Quat {
+        let (_, rotation, _) = self.matrix.to_scale_rotation_translation();
+        rotation
+    }
+    pub fn forward(&self) -> Vec3 {
+        self.matrix.transform_vector3(Vec3::Z).normalize()
+    }
+    pub fn up(&self) -> Vec3 {
+        self.matrix.transform_vector3(Vec3::Y).normalize()
+    }
+    pub fn right(&self) -> Vec3 {
+        self.matrix.transform_vector3(Vec3::X).normalize()
+    }
+    pub fn to_transform(&self) -> Transform {
+        Transform::from_matrix(self.matrix)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HierarchyError {
+    NoSuchEntity,
+    MissingComponent(MissingComponent),
+    /// The new parent is the entity itself or one of its descendants
+    Cycle,
+}
+
+impl std::fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HierarchyError::NoSuchEntity => f.write_str("no such entity"),
+            HierarchyError::MissingComponent(missing) => write!(f, "{}", missing),
+            HierarchyError::Cycle => {
+                f.write_str("an entity cannot be parented to itself or one of its descendants")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
+impl From<NoSuchEntity> for HierarchyError {
+    fn from(_: NoSuchEntity) -> Self {
+        HierarchyError::NoSuchEntity
+    }
+}
+
+impl From<ComponentError> for HierarchyError {
+    fn from(err: ComponentError) -> Self {
+        match err {
+            ComponentError::NoSuchEntity => HierarchyError::NoSuchEntity,
+            ComponentError::MissingComponent(missing) => HierarchyError::MissingComponent(missing),
+        }
+    }
+}
+
+impl World {
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.raw()
+            .get::<&Parent>(entity)
+            .ok()
+            .map(|parent| parent.entity)
+            .filter(|&parent| self.contains(parent))
+    }
+    /// Returns the children of `entity`, in the order they were attached
+    pub fn children(&self, entity: Entity) -> Vec<Entity> {
+        self.raw()
+            .get::<&Children>(entity)
+            .map(|children| children.0.clone())
+            .unwrap_or_default()
+    }
+    /// Returns all descendants of `entity` in depth first order, not including `entity` itself
+    pub fn descendants(&self, entity: Entity) -> Vec<Entity> {
+        let mut descendants = Vec::new();
+        let mut stack = self.children(entity);
+        stack.reverse();
+
+        while let Some(current) = stack.pop() {
+            descendants.push(current);
+
+            let children = self.children(current);
+            stack.extend(children.iter().rev());
+        }
+
+        descendants
+    }
+    /// Computes the world space matrix of `entity` by walking up its ancestors.
+    /// Unlike [`GlobalTransform`], this is never out of date
+    pub fn world_matrix(&self, entity: Entity) -> Result<Mat4, NoSuchEntity> {
+        if !self.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+
+        let mut matrix = Mat4::IDENTITY;
+        let mut current = Some(entity);
+
+        while let Some(entity) = current {
+            if let Ok(transform) = self.raw().get::<&Transform>(entity) {
+                matrix = transform.get_matrix() * matrix;
+            }
+
+            current = self.parent(entity);
+        }
+
+        Ok(matrix)
+    }
+    /// Makes `child` a child of `parent`.
+    /// The local transform of `child` is adjusted so that its world position doesn't change
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), HierarchyError> {
+        if !self.contains(child) || !self.contains(parent) {
+            return Err(HierarchyError::NoSuchEntity);
+        }
+
+        let mut ancestor = Some(parent);
+        while let Some(current) = ancestor {
+            if current == child {
+                return Err(HierarchyError::Cycle);
+            }
+            ancestor = self.parent(current);
+        }
+
+        let child_world = self.world_matrix(child)?;
+        let parent_world = self.world_matrix(parent)?;
+        let uuid = self.entity_uuid(parent)?;
+
+        self.unlink_from_parent(child);
+
+        self.raw_mut().insert_one(
+            child,
+            Parent {
+                entity: parent,
+                uuid,
+            },
+        )?;
+        self.add_child(parent, child);
+
+        self.set_local_matrix(child, parent_world.inverse() * child_world)?;
+
+        Ok(())
+    }
+    /// Removes `child` from its parent, making it a root entity.
+    /// The local transform of `child` is adjusted so that its world position doesn't change
+    pub fn detach(&mut self, child: Entity) -> Result<(), HierarchyError> {
+        let child_world = self.world_matrix(child)?;
+
+        if self.unlink_from_parent(child) {
+            self.set_local_matrix(child, child_world)?;
+        }
+
+        Ok(())
+    }
+    /// Removes `entity` along with all of its descendants
+    pub fn remove_entity_recursive(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        if !self.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+
+        self.unlink_from_parent(entity);
+
+        for descendant in self.descendants(entity) {
+            self.raw_mut().despawn(descendant)?;
+        }
+
+        self.raw_mut().despawn(entity)
+    }
+    /// Updates the [`GlobalTransform`] of every entity which has a [`Transform`].
+    /// Like in [`World::world_matrix`], entities without a [`Transform`] count as identity for their descendants
+    pub fn propagate_transforms(&mut self) {
+        hikari_dev::profile_function!();
+
+        let mut matrices = Vec::with_capacity(self.len());
+        let mut stack = Vec::new();
+
+        for (entity, parent) in self.raw().query::<Option<&Parent>>().iter() {
+            let is_root = match parent {
+                Some(parent) => !self.raw().contains(parent.entity),
+                None => true,
+            };
+
+            if is_root {
+                stack.push((entity, Mat4::IDENTITY));
+            }
+        }
+
+        while let Some((entity, parent_matrix)) = stack.pop() {
+            let matrix = match self.raw().get::<&Transform>(entity) {
+                Ok(transform) => {
+                    let matrix = parent_matrix * transform.get_matrix();
+                    matrices.push((entity, matrix));
+                    matrix
+                }
+                Err(_) => parent_matrix,
+            };
+
+            if let Ok(children) = self.raw().get::<&Children>(entity) {
+                stack.extend(children.iter().map(|&child| (child, matrix)));
+            }
+        }
+
+        let mut missing = Vec::new();
+        for (entity, matrix) in matrices {
+            match self.raw().get::<&mut GlobalTransform>(entity) {
+                Ok(mut global) => global.matrix = matrix,
+                Err(_) => missing.push((entity, GlobalTransform::from_matrix(matrix))),
+            }
+        }
+
+        for (entity, global) in missing {
+            self.raw_mut().insert_one(entity, global).unwrap();
+        }
+    }
+    /// Resolves every [`Parent`] by the uuid of its entity and rebuilds all [`Children`].
+    /// This needs to be called whenever entities are created with a [`Parent`] that may point to a stale entity, e.g. after deserialization.
+    /// Parents which form a cycle are broken up by detaching one entity of the cycle
+    pub fn relink_hierarchy(&mut self) {
+        let entities: Vec<Entity> = self.entities().map(|entity| entity.entity()).collect();
+        self.relink_hierarchy_of(&entities);
+    }
+    /// Like [`World::relink_hierarchy`], but only for `entities`, whose parents have to be among them as well.
+    /// Used when entities are added to a world whose other entities are already linked
+    pub(crate) fn relink_hierarchy_of(&mut self, entities: &[Entity]) {
+        let uuid_to_entity: HashMap<Uuid, Entity> = entities
+            .iter()
+            .filter_map(|&entity| Some((self.entity_uuid(entity).ok()?, entity)))
+            .collect();
+
+        for &entity in entities {
+            if let Ok(mut children) = self.raw().get::<&mut Children>(entity) {
+                children.0.clear();
+            }
+        }
+
+        let mut links = Vec::new();
+        let mut orphans = Vec::new();
+        for &entity in entities {
+            let Ok(mut parent) = self.raw().get::<&mut Parent>(entity) else { continue };
+            match uuid_to_entity.get(&parent.uuid) {
+                Some(&parent_entity) if parent_entity != entity => {
+                    parent.entity = parent_entity;
+                    links.push((parent_entity, entity));
+                }
+                _ => orphans.push(entity),
+            }
+        }
+
+        for orphan in orphans {
+            log::warn!("Parent of entity {:?} doesn't exist, detaching", orphan);
+            let _ = self.raw_mut().remove_one::<Parent>(orphan);
+        }
+
+        // Keeps the order of children stable across loads
+        links.sort_by_key(|&(_, child)| child.id());
+
+        let cycles = find_cycles(&links);
+        for &entity in &cycles {
+            log::warn!("Entity {:?} is part of a parent cycle, detaching", entity);
+            let _ = self.raw_mut().remove_one::<Parent>(entity);
+        }
+        links.retain(|(_, child)| !cycles.contains(child));
+
+        for (parent, child) in links {
+            self.add_child(parent, child);
+        }
+    }
+    /// Removes `entity` from the children of its parent and removes its [`Parent`] component.
+    /// Returns true if `entity` had a parent
+    pub(crate) fn unlink_from_parent(&mut self, entity: Entity) -> bool {
+        let Ok(parent) = self.raw_mut().remove_one::<Parent>(entity) else {
+            return false;
+        };
+
+        if let Ok(mut children) = self.raw().get::<&mut Children>(parent.entity) {
+            children.0.retain(|&child| child != entity);
+        }
+
+        true
+    }
+    /// Detaches all children of `entity` and removes it from its parent
+    pub(crate) fn unlink_hierarchy(&mut self, entity: Entity) {
+        self.unlink_from_parent(entity);
+
+        for child in self.children(entity) {
+            let _ = self.detach(child);
+        }
+    }
+    pub(crate) fn add_child(&mut self, parent: Entity, child: Entity) {
+        let added = match self.raw().get::<&mut Children>(parent) {
+            Ok(mut children) => {
+                if !children.contains(child) {
+                    children.0.push(child);
+                }
+                true
+            }
+            Err(_) => false,
+        };
+
+        if !added {
+            let _ = self.raw_mut().insert_one(parent, Children(vec![child]));
+        }
+    }
+    fn set_local_matrix(&mut self, entity: Entity, matrix: Mat4) -> Result<(), NoSuchEntity> {
+        let local = Transform::from_matrix(matrix);
+
+        match self.raw().get::<&mut Transform>(entity) {
+            Ok(mut transform) => {
+                *transform = local;
+                Ok(())
+            }
+            Err(_) => self.raw_mut().insert_one(entity, local),
+        }
+    }
+}
+
+/// Walks up the parents of every child in `links`, a `(parent, child)` list, and returns one entity of every cycle.
+/// Removing the parent of each returned entity leaves a valid hierarchy
+fn find_cycles(links: &[(Entity, Entity)]) -> HashSet<Entity> {
+    let parents: HashMap<Entity, Entity> = links.iter().map(|&(parent, child)| (child, parent)).collect();
+
+    let mut visited = HashSet::new();
+    let mut cycles = HashSet::new();
+    for &(_, start) in links {
+        let mut path = Vec::new();
+        let mut on_path = HashSet::new();
+        let mut current = start;
+
+        while !visited.contains(&current) {
+            if !on_path.insert(current) {
+                // The last entity on the path links back into it
+                cycles.insert(*path.last().unwrap());
+                break;
+            }
+            path.push(current);
+
+            match parents.get(&current) {
+                Some(&parent) => current = parent,
+                None => break,
+            }
+        }
+
+        visited.extend(path);
+    }
+
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use hikari_math::{Quat, Transform, Vec3};
+
+    use super::{GlobalTransform, Parent};
+    use crate::{Entity, HierarchyError, Registry, World};
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!(a.abs_diff_eq(b, 1e-4), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn reparent_preserves_world_position() {
+        let mut world = World::new();
+        let parent = world.create_entity_with((Transform {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            rotation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            scale: Vec3::splat(2.0),
+        },));
+        let child = world.create_entity_with((Transform::from_position(Vec3::new(5.0, 0.0, 0.0)),));
+
+        world.set_parent(child, parent).unwrap();
+
+        assert_eq!(world.parent(child), Some(parent));
+        assert_eq!(world.children(parent), vec![child]);
+        assert_close(
+            world.world_matrix(child).unwrap().w_axis.truncate(),
+            Vec3::new(5.0, 0.0, 0.0),
+        );
+
+        world.detach(child).unwrap();
+
+        assert_eq!(world.parent(child), None);
+        assert!(world.children(parent).is_empty());
+        let transform = *world.get_component::<&Transform>(child).unwrap();
+        assert_close(transform.position, Vec3::new(5.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn propagation() {
+        let mut world = World::new();
+        let root = world.create_entity_with((Transform::from_position(Vec3::X),));
+        let middle = world.create_entity();
+        let leaf = world.create_entity();
+
+        world.set_parent(middle, root).unwrap();
+        world.set_parent(leaf, middle).unwrap();
+        world.get_component::<&mut Transform>(leaf).unwrap().position = Vec3::Y;
+
+        world.propagate_transforms();
+
+        let global = *world.get_component::<&super::GlobalTransform>(leaf).unwrap();
+        assert_close(global.position(), Vec3::new(1.0, 1.0, 0.0));
+
+        world.get_component::<&mut Transform>(root).unwrap().position = Vec3::Z;
+        world.propagate_transforms();
+
+        let global = *world.get_component::<&super::GlobalTransform>(leaf).unwrap();
+        assert_close(global.position(), Vec3::new(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn propagation_through_entities_without_transform() {
+        let mut world = World::new();
+        let root = world.create_entity_with((Transform::from_position(Vec3::X),));
+        let middle = world.create_entity();
+        let leaf = world.create_entity();
+
+        world.set_parent(middle, root).unwrap();
+        world.set_parent(leaf, middle).unwrap();
+        world.remove_component::<Transform>(middle).unwrap();
+        world.get_component::<&mut Transform>(leaf).unwrap().position = Vec3::Y;
+
+        world.propagate_transforms();
+
+        let global = *world.get_component::<&GlobalTransform>(leaf).unwrap();
+        assert_close(global.position(), world.world_matrix(leaf).unwrap().w_axis.truncate());
+        assert_close(global.position(), Vec3::new(1.0, 1.0, 0.0));
+        assert!(world.get_component::<&GlobalTransform>(middle).is_err());
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let mut world = World::new();
+        let a = world.create_entity();
+        let b = world.create_entity();
+
+        world.set_parent(b, a).unwrap();
+
+        assert_eq!(world.set_parent(a, b), Err(HierarchyError::Cycle));
+        assert_eq!(world.set_parent(a, a), Err(HierarchyError::Cycle));
+    }
+
+    #[test]
+    fn relink_breaks_cycles() {
+        let mut world = World::new();
+        let a = world.create_entity();
+        let b = world.create_entity();
+        let c = world.create_entity();
+
+        // A loaded world may contain a cycle, e.g. a <- b <- c <- a
+        for (child, parent) in [(a, c), (b, a), (c, b)] {
+            let uuid = world.entity_uuid(parent).unwrap();
+            world.raw_mut().insert_one(child, Parent::new(parent, uuid)).unwrap();
+        }
+
+        world.relink_hierarchy();
+
+        let roots: Vec<Entity> = [a, b, c].into_iter().filter(|&entity| world.parent(entity).is_none()).collect();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(world.descendants(roots[0]).len(), 2);
+
+        world.propagate_transforms();
+    }
+
+    #[test]
+    fn removal() {
+        let mut world = World::new();
+        let a = world.create_entity();
+        let b = world.create_entity();
+        let c = world.create_entity();
+
+        world.set_parent(b, a).unwrap();
+        world.set_parent(c, b).unwrap();
+
+        world.remove_entity(b).unwrap();
+        assert!(world.children(a).is_empty());
+        assert_eq!(world.parent(c), None);
+
+        world.set_parent(c, a).unwrap();
+        world.remove_entity_recursive(a).unwrap();
+        assert!(!world.contains(c));
+    }
+
+    #[test]
+    fn merge_keeps_hierarchy() {
+        let registry = Registry::builder().build();
+
+        let mut level = World::new();
+        let parent = level.create_entity_with_name("parent");
+        let child = level.create_entity_with_name("child");
+        level.set_parent(child, parent).unwrap();
+
+        let mut world = World::new();
+        let named = |world: &World, copies: &[Entity], name: &str| {
+            *copies.iter().find(|&&copy| world.entity_id(copy).unwrap().name == name).unwrap()
+        };
+        let first = level.merge_into(&registry, &mut world);
+        let second = level.merge_into(&registry, &mut world);
+        assert_eq!(world.len(), 4);
+
+        // The second copy collides with the first and is given new uuids
+        let first_parent = named(&world, &first, "parent");
+        let second_parent = named(&world, &second, "parent");
+        assert_eq!(world.entity_uuid(first_parent).unwrap(), level.entity_uuid(parent).unwrap());
+        assert_ne!(world.entity_uuid(second_parent).unwrap(), world.entity_uuid(first_parent).unwrap());
+
+        for copies in [first, second] {
+            let (parent, child) = (named(&world, &copies, "parent"), named(&world, &copies, "child"));
+            assert_eq!(world.parent(child), Some(parent));
+            assert_eq!(world.children(parent), vec![child]);
+        }
+    }
+
+    #[test]
+    fn merge_leaves_destination_alone() {
+        let registry = Registry::builder().build();
+
+        let mut world = World::new();
+        let parent = world.create_entity();
+        let first = world.create_entity();
+        let second = world.create_entity();
+        // Children are kept in the order they were attached, relinking would order them by handle
+        world.set_parent(second, parent).unwrap();
+        world.set_parent(first, parent).unwrap();
+
+        let mut level = World::new();
+        let level_parent = level.create_entity();
+        let level_child = level.create_entity();
+        level.set_parent(level_child, level_parent).unwrap();
+
+        level.merge_into(&registry, &mut world);
+
+        assert_eq!(world.children(parent), vec![second, first]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::{any::TypeId, collections::HashMap, hash::Hash};
+
+use hecs::NoSuchEntity;
+use uuid::Uuid;
+
+use crate::{Component, Entity, EntityId, Registry, RegistryBuilder, World};
+
+/// A reference to another entity which survives save/load, cloning and prefab instantiation.
+/// Like [`crate::Parent`], the target is persisted by its [`EntityId`] uuid and the entity handle is only a cache.
+/// The cache is kept up to date by [`World::resolve_entity_links`] for components registered with [`RegistryBuilder::register_entity_links`]
+#[derive(Clone, Copy, Debug)]
+pub struct EntityLink {
+    entity: Entity,
+    uuid: Uuid,
+}
+
+impl EntityLink {
+    pub fn new(world: &World, entity: Entity) -> Result<Self, NoSuchEntity> {
+        let uuid = world.entity_uuid(entity).map_err(|_| NoSuchEntity)?;
+
+        Ok(Self { entity, uuid })
+    }
+    /// Creates a link to the entity with the given uuid, which is found the next time the link is resolved
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        Self {
+            entity: Entity::DANGLING,
+            uuid,
+        }
+    }
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+    /// The entity the link pointed to when it was last resolved
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+    /// Returns the linked entity if it exists in `world`.
+    /// Falls back to searching the world by uuid if the link hasn't been resolved since the world changed
+    pub fn resolve(&self, world: &World) -> Option<Entity> {
+        if world.entity_uuid(self.entity).ok() == Some(self.uuid) {
+            return Some(self.entity);
+        }
+
+        world
+            .raw()
+            .query::<&EntityId>()
+            .iter()
+            .find(|(_, id)| id.uuid == self.uuid)
+            .map(|(entity, _)| entity)
+    }
+}
+
+// Links are equal if they point to the same entity, regardless of whether they have been resolved
+impl PartialEq for EntityLink {
+    fn eq(&self, other: &Self) -> bool {
+        self.uuid == other.uuid
+    }
+}
+impl Eq for EntityLink {}
+impl Hash for EntityLink {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.uuid.hash(state)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for EntityLink {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.uuid.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EntityLink {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Uuid::deserialize(deserializer).map(Self::from_uuid)
+    }
+}
+
+/// Implemented by components which hold [`EntityLink`]s, gives access to every link of the component
+pub trait MapEntityLinks {
+    fn map_entity_links(&mut self, f: &mut dyn FnMut(&mut EntityLink));
+}
+
+impl MapEntityLinks for EntityLink {
+    fn map_entity_links(&mut self, f: &mut dyn FnMut(&mut EntityLink)) {
+        f(self)
+    }
+}
+impl<T: MapEntityLinks> MapEntityLinks for Option<T> {
+    fn map_entity_links(&mut self, f: &mut dyn FnMut(&mut EntityLink)) {
+        if let Some(value) = self {
+            value.map_entity_links(f)
+        }
+    }
+}
+impl<T: MapEntityLinks> MapEntityLinks for Vec<T> {
+    fn map_entity_links(&mut self, f: &mut dyn FnMut(&mut EntityLink)) {
+        for value in self {
+            value.map_entity_links(f)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct LinkFns {
+    world: fn(&mut World, &mut dyn FnMut(&mut EntityLink)),
+    entity: fn(&World, Entity, &mut dyn FnMut(&mut EntityLink)),
+}
+
+impl RegistryBuilder {
+    /// Registers `C` as holding [`EntityLink`]s, so that they are remapped after loading, cloning and instantiating prefabs
+    pub fn register_entity_links<C: Component + MapEntityLinks>(&mut self) {
+        self.registry.link_fns.insert(
+            TypeId::of::<C>(),
+            LinkFns {
+                world: |world, f| {
+                    for (_, component) in world.query_mut::<&mut C>() {
+                        component.map_entity_links(f);
+                    }
+                },
+                entity: |world, entity, f| {
+                    if let Ok(mut component) = world.get_component::<&mut C>(entity) {
+                        component.map_entity_links(f);
+                    }
+                },
+            },
+        );
+    }
+}
+
+impl World {
+    /// Points every registered [`EntityLink`] at the entity which currently has its uuid.
+    /// Links to entities which don't exist are left dangling.
+    /// This needs to be called whenever entities are created from serialized or cloned data, like [`World::relink_hierarchy`]
+    pub fn resolve_entity_links(&mut self, registry: &Registry) {
+        hikari_dev::profile_function!();
+        if registry.inner.link_fns.is_empty() {
+            return;
+        }
+
+        let uuid_to_entity: HashMap<Uuid, Entity> = self
+            .raw()
+            .query::<&EntityId>()
+            .iter()
+            .map(|(entity, id)| (id.uuid, entity))
+            .collect();
+
+        for link_fns in registry.inner.link_fns.values() {
+            (link_fns.world)(self, &mut |link| {
+                link.entity = uuid_to_entity
+                    .get(&link.uuid)
+                    .copied()
+                    .unwrap_or(Entity::DANGLING);
+            });
+        }
+    }
+    /// Retargets the links held by `entities` according to `uuids`, which maps old uuids to new ones.
+    /// Used when copies of entities are given new uuids, so links between the copies point to the copies.
+    /// The links need to be resolved afterwards
+    pub(crate) fn remap_entity_links(&self, registry: &Registry, entities: &[Entity], uuids: &HashMap<Uuid, Uuid>) {
+        for link_fns in registry.inner.link_fns.values() {
+            for &entity in entities {
+                (link_fns.entity)(self, entity, &mut |link| {
+                    if let Some(&uuid) = uuids.get(&link.uuid) {
+                        link.uuid = uuid;
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Target(EntityLink);
+
+    impl MapEntityLinks for Target {
+        fn map_entity_links(&mut self, f: &mut dyn FnMut(&mut EntityLink)) {
+            self.0.map_entity_links(f)
+        }
+    }
+
+    #[test]
+    fn remap_links() {
+        let mut registry = Registry::builder();
+        registry.register_clone::<Target>();
+        registry.register_entity_links::<Target>();
+        let registry = registry.build();
+
+        let mut world = World::new();
+        let target = world.create_entity_with_name("target");
+        let follower = world.create_entity_with((Target(EntityLink::new(&world, target).unwrap()),));
+
+        let mut clone = world.clone(&registry);
+        let linked = |world: &World| world.get_component::<&Target>(follower).unwrap().0;
+        assert_eq!(linked(&clone).resolve(&clone), Some(target));
+
+        // The target is given a new uuid, like copies spawned from a prefab
+        let old_uuid = clone.entity_uuid(target).unwrap();
+        let new_uuid = Uuid::new_v4();
+        clone.get_component::<&mut EntityId>(target).unwrap().uuid = new_uuid;
+        assert_eq!(linked(&clone).resolve(&clone), None);
+
+        clone.remap_entity_links(&registry, &[follower], &HashMap::from([(old_uuid, new_uuid)]));
+        clone.resolve_entity_links(&registry);
+        assert_eq!(linked(&clone).uuid(), new_uuid);
+        assert_eq!(linked(&clone).entity(), target);
+
+        clone.remove_entity(target).unwrap();
+        clone.resolve_entity_links(&registry);
+        assert_eq!(linked(&clone).entity(), Entity::DANGLING);
+        assert_eq!(linked(&clone).resolve(&clone), None);
+    }
+}
@@ -1,8 +1,14 @@
-use hikari_asset::{Loader, Saver};
+use std::{io::Write, path::Path};
 
-use crate::{Registry, World, Plugin};
+use hikari_asset::{AssetManager, Loader, Saver};
+
+use crate::{
+    prefab::{sync_prefab_instances, PrefabLoader},
+    Plugin, Prefab, Registry, Task, World,
+};
 
 pub const SUPPORTED_WORLD_EXTENSIONS: [&str; 1] = ["hworld"]; 
+pub const SUPPORTED_BINARY_WORLD_EXTENSIONS: [&str; 1] = ["hworldb"];
 pub struct WorldLoader {
     registry: Registry
 }
@@ -35,6 +41,74 @@ impl Saver for WorldLoader {
     }
 }
 
+/// Loads and saves worlds in the compact binary format, see [`World::to_binary`]
+pub struct BinaryWorldLoader {
+    registry: Registry
+}
+
+impl Loader for BinaryWorldLoader {
+    fn extensions(&self) -> &[&str] {
+        &SUPPORTED_BINARY_WORLD_EXTENSIONS
+    }
+
+    fn load(&self, ctx: &mut hikari_asset::LoadContext) -> anyhow::Result<()> {
+        let mut bytes = rkyv::AlignedVec::new();
+        bytes.extend_from_reader(ctx.reader())?;
+
+        ctx.set_asset(World::from_binary(&bytes, &self.registry)?);
+        Ok(())
+    }
+}
+
+impl Saver for BinaryWorldLoader {
+    fn extensions(&self) -> &[&str] {
+        &SUPPORTED_BINARY_WORLD_EXTENSIONS
+    }
+
+    fn save(&self, context: &mut hikari_asset::SaveContext, writer: &mut dyn std::io::Write) -> anyhow::Result<()> {
+        let asset = context.get_asset::<World>();
+
+        writer.write_all(&asset.to_binary(&self.registry)?)?;
+        Ok(())
+    }
+}
+
+fn is_binary_world(path: &Path) -> anyhow::Result<bool> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Couldn't determine file extension: {:#?}", path))?;
+
+    if SUPPORTED_BINARY_WORLD_EXTENSIONS.contains(&extension) {
+        Ok(true)
+    } else if SUPPORTED_WORLD_EXTENSIONS.contains(&extension) {
+        Ok(false)
+    } else {
+        Err(anyhow::anyhow!("Not a world file: {:#?}", path))
+    }
+}
+
+/// Reads the world at `src` and writes it to `dst`, the formats are picked by file extension.
+/// Assets referenced by the world are resolved through the asset manager, so it must be initialized
+pub fn convert_world(src: &Path, dst: &Path, registry: &Registry) -> anyhow::Result<()> {
+    let world = if is_binary_world(src)? {
+        World::from_binary(&std::fs::read(src)?, registry)?
+    } else {
+        let deserializer = serde_yaml::Deserializer::from_reader(std::io::BufReader::new(std::fs::File::open(src)?));
+        World::deserialize(deserializer, registry)?
+    };
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(dst)?);
+    if is_binary_world(dst)? {
+        writer.write_all(&world.to_binary(registry)?)?;
+    } else {
+        serde_yaml::to_writer(&mut writer, &world.as_serializable(registry))?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
 pub struct WorldLoaderPlugin;
 impl Plugin for WorldLoaderPlugin {
     fn build(self, game: &mut crate::Game) {
@@ -47,7 +121,35 @@ impl Plugin for WorldLoaderPlugin {
         });
 
         game.register_asset_saver::<World, WorldLoader>(WorldLoader {
-            registry: registry
+            registry: registry.clone()
         });
+
+        game.register_asset_loader::<World, BinaryWorldLoader>(BinaryWorldLoader {
+            registry: registry.clone()
+        });
+
+        game.register_asset_saver::<World, BinaryWorldLoader>(BinaryWorldLoader {
+            registry: registry.clone()
+        });
+
+        game.create_asset::<Prefab>();
+
+        game.register_asset_loader::<Prefab, PrefabLoader>(PrefabLoader {
+            registry: registry.clone()
+        });
+
+        game.register_asset_saver::<Prefab, PrefabLoader>(PrefabLoader {
+            registry
+        });
+
+        game.add_task(
+            crate::FIRST,
+            Task::new(
+                "Sync Prefab Instances",
+                |world: &mut World, registry: &Registry, asset_manager: &AssetManager| {
+                    sync_prefab_instances(world, registry, asset_manager);
+                },
+            ),
+        );
     }
 }
\ No newline at end of file
@@ -2,14 +2,25 @@ pub mod entity;
 pub mod registry;
 pub mod world;
 pub mod component;
+pub mod hierarchy;
+pub mod link;
 
 pub use entity::*;
 pub use registry::*;
 pub use world::*;
 pub use component::*;
+pub use hierarchy::*;
+pub use link::*;
 
 #[cfg(feature = "serde")]
 pub mod serialize;
 #[cfg(feature = "serde")]
 pub mod load_save;
+#[cfg(feature = "serde")]
+pub mod binary;
+
+#[cfg(feature = "serde")]
+pub mod prefab;
 
+#[cfg(feature = "serde")]
+pub use prefab::{Prefab, PrefabInstance};
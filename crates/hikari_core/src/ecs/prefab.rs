@@ -0,0 +1,686 @@
+use std::{
+    any::TypeId,
+    collections::{BTreeSet, HashMap},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use hecs::{EntityBuilder, NoSuchEntity};
+use hikari_asset::{Asset, AssetManager, Handle, Loader, Saver};
+use hikari_math::Transform;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use uuid::Uuid;
+
+use crate::{Children, Entity, EntityId, Parent, Registry, World};
+
+pub const SUPPORTED_PREFAB_EXTENSIONS: [&str; 1] = ["hprefab"];
+
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A reusable hierarchy of entities with a single root, stored in the same format as worlds.
+/// Every time the prefab is modified or reloaded, its instances are brought up to date by [`sync_prefab_instances`]
+#[derive(type_uuid::TypeUuid)]
+#[uuid = "5e3d8a38-5c0f-4a8e-9a4e-2f1f1b6c9d47"]
+pub struct Prefab {
+    world: World,
+    root: Entity,
+    revision: u64,
+}
+
+impl Asset for Prefab {
+    type Settings = ();
+}
+
+impl Prefab {
+    /// Creates a prefab from a world containing exactly one root entity
+    pub fn new(world: World) -> anyhow::Result<Self> {
+        let mut roots = world
+            .entities()
+            .map(|entity_ref| entity_ref.entity())
+            .filter(|&entity| world.parent(entity).is_none());
+
+        let root = roots
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Prefab has no entities"))?;
+        if roots.next().is_some() {
+            return Err(anyhow::anyhow!("Prefab has more than one root entity"));
+        }
+
+        Ok(Self {
+            world,
+            root,
+            revision: next_revision(),
+        })
+    }
+    /// Copies `root` and all of its descendants into a new prefab.
+    /// The copies keep the uuids of the originals, so the originals can become instances with [`PrefabInstance::link`]
+    pub fn from_hierarchy(world: &World, registry: &Registry, root: Entity) -> Result<Self, NoSuchEntity> {
+        let mut entities = vec![root];
+        entities.extend(world.descendants(root));
+
+        let mut prefab_world = World::new();
+        let mapping = copy_entities(world, &entities, &mut prefab_world, registry, |_| {})?;
+
+        // Nested prefabs aren't supported, the copies are plain entities
+        for &copy in mapping.values() {
+            let _ = prefab_world.raw_mut().remove_one::<PrefabInstance>(copy);
+        }
+        prefab_world.resolve_entity_links(registry);
+
+        Ok(Self {
+            world: prefab_world,
+            root: mapping[&root],
+            revision: next_revision(),
+        })
+    }
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+    /// Gives mutable access to the entities of the prefab, marking it as changed
+    pub fn world_mut(&mut self) -> &mut World {
+        self.revision = next_revision();
+        &mut self.world
+    }
+    pub fn root(&self) -> Entity {
+        self.root
+    }
+    /// Changes every time the prefab is modified
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+    /// Finds the entity of the prefab with the given uuid
+    pub fn find(&self, uuid: Uuid) -> Option<Entity> {
+        self.world
+            .raw()
+            .query::<&EntityId>()
+            .iter()
+            .find(|(_, id)| id.uuid == uuid)
+            .map(|(entity, _)| entity)
+    }
+    /// Spawns a copy of the prefab into `world` and returns the root of the copy.
+    /// `handle` must refer to this prefab, the spawned entities are linked to it
+    pub fn instantiate(&self, handle: &Handle<Prefab>, world: &mut World, registry: &Registry) -> Entity {
+        let mut entities = vec![self.root];
+        entities.extend(self.world.descendants(self.root));
+
+        let mut uuids = HashMap::with_capacity(entities.len());
+        let mapping = copy_entities(&self.world, &entities, world, registry, |builder| {
+            let id = builder.get_mut::<&mut EntityId>().unwrap();
+            let source_uuid = id.uuid;
+            id.uuid = Uuid::new_v4();
+            uuids.insert(source_uuid, id.uuid);
+
+            builder.add(PrefabInstance {
+                prefab: handle.clone(),
+                source: source_uuid,
+                overrides: HashMap::new(),
+                revision: self.revision,
+            });
+        })
+        .expect("Prefab entities are always present");
+
+        // Links between entities of the prefab point to the new copies
+        let copies: Vec<Entity> = mapping.values().copied().collect();
+        world.remap_entity_links(registry, &copies, &uuids);
+        world.resolve_entity_links(registry);
+
+        mapping[&self.root]
+    }
+    /// Replaces the contents of the prefab with the hierarchy of the instance `root`.
+    /// The transform of the prefab root is kept, so other instances don't move.
+    /// Overrides of the instance are cleared as they are now part of the prefab.
+    /// Nested instances of other prefabs join the prefab as plain entities and stay linked to their own prefab
+    pub fn apply_instance(&mut self, world: &mut World, registry: &Registry, root: Entity) -> anyhow::Result<()> {
+        let handle = world.get_component::<&PrefabInstance>(root)?.prefab.clone();
+
+        let mut entities = vec![root];
+        entities.extend(world.descendants(root));
+
+        let mut prefab_world = World::new();
+        let mut uuids = HashMap::with_capacity(entities.len());
+        let mapping = copy_entities(world, &entities, &mut prefab_world, registry, |builder| {
+            // Linked entities go back to the uuid the prefab knows them by,
+            // entities which were added to the instance join the prefab under their own uuid
+            let source = builder
+                .get::<&PrefabInstance>()
+                .filter(|instance| instance.prefab == handle)
+                .map(|instance| instance.source);
+            if let Some(source) = source {
+                let id = builder.get_mut::<&mut EntityId>().unwrap();
+                uuids.insert(id.uuid, source);
+                id.uuid = source;
+            }
+        })?;
+
+        for &copy in mapping.values() {
+            let _ = prefab_world.raw_mut().remove_one::<PrefabInstance>(copy);
+        }
+        let copies: Vec<Entity> = mapping.values().copied().collect();
+        prefab_world.remap_entity_links(registry, &copies, &uuids);
+        prefab_world.resolve_entity_links(registry);
+
+        let new_root = mapping[&root];
+        if let Ok(transform) = self.world.get_component::<&Transform>(self.root) {
+            let transform = *transform;
+            prefab_world.raw_mut().insert_one(new_root, transform)?;
+        }
+
+        self.world = prefab_world;
+        self.root = new_root;
+        self.revision = next_revision();
+
+        // Entities which were added to the instance are linked now that the prefab contains them
+        for entity in entities {
+            let has_instance = match world.get_component::<&mut PrefabInstance>(entity) {
+                Ok(mut instance) => {
+                    if instance.prefab == handle {
+                        instance.overrides.clear();
+                        instance.revision = self.revision;
+                    }
+                    true
+                }
+                Err(_) => false,
+            };
+
+            if !has_instance {
+                let uuid = world.entity_uuid(entity)?;
+                world.add_component(
+                    entity,
+                    PrefabInstance {
+                        prefab: handle.clone(),
+                        source: uuid,
+                        overrides: HashMap::new(),
+                        revision: self.revision,
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Clones `entities` from `src` into `dst` and recreates the hierarchy between them.
+/// `modify` is called on every clone before it is spawned.
+/// Returns the mapping from source entities to their clones
+fn copy_entities(
+    src: &World,
+    entities: &[Entity],
+    dst: &mut World,
+    registry: &Registry,
+    mut modify: impl FnMut(&mut EntityBuilder),
+) -> Result<HashMap<Entity, Entity>, NoSuchEntity> {
+    let mut mapping = HashMap::with_capacity(entities.len());
+
+    for &entity in entities {
+        let mut builder = registry.clone_entity(src.entity(entity)?);
+        modify(&mut builder);
+
+        let copy = dst.raw_mut().spawn(builder.build());
+
+        // The cloned links still point into `src`
+        let _ = dst.raw_mut().remove_one::<Parent>(copy);
+        let _ = dst.raw_mut().remove_one::<Children>(copy);
+
+        mapping.insert(entity, copy);
+    }
+
+    for &entity in entities {
+        let parent_copy = mapping[&entity];
+
+        for child in src.children(entity) {
+            let Some(&child_copy) = mapping.get(&child) else { continue };
+
+            let uuid = dst.entity_uuid(parent_copy).map_err(|_| NoSuchEntity)?;
+            dst.raw_mut()
+                .insert_one(child_copy, Parent::new(parent_copy, uuid))?;
+            dst.add_child(parent_copy, child_copy);
+        }
+    }
+
+    Ok(mapping)
+}
+
+/// How an instance differs from its prefab
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Override {
+    /// The whole component is owned by the instance
+    Component,
+    /// Only these top level fields of the component are owned by the instance
+    Fields(BTreeSet<String>),
+}
+
+impl Override {
+    /// Fields which differ between the serialized component of the prefab and the instance
+    fn diff(prefab: &Value, instance: &Value) -> Option<Self> {
+        if prefab == instance {
+            return None;
+        }
+
+        let (Value::Mapping(prefab), Value::Mapping(instance)) = (prefab, instance) else {
+            return Some(Override::Component);
+        };
+
+        let mut fields = BTreeSet::new();
+        for (key, value) in instance {
+            if prefab.get(key) == Some(value) {
+                continue;
+            }
+
+            match key.as_str() {
+                Some(field) => {
+                    fields.insert(field.to_owned());
+                }
+                None => return Some(Override::Component),
+            }
+        }
+
+        (!fields.is_empty()).then_some(Override::Fields(fields))
+    }
+    fn merge(&mut self, other: Override) {
+        match (self, other) {
+            (Override::Fields(fields), Override::Fields(other)) => fields.extend(other),
+            (this, _) => *this = Override::Component,
+        }
+    }
+    /// Combines the component of the prefab with the overridden fields of the instance.
+    /// Returns `None` if the instance should be left as is
+    fn apply(override_: Option<&Self>, prefab: Value, instance: Option<&Value>) -> Option<Value> {
+        match override_ {
+            None => Some(prefab),
+            Some(Override::Component) => None,
+            Some(Override::Fields(fields)) => {
+                let (Value::Mapping(mut merged), Some(Value::Mapping(instance))) = (prefab, instance) else {
+                    return None;
+                };
+
+                for field in fields {
+                    if let Some(value) = instance.get(field.as_str()) {
+                        merged.insert(Value::String(field.clone()), value.clone());
+                    }
+                }
+
+                Some(Value::Mapping(merged))
+            }
+        }
+    }
+}
+
+/// Links an entity to the entity of a prefab it was created from
+#[derive(Clone, Serialize, Deserialize, type_uuid::TypeUuid)]
+#[uuid = "0d7f2c6e-8b8a-4f57-8f0c-6a4b0e3d91a2"]
+pub struct PrefabInstance {
+    prefab: Handle<Prefab>,
+    /// Uuid of the entity in the prefab
+    source: Uuid,
+    /// Components which differ from the prefab, keyed by the uuid of the component type
+    #[serde(default)]
+    overrides: HashMap<Uuid, Override>,
+    /// Revision of the prefab the instance was last synced with, instances are synced once after being loaded
+    #[serde(skip)]
+    revision: u64,
+}
+
+impl PrefabInstance {
+    pub fn prefab(&self) -> &Handle<Prefab> {
+        &self.prefab
+    }
+    pub fn source(&self) -> Uuid {
+        self.source
+    }
+    pub fn overrides(&self) -> &HashMap<Uuid, Override> {
+        &self.overrides
+    }
+    /// Turns `root` and its descendants into instances of `prefab`, which must have been created with [`Prefab::from_hierarchy`]
+    pub fn link(world: &mut World, root: Entity, prefab: &Handle<Prefab>, revision: u64) -> Result<(), NoSuchEntity> {
+        let mut entities = vec![root];
+        entities.extend(world.descendants(root));
+
+        for entity in entities {
+            let source = world.entity_uuid(entity).map_err(|_| NoSuchEntity)?;
+            world.add_component(
+                entity,
+                PrefabInstance {
+                    prefab: prefab.clone(),
+                    source,
+                    overrides: HashMap::new(),
+                    revision,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+    /// Compares the components of `entity` with its prefab and marks the fields which differ as overridden.
+    /// Components of the prefab which were removed from the instance are overridden as a whole.
+    /// Should be called whenever an instance is edited, so that the edits survive changes to the prefab.
+    /// Does nothing if `entity` isn't a prefab instance
+    pub fn record_overrides(
+        world: &World,
+        registry: &Registry,
+        asset_manager: &AssetManager,
+        entity: Entity,
+    ) -> anyhow::Result<()> {
+        let prefabs = asset_manager.read_assets::<Prefab>().unwrap();
+        record_instance_overrides(world, registry, &prefabs, entity)
+    }
+    /// Forgets all overrides of `entity`, the next sync resets it to the prefab
+    pub fn revert(world: &World, entity: Entity) -> Result<(), hecs::ComponentError> {
+        let mut instance = world.get_component::<&mut PrefabInstance>(entity)?;
+        instance.overrides.clear();
+        instance.revision = 0;
+
+        Ok(())
+    }
+}
+
+fn record_instance_overrides(
+    world: &World,
+    registry: &Registry,
+    prefabs: &hikari_asset::AssetPool<Prefab>,
+    entity: Entity,
+) -> anyhow::Result<()> {
+    let Ok(instance) = world.get_component::<&PrefabInstance>(entity) else {
+        return Ok(());
+    };
+    let (handle, source) = (instance.prefab.clone(), instance.source);
+    drop(instance);
+
+    let Some(prefab) = prefabs.get(&handle) else {
+        return Ok(());
+    };
+    let Some(source) = prefab.find(source) else {
+        return Ok(());
+    };
+
+    let source_ref = prefab.world.entity(source)?;
+    let entity_ref = world.entity(entity)?;
+    let uuids = instance_uuids(world, entity, &handle);
+
+    let mut found = Vec::new();
+    for type_id in entity_ref.component_types().filter(|&type_id| is_synced(type_id)) {
+        let Some(&uuid) = registry.type_id_to_uuid(type_id) else { continue };
+        let Some(mut prefab_value) = serialize(registry, type_id, source_ref)? else { continue };
+        remap_uuids(&mut prefab_value, &uuids);
+        let Some(value) = serialize(registry, type_id, entity_ref)? else { continue };
+
+        if let Some(override_) = Override::diff(&prefab_value, &value) {
+            found.push((uuid, override_));
+        }
+    }
+
+    // Without an override, the next sync would add removed components back
+    let mut removed = Vec::new();
+    for type_id in source_ref.component_types().filter(|&type_id| is_synced(type_id)) {
+        let Some(&uuid) = registry.type_id_to_uuid(type_id) else { continue };
+        if !entity_ref.component_types().any(|ty| ty == type_id) {
+            removed.push(uuid);
+        }
+    }
+
+    let mut instance = world.get_component::<&mut PrefabInstance>(entity)?;
+    for (uuid, override_) in found {
+        match instance.overrides.get_mut(&uuid) {
+            Some(existing) => existing.merge(override_),
+            None => {
+                instance.overrides.insert(uuid, override_);
+            }
+        }
+    }
+    for uuid in removed {
+        instance.overrides.insert(uuid, Override::Component);
+    }
+
+    Ok(())
+}
+
+/// Links and derived components are never copied from the prefab
+fn is_synced(type_id: TypeId) -> bool {
+    type_id != TypeId::of::<Parent>() && type_id != TypeId::of::<PrefabInstance>()
+}
+
+/// Maps the uuids of the prefab entities to the uuids of the entities of the instance `entity` belongs to
+fn instance_uuids(world: &World, entity: Entity, prefab: &Handle<Prefab>) -> HashMap<Uuid, Uuid> {
+    let is_instance = |entity: Entity| {
+        world
+            .get_component::<&PrefabInstance>(entity)
+            .map_or(false, |instance| instance.prefab == *prefab)
+    };
+
+    let mut root = entity;
+    while let Some(parent) = world.parent(root).filter(|&parent| is_instance(parent)) {
+        root = parent;
+    }
+
+    let mut entities = vec![root];
+    entities.extend(world.descendants(root));
+
+    entities
+        .into_iter()
+        .filter_map(|entity| {
+            let instance = world.get_component::<&PrefabInstance>(entity).ok()?;
+            (instance.prefab == *prefab).then_some((instance.source, world.entity_uuid(entity).ok()?))
+        })
+        .collect()
+}
+
+/// Replaces uuids in a serialized component, so [`crate::EntityLink`]s of the prefab can be compared with and copied to an instance
+fn remap_uuids(value: &mut Value, uuids: &HashMap<Uuid, Uuid>) {
+    match value {
+        Value::String(string) => {
+            if let Some(uuid) = Uuid::parse_str(string).ok().and_then(|uuid| uuids.get(&uuid)) {
+                *string = uuid.to_string();
+            }
+        }
+        Value::Sequence(values) => values.iter_mut().for_each(|value| remap_uuids(value, uuids)),
+        Value::Mapping(mapping) => mapping.values_mut().for_each(|value| remap_uuids(value, uuids)),
+        Value::Tagged(tagged) => remap_uuids(&mut tagged.value, uuids),
+        _ => {}
+    }
+}
+
+fn serialize(registry: &Registry, type_id: TypeId, entity_ref: hecs::EntityRef) -> anyhow::Result<Option<Value>> {
+    registry
+        .serialize_component_of_type(type_id, entity_ref, serde_yaml::value::Serializer)
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Copies the components of prefabs which changed since the last sync to their instances, except for overridden fields.
+/// Changes to the hierarchy of a prefab only show up in newly created instances
+pub fn sync_prefab_instances(world: &mut World, registry: &Registry, asset_manager: &AssetManager) {
+    hikari_dev::profile_function!();
+
+    let prefabs = asset_manager.read_assets::<Prefab>().unwrap();
+    let outdated: Vec<Entity> = world
+        .raw()
+        .query::<&PrefabInstance>()
+        .iter()
+        .filter(|(_, instance)| {
+            prefabs
+                .get(&instance.prefab)
+                .map_or(false, |prefab| prefab.revision != instance.revision)
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+
+    if outdated.is_empty() {
+        return;
+    }
+
+    for entity in outdated {
+        if let Err(err) = sync_instance(world, registry, &prefabs, entity) {
+            log::error!("Failed to sync prefab instance {:?}: {}", entity, err);
+        }
+    }
+    world.resolve_entity_links(registry);
+}
+
+fn sync_instance(
+    world: &mut World,
+    registry: &Registry,
+    prefabs: &hikari_asset::AssetPool<Prefab>,
+    entity: Entity,
+) -> anyhow::Result<()> {
+    let instance = world.get_component::<&PrefabInstance>(entity)?;
+    let handle = instance.prefab.clone();
+    let prefab = prefabs.get(&handle).unwrap();
+    let source = prefab.find(instance.source);
+    let overrides = instance.overrides.clone();
+    drop(instance);
+
+    let mut updates = Vec::new();
+    if let Some(source) = source {
+        let source_ref = prefab.world.entity(source)?;
+        let entity_ref = world.entity(entity)?;
+        let uuids = instance_uuids(world, entity, &handle);
+
+        for type_id in source_ref.component_types().filter(|&type_id| is_synced(type_id)) {
+            let Some(uuid) = registry.type_id_to_uuid(type_id) else { continue };
+            let Some(mut prefab_value) = serialize(registry, type_id, source_ref)? else { continue };
+            remap_uuids(&mut prefab_value, &uuids);
+            let current = serialize(registry, type_id, entity_ref)?;
+
+            let Some(merged) = Override::apply(overrides.get(uuid), prefab_value, current.as_ref()) else {
+                continue;
+            };
+            if current.as_ref() != Some(&merged) {
+                updates.push((type_id, merged));
+            }
+        }
+    }
+
+    for (type_id, value) in updates {
+        registry.deserialize_component_of_type(type_id, entity, world, value)?;
+    }
+
+    world.get_component::<&mut PrefabInstance>(entity)?.revision = prefab.revision;
+
+    Ok(())
+}
+
+pub struct PrefabLoader {
+    pub(crate) registry: Registry,
+}
+
+impl Loader for PrefabLoader {
+    fn extensions(&self) -> &[&str] {
+        &SUPPORTED_PREFAB_EXTENSIONS
+    }
+
+    fn load(&self, ctx: &mut hikari_asset::LoadContext) -> anyhow::Result<()> {
+        let deserializer = serde_yaml::Deserializer::from_reader(ctx.reader());
+        let world = World::deserialize(deserializer, &self.registry)?;
+
+        ctx.set_asset(Prefab::new(world)?);
+        Ok(())
+    }
+}
+
+impl Saver for PrefabLoader {
+    fn extensions(&self) -> &[&str] {
+        &SUPPORTED_PREFAB_EXTENSIONS
+    }
+
+    fn save(&self, context: &mut hikari_asset::SaveContext, writer: &mut dyn std::io::Write) -> anyhow::Result<()> {
+        let prefab = context.get_asset::<Prefab>();
+
+        serde_yaml::to_writer(writer, &prefab.world.as_serializable(&self.registry))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use type_uuid::TypeUuid;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TypeUuid)]
+    #[uuid = "2b1f5a0e-7c3d-4e59-b8a1-0c6f9d2e4a71"]
+    struct Health {
+        current: f32,
+        max: f32,
+    }
+
+    #[test]
+    fn override_fields() {
+        let prefab = serde_yaml::to_value(Health { current: 10.0, max: 10.0 }).unwrap();
+        let instance = serde_yaml::to_value(Health { current: 5.0, max: 10.0 }).unwrap();
+
+        let override_ = Override::diff(&prefab, &instance).unwrap();
+        assert_eq!(override_, Override::Fields(["current".to_owned()].into()));
+        assert_eq!(Override::diff(&prefab, &prefab), None);
+
+        // The prefab raised max health, the instance keeps its current health
+        let changed = serde_yaml::to_value(Health { current: 20.0, max: 20.0 }).unwrap();
+        let merged = Override::apply(Some(&override_), changed.clone(), Some(&instance)).unwrap();
+        assert_eq!(
+            serde_yaml::from_value::<Health>(merged).unwrap(),
+            Health { current: 5.0, max: 20.0 }
+        );
+
+        assert_eq!(Override::apply(None, changed.clone(), Some(&instance)), Some(changed.clone()));
+        assert_eq!(Override::apply(Some(&Override::Component), changed, Some(&instance)), None);
+    }
+
+    #[test]
+    fn hierarchy_copy() {
+        let mut registry = Registry::builder();
+        registry.register_clone::<Health>();
+        let registry = registry.build();
+
+        let mut world = World::new();
+        let root = world.create_entity_with_name("root");
+        let child = world.create_entity_with((Health { current: 1.0, max: 2.0 },));
+        world.set_parent(child, root).unwrap();
+
+        let prefab = Prefab::from_hierarchy(&world, &registry, root).unwrap();
+        let prefab_child = prefab.world().children(prefab.root())[0];
+
+        assert_eq!(prefab.world().len(), 2);
+        assert_eq!(prefab.find(world.entity_uuid(child).unwrap()), Some(prefab_child));
+        assert_eq!(
+            *prefab.world().get_component::<&Health>(prefab_child).unwrap(),
+            Health { current: 1.0, max: 2.0 }
+        );
+        assert_eq!(prefab.world().parent(prefab_child), Some(prefab.root()));
+    }
+
+    #[test]
+    fn removed_components_stay_removed() {
+        let mut registry = Registry::builder();
+        registry.register_serde::<Health>();
+        registry.register_clone::<Health>();
+        let registry = registry.build();
+
+        let mut world = World::new();
+        let root = world.create_entity_with((Health { current: 1.0, max: 2.0 },));
+
+        let prefab = Prefab::from_hierarchy(&world, &registry, root).unwrap();
+        let revision = prefab.revision();
+        let mut prefabs = hikari_asset::AssetPool::new();
+        let handle = prefabs.insert(prefab);
+        PrefabInstance::link(&mut world, root, &handle, revision).unwrap();
+
+        world.remove_component::<Health>(root).unwrap();
+        record_instance_overrides(&world, &registry, &prefabs, root).unwrap();
+
+        let uuid = Uuid::from_bytes(Health::UUID);
+        assert_eq!(
+            world.get_component::<&PrefabInstance>(root).unwrap().overrides().get(&uuid),
+            Some(&Override::Component)
+        );
+
+        // Instances are synced again after being loaded
+        world.get_component::<&mut PrefabInstance>(root).unwrap().revision = 0;
+        sync_instance(&mut world, &registry, &prefabs, root).unwrap();
+
+        assert!(world.get_component::<&Health>(root).is_err());
+    }
+}
@@ -2,7 +2,7 @@ use hecs::NoSuchEntity;
 use std::{any::TypeId, collections::HashMap, sync::Arc};
 use uuid::Uuid;
 
-use crate::{Component, Entity, EntityBuilder, EntityRef, World, EntityId};
+use crate::{Children, Component, Entity, EntityBuilder, EntityRef, EntityId, GlobalTransform, Parent, World};
 
 pub trait CloneComponent: Component + Clone {}
 
@@ -30,16 +30,22 @@ impl CloneFn {
 pub(crate) struct RegistryInner {
     pub(crate) type_id_to_uuid: HashMap<TypeId, Uuid>,
     pub clone_fns: HashMap<TypeId, CloneFn>,
+    pub(crate) link_fns: HashMap<TypeId, crate::link::LinkFns>,
     #[cfg(feature = "serde")]
     pub(crate) serialize_fns: HashMap<Uuid, crate::serialize::SerializeFns>,
+    #[cfg(feature = "serde")]
+    pub(crate) migrations: HashMap<(Uuid, u32), crate::serialize::Migration>,
 }
 impl RegistryInner {
     fn new() -> Self {
         Self {
             type_id_to_uuid: Default::default(),
             clone_fns: Default::default(),
+            link_fns: Default::default(),
             #[cfg(feature = "serde")]
             serialize_fns: Default::default(),
+            #[cfg(feature = "serde")]
+            migrations: Default::default(),
         }
     }
 }
@@ -95,6 +101,16 @@ impl Default for RegistryBuilder {
             registry: RegistryInner::new(),
         };
         builder.register_clone::<EntityId>();
+        builder.register_clone::<Parent>();
+        builder.register_clone::<Children>();
+        builder.register_clone::<GlobalTransform>();
+        #[cfg(feature = "serde")]
+        {
+            builder.register_serde::<Parent>();
+            builder.register_serde::<crate::PrefabInstance>();
+            builder.register_clone::<crate::PrefabInstance>();
+            builder.register_clone::<crate::serialize::UnknownComponents>();
+        }
         builder
     }
 }
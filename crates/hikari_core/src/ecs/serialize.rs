@@ -6,10 +6,68 @@ use serde::{
     ser::SerializeMap,
     Deserialize, Deserializer, Serialize, Serializer,
 };
+use serde_yaml::Value;
 use type_uuid::TypeUuid;
 use uuid::Uuid;
 
-use crate::{Component, Entity, Registry, RegistryBuilder, RegistryInner, World, EntityId};
+use crate::{Children, Component, Entity, EntityId, GlobalTransform, Registry, RegistryBuilder, RegistryInner, World};
+
+/// Upgrades the serialized form of a component by one version
+pub type Migration = Box<dyn Fn(Value) -> anyhow::Result<Value> + Send + Sync>;
+
+/// Components are keyed by their uuid, followed by the schema version if it isn't 0
+pub(crate) fn component_key(uuid: &Uuid, version: u32) -> String {
+    if version == 0 {
+        uuid.to_string()
+    } else {
+        format!("{}@{}", uuid, version)
+    }
+}
+fn parse_component_key(key: &str) -> Option<(Uuid, u32)> {
+    match key.split_once('@') {
+        Some((uuid, version)) => Some((Uuid::parse_str(uuid).ok()?, version.parse().ok()?)),
+        None => Some((Uuid::parse_str(key).ok()?, 0)),
+    }
+}
+
+/// Components which couldn't be loaded, either because their type isn't registered or because they couldn't be migrated.
+/// They are kept as they were read, so that saving the world again doesn't lose them
+#[derive(Clone, Debug, Default)]
+pub struct UnknownComponents {
+    pub(crate) entries: Vec<UnknownComponent>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum UnknownComponent {
+    Yaml { key: String, value: Value },
+    Binary { uuid: Uuid, version: u32, data: Vec<u8> },
+}
+
+impl UnknownComponents {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Uuids of the preserved components
+    pub fn uuids(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.entries.iter().filter_map(|entry| match entry {
+            UnknownComponent::Yaml { key, .. } => parse_component_key(key).map(|(uuid, _)| uuid),
+            UnknownComponent::Binary { uuid, .. } => Some(*uuid),
+        })
+    }
+    pub(crate) fn push(world: &mut World, entity: Entity, entry: UnknownComponent) {
+        if let Ok(mut unknown) = world.get_component::<&mut UnknownComponents>(entity) {
+            unknown.entries.push(entry);
+            return;
+        }
+
+        world
+            .add_component(entity, UnknownComponents { entries: vec![entry] })
+            .unwrap();
+    }
+}
 
 pub trait SerializeComponent: Component + Serialize + for<'de> Deserialize<'de> + TypeUuid {}
 impl<T: Component + Serialize + for<'de> Deserialize<'de> + TypeUuid> SerializeComponent for T {}
@@ -20,15 +78,26 @@ impl<'r, 'e> Serialize for ComponentsSerialize<'r, 'e> {
     where
         S: serde::Serializer,
     {
-        let mut map = serializer.serialize_map(Some(self.1.component_types().count()))?;
+        let mut map = serializer.serialize_map(None)?;
 
         for type_id in self.1.component_types() {
             if let Some(uuid) = self.0.type_id_to_uuid(type_id) {
-                map.serialize_entry(uuid, &ComponentSerialize(self.0, self.1, *uuid))?;
-            } else {
-                if type_id != TypeId::of::<EntityId>() {
-                    log::warn!("Skipping serializing typeid: {:#?}", type_id);
+                map.serialize_entry(
+                    &component_key(uuid, self.0.component_version(uuid)),
+                    &ComponentSerialize(self.0, self.1, *uuid),
+                )?;
+            } else if type_id == TypeId::of::<UnknownComponents>() {
+                let unknown = self.1.get::<&UnknownComponents>().unwrap();
+                for entry in &unknown.entries {
+                    match entry {
+                        UnknownComponent::Yaml { key, value } => map.serialize_entry(key, value)?,
+                        UnknownComponent::Binary { uuid, .. } => {
+                            log::warn!("Dropping component {} which was loaded from a binary world", uuid)
+                        }
+                    }
                 }
+            } else if !is_derived_component(type_id) {
+                log::warn!("Skipping serializing typeid: {:#?}", type_id);
             }
         }
 
@@ -36,7 +105,14 @@ impl<'r, 'e> Serialize for ComponentsSerialize<'r, 'e> {
     }
 }
 
-struct ComponentSerialize<'r, 'e>(&'r Registry, EntityRef<'e>, Uuid);
+/// Components which are stored implicitly or rebuilt after loading
+pub(crate) fn is_derived_component(type_id: TypeId) -> bool {
+    type_id == TypeId::of::<EntityId>()
+        || type_id == TypeId::of::<Children>()
+        || type_id == TypeId::of::<GlobalTransform>()
+}
+
+pub(crate) struct ComponentSerialize<'r, 'e>(pub &'r Registry, pub EntityRef<'e>, pub Uuid);
 
 impl<'r, 'e> Serialize for ComponentSerialize<'r, 'e> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -48,6 +124,7 @@ impl<'r, 'e> Serialize for ComponentSerialize<'r, 'e> {
 }
 #[derive(Clone)]
 pub(crate) struct SerializeFns {
+    version: u32,
     serialize_fn: fn(EntityRef<'_>, &mut dyn FnMut(&dyn erased_serde::Serialize)),
     deserialize_fn: fn(
         Entity,
@@ -95,9 +172,38 @@ impl RegistryInner {
     }
 }
 impl Registry {
-    fn has_serde(&self, component_id: &Uuid) -> bool{
+    pub(crate) fn has_serde(&self, component_id: &Uuid) -> bool{
         self.inner.serialize_fns.contains_key(component_id)
     }
+    /// Current schema version of a component registered for serialization
+    pub fn component_version(&self, component_id: &Uuid) -> u32 {
+        self.inner
+            .serialize_fns
+            .get(component_id)
+            .map_or(0, |serialize_fns| serialize_fns.version)
+    }
+    /// Upgrades a component saved with schema version `version` to the current version
+    pub fn migrate_component(&self, component_id: &Uuid, version: u32, mut value: Value) -> anyhow::Result<Value> {
+        let current = self.component_version(component_id);
+        if version > current {
+            return Err(anyhow::anyhow!(
+                "Component {} was saved with version {}, but only versions up to {} are supported",
+                component_id,
+                version,
+                current
+            ));
+        }
+
+        for from in version..current {
+            let migration = self.inner.migrations.get(&(*component_id, from)).ok_or_else(|| {
+                anyhow::anyhow!("No migration for component {} from version {}", component_id, from)
+            })?;
+
+            value = migration(value)?;
+        }
+
+        Ok(value)
+    }
     fn serialize_component<S: Serializer>(
         &self,
         component_id: &Uuid,
@@ -107,7 +213,7 @@ impl Registry {
         self.inner
             .serialize_component(component_id, entity_ref, serializer)
     }
-    fn deserialize_component<'de, D: Deserializer<'de>>(
+    pub(crate) fn deserialize_component<'de, D: Deserializer<'de>>(
         &self,
         component_id: &Uuid,
         entity: Entity,
@@ -117,10 +223,45 @@ impl Registry {
         self.inner
             .deserialize_component(component_id, entity, world, deserializer)
     }
+    /// Serializes the component of type `type_id` on `entity_ref`.
+    /// Returns `None` if the type isn't registered for serialization or the entity doesn't have the component
+    pub fn serialize_component_of_type<S: Serializer>(
+        &self,
+        type_id: TypeId,
+        entity_ref: EntityRef,
+        serializer: S,
+    ) -> Option<Result<S::Ok, S::Error>> {
+        let uuid = self.type_id_to_uuid(type_id)?;
+        if !entity_ref.component_types().any(|ty| ty == type_id) {
+            return None;
+        }
+
+        Some(self.serialize_component(uuid, entity_ref, serializer))
+    }
+    /// Deserializes a component of type `type_id` and adds it to `entity`, replacing the current value if present
+    pub fn deserialize_component_of_type<'de, D: Deserializer<'de>>(
+        &self,
+        type_id: TypeId,
+        entity: Entity,
+        world: &mut World,
+        deserializer: D,
+    ) -> Result<(), D::Error> {
+        let Some(&uuid) = self.type_id_to_uuid(type_id) else {
+            return Err(serde::de::Error::custom("Type not registered for deserialization"));
+        };
+
+        self.deserialize_component(&uuid, entity, world, deserializer)
+    }
 }
 impl RegistryBuilder {
     pub fn register_serde<C: SerializeComponent>(&mut self) {
+        self.register_serde_versioned::<C>(0);
+    }
+    /// Registers `C` for serialization with a schema version, which is saved along with the component.
+    /// Components saved with an older version are upgraded with the migrations registered through [`RegistryBuilder::register_migration`]
+    pub fn register_serde_versioned<C: SerializeComponent>(&mut self, version: u32) {
         let serialize_fns = SerializeFns {
+            version,
             serialize_fn: |entity_ref, serialize_fn| {
                 let component = entity_ref.get::<&C>().unwrap();
                 (serialize_fn)(&*component)
@@ -140,6 +281,16 @@ impl RegistryBuilder {
 
         self.registry.serialize_fns.insert(uuid, serialize_fns);
     }
+    /// Registers a migration upgrading the serialized form of `C` from `from_version` to `from_version + 1`
+    pub fn register_migration<C: SerializeComponent>(
+        &mut self,
+        from_version: u32,
+        migration: impl Fn(Value) -> anyhow::Result<Value> + Send + Sync + 'static,
+    ) {
+        self.registry
+            .migrations
+            .insert((Uuid::from_bytes(C::UUID), from_version), Box::new(migration));
+    }
 }
 
 pub struct SerializableWorld<'w, 'r> {
@@ -199,6 +350,9 @@ impl World {
         }
         .deserialize(deserializer)?;
 
+        world.relink_hierarchy();
+        world.resolve_entity_links(registry);
+
         Ok(world)
     }
 }
@@ -288,14 +442,34 @@ impl<'w, 'r, 'de> Visitor<'de> for ComponentsVisitor<'w, 'r> {
     where
         A: MapAccess<'de>,
     {
-        while let Some(uuid) = map.next_key::<uuid::Uuid>()? {
-            if self.2.has_serde(&uuid) {                
+        while let Some(key) = map.next_key::<String>()? {
+            let (uuid, version) = parse_component_key(&key)
+                .ok_or_else(|| serde::de::Error::custom(format!("Invalid component key: {}", key)))?;
+
+            if !self.2.has_serde(&uuid) {
+                let value = map.next_value::<Value>()?;
+                log::warn!("Preserving unknown component uuid: {}", uuid);
+                UnknownComponents::push(self.1, self.0, UnknownComponent::Yaml { key, value });
+                continue;
+            }
+
+            if version == self.2.component_version(&uuid) {
                 map.next_value_seed::<ComponentDeserializer>(ComponentDeserializer(
                     uuid, self.0, self.1, self.2,
                 ))?;
-            } else {
-                map.next_value::<serde_yaml::Value>()?;
-                log::warn!("Skipping deserializing uuid: {}", uuid);
+                continue;
+            }
+
+            // Old versions go through a value, so that migrations can rewrite it
+            let value = map.next_value::<Value>()?;
+            let result = self
+                .2
+                .migrate_component(&uuid, version, value.clone())
+                .and_then(|migrated| Ok(self.2.deserialize_component(&uuid, self.0, self.1, migrated)?));
+
+            if let Err(err) = result {
+                log::error!("Failed to migrate component {}: {}", uuid, err);
+                UnknownComponents::push(self.1, self.0, UnknownComponent::Yaml { key, value });
             }
         }
 
@@ -377,6 +551,43 @@ fn serialize_component() {
     );
 }
 
+#[test]
+fn component_of_type_round_trip() {
+    #[derive(Serialize, Deserialize, TypeUuid, PartialEq, Debug)]
+    #[uuid = "af785417-dd52-4397-ba55-f747c6d67fc9"]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    let mut world = World::new();
+    let entity = world.create_entity();
+    world
+        .add_component(entity, Position { x: 0.5, y: 0.0 })
+        .unwrap();
+
+    let mut registry = Registry::builder();
+    registry.register_serde::<Position>();
+    let registry = registry.build();
+
+    let type_id = TypeId::of::<Position>();
+    let snapshot = registry
+        .serialize_component_of_type(type_id, world.entity(entity).unwrap(), serde_yaml::value::Serializer)
+        .unwrap()
+        .unwrap();
+
+    world.get_component::<&mut Position>(entity).unwrap().x = 2.0;
+    registry
+        .deserialize_component_of_type(type_id, entity, &mut world, snapshot)
+        .unwrap();
+    assert_eq!(&Position { x: 0.5, y: 0.0 }, &*world.get_component::<&Position>(entity).unwrap());
+
+    world.remove_component::<Position>(entity).unwrap();
+    assert!(registry
+        .serialize_component_of_type(type_id, world.entity(entity).unwrap(), serde_yaml::value::Serializer)
+        .is_none());
+}
+
 #[test]
 fn round_trip() {
     #[derive(Serialize, Deserialize, TypeUuid, PartialEq, Debug)]
@@ -425,3 +636,110 @@ fn round_trip() {
 
     assert_eq!(&Id { name: "Foo".into() }, &*id);
 }
+
+#[test]
+fn hierarchy_round_trip() {
+    let mut world_in = World::new();
+    let parent = world_in.create_entity();
+    let child = world_in.create_entity();
+    world_in.set_parent(child, parent).unwrap();
+
+    let registry = Registry::builder().build();
+
+    let world_string = serde_yaml::to_string(&world_in.as_serializable(&registry)).unwrap();
+
+    let deserializer = serde_yaml::Deserializer::from_str(&world_string);
+    let world_out = World::deserialize(deserializer, &registry).unwrap();
+
+    assert_eq!(world_out.parent(child), Some(parent));
+    assert_eq!(world_out.children(parent), vec![child]);
+}
+
+#[test]
+fn migrate_component() {
+    #[derive(Serialize, Deserialize, TypeUuid)]
+    #[uuid = "af785417-dd52-4397-ba55-f747c6d67fc9"]
+    struct OldLight {
+        power: f32,
+    }
+
+    #[derive(Serialize, Deserialize, TypeUuid, PartialEq, Debug)]
+    #[uuid = "af785417-dd52-4397-ba55-f747c6d67fc9"]
+    struct Light {
+        intensity: f32,
+        range: f32,
+    }
+
+    let mut world = World::new();
+    let entity = world.create_entity();
+    world.add_component(entity, OldLight { power: 5.0 }).unwrap();
+
+    let mut old_registry = Registry::builder();
+    old_registry.register_serde::<OldLight>();
+    let old_registry = old_registry.build();
+    let world_string = serde_yaml::to_string(&world.as_serializable(&old_registry)).unwrap();
+
+    let mut registry = Registry::builder();
+    registry.register_serde_versioned::<Light>(2);
+    // Version 1 renamed `power` to `intensity`
+    registry.register_migration::<Light>(0, |mut value| {
+        let map = value.as_mapping_mut().unwrap();
+        let power = map.remove("power").unwrap();
+        map.insert("intensity".into(), power);
+        Ok(value)
+    });
+    // Version 2 added `range`
+    registry.register_migration::<Light>(1, |mut value| {
+        value.as_mapping_mut().unwrap().insert("range".into(), 10.0.into());
+        Ok(value)
+    });
+    let registry = registry.build();
+
+    let deserializer = serde_yaml::Deserializer::from_str(&world_string);
+    let world = World::deserialize(deserializer, &registry).unwrap();
+
+    assert_eq!(
+        &Light { intensity: 5.0, range: 10.0 },
+        &*world.get_component::<&Light>(entity).unwrap()
+    );
+
+    // Saving writes the current version
+    let saved = serde_yaml::to_string(&world.as_serializable(&registry)).unwrap();
+    assert!(saved.contains(&format!("{}@2", Uuid::from_bytes(Light::UUID))));
+}
+
+#[test]
+fn preserve_unknown_components() {
+    #[derive(Serialize, Deserialize, TypeUuid)]
+    #[uuid = "af785417-dd52-4397-ba55-f747c6d67fc9"]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    let mut registry = Registry::builder();
+    registry.register_serde::<Position>();
+    let full_registry = registry.build();
+
+    let mut world = World::new();
+    let entity = world.create_entity();
+    world.add_component(entity, Position { x: 0.5, y: 1.0 }).unwrap();
+    let world_string = serde_yaml::to_string(&world.as_serializable(&full_registry)).unwrap();
+
+    // A registry which doesn't know about `Position` keeps it as an opaque value
+    let registry = Registry::builder().build();
+    let deserializer = serde_yaml::Deserializer::from_str(&world_string);
+    let world = World::deserialize(deserializer, &registry).unwrap();
+
+    let unknown = world.get_component::<&UnknownComponents>(entity).unwrap();
+    assert_eq!(unknown.uuids().collect::<Vec<_>>(), vec![Uuid::from_bytes(Position::UUID)]);
+    drop(unknown);
+
+    let world = world.clone(&registry);
+    let resaved = serde_yaml::to_string(&world.as_serializable(&registry)).unwrap();
+
+    let deserializer = serde_yaml::Deserializer::from_str(&resaved);
+    let world = World::deserialize(deserializer, &full_registry).unwrap();
+    let position = world.get_component::<&Position>(entity).unwrap();
+    assert_eq!((position.x, position.y), (0.5, 1.0));
+}
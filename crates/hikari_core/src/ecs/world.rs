@@ -1,6 +1,9 @@
-use std::any::TypeId;
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+};
 
-use crate::{CloneComponent, Component, Registry, EntityId};
+use crate::{Children, CloneComponent, Component, EntityId, Parent, Registry};
 
 pub type Entity = hecs::Entity;
 
@@ -96,8 +99,13 @@ impl World {
             self.add_component(handle, EntityId::new()).unwrap();
         }
     }
-    #[inline]
+    /// Removes `entity`, its children are detached and become root entities
     pub fn remove_entity(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        if !self.inner.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        self.unlink_hierarchy(entity);
+
         self.inner.despawn(entity)
     }
     pub fn clone_entity(
@@ -123,6 +131,12 @@ impl World {
 
         let dup_entity = self.inner.spawn(builder.build());
 
+        // Only the entity itself is duplicated, it becomes a sibling of the original
+        let _ = self.inner.remove_one::<Children>(dup_entity);
+        if let Some(parent) = self.parent(dup_entity) {
+            self.add_child(parent, dup_entity);
+        }
+
         Ok(dup_entity)
     }
     #[inline]
@@ -156,6 +170,42 @@ impl World {
             let entity = entity.entity();
             dst.create_entity_at(entity, builder.build());
         }
+        dst.resolve_entity_links(registry);
+    }
+    /// Copies every entity into `dst` alongside the entities it already has and returns the copies.
+    /// Copies get new handles, and new uuids if `dst` already has entities with the same uuids
+    pub fn merge_into(&self, registry: &Registry, dst: &mut World) -> Vec<Entity> {
+        hikari_dev::profile_function!();
+        let existing: HashSet<Uuid> = dst.raw().query::<&EntityId>().iter().map(|(_, id)| id.uuid).collect();
+        let uuids: HashMap<Uuid, Uuid> = self
+            .raw()
+            .query::<&EntityId>()
+            .iter()
+            .filter(|(_, id)| existing.contains(&id.uuid))
+            .map(|(_, id)| (id.uuid, Uuid::new_v4()))
+            .collect();
+
+        let mut copies = Vec::with_capacity(self.len());
+        for entity in self.entities() {
+            let mut builder = registry.clone_entity(entity);
+            if let Some(id) = builder.get_mut::<&mut EntityId>() {
+                if let Some(&uuid) = uuids.get(&id.uuid) {
+                    id.uuid = uuid;
+                }
+            }
+            let parent = builder.get::<&Parent>().and_then(|parent| uuids.get(&parent.uuid()).copied());
+            if let Some(parent) = parent {
+                builder.add(Parent::new(Entity::DANGLING, parent));
+            }
+
+            copies.push(dst.raw_mut().spawn(builder.build()));
+        }
+
+        dst.relink_hierarchy_of(&copies);
+        dst.remap_entity_links(registry, &copies, &uuids);
+        dst.resolve_entity_links(registry);
+
+        copies
     }
     pub fn clone(&self, registry: &Registry) -> World {
         hikari_dev::profile_function!();
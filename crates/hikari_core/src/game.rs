@@ -1,6 +1,6 @@
-use std::{sync::Arc, path::Path};
+use std::{any::TypeId, collections::HashMap, path::Path, sync::Arc};
 
-use hikari_asset::{Asset, AssetManager, Loader, Saver, AssetManagerBuilder};
+use hikari_asset::{Asset, AssetManager, Importer, Loader, Saver, AssetManagerBuilder};
 use hikari_systems::*;
 
 use rayon::ThreadPoolBuilder;
@@ -10,7 +10,10 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
-use crate::Plugin;
+use crate::{
+    states::{StateTransitions, TransitionBuilder},
+    Plugin, StateValue, States, Time,
+};
 
 pub type InitResult = anyhow::Result<()>;
 pub struct Game {
@@ -19,6 +22,7 @@ pub struct Game {
     init_schedule: ScheduleBuilder<InitResult>,
     run_schedule: ScheduleBuilder<()>,
     exit_schedule: ScheduleBuilder<()>,
+    state_transitions: HashMap<TypeId, Box<dyn TransitionBuilder>>,
     asset_manager_builder: AssetManagerBuilder,
     event_hooks:
         Vec<Box<dyn FnMut(&GlobalState, &mut Window, &Event<()>, &mut ControlFlow) + 'static>>,
@@ -30,6 +34,7 @@ impl Game {
         let window = window_builder.build(&event_loop)?;
         let mut state = StateBuilder::new();
         state.add_state(window);
+        state.add_state(TaskControl::default());
 
         Ok(Self {
             state,
@@ -37,6 +42,7 @@ impl Game {
             init_schedule: ScheduleBuilder::new(),
             run_schedule: ScheduleBuilder::new(),
             exit_schedule: ScheduleBuilder::new(),
+            state_transitions: HashMap::new(),
             asset_manager_builder: AssetManager::builder(),
             event_hooks: Vec::new(),
         })
@@ -71,6 +77,11 @@ impl Game {
         self.run_schedule.add_task(stage, task);
         self
     }
+    /// Configures the ordering and run conditions of a set of tasks in the run schedule
+    pub fn configure_set(&mut self, set: TaskSet) -> &mut Self {
+        self.run_schedule.configure_set(set);
+        self
+    }
     pub fn add_init_task(&mut self, stage: &str, task: Task<InitResult>) -> &mut Self {
         self.init_schedule.add_task(stage, task);
         self
@@ -105,6 +116,51 @@ impl Game {
         );
         self
     }
+    /// Adds [`Events<T>`] to the state, so that tasks can communicate through [`EventWriter<T>`] and [`EventReader<T>`].
+    /// Events are kept until the end of the next frame
+    pub fn add_event<T: State>(&mut self) -> &mut Self {
+        self.add_state(Events::<T>::new());
+
+        let mut task_name = String::from(std::any::type_name::<T>());
+        task_name.push_str("_event_update");
+        self.add_task(
+            crate::FIRST,
+            Task::new(&task_name, |events: &mut Events<T>| {
+                events.update();
+            }),
+        );
+        self
+    }
+    /// Adds the state machine [`States<S>`], starting in `initial`.
+    /// The enter tasks of `initial` run at the start of the first frame
+    pub fn add_states<S: StateValue>(&mut self, initial: S) -> &mut Self {
+        self.add_state(States::new(initial));
+        self.state_transitions
+            .entry(TypeId::of::<S>())
+            .or_insert_with(crate::states::transition_builder::<S>);
+
+        self
+    }
+    /// Adds a task which runs once whenever `state` is entered, before the run schedule of that frame
+    pub fn add_on_enter_task<S: StateValue>(&mut self, state: S, task: Task<()>) -> &mut Self {
+        crate::states::add_transition_task(self.transitions_mut::<S>(), state, true, task);
+        self
+    }
+    /// Adds a task which runs once whenever `state` is left, before the enter tasks of the next state
+    pub fn add_on_exit_task<S: StateValue>(&mut self, state: S, task: Task<()>) -> &mut Self {
+        crate::states::add_transition_task(self.transitions_mut::<S>(), state, false, task);
+        self
+    }
+    /// Adds a task to the run schedule which only runs while the state machine is in `state`
+    pub fn add_task_in_state<S: StateValue>(&mut self, stage: &str, state: S, task: Task<()>) -> &mut Self {
+        self.add_task(stage, task.run_if(crate::in_state(state)))
+    }
+    fn transitions_mut<S: StateValue>(&mut self) -> &mut dyn TransitionBuilder {
+        self.state_transitions
+            .get_mut(&TypeId::of::<S>())
+            .unwrap_or_else(|| panic!("States {} have not been added", std::any::type_name::<S>()))
+            .as_mut()
+    }
     pub fn register_asset_loader<T: Asset, L: Loader>(&mut self, loader: L) -> &mut Self {
         self.asset_manager_builder.register_loader::<T, L>(loader);
 
@@ -115,11 +171,22 @@ impl Game {
 
         self
     }
+    pub fn register_asset_importer<T: Asset, I: Importer>(&mut self, importer: I) -> &mut Self {
+        self.asset_manager_builder.register_importer::<T, I>(importer);
+
+        self
+    }
     pub fn set_asset_dir(&mut self, path: impl AsRef<Path>) -> &mut Self {
         self.asset_manager_builder.set_asset_dir(path);
 
         self
     }
+    /// Reloads assets when their files are modified on disk
+    pub fn set_hot_reload(&mut self, enabled: bool) -> &mut Self {
+        self.asset_manager_builder.hot_reload(enabled);
+
+        self
+    }
     pub fn window(&mut self) -> SysRefMut<Window> {
         self.state.get_mut::<Window>()
     }
@@ -135,6 +202,12 @@ impl Game {
         let mut exit = self.exit_schedule
         .build()
         .expect("Failed to create exit schedule");
+        let mut state_transitions: Vec<Box<dyn StateTransitions>> = self
+            .state_transitions
+            .into_values()
+            .map(|transitions| transitions.build())
+            .collect::<Result<_, _>>()
+            .expect("Failed to create state transition schedules");
 
         let asset_manager = {
             let threadpool = Arc::new(ThreadPoolBuilder::new().num_threads(2).build().unwrap());
@@ -145,6 +218,8 @@ impl Game {
         };
 
         self.state.add_state(asset_manager);
+        // Lets tools inspect how the run schedule was resolved
+        self.state.add_state(update.info());
 
         let mut state = self.state.build();
         let mut hooks = self.event_hooks;
@@ -165,7 +240,16 @@ impl Game {
             match &event {
                 Event::RedrawRequested(_) => {
                     hikari_dev::profile_scope!("Gameloop");
-                    update.execute(&mut state);
+                    for transitions in &mut state_transitions {
+                        transitions.apply(&mut state);
+                    }
+                    update.execute_parallel_with(&mut state, |stage, state| {
+                        if stage == crate::FIXED_UPDATE {
+                            state.get_mut::<Time>().unwrap().take_fixed_steps()
+                        } else {
+                            1
+                        }
+                    });
                     hikari_dev::finish_frame!();
                 }
                 Event::MainEventsCleared => {
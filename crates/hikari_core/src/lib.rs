@@ -1,6 +1,7 @@
 mod ecs;
 mod game;
 mod plugin;
+mod states;
 mod time;
 mod window;
 
@@ -8,6 +9,7 @@ use std::sync::Arc;
 
 pub use ecs::*;
 pub use game::*;
+pub use states::*;
 pub use time::*;
 
 pub use hikari_systems::*;
@@ -17,7 +19,10 @@ use rayon::ThreadPoolBuilder;
 pub use winit;
 
 pub const FIRST: &'static str = "First";
+/// Runs zero or more times per frame, once for every [`Time::fixed_dt`] that has passed
+pub const FIXED_UPDATE: &'static str = "FixedUpdate";
 pub const UPDATE: &'static str = "Update";
+pub const POST_UPDATE: &'static str = "PostUpdate";
 pub const RENDER: &'static str = "Render";
 pub const POST_RENDER: &'static str = "PostRender";
 pub const LAST: &'static str = "Last";
@@ -26,7 +31,9 @@ pub struct CorePlugin;
 impl crate::Plugin for CorePlugin {
     fn build(self, game: &mut Game) {
         game.create_stage(FIRST);
+        game.create_stage(FIXED_UPDATE);
         game.create_stage(UPDATE);
+        game.create_stage(POST_UPDATE);
         game.create_stage(RENDER);
         game.create_stage(POST_RENDER);
         game.create_stage(LAST);
@@ -42,6 +49,13 @@ impl crate::Plugin for CorePlugin {
 
         game.add_state(World::new());
 
+        game.add_task(
+            POST_UPDATE,
+            Task::new("Propagate Transforms", |world: &mut World| {
+                world.propagate_transforms();
+            }),
+        );
+
         let threadpool = ThreadPoolBuilder::new()
             //.num_threads(2)
             .build()
@@ -0,0 +1,218 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
+
+use hikari_systems::{GlobalState, IntoFunction, Schedule, ScheduleBuilder, Task};
+
+/// A value of a game-defined state machine, usually a fieldless enum like `MainMenu`, `Loading`, `Playing`
+pub trait StateValue: Clone + Eq + Hash + Debug + Send + Sync + 'static {}
+
+impl<S: Clone + Eq + Hash + Debug + Send + Sync + 'static> StateValue for S {}
+
+const TRANSITION_STAGE: &str = "Transition";
+
+/// The current state of a state machine added with [`crate::Game::add_states`].
+/// Transitions requested through [`States::set`] are applied at the start of the next frame,
+/// running the exit tasks of the old state and the enter tasks of the new one
+#[derive(Debug)]
+pub struct States<S: StateValue> {
+    current: S,
+    previous: Option<S>,
+    next: Option<S>,
+    entered: bool,
+}
+
+impl<S: StateValue> States<S> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            current: initial,
+            previous: None,
+            next: None,
+            entered: false,
+        }
+    }
+    pub fn current(&self) -> &S {
+        &self.current
+    }
+    /// The state before the last transition
+    pub fn previous(&self) -> Option<&S> {
+        self.previous.as_ref()
+    }
+    /// The state which will be entered at the start of the next frame
+    pub fn next(&self) -> Option<&S> {
+        self.next.as_ref()
+    }
+    pub fn is(&self, state: &S) -> bool {
+        &self.current == state
+    }
+    /// Queues a transition to `state`. Setting the current state again reruns its exit and enter tasks
+    pub fn set(&mut self, state: S) {
+        self.next = Some(state);
+    }
+    /// Returns the state to exit, if any, and the state to enter
+    fn take_transition(&mut self) -> Option<(Option<S>, S)> {
+        if !self.entered {
+            self.entered = true;
+            return Some((None, self.current.clone()));
+        }
+
+        let next = self.next.take()?;
+        let exited = std::mem::replace(&mut self.current, next);
+        self.previous = Some(exited.clone());
+
+        Some((self.previous.clone(), self.current.clone()))
+    }
+}
+
+/// Run condition which passes while the state machine is in `state`, see [`Task::run_if`]
+pub fn in_state<S: StateValue>(state: S) -> impl IntoFunction<(&'static States<S>,), bool> {
+    move |states: &States<S>| states.is(&state)
+}
+
+/// Enter and exit schedules of every value of a state machine
+struct Transitions<S, Sched> {
+    enter: HashMap<S, Sched>,
+    exit: HashMap<S, Sched>,
+}
+
+/// Type erased [`Transitions`] so that [`crate::Game`] can hold the schedules of every state machine
+pub(crate) trait TransitionBuilder {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+    fn build(self: Box<Self>) -> Result<Box<dyn StateTransitions>, String>;
+}
+
+pub(crate) trait StateTransitions {
+    /// Runs the exit and enter schedules if a transition is pending
+    fn apply(&mut self, state: &mut GlobalState);
+}
+
+pub(crate) fn transition_builder<S: StateValue>() -> Box<dyn TransitionBuilder> {
+    Box::new(Transitions::<S, ScheduleBuilder<()>> {
+        enter: HashMap::new(),
+        exit: HashMap::new(),
+    })
+}
+
+pub(crate) fn add_transition_task<S: StateValue>(
+    builder: &mut dyn TransitionBuilder,
+    state: S,
+    enter: bool,
+    task: Task<()>,
+) {
+    let transitions = builder
+        .as_any_mut()
+        .downcast_mut::<Transitions<S, ScheduleBuilder<()>>>()
+        .expect("Mismatched state transitions");
+    let schedules = if enter {
+        &mut transitions.enter
+    } else {
+        &mut transitions.exit
+    };
+
+    schedules
+        .entry(state)
+        .or_insert_with(|| {
+            let mut schedule = ScheduleBuilder::new();
+            schedule.create_stage(TRANSITION_STAGE);
+            schedule
+        })
+        .add_task(TRANSITION_STAGE, task);
+}
+
+fn build_schedules<S: StateValue>(
+    schedules: HashMap<S, ScheduleBuilder<()>>,
+) -> Result<HashMap<S, Schedule<()>>, String> {
+    schedules
+        .into_iter()
+        .map(|(state, schedule)| {
+            let schedule = schedule
+                .build()
+                .map_err(|err| format!("Failed to build transition schedule of {:?}: {}", state, err))?;
+            Ok((state, schedule))
+        })
+        .collect()
+}
+
+impl<S: StateValue> TransitionBuilder for Transitions<S, ScheduleBuilder<()>> {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+    fn build(self: Box<Self>) -> Result<Box<dyn StateTransitions>, String> {
+        Ok(Box::new(Transitions {
+            enter: build_schedules(self.enter)?,
+            exit: build_schedules(self.exit)?,
+        }))
+    }
+}
+
+impl<S: StateValue> StateTransitions for Transitions<S, Schedule<()>> {
+    fn apply(&mut self, state: &mut GlobalState) {
+        let transition = state.get_mut::<States<S>>().unwrap().take_transition();
+
+        if let Some((exited, entered)) = transition {
+            hikari_dev::profile_function!();
+            log::debug!("Entering state {:?}", entered);
+
+            if let Some(schedule) = exited.and_then(|exited| self.exit.get_mut(&exited)) {
+                schedule.execute(state);
+            }
+            if let Some(schedule) = self.enter.get_mut(&entered) {
+                schedule.execute(state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hikari_systems::StateBuilder;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    enum Menu {
+        Main,
+        Playing,
+    }
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    fn log_task(name: &'static str) -> Task<()> {
+        Task::new(name, move |log: &mut Log| log.0.push(name))
+    }
+
+    #[test]
+    fn transitions_run_once() {
+        let mut builder = transition_builder::<Menu>();
+        add_transition_task(builder.as_mut(), Menu::Main, true, log_task("enter main"));
+        add_transition_task(builder.as_mut(), Menu::Main, false, log_task("exit main"));
+        add_transition_task(builder.as_mut(), Menu::Playing, true, log_task("enter playing"));
+        let mut transitions = builder.build().unwrap();
+
+        let mut state = StateBuilder::new();
+        state.add_state(States::new(Menu::Main));
+        state.add_state(Log::default());
+        let mut state = state.build();
+
+        transitions.apply(&mut state);
+        transitions.apply(&mut state);
+        assert_eq!(state.get::<Log>().unwrap().0, ["enter main"]);
+
+        state.get_mut::<States<Menu>>().unwrap().set(Menu::Playing);
+        transitions.apply(&mut state);
+        transitions.apply(&mut state);
+        assert_eq!(state.get::<Log>().unwrap().0, ["enter main", "exit main", "enter playing"]);
+
+        {
+            let states = state.get::<States<Menu>>().unwrap();
+            assert!(states.is(&Menu::Playing));
+            assert_eq!(states.previous(), Some(&Menu::Main));
+        }
+
+        let mut schedule = ScheduleBuilder::new();
+        schedule.create_stage("Update");
+        schedule.add_task("Update", log_task("main menu").run_if(in_state(Menu::Main)));
+        schedule.add_task("Update", log_task("gameplay").run_if(in_state(Menu::Playing)));
+        schedule.build().unwrap().execute(&mut state);
+        assert_eq!(state.get::<Log>().unwrap().0.last(), Some(&"gameplay"));
+    }
+}
@@ -1,25 +1,159 @@
 use std::time::{Duration, Instant};
 
+const DEFAULT_FIXED_DT: Duration = Duration::from_nanos(1_000_000_000 / 60);
+const DEFAULT_MAX_FIXED_STEPS: usize = 8;
+
 #[derive(Clone, Copy, Debug)]
 pub struct Time {
     last: Instant,
     current_dt: Duration,
+    unscaled_dt: Duration,
+    elapsed: Duration,
+    frame_count: u64,
+    time_scale: f32,
+    fixed_dt: Duration,
+    accumulator: Duration,
+    max_fixed_steps: usize,
 }
 impl Time {
     pub(crate) fn new() -> Self {
         Self {
             last: Instant::now(),
             current_dt: Duration::ZERO,
+            unscaled_dt: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame_count: 0,
+            time_scale: 1.0,
+            fixed_dt: DEFAULT_FIXED_DT,
+            accumulator: Duration::ZERO,
+            max_fixed_steps: DEFAULT_MAX_FIXED_STEPS,
         }
     }
     pub(crate) fn update(&mut self) {
         let now = Instant::now();
 
-        self.current_dt = now - self.last;
+        self.advance(now - self.last);
 
         self.last = now;
     }
+    fn advance(&mut self, unscaled_dt: Duration) {
+        self.unscaled_dt = unscaled_dt;
+        self.current_dt = unscaled_dt.mul_f32(self.time_scale);
+        self.elapsed += self.current_dt;
+        self.accumulator += self.current_dt;
+        self.frame_count += 1;
+    }
+    /// Consumes the accumulated time in steps of [`Time::fixed_dt`] and returns how many fixed updates should run this frame.
+    /// If the game falls too far behind, the surplus time is dropped instead of running ever more steps
+    pub(crate) fn take_fixed_steps(&mut self) -> usize {
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_dt {
+            if steps == self.max_fixed_steps {
+                log::debug!("Fixed update is falling behind, skipping {:?}", self.accumulator);
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+
+            self.accumulator -= self.fixed_dt;
+            steps += 1;
+        }
+
+        steps
+    }
+    /// Scaled time since the last frame in seconds
     pub fn dt(&self) -> f32 {
         self.current_dt.as_secs_f32()
     }
+    /// Time since the last frame in seconds, ignoring the time scale
+    pub fn unscaled_dt(&self) -> f32 {
+        self.unscaled_dt.as_secs_f32()
+    }
+    /// Scaled time since the game started in seconds
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+    /// Number of frames since the game started
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+    /// Speeds up or slows down the game, 0 pauses it. Fixed updates don't run while paused
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        assert!(time_scale >= 0.0, "Time scale must not be negative");
+        self.time_scale = time_scale;
+    }
+    /// Time simulated by every run of the [`crate::FIXED_UPDATE`] stage in seconds
+    pub fn fixed_dt(&self) -> f32 {
+        self.fixed_dt.as_secs_f32()
+    }
+    /// Sets how many times per second [`crate::FIXED_UPDATE`] runs
+    pub fn set_fixed_rate(&mut self, hz: f32) {
+        assert!(hz > 0.0, "Fixed update rate must be positive");
+        self.fixed_dt = Duration::from_secs_f64(1.0 / hz as f64);
+    }
+    /// Limits how many fixed updates can run in a single frame
+    pub fn set_max_fixed_steps(&mut self, steps: usize) {
+        self.max_fixed_steps = steps.max(1);
+    }
+    /// How far the game is between the last and the next fixed update, from 0 to 1.
+    /// State updated in [`crate::FIXED_UPDATE`] should be interpolated by this when rendering
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.fixed_dt.as_secs_f64()) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(time: &mut Time, frame_dt: Duration, frames: usize) -> usize {
+        (0..frames)
+            .map(|_| {
+                time.advance(frame_dt);
+                time.take_fixed_steps()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn fixed_steps_independent_of_framerate() {
+        let mut slow = Time::new();
+        let mut fast = Time::new();
+
+        // One second at 60 Hz and at 144 Hz
+        let slow_steps = frames(&mut slow, Duration::from_nanos(1_000_000_000 / 60), 60);
+        let fast_steps = frames(&mut fast, Duration::from_nanos(1_000_000_000 / 144), 144);
+
+        assert!((59..=60).contains(&slow_steps));
+        assert!((59..=60).contains(&fast_steps));
+        assert!((0.0..1.0).contains(&slow.alpha()));
+        assert!((0.0..1.0).contains(&fast.alpha()));
+        assert_eq!(fast.frame_count(), 144);
+    }
+
+    #[test]
+    fn time_scale() {
+        let mut time = Time::new();
+        time.set_fixed_rate(10.0);
+
+        time.set_time_scale(0.0);
+        assert_eq!(frames(&mut time, Duration::from_millis(100), 10), 0);
+        assert_eq!(time.elapsed(), 0.0);
+        assert_eq!(time.unscaled_dt(), 0.1);
+
+        time.set_time_scale(0.5);
+        assert_eq!(frames(&mut time, Duration::from_millis(100), 10), 5);
+        assert!((time.elapsed() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn caps_fixed_steps() {
+        let mut time = Time::new();
+        time.set_max_fixed_steps(4);
+
+        assert_eq!(frames(&mut time, Duration::from_secs(1), 1), 4);
+        assert_eq!(time.alpha(), 0.0);
+    }
 }
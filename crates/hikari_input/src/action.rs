@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, HashMap};
+
+use hikari_math::Vec2;
+
+use crate::{Input, KeyCode, MouseButton};
+
+/// A key or a mouse button
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Button {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Button {
+    fn is_down(&self, input: &Input) -> bool {
+        match *self {
+            Button::Key(key) => input.keyboard().is_key_down(key),
+            Button::Mouse(button) => input.mouse().is_pressed(button),
+        }
+    }
+}
+
+impl From<KeyCode> for Button {
+    fn from(key: KeyCode) -> Self {
+        Button::Key(key)
+    }
+}
+impl From<MouseButton> for Button {
+    fn from(button: MouseButton) -> Self {
+        Button::Mouse(button)
+    }
+}
+
+/// Modifier keys which have to be held for a binding, either the left or the right key counts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Self = Self {
+        shift: false,
+        ctrl: false,
+        alt: false,
+        logo: false,
+    };
+    pub const SHIFT: Self = Self { shift: true, ..Self::NONE };
+    pub const CTRL: Self = Self { ctrl: true, ..Self::NONE };
+    pub const ALT: Self = Self { alt: true, ..Self::NONE };
+
+    fn are_held(&self, input: &Input) -> bool {
+        let keyboard = input.keyboard();
+        let held = |left, right| keyboard.is_key_down(left) || keyboard.is_key_down(right);
+
+        (!self.shift || held(KeyCode::LShift, KeyCode::RShift))
+            && (!self.ctrl || held(KeyCode::LControl, KeyCode::RControl))
+            && (!self.alt || held(KeyCode::LAlt, KeyCode::RAlt))
+            && (!self.logo || held(KeyCode::LWin, KeyCode::RWin))
+    }
+}
+
+/// Binds a button, optionally combined with modifier keys, to a button action
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ButtonBinding {
+    pub button: Button,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_none"))]
+    pub modifiers: Modifiers,
+}
+
+#[cfg(feature = "serde")]
+fn is_none(modifiers: &Modifiers) -> bool {
+    *modifiers == Modifiers::NONE
+}
+
+impl ButtonBinding {
+    pub fn new(button: impl Into<Button>) -> Self {
+        Self {
+            button: button.into(),
+            modifiers: Modifiers::NONE,
+        }
+    }
+    pub fn with_modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+    fn is_down(&self, input: &Input) -> bool {
+        self.button.is_down(input) && self.modifiers.are_held(input)
+    }
+}
+
+impl From<KeyCode> for ButtonBinding {
+    fn from(key: KeyCode) -> Self {
+        Self::new(key)
+    }
+}
+impl From<MouseButton> for ButtonBinding {
+    fn from(button: MouseButton) -> Self {
+        Self::new(button)
+    }
+}
+
+/// Input which has a value every frame instead of being pressed or released
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnalogAxis {
+    /// Horizontal cursor movement since the last frame in pixels
+    MouseX,
+    /// Vertical cursor movement since the last frame in pixels
+    MouseY,
+    ScrollX,
+    ScrollY,
+}
+
+impl AnalogAxis {
+    fn value(&self, input: &Input) -> f32 {
+        let mouse = input.mouse();
+        match self {
+            AnalogAxis::MouseX => mouse.get_cursor_delta().x,
+            AnalogAxis::MouseY => mouse.get_cursor_delta().y,
+            AnalogAxis::ScrollX => mouse.get_scroll_delta().x,
+            AnalogAxis::ScrollY => mouse.get_scroll_delta().y,
+        }
+    }
+}
+
+/// Binds input to an axis action
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AxisBinding {
+    /// -1 while `negative` is held, 1 while `positive` is held and 0 while both or neither are
+    Buttons { negative: Button, positive: Button },
+    /// The value of `axis` multiplied by `scale`
+    Analog { axis: AnalogAxis, scale: f32 },
+}
+
+impl AxisBinding {
+    pub fn buttons(negative: impl Into<Button>, positive: impl Into<Button>) -> Self {
+        AxisBinding::Buttons {
+            negative: negative.into(),
+            positive: positive.into(),
+        }
+    }
+    pub fn analog(axis: AnalogAxis) -> Self {
+        AxisBinding::Analog { axis, scale: 1.0 }
+    }
+    fn value(&self, input: &Input) -> f32 {
+        match self {
+            AxisBinding::Buttons { negative, positive } => {
+                positive.is_down(input) as i32 as f32 - negative.is_down(input) as i32 as f32
+            }
+            AxisBinding::Analog { axis, scale } => axis.value(input) * scale,
+        }
+    }
+}
+
+/// Binds a pair of axes to a 2D axis action
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Axis2dBinding {
+    pub x: AxisBinding,
+    pub y: AxisBinding,
+}
+
+impl Axis2dBinding {
+    pub fn new(x: AxisBinding, y: AxisBinding) -> Self {
+        Self { x, y }
+    }
+    fn value(&self, input: &Input) -> Vec2 {
+        Vec2::new(self.x.value(input), self.y.value(input))
+    }
+}
+
+/// The kind of an action together with everything bound to it.
+/// The values of all bindings of an axis are added up
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    Button(Vec<ButtonBinding>),
+    Axis(Vec<AxisBinding>),
+    Axis2d(Vec<Axis2dBinding>),
+}
+
+impl Action {
+    fn value(&self, input: &Input) -> Vec2 {
+        match self {
+            Action::Button(bindings) => {
+                let pressed = bindings.iter().any(|binding| binding.is_down(input));
+                Vec2::new(pressed as i32 as f32, 0.0)
+            }
+            Action::Axis(bindings) => Vec2::new(bindings.iter().map(|binding| binding.value(input)).sum(), 0.0),
+            Action::Axis2d(bindings) => bindings.iter().map(|binding| binding.value(input)).sum(),
+        }
+    }
+}
+
+/// Named actions and their bindings. This is what gets saved when players rebind their controls
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct InputMap {
+    actions: BTreeMap<String, Action>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a binding to the button action `action`, creating it if it doesn't exist
+    pub fn bind_button(&mut self, action: &str, binding: impl Into<ButtonBinding>) -> &mut Self {
+        match self.actions.entry(action.to_owned()).or_insert(Action::Button(Vec::new())) {
+            Action::Button(bindings) => bindings.push(binding.into()),
+            _ => panic!("Action {:?} is not a button", action),
+        }
+        self
+    }
+    /// Adds a binding to the axis action `action`, creating it if it doesn't exist
+    pub fn bind_axis(&mut self, action: &str, binding: AxisBinding) -> &mut Self {
+        match self.actions.entry(action.to_owned()).or_insert(Action::Axis(Vec::new())) {
+            Action::Axis(bindings) => bindings.push(binding),
+            _ => panic!("Action {:?} is not an axis", action),
+        }
+        self
+    }
+    /// Adds a binding to the 2D axis action `action`, creating it if it doesn't exist
+    pub fn bind_axis2d(&mut self, action: &str, binding: Axis2dBinding) -> &mut Self {
+        match self.actions.entry(action.to_owned()).or_insert(Action::Axis2d(Vec::new())) {
+            Action::Axis2d(bindings) => bindings.push(binding),
+            _ => panic!("Action {:?} is not a 2D axis", action),
+        }
+        self
+    }
+    /// Replaces the bindings of `action`, e.g. when the player rebinds it
+    pub fn set(&mut self, action: &str, bindings: Action) {
+        self.actions.insert(action.to_owned(), bindings);
+    }
+    pub fn get(&self, action: &str) -> Option<&Action> {
+        self.actions.get(action)
+    }
+    pub fn remove(&mut self, action: &str) -> Option<Action> {
+        self.actions.remove(action)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Action)> {
+        self.actions.iter().map(|(name, action)| (name.as_str(), action))
+    }
+    /// Replaces the bindings of every action in `overrides`, used to apply saved player bindings on top of the defaults
+    pub fn merge(&mut self, overrides: InputMap) {
+        self.actions.extend(overrides.actions);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ActionState {
+    pressed: bool,
+    was_pressed: bool,
+    value: Vec2,
+}
+
+/// Per frame state of the actions in an [`InputMap`], updated at the start of every frame by the [`crate::InputPlugin`].
+/// Actions which don't exist are never pressed and have a value of 0
+#[derive(Default)]
+pub struct Actions {
+    map: InputMap,
+    states: HashMap<String, ActionState>,
+}
+
+impl Actions {
+    pub fn new(map: InputMap) -> Self {
+        Self {
+            map,
+            states: HashMap::new(),
+        }
+    }
+    pub fn map(&self) -> &InputMap {
+        &self.map
+    }
+    /// Bindings changed through this apply from the next update
+    pub fn map_mut(&mut self) -> &mut InputMap {
+        &mut self.map
+    }
+    pub fn update(&mut self, input: &Input) {
+        let map = &self.map;
+        self.states.retain(|name, _| map.get(name).is_some());
+
+        for (name, action) in map.iter() {
+            let value = action.value(input);
+            let state = self.states.entry(name.to_owned()).or_default();
+
+            state.was_pressed = state.pressed;
+            state.pressed = value != Vec2::ZERO;
+            state.value = value;
+        }
+    }
+    fn state(&self, action: &str) -> ActionState {
+        self.states.get(action).copied().unwrap_or_default()
+    }
+    /// Returns true while a button is held or an axis is not 0
+    pub fn pressed(&self, action: &str) -> bool {
+        self.state(action).pressed
+    }
+    pub fn just_pressed(&self, action: &str) -> bool {
+        let state = self.state(action);
+        state.pressed && !state.was_pressed
+    }
+    pub fn just_released(&self, action: &str) -> bool {
+        let state = self.state(action);
+        !state.pressed && state.was_pressed
+    }
+    /// Value of an axis, or 1 while a button is held
+    pub fn axis(&self, action: &str) -> f32 {
+        self.state(action).value.x
+    }
+    pub fn axis2d(&self, action: &str) -> Vec2 {
+        self.state(action).value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hikari_core::winit::event::*;
+
+    use super::*;
+    use crate::KeyState;
+
+    #[allow(deprecated)]
+    fn key_event(code: KeyCode, state: KeyState) -> WindowEvent<'static> {
+        WindowEvent::KeyboardInput {
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode: 0,
+                state,
+                virtual_keycode: Some(code),
+                modifiers: ModifiersState::empty(),
+            },
+            is_synthetic: true,
+        }
+    }
+
+    #[test]
+    fn buttons_and_axes() {
+        let mut map = InputMap::new();
+        map.bind_button("Jump", KeyCode::Space)
+            .bind_button("Save", ButtonBinding::new(KeyCode::S).with_modifiers(Modifiers::CTRL))
+            .bind_axis2d(
+                "Move",
+                Axis2dBinding::new(
+                    AxisBinding::buttons(KeyCode::A, KeyCode::D),
+                    AxisBinding::buttons(KeyCode::S, KeyCode::W),
+                ),
+            );
+        let mut actions = Actions::new(map);
+        let mut input = Input::new();
+
+        input.update(&key_event(KeyCode::Space, KeyState::Pressed));
+        input.update(&key_event(KeyCode::S, KeyState::Pressed));
+        input.update(&key_event(KeyCode::D, KeyState::Pressed));
+        actions.update(&input);
+        assert!(actions.just_pressed("Jump"));
+        assert!(!actions.pressed("Save"));
+        assert_eq!(actions.axis2d("Move"), Vec2::new(1.0, -1.0));
+        assert!(!actions.pressed("Unknown"));
+
+        input.update(&key_event(KeyCode::LControl, KeyState::Pressed));
+        actions.update(&input);
+        assert!(actions.pressed("Jump"));
+        assert!(!actions.just_pressed("Jump"));
+        assert!(actions.just_pressed("Save"));
+
+        input.update(&key_event(KeyCode::Space, KeyState::Released));
+        actions.update(&input);
+        assert!(actions.just_released("Jump"));
+    }
+
+    #[test]
+    fn rebinding() {
+        let mut map = InputMap::new();
+        map.bind_button("Jump", KeyCode::Space).bind_button("Crouch", KeyCode::C);
+
+        let mut overrides = InputMap::new();
+        overrides.bind_button("Jump", MouseButton::Right);
+        map.merge(overrides);
+
+        assert_eq!(map.get("Jump"), Some(&Action::Button(vec![MouseButton::Right.into()])));
+        assert_eq!(map.get("Crouch"), Some(&Action::Button(vec![KeyCode::C.into()])));
+    }
+}
@@ -1,7 +1,9 @@
+pub mod action;
 pub mod keyboard;
 mod mouse;
 
 use hikari_systems::Task;
+pub use action::*;
 pub use keyboard::*;
 pub use mouse::*;
 use hikari_core::winit::event::WindowEvent;
@@ -32,7 +34,8 @@ impl Input {
         self.mouse_state.update(event);
     }
     pub fn new_frame(&mut self) {
-        self.keyboard_state.new_frame()
+        self.keyboard_state.new_frame();
+        self.mouse_state.new_frame();
     }
 }
 
@@ -41,6 +44,13 @@ pub struct InputPlugin;
 impl hikari_core::Plugin for InputPlugin {
     fn build(self, game: &mut hikari_core::Game) {
         game.add_state(Input::new());
+        game.add_state(Actions::default());
+        game.add_task(
+            hikari_core::FIRST,
+            Task::new("Update Actions", |actions: &mut Actions, input: &Input| {
+                actions.update(input);
+            }),
+        );
         game.add_task(
             hikari_core::LAST,
             Task::new("Input New Frame", |input: &mut Input| {
@@ -21,9 +21,11 @@ impl MouseState {
         }
     }
     pub(crate) fn update(&mut self, event: &WindowEvent) {
-        let prev_position = self.position;
         match event {
             WindowEvent::CursorMoved { position, .. } => {
+                // Several moves can happen within a frame
+                self.cur_delta.x += (position.x - self.position.x) as f32;
+                self.cur_delta.y += (position.y - self.position.y) as f32;
                 self.position = *position;
             }
             WindowEvent::MouseInput { state, button, .. } => {
@@ -34,26 +36,29 @@ impl MouseState {
             }
             WindowEvent::MouseWheel { delta, .. } => match delta {
                 hikari_core::winit::event::MouseScrollDelta::LineDelta(x, y) => {
-                    self.scroll_delta.x = *x;
-                    self.scroll_delta.y = *y;
+                    self.scroll_delta.x += *x;
+                    self.scroll_delta.y += *y;
                 }
                 hikari_core::winit::event::MouseScrollDelta::PixelDelta(_) => todo!(),
             },
             _ => {}
         }
-
-        self.cur_delta.x = (self.position.x - prev_position.x) as f32;
-        self.cur_delta.y = (self.position.y - prev_position.y) as f32;
+    }
+    pub(crate) fn new_frame(&mut self) {
+        self.cur_delta = Vec2::ZERO;
+        self.scroll_delta = Vec2::ZERO;
     }
 
     #[inline]
     pub fn get_position(&self) -> Vec2 {
         vec2(self.position.x as f32, self.position.y as f32)
     }
+    /// Cursor movement since the last frame in pixels
     #[inline]
     pub fn get_cursor_delta(&self) -> Vec2 {
         self.cur_delta
     }
+    /// Scrolled lines since the last frame
     #[inline]
     pub fn get_scroll_delta(&self) -> Vec2 {
         self.scroll_delta
@@ -0,0 +1,150 @@
+use crate::*;
+
+/// Axis aligned bounding box
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self {
+            min: Vec3::ZERO,
+            max: Vec3::ZERO,
+        }
+    }
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+    /// Smallest box containing all points, a zero sized box at the origin if there are none
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec3>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return Self::default();
+        };
+
+        let (min, max) = points.fold((*first, *first), |(min, max), point| {
+            (min.min(*point), max.max(*point))
+        });
+        Self { min, max }
+    }
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+    /// Half of the size of the box along each axis
+    pub fn extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+    /// Box enclosing this box after it has been transformed by `matrix`
+    pub fn transform(&self, matrix: &Mat4) -> Self {
+        // Arvo 1990, "Transforming Axis-Aligned Bounding Boxes"
+        let center = matrix.transform_point3(self.center());
+        let extents = self.extents();
+        let extents = matrix.x_axis.truncate().abs() * extents.x
+            + matrix.y_axis.truncate().abs() * extents.y
+            + matrix.z_axis.truncate().abs() * extents.z;
+
+        Self {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        let closest = center.clamp(self.min, self.max);
+        closest.distance_squared(center) <= radius * radius
+    }
+}
+
+/// The six planes of a view frustum, with normals pointing inwards
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a view projection matrix with a clip space depth range of 0 to 1
+    pub fn from_view_proj(view_proj: &Mat4) -> Self {
+        // Gribb, Hartmann 2001, "Fast Extraction of Viewing Frustum Planes from the World-View-Projection Matrix"
+        let row0 = view_proj.row(0);
+        let row1 = view_proj.row(1);
+        let row2 = view_proj.row(2);
+        let row3 = view_proj.row(3);
+
+        let planes = [
+            row3 + row0,
+            row3 - row0,
+            row3 + row1,
+            row3 - row1,
+            row2,
+            row3 - row2,
+        ]
+        .map(|plane| plane / plane.truncate().length());
+
+        Self { planes }
+    }
+    fn is_outside_plane(plane: Vec4, aabb: &Aabb) -> bool {
+        let normal = plane.truncate();
+        // Corner of the box furthest along the plane normal
+        let positive = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+
+        normal.dot(positive) + plane.w < 0.0
+    }
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        !self
+            .planes
+            .iter()
+            .any(|&plane| Self::is_outside_plane(plane, aabb))
+    }
+    /// Tests the volume covered by moving `aabb` infinitely far along `direction`.
+    /// Used to find objects which can cast shadows into the frustum from a directional light
+    pub fn intersects_swept_aabb(&self, aabb: &Aabb, direction: Vec3) -> bool {
+        // Planes facing the sweep direction can always be crossed
+        !self
+            .planes
+            .iter()
+            .filter(|plane| plane.truncate().dot(direction) <= 0.0)
+            .any(|&plane| Self::is_outside_plane(plane, aabb))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frustum_culling() {
+        let view = Mat4::look_at_lh(Vec3::ZERO, Vec3::Z, Vec3::Y);
+        let proj = Mat4::perspective_lh(90.0f32.to_radians(), 1.0, 0.1, 100.0);
+        let frustum = Frustum::from_view_proj(&(proj * view));
+
+        let unit = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+        let at = |position: Vec3| unit.transform(&Mat4::from_translation(position));
+
+        assert!(frustum.intersects_aabb(&at(Vec3::new(0.0, 0.0, 10.0))));
+        assert!(frustum.intersects_aabb(&at(Vec3::new(10.0, 0.0, 10.0))));
+        assert!(!frustum.intersects_aabb(&at(Vec3::new(0.0, 0.0, -10.0))));
+        assert!(!frustum.intersects_aabb(&at(Vec3::new(20.0, 0.0, 10.0))));
+        assert!(!frustum.intersects_aabb(&at(Vec3::new(0.0, 0.0, 200.0))));
+
+        // Above the frustum, but casts a shadow into it from a light pointing down
+        let above = at(Vec3::new(0.0, 50.0, 10.0));
+        assert!(!frustum.intersects_aabb(&above));
+        assert!(frustum.intersects_swept_aabb(&above, Vec3::NEG_Y));
+        assert!(!frustum.intersects_swept_aabb(&above, Vec3::Y));
+    }
+
+    #[test]
+    fn transformed_bounds() {
+        let aabb = Aabb::from_points(&[Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 0.5)]);
+        assert_eq!(aabb, Aabb::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 0.5)));
+
+        let rotated = aabb.transform(&Mat4::from_rotation_z(90.0f32.to_radians()));
+        assert!(rotated.min.abs_diff_eq(Vec3::new(-2.0, -1.0, 0.0), 1e-5));
+        assert!(rotated.max.abs_diff_eq(Vec3::new(0.0, 1.0, 0.5), 1e-5));
+    }
+}
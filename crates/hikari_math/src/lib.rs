@@ -1,4 +1,6 @@
+pub mod bounds;
 pub mod transform;
 
 pub use glam::*;
+pub use bounds::*;
 pub use transform::*;
@@ -20,6 +20,7 @@ pub struct WorldUBO {
     pub brdf_lut_ix: u32,
     pub dir_light: DirLight,
     pub show_cascades: u32,
+    pub n_local_lights: u32,
 }
 #[repr(C)]
 #[derive(Copy, Clone, Default)]
@@ -46,4 +47,19 @@ pub struct PushConstants {
 #[repr(C)]
 pub struct PerInstanceData {
     pub transform: Mat4,
+    /// Index of the first joint of the instance in the joint buffer, [`NO_JOINTS`] for meshes without a skin
+    pub joint_offset: u32,
+    _pad: [u32; 3],
+}
+
+pub const NO_JOINTS: u32 = u32::MAX;
+
+impl PerInstanceData {
+    pub fn new(transform: Mat4, joint_offset: u32) -> Self {
+        Self {
+            transform,
+            joint_offset,
+            _pad: [0; 3],
+        }
+    }
 }
\ No newline at end of file
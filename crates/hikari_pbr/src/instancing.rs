@@ -1,8 +1,54 @@
+use std::collections::HashMap;
+
 use hikari_3d::{Mesh, SubMesh};
-use hikari_math::{Transform};
-use vec_map::VecMap;
+use hikari_math::{Aabb, Frustum, Mat4, Vec3};
+
+use crate::common::{PerInstanceData, NO_JOINTS};
 
-use crate::common::PerInstanceData;
+/// Decides which submeshes are submitted by a [`MeshInstancer`]
+#[derive(Default)]
+pub enum Culling {
+    /// Every submesh is submitted
+    #[default]
+    Disabled,
+    Frustum(Frustum),
+    /// Submeshes which can cast a shadow into the frustum from a directional light shining along `direction`
+    ShadowCasters { frustum: Frustum, direction: Vec3 },
+    /// Submeshes touching any of the spheres, given as center and radius
+    Spheres(Vec<(Vec3, f32)>),
+}
+
+impl Culling {
+    fn is_visible(&self, bounds: &Aabb, transform: &Mat4) -> bool {
+        match self {
+            Culling::Disabled => true,
+            Culling::Frustum(frustum) => frustum.intersects_aabb(&bounds.transform(transform)),
+            Culling::ShadowCasters { frustum, direction } => {
+                frustum.intersects_swept_aabb(&bounds.transform(transform), *direction)
+            }
+            Culling::Spheres(spheres) => {
+                let bounds = bounds.transform(transform);
+                spheres
+                    .iter()
+                    .any(|&(center, radius)| bounds.intersects_sphere(center, radius))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CullingStats {
+    pub submitted: usize,
+    pub culled: usize,
+}
+
+/// Culling results of the last rendered frame
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CullingReport {
+    pub camera: CullingStats,
+    pub directional_shadows: CullingStats,
+    pub local_shadows: CullingStats,
+}
 
 pub struct InstanceBatch {
     submesh: *const SubMesh,
@@ -36,10 +82,10 @@ pub struct BatchIter<'a> {
 }
 
 impl<'a> BatchIter<'a> {
-    pub fn new(batches: std::slice::Iter<'a, InstanceBatch>) -> Self {
+    pub fn new(batches: std::slice::Iter<'a, InstanceBatch>, first_instance: usize) -> Self {
         Self {
             batches,
-            instance_id: 0,
+            instance_id: first_instance,
             prev_instance_count: 0
         }
     }
@@ -49,76 +95,113 @@ impl<'a> Iterator for BatchIter<'a> {
     type Item = (usize, &'a InstanceBatch);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let batch = self.batches.next()?;
+        loop {
+            let batch = self.batches.next()?;
+
+            self.instance_id += self.prev_instance_count;
+            self.prev_instance_count = batch.count();
 
-        self.instance_id += self.prev_instance_count;
-        self.prev_instance_count = batch.count();
+            // Every instance of the submesh was culled
+            if batch.count() == 0 {
+                continue;
+            }
 
-        Some((self.instance_id, batch))
+            return Some((self.instance_id, batch));
+        }
     }
 }
 
 #[derive(Default)]
 pub struct MeshInstancer { 
-    mesh_to_batch_ix: VecMap<usize>,
+    /// Keyed by the index of the scene handle and the index of the mesh in the scene
+    mesh_to_batch_ix: HashMap<(usize, usize), usize>,
     submesh_batches: Vec<InstanceBatch>,
+    culling: Culling,
+    stats: CullingStats,
+    first_instance: usize,
 }
 
 impl MeshInstancer {
     pub fn new() -> Self {
         Self::default()
     }
-    pub fn add_mesh(&mut self, index: usize, mesh: &Mesh, transform: &Transform) {
+    /// Sets the culling used by subsequent calls to `add_mesh`
+    pub fn set_culling(&mut self, culling: Culling) {
+        self.culling = culling;
+    }
+    /// `transform` is the model matrix of the mesh, including its transform relative to the entity.
+    /// `joint_offset` is the index of the joint palette of skinned meshes in the joint buffer, see [`crate::common::NO_JOINTS`].
+    /// Skinned meshes are never culled, as their bounds only cover the bind pose
+    pub fn add_mesh(&mut self, key: (usize, usize), mesh: &Mesh, transform: Mat4, joint_offset: u32) {
         let submesh_count = mesh.sub_meshes.len();
 
-        match self.mesh_to_batch_ix.get(index) {
-            Some(&batch_ix) => {
-                for batch in &mut self.submesh_batches[batch_ix..batch_ix + submesh_count] {
-                    batch.count+= 1;
-                    batch.per_instance.push(
-                        PerInstanceData {
-                            transform: transform.get_matrix() * mesh.transform.get_matrix()
-                        }
-                    )
-                }
-            }
+        let batch_ix = match self.mesh_to_batch_ix.get(&key) {
+            Some(&batch_ix) => batch_ix,
             None => {
                 let batch_ix = self.submesh_batches.len();
 
                 for submesh in &mesh.sub_meshes {
                     self.submesh_batches.push(InstanceBatch {
                         submesh: submesh as *const _,
-                        count: 1,
+                        count: 0,
                         //TODO: Remove Allocation 
-                        per_instance: vec![
-                            PerInstanceData {
-                                transform: transform.get_matrix() * mesh.transform.get_matrix()
-                            }
-                        ]
+                        per_instance: Vec::new(),
                     });
                 }
-                self.mesh_to_batch_ix.insert(index, batch_ix);
+                self.mesh_to_batch_ix.insert(key, batch_ix);
+                batch_ix
+            }
+        };
+
+        let batches = &mut self.submesh_batches[batch_ix..batch_ix + submesh_count];
+        let is_skinned = joint_offset != NO_JOINTS;
+        for (batch, submesh) in batches.iter_mut().zip(&mesh.sub_meshes) {
+            if !is_skinned && !self.culling.is_visible(&submesh.bounds, &transform) {
+                self.stats.culled += 1;
+                continue;
             }
+
+            self.stats.submitted += 1;
+            batch.count += 1;
+            batch.per_instance.push(PerInstanceData::new(transform, joint_offset));
         }
     }
-    pub fn write_instance_buffer(&self, buffer: &mut [PerInstanceData]) {
+    /// Writes the instances of all batches to `buffer` and returns the number of instances written.
+    /// `first_instance` is the index in the instance buffer where `buffer` starts.
+    /// Instances which don't fit in `buffer` are dropped from their batches so they are never drawn
+    pub fn write_instance_buffer(&mut self, buffer: &mut [PerInstanceData], first_instance: usize) -> usize {
         hikari_dev::profile_function!();
 
-        let mut buffer_iter = buffer.iter_mut();
+        self.first_instance = first_instance;
+        let mut written = 0;
+        let mut dropped = 0;
 
-        for batch in &self.submesh_batches {
-            for per_instance in &batch.per_instance {
-                if let Some(per_instance_out) = buffer_iter.next() {
-                    *per_instance_out = *per_instance;
-                }
-            }
+        for batch in &mut self.submesh_batches {
+            let count = batch.per_instance.len().min(buffer.len() - written);
+            buffer[written..written + count].copy_from_slice(&batch.per_instance[..count]);
+
+            dropped += batch.count - count;
+            batch.count = count;
+            written += count;
         }
+
+        if dropped > 0 {
+            log::warn!("Instance buffer is full, {} instances are not drawn", dropped);
+        }
+
+        written
     }
     pub fn batches(&self) -> BatchIter {
-        BatchIter::new(self.submesh_batches.iter())
+        BatchIter::new(self.submesh_batches.iter(), self.first_instance)
+    }
+    pub fn stats(&self) -> CullingStats {
+        self.stats
     }
     pub fn new_frame(&mut self) {
         self.mesh_to_batch_ix.clear();
         self.submesh_batches.clear();
+        self.culling = Culling::Disabled;
+        self.stats = CullingStats::default();
+        self.first_instance = 0;
     }
 }
\ No newline at end of file
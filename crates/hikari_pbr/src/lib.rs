@@ -23,6 +23,7 @@ use winit::event::{Event, WindowEvent};
 use common::*;
 pub use world_renderer::WorldRenderer;
 pub use settings::*;
+pub use instancing::{CullingReport, CullingStats};
 
 type Args = (World, RenderResources, ShaderLibrary, AssetManager);
 
@@ -37,3 +37,44 @@ pub struct CascadeRenderInfo {
     view: [f32; 16],
     view_proj: [f32; 16],
 }
+
+pub const MAX_LOCAL_LIGHTS: usize = 256;
+
+pub const LOCAL_LIGHT_POINT: u32 = 0;
+pub const LOCAL_LIGHT_SPOT: u32 = 1;
+
+/// A point or spot light as seen by the shaders
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct LocalLight {
+    pub position: hikari_math::Vec3,
+    pub range: f32,
+    pub color: hikari_math::Vec3,
+    pub intensity: f32,
+    pub direction: hikari_math::Vec3,
+    pub kind: u32,
+    pub cos_inner_cone: f32,
+    pub cos_outer_cone: f32,
+    /// Index of the first shadow tile of the light, -1 if it doesn't cast shadows
+    pub shadow_tile: i32,
+    pub normal_bias: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct LocalShadowTile {
+    pub view_proj: hikari_math::Mat4,
+    pub atlas_uv_offset: hikari_math::Vec2,
+    pub atlas_size_ratio: hikari_math::Vec2,
+    pub map_texel_size: f32,
+    _pad: [f32; 3],
+}
+
+/// CPU side information needed to render the shadow tiles of a single light
+#[derive(Copy, Clone, Debug)]
+pub struct LocalShadowCaster {
+    pub first_tile: usize,
+    pub n_tiles: usize,
+    pub slope_scaled_bias: f32,
+    pub cull_front_face: bool,
+}
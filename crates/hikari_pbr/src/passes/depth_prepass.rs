@@ -20,6 +20,8 @@ pub fn build_pass(
 
     let layout = VertexInputLayout::builder()
         .buffer(&[ShaderDataType::Vec3f], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4u], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4f], StepMode::Vertex)
         .build();
 
     let depth_output = graph
@@ -73,7 +75,7 @@ pub fn build_pass(
                             let submesh = batch.submesh();
                             {
                                 hikari_dev::profile_scope!("Set vertex and index buffers");
-                                cmd.set_vertex_buffer(&submesh.position, 0);
+                                cmd.set_vertex_buffers(&[&submesh.position, &submesh.joints, &submesh.weights], 0);
                                 cmd.set_index_buffer(&submesh.indices);
                             }
 
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use crate::Args;
+use hikari_3d::*;
+use hikari_render::*;
+
+// Must match the constants in cluster.glsl
+pub const CLUSTER_X: u32 = 16;
+pub const CLUSTER_Y: u32 = 9;
+pub const CLUSTER_Z: u32 = 24;
+pub const N_CLUSTERS: usize = (CLUSTER_X * CLUSTER_Y * CLUSTER_Z) as usize;
+pub const MAX_LIGHTS_PER_CLUSTER: usize = 64;
+
+/// Depth range of the clusters followed by per cluster light counts and light indices
+const LIGHT_CLUSTER_BUFFER_LEN: usize = 4 + N_CLUSTERS + N_CLUSTERS * MAX_LIGHTS_PER_CLUSTER;
+
+/// Bins point and spot lights into view space clusters.
+/// Clusters are sliced between the nearest and farthest depth found by the depth prepass reduction,
+/// so this needs to run after the hierarchical depth has been generated
+pub fn build_pass(
+    device: &Arc<Device>,
+    graph: &mut GraphBuilder<Args>,
+    shader_lib: &mut ShaderLibrary,
+) -> anyhow::Result<GpuHandle<GpuBuffer<u32>>> {
+    let light_clusters = GpuBuffer::<u32>::new(
+        device,
+        LIGHT_CLUSTER_BUFFER_LEN,
+        vk::BufferUsageFlags::STORAGE_BUFFER,
+    )?;
+    let light_clusters = graph.add_buffer("LightClusters".into(), light_clusters);
+
+    shader_lib.insert("light_culling")?;
+
+    graph.add_computepass(
+        ComputePass::<Args>::new("LightCulling")
+            .write_buffer(&light_clusters, AccessType::ComputeShaderWrite)
+            .cmd(move |cmd, graph_res, _, (_world, res, shader_lib, _assets)| {
+                if res.camera.is_none() {
+                    return;
+                }
+
+                let reduced_depth_image = res.hi_z_images.last().unwrap();
+                let light_clusters = graph_res.get_buffer(&light_clusters).unwrap();
+
+                cmd.set_shader(shader_lib.get("light_culling").unwrap());
+
+                cmd.set_image(reduced_depth_image, 2, 0);
+                cmd.set_buffer(light_clusters, 0..light_clusters.len(), 2, 1);
+
+                //FIXME: Do this automatically; Implement graph external resources
+                cmd.apply_image_barrier(
+                    reduced_depth_image,
+                    &[AccessType::ComputeShaderWrite],
+                    &[AccessType::ComputeShaderReadOther],
+                    vk_sync::ImageLayout::General,
+                    vk_sync::ImageLayout::General,
+                    vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                );
+
+                cmd.dispatch((1, 1, CLUSTER_Z));
+            }),
+    );
+
+    Ok(light_clusters)
+}
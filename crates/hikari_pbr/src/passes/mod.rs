@@ -1,6 +1,7 @@
 pub mod debug;
 pub mod depth_prepass;
 pub mod fxaa;
+pub mod light_culling;
 pub mod pbr;
 pub mod shadow;
 pub mod prepare;
\ No newline at end of file
@@ -13,6 +13,8 @@ struct PBRPass {
     primitives: Arc<Primitives>,
     shadow_atlas: GpuHandle<SampledImage>,
     cascade_render_buffer: GpuHandle<GpuBuffer<CascadeRenderInfo>>,
+    local_shadow_atlas: GpuHandle<SampledImage>,
+    light_clusters: GpuHandle<GpuBuffer<u32>>,
     shader_ids: ShaderIds
 }
 impl PBRPass {
@@ -22,6 +24,8 @@ impl PBRPass {
         primitives: &Arc<hikari_3d::primitives::Primitives>,
         shadow_atlas: &GpuHandle<SampledImage>,
         cascade_render_buffer: &GpuHandle<GpuBuffer<CascadeRenderInfo>>,
+        local_shadow_atlas: &GpuHandle<SampledImage>,
+        light_clusters: &GpuHandle<GpuBuffer<u32>>,
         depth_prepass: &GpuHandle<SampledImage>,
     ) -> anyhow::Result<GpuHandle<SampledImage>> {
         let layout = VertexInputLayout::builder()
@@ -29,6 +33,9 @@ impl PBRPass {
             .buffer(&[ShaderDataType::Vec3f], StepMode::Vertex)
             .buffer(&[ShaderDataType::Vec2f], StepMode::Vertex)
             .buffer(&[ShaderDataType::Vec2f], StepMode::Vertex)
+            .buffer(&[ShaderDataType::Vec4f], StepMode::Vertex)
+            .buffer(&[ShaderDataType::Vec4u], StepMode::Vertex)
+            .buffer(&[ShaderDataType::Vec4f], StepMode::Vertex)
             .build();
 
         let skybox_layout = VertexInputLayout::builder()
@@ -37,12 +44,14 @@ impl PBRPass {
 
         let lit = shader_lib.insert_with_defines("pbr", &["LIGHT_MODE_LIT"])?;
         let unlit = shader_lib.insert_with_defines("pbr", &["LIGHT_MODE_UNLIT"])?;
+        let light_clusters_debug = shader_lib.insert_with_defines("pbr", &["LIGHT_MODE_LIT", "DEBUG_LIGHT_CLUSTERS"])?;
         let skybox = shader_lib.insert("skybox")?;
         let outline = shader_lib.insert("outline")?;
 
         let shader_ids = ShaderIds {
             lit,
             unlit,
+            light_clusters_debug,
             skybox,
             outline,
         };
@@ -56,6 +65,8 @@ impl PBRPass {
 
         let shadow_atlas = shadow_atlas.clone();
         let cascade_render_buffer = cascade_render_buffer.clone();
+        let local_shadow_atlas = local_shadow_atlas.clone();
+        let light_clusters = light_clusters.clone();
 
         let mut renderer = Self {
             layout,
@@ -63,6 +74,8 @@ impl PBRPass {
             primitives,
             shadow_atlas,
             cascade_render_buffer,
+            local_shadow_atlas,
+            light_clusters,
             shader_ids
         };
 
@@ -72,6 +85,11 @@ impl PBRPass {
                 AccessType::FragmentShaderReadSampledImageOrUniformTexelBuffer,
             )
             .read_buffer(&cascade_render_buffer, AccessType::FragmentShaderReadOther)
+            .read_image(
+                &local_shadow_atlas,
+                AccessType::FragmentShaderReadSampledImageOrUniformTexelBuffer,
+            )
+            .read_buffer(&light_clusters, AccessType::FragmentShaderReadOther)
             .draw_image(&color_output, AttachmentConfig::color_default(0))
             .draw_image(
                 &depth_prepass,
@@ -230,6 +248,9 @@ impl PBRPass {
                     &submesh.normals,
                     &submesh.tc0,
                     &submesh.tc1,
+                    &submesh.tangents,
+                    &submesh.joints,
+                    &submesh.weights,
                 ],
                 0,
             );
@@ -310,6 +331,9 @@ impl PBRPass {
                     &submesh.normals,
                     &submesh.tc0,
                     &submesh.tc1,
+                    &submesh.tangents,
+                    &submesh.joints,
+                    &submesh.weights,
                 ],
                 0,
             );
@@ -377,6 +401,11 @@ impl PBRPass {
                     cmd.set_shader(assets.pbr_shader);
                     cmd.set_rasterizer_state(RasterizerState::default());
                 }
+                DebugView::LightClusters => {
+                    self.render_skybox(cmd, &assets, res);
+                    cmd.set_shader(assets.light_clusters_debug_shader);
+                    cmd.set_rasterizer_state(RasterizerState::default());
+                }
             }
 
             cmd.set_vertex_input_layout(self.layout);
@@ -386,6 +415,12 @@ impl PBRPass {
             
             cmd.set_buffer(cascade_render_buffer, 0..cascade_render_buffer.len(), 2, 0);
             cmd.set_image(shadow_atlas, 2, 1);
+
+            let local_shadow_atlas = graph_res.get_image(&self.local_shadow_atlas).unwrap();
+            cmd.set_image(local_shadow_atlas, 2, 2);
+
+            let light_clusters = graph_res.get_buffer(&self.light_clusters).unwrap();
+            cmd.set_buffer(light_clusters, 0..light_clusters.len(), 2, 3);
             
             self.render_world(cmd, &res.mesh_instancer, &assets, &res.settings);
         } else {
@@ -397,6 +432,7 @@ impl PBRPass {
 struct ShaderIds {
     lit: ShaderId,
     unlit: ShaderId,
+    light_clusters_debug: ShaderId,
     outline: ShaderId,
     skybox: ShaderId,
 }
@@ -406,6 +442,7 @@ struct Assets<'a> {
 
     pbr_shader: &'a Arc<Shader>,
     unlit_shader: &'a Arc<Shader>,
+    light_clusters_debug_shader: &'a Arc<Shader>,
     outline_shader: &'a Arc<Shader>,
     skybox_shader: &'a Arc<Shader>,
 }
@@ -423,6 +460,9 @@ impl<'a> Assets<'a> {
 
         let pbr_shader = shader_lib.get_by_id(shader_ids.lit).expect("Failed to fetch PBR Shader");
         let unlit_shader = shader_lib.get_by_id(shader_ids.unlit).expect("Failed to fetch unlit Shader");
+        let light_clusters_debug_shader = shader_lib
+            .get_by_id(shader_ids.light_clusters_debug)
+            .expect("Failed to fetch light clusters debug Shader");
         let outline_shader = shader_lib
             .get_by_id(shader_ids.outline)
             .expect("Failed to get outline shader");
@@ -433,6 +473,7 @@ impl<'a> Assets<'a> {
             textures,
             pbr_shader, 
             unlit_shader,
+            light_clusters_debug_shader,
             outline_shader,
             skybox_shader
         }
@@ -474,6 +515,8 @@ pub fn build_pass(
     primitives: &Arc<hikari_3d::primitives::Primitives>,
     shadow_atlas: &GpuHandle<SampledImage>,
     cascade_render_buffer: &GpuHandle<GpuBuffer<CascadeRenderInfo>>,
+    local_shadow_atlas: &GpuHandle<SampledImage>,
+    light_clusters: &GpuHandle<GpuBuffer<u32>>,
     depth_prepass: &GpuHandle<SampledImage>,
 ) -> anyhow::Result<GpuHandle<SampledImage>> {
     PBRPass::build(
@@ -482,6 +525,8 @@ pub fn build_pass(
         primitives,
         shadow_atlas,
         cascade_render_buffer,
+        local_shadow_atlas,
+        light_clusters,
         depth_prepass,
     )
 }
@@ -13,5 +13,8 @@ pub fn build_pass(device: &Arc<hikari_render::Device>, graph_builder: &mut Graph
         let res: &RenderResources = res;
         cmd.set_buffer(&res.world_ubo, 0..res.world_ubo.len(), SCENE_SET_ID, 0);
         cmd.set_buffer(&res.instance_ssbo, 0..res.instance_ssbo.len(), SCENE_SET_ID, 1);
+        cmd.set_buffer(&res.local_light_ssbo, 0..res.local_light_ssbo.len(), SCENE_SET_ID, 2);
+        cmd.set_buffer(&res.local_shadow_ssbo, 0..res.local_shadow_ssbo.len(), SCENE_SET_ID, 3);
+        cmd.set_buffer(&res.joint_ssbo, 0..res.joint_ssbo.len(), SCENE_SET_ID, 4);
     }));
 }
\ No newline at end of file
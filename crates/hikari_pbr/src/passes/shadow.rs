@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{light::CascadeRenderInfo, common::{WorldUBO, MaterialInputs, PushConstants}, Args, Settings};
+use crate::{light::{CascadeRenderInfo, LocalLight, LocalShadowTile, LocalShadowCaster, LOCAL_LIGHT_POINT}, common::{WorldUBO, MaterialInputs, PushConstants}, Args, Settings};
 use hikari_3d::*;
 use hikari_math::*;
 use hikari_render::*;
@@ -8,6 +8,21 @@ use hikari_render::*;
 pub const N_CASCADES: usize = 4;
 //pub const SHADOW_MAP_SIZE: u32 = 1024;
 
+pub const LOCAL_SHADOW_ATLAS_COLUMNS: u32 = 8;
+pub const LOCAL_SHADOW_ATLAS_ROWS: u32 = 4;
+pub const MAX_LOCAL_SHADOW_TILES: usize = (LOCAL_SHADOW_ATLAS_COLUMNS * LOCAL_SHADOW_ATLAS_ROWS) as usize;
+const LOCAL_SHADOW_NEAR: f32 = 0.05;
+
+/// Order matches the face selection in pbr.frag: +X, -X, +Y, -Y, +Z, -Z
+const CUBE_FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::Y),
+    (Vec3::NEG_X, Vec3::Y),
+    (Vec3::Y, Vec3::NEG_Z),
+    (Vec3::NEG_Y, Vec3::Z),
+    (Vec3::Z, Vec3::Y),
+    (Vec3::NEG_Z, Vec3::Y),
+];
+
 pub fn compute_cascades(world_ubo: &mut WorldUBO, settings: &Settings) {
     let shadow_map_size = settings.directional_shadow_map_resolution.size();
 
@@ -29,6 +44,81 @@ pub fn compute_cascades(world_ubo: &mut WorldUBO, settings: &Settings) {
         world_ubo.dir_light.cascades[i].atlas_uv_offset = atlas_uv_offset;
     }
 }
+/// Assigns shadow atlas tiles to shadow casting point and spot lights and computes their view projection matrices.
+/// Lights closest to the camera are served first, lights which don't fit in the atlas are rendered without shadows
+pub fn compute_local_shadows(
+    lights: &mut [(LocalLight, Option<ShadowInfo>)],
+    camera_position: Vec3,
+    settings: &Settings,
+    tiles: &mut [LocalShadowTile],
+    casters: &mut Vec<LocalShadowCaster>,
+) {
+    let tile_size = settings.local_shadow_map_resolution.size();
+    let atlas_size_ratio = Vec2::new(
+        1.0 / LOCAL_SHADOW_ATLAS_COLUMNS as f32,
+        1.0 / LOCAL_SHADOW_ATLAS_ROWS as f32,
+    );
+
+    let mut order: Vec<usize> = (0..lights.len())
+        .filter(|&ix| lights[ix].1.is_some())
+        .collect();
+    order.sort_by(|&a, &b| {
+        let a = lights[a].0.position.distance_squared(camera_position);
+        let b = lights[b].0.position.distance_squared(camera_position);
+        a.total_cmp(&b)
+    });
+
+    let max_tiles = tiles.len().min(MAX_LOCAL_SHADOW_TILES);
+    let mut next_tile = 0;
+    for ix in order {
+        let (light, shadow) = &mut lights[ix];
+        let shadow = shadow.as_ref().unwrap();
+
+        let n_tiles = if light.kind == LOCAL_LIGHT_POINT { 6 } else { 1 };
+        if next_tile + n_tiles > max_tiles {
+            continue;
+        }
+
+        let far = light.range.max(LOCAL_SHADOW_NEAR * 2.0);
+        let position = light.position;
+        let mut write_tile = |tile_ix: usize, view: Mat4, proj: Mat4| {
+            let column = tile_ix as u32 % LOCAL_SHADOW_ATLAS_COLUMNS;
+            let row = tile_ix as u32 / LOCAL_SHADOW_ATLAS_COLUMNS;
+
+            let tile = &mut tiles[tile_ix];
+            tile.view_proj = proj * view;
+            tile.atlas_uv_offset = Vec2::new(column as f32, row as f32) * atlas_size_ratio;
+            tile.atlas_size_ratio = atlas_size_ratio;
+            tile.map_texel_size = 1.0 / tile_size as f32;
+        };
+
+        if light.kind == LOCAL_LIGHT_POINT {
+            let proj = Mat4::perspective_lh(std::f32::consts::FRAC_PI_2, 1.0, LOCAL_SHADOW_NEAR, far);
+            for (face, (direction, up)) in CUBE_FACES.iter().enumerate() {
+                let view = Mat4::look_at_lh(position, position + *direction, *up);
+                write_tile(next_tile + face, view, proj);
+            }
+        } else {
+            let direction = light.direction;
+            let up = if direction.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+            let fov = (light.cos_outer_cone.clamp(-1.0, 1.0).acos() * 2.0).clamp(0.01, 3.1);
+            let proj = Mat4::perspective_lh(fov, 1.0, LOCAL_SHADOW_NEAR, far);
+            let view = Mat4::look_at_lh(position, position + direction, up);
+            write_tile(next_tile, view, proj);
+        }
+
+        light.shadow_tile = next_tile as i32;
+        light.normal_bias = shadow.normal_bias;
+        casters.push(LocalShadowCaster {
+            first_tile: next_tile,
+            n_tiles,
+            slope_scaled_bias: shadow.slope_scaled_bias,
+            cull_front_face: shadow.cull_front_face,
+        });
+
+        next_tile += n_tiles;
+    }
+}
 const NUM_THREADS: u32 = 16;
 pub fn create_hi_z_images(
     device: &Arc<Device>,
@@ -196,6 +286,8 @@ pub fn build_pass(
     
     let layout = VertexInputLayout::builder()
         .buffer(&[ShaderDataType::Vec3f], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4u], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4f], StepMode::Vertex)
         .build();
 
     graph.add_renderpass(
@@ -254,7 +346,7 @@ pub fn build_pass(
                             shadow_map_size,
                         );
 
-                        for (instance_id, batch) in res.mesh_instancer.batches() { 
+                        for (instance_id, batch) in res.shadow_instancer.batches() { 
 
                             cmd.push_constants(
                                 &PushConstants {
@@ -272,7 +364,7 @@ pub fn build_pass(
                                 hikari_dev::profile_scope!(
                                     "Set vertex and index buffers"
                                 );
-                                cmd.set_vertex_buffer(&submesh.position, 0);
+                                cmd.set_vertex_buffers(&[&submesh.position, &submesh.joints, &submesh.weights], 0);
                                 cmd.set_index_buffer(&submesh.indices);
                             }
 
@@ -288,10 +380,111 @@ pub fn build_pass(
     Ok((shadow_atlas, cascade_render_buffer))
 }
 
+/// Renders the shadow maps of point and spot lights into a single atlas.
+/// Tiles are assigned every frame by [`compute_local_shadows`]
+pub fn build_local_pass(
+    device: &Arc<Device>,
+    graph: &mut GraphBuilder<Args>,
+    shader_lib: &mut ShaderLibrary,
+    settings: &Settings,
+) -> anyhow::Result<GpuHandle<SampledImage>> {
+    let tile_size = settings.local_shadow_map_resolution.size();
+
+    let atlas_size = ImageSize::absolute_xy(
+        tile_size * LOCAL_SHADOW_ATLAS_COLUMNS,
+        tile_size * LOCAL_SHADOW_ATLAS_ROWS,
+    );
+    let mut config = ImageConfig::depth_only_attachment(device);
+    config.format = vk::Format::D32_SFLOAT;
+    let local_shadow_atlas = graph.create_image("LocalShadowMapAtlas", config, atlas_size)?;
+
+    let shader = shader_lib.insert_with_defines("shadow", &["LOCAL_LIGHT"])?;
+
+    let layout = VertexInputLayout::builder()
+        .buffer(&[ShaderDataType::Vec3f], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4u], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4f], StepMode::Vertex)
+        .build();
+
+    graph.add_renderpass(
+        Renderpass::<Args>::new("LocalShadowMapping", atlas_size)
+            .draw_image(&local_shadow_atlas, AttachmentConfig::depth_only_default())
+            .cmd(move |cmd, _, _, (_world, res, shader_lib, _assets)| {
+                if res.local_shadow_casters.is_empty() {
+                    return;
+                }
+
+                cmd.set_shader(shader_lib.get_by_id(shader).unwrap());
+                cmd.set_vertex_input_layout(layout);
+
+                cmd.set_depth_stencil_state(DepthStencilState {
+                    depth_test_enabled: true,
+                    depth_write_enabled: true,
+                    depth_compare_op: CompareOp::LessOrEqual,
+                    ..Default::default()
+                });
+
+                for caster in &res.local_shadow_casters {
+                    cmd.set_rasterizer_state(RasterizerState {
+                        cull_mode: if caster.cull_front_face {
+                            CullMode::Front
+                        } else {
+                            CullMode::Back
+                        },
+                        depth_bias_enable: true,
+                        depth_bias_slope_factor: caster.slope_scaled_bias,
+                        ..Default::default()
+                    });
+
+                    for tile_ix in caster.first_tile..caster.first_tile + caster.n_tiles {
+                        let column = tile_ix as u32 % LOCAL_SHADOW_ATLAS_COLUMNS;
+                        let row = tile_ix as u32 / LOCAL_SHADOW_ATLAS_COLUMNS;
+
+                        cmd.set_viewport(
+                            (column * tile_size) as f32,
+                            (row * tile_size) as f32,
+                            tile_size as f32,
+                            tile_size as f32,
+                        );
+                        cmd.set_scissor(
+                            (column * tile_size) as i32,
+                            (row * tile_size) as i32,
+                            tile_size,
+                            tile_size,
+                        );
+
+                        for (instance_id, batch) in res.local_shadow_instancer.batches() {
+                            cmd.push_constants(
+                                &PushConstants {
+                                    mat: MaterialInputs {
+                                        uv_set: tile_ix as u32,
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                },
+                                0,
+                            );
+                            let submesh = batch.submesh();
+
+                            cmd.set_vertex_buffers(&[&submesh.position, &submesh.joints, &submesh.weights], 0);
+                            cmd.set_index_buffer(&submesh.indices);
+
+                            cmd.draw_indexed(0..submesh.indices.capacity(), 0, instance_id..instance_id + batch.count());
+                        }
+                    }
+                }
+            }),
+    );
+
+    Ok(local_shadow_atlas)
+}
+
 
 fn multipass_shadows(graph: &mut GraphBuilder<Args>, cascade_render_buffer: GpuHandle<GpuBuffer<CascadeRenderInfo>>, shadow_atlas: GpuHandle<SampledImage>, shadow_map_size: u32) {
     let layout = VertexInputLayout::builder()
         .buffer(&[ShaderDataType::Vec3f], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4u], StepMode::Vertex)
+        .buffer(&[ShaderDataType::Vec4f], StepMode::Vertex)
         .build();
 
     for cascade_ix in 0..N_CASCADES {
@@ -351,7 +544,7 @@ fn multipass_shadows(graph: &mut GraphBuilder<Args>, cascade_render_buffer: GpuH
                                 shadow_map_size,
                             );
 
-                            for (instance_id, batch) in res.mesh_instancer.batches() { 
+                            for (instance_id, batch) in res.shadow_instancer.batches() { 
 
                                 cmd.push_constants(
                                     &PushConstants {
@@ -369,7 +562,7 @@ fn multipass_shadows(graph: &mut GraphBuilder<Args>, cascade_render_buffer: GpuH
                                     hikari_dev::profile_scope!(
                                         "Set vertex and index buffers"
                                     );
-                                    cmd.set_vertex_buffer(&submesh.position, 0);
+                                    cmd.set_vertex_buffers(&[&submesh.position, &submesh.joints, &submesh.weights], 0);
                                     cmd.set_index_buffer(&submesh.indices);
                                 }
 
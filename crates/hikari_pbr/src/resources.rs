@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
-use crate::{Settings, WorldUBO, common::PerInstanceData, instancing::MeshInstancer};
+use crate::{Settings, WorldUBO, common::PerInstanceData, instancing::{MeshInstancer, CullingReport}, light::{LocalLight, LocalShadowTile, LocalShadowCaster, MAX_LOCAL_LIGHTS}, passes::shadow::MAX_LOCAL_SHADOW_TILES};
+use hikari_math::Mat4;
 use hikari_render::{Device, SampledImage, RingBuffer};
 
 pub const MAX_ENTITIES: usize = 10_000;
+pub const MAX_JOINTS: usize = 16_384;
 pub const SCENE_SET_ID: u32 = 1;
 pub struct RenderResources {
     device: Arc<Device>,
@@ -11,7 +13,18 @@ pub struct RenderResources {
     pub viewport: (f32, f32),
     pub world_ubo: RingBuffer<WorldUBO>,
     pub instance_ssbo: RingBuffer<PerInstanceData>,
+    /// Joint palettes of all skinned instances, one after the other
+    pub joint_ssbo: RingBuffer<Mat4>,
+    pub local_light_ssbo: RingBuffer<LocalLight>,
+    pub local_shadow_ssbo: RingBuffer<LocalShadowTile>,
+    pub local_shadow_casters: Vec<LocalShadowCaster>,
+    /// Submeshes visible to the camera
     pub mesh_instancer: MeshInstancer,
+    /// Submeshes casting shadows from the directional light
+    pub shadow_instancer: MeshInstancer,
+    /// Submeshes within range of shadow casting point and spot lights
+    pub local_shadow_instancer: MeshInstancer,
+    pub culling_report: CullingReport,
     pub hi_z_images: Vec<SampledImage>,
 
     pub camera: Option<hikari_core::Entity>,
@@ -28,7 +41,14 @@ impl RenderResources {
             directional_light: None,
             world_ubo: hikari_render::create_uniform_buffer(device, 1)?,
             instance_ssbo: hikari_render::create_storage_buffer(device, MAX_ENTITIES)?,
+            joint_ssbo: hikari_render::create_storage_buffer(device, MAX_JOINTS)?,
+            local_light_ssbo: hikari_render::create_storage_buffer(device, MAX_LOCAL_LIGHTS)?,
+            local_shadow_ssbo: hikari_render::create_storage_buffer(device, MAX_LOCAL_SHADOW_TILES)?,
+            local_shadow_casters: Vec::new(),
             mesh_instancer: MeshInstancer::new(),
+            shadow_instancer: MeshInstancer::new(),
+            local_shadow_instancer: MeshInstancer::new(),
+            culling_report: CullingReport::default(),
             hi_z_images: crate::passes::shadow::create_hi_z_images(device, width, height)?,
         })
     }
@@ -45,6 +45,8 @@ pub enum DebugView {
     None = 0,
     Unlit,
     Wireframe,
+    /// Overlays the number of point and spot lights affecting each light cluster
+    LightClusters,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -57,6 +59,9 @@ pub struct Settings {
     pub fxaa: bool,
     pub vsync: bool,
     pub directional_shadow_map_resolution: ShadowResolution,
+    /// Resolution of a single tile in the point and spot light shadow atlas.
+    /// Point lights use six tiles, one per cube face
+    pub local_shadow_map_resolution: ShadowResolution,
     pub debug: DebugSettings,
 }
 
@@ -66,6 +71,7 @@ impl Default for Settings {
             fxaa: true,
             vsync: true,
             directional_shadow_map_resolution: Default::default(),
+            local_shadow_map_resolution: ShadowResolution::D512,
             debug: DebugSettings::default()
         }
     }
@@ -19,3 +19,12 @@ pub fn get_camera(world: &World) -> Option<Entity> {
         .next()
         .map(|(entity, _)| entity)
 }
+
+/// World space matrix of an entity.
+/// Falls back to the local transform if transforms haven't been propagated yet
+pub fn world_matrix(transform: &Transform, global: Option<&GlobalTransform>) -> Mat4 {
+    match global {
+        Some(global) => global.matrix(),
+        None => transform.get_matrix(),
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use hikari_3d::*;
 use hikari_asset::AssetManager;
-use hikari_core::{Entity, World};
+use hikari_core::{Entity, GlobalTransform, World};
 use hikari_math::*;
 use hikari_render::*;
 
@@ -12,7 +12,10 @@ use hikari_render::{AccessType, Renderpass};
 use crate::{
     passes::{self},
     util,
-    common::WorldUBO,
+    common::{WorldUBO, NO_JOINTS},
+    resources::MAX_JOINTS,
+    light::{LocalLight, LOCAL_LIGHT_POINT, LOCAL_LIGHT_SPOT, MAX_LOCAL_LIGHTS},
+    instancing::{Culling, CullingReport},
     Args, RenderResources, Settings,
 };
 
@@ -78,6 +81,13 @@ impl WorldRenderer {
             &res.settings,
             &depth_prepass,
         )?;
+        let light_clusters = passes::light_culling::build_pass(&device, &mut graph, shader_library)?;
+        let local_shadow_atlas = passes::shadow::build_local_pass(
+            &device,
+            &mut graph,
+            shader_library,
+            &res.settings,
+        )?;
         let pbr_output = passes::pbr::build_pass(
             &device,
             &mut graph,
@@ -85,6 +95,8 @@ impl WorldRenderer {
             primitives,
             &shadow_cascades,
             &cascade_render_buffer,
+            &local_shadow_atlas,
+            &light_clusters,
             &depth_prepass,
         )?;
         #[cfg(feature = "editor")]
@@ -129,6 +141,8 @@ impl WorldRenderer {
         let (width, height) = self.graph.size();
         if self.res.settings.directional_shadow_map_resolution
             != old_settings.directional_shadow_map_resolution
+            || self.res.settings.local_shadow_map_resolution
+                != old_settings.local_shadow_map_resolution
         {
             self.graph.finish()?;
             self.graph = Self::build_graph(
@@ -154,17 +168,71 @@ impl WorldRenderer {
             .get_image_by_name("FXAAOutput")
             .unwrap()
     }
-    fn write_instances(&mut self, world: &World, assets: &AssetManager) {
+    pub fn culling_report(&self) -> &CullingReport {
+        &self.res.culling_report
+    }
+    fn write_instances(
+        &mut self,
+        world: &World,
+        assets: &AssetManager,
+        camera_culling: Culling,
+        shadow_culling: Option<Culling>,
+        local_shadow_spheres: Vec<(Vec3, f32)>,
+    ) {
         let scenes = assets.read_assets::<hikari_3d::Scene>().expect("Scenes pool not found");
-        let instance_ssbo = self.res.instance_ssbo.mapped_slice_mut();
-        let instancer = &mut self.res.mesh_instancer;
+        let res = &mut self.res;
 
-        for (_entity, (transform, mesh_comp)) in world.query::<(&Transform, &MeshRender)>().iter() {
+        let has_shadows = shadow_culling.is_some();
+        let has_local_shadows = !local_shadow_spheres.is_empty();
+
+        res.mesh_instancer.set_culling(camera_culling);
+        if let Some(shadow_culling) = shadow_culling {
+            res.shadow_instancer.set_culling(shadow_culling);
+        }
+        res.local_shadow_instancer.set_culling(Culling::Spheres(local_shadow_spheres));
+
+        let joint_ssbo = res.joint_ssbo.mapped_slice_mut();
+        let mut joints_written = 0;
+
+        for (_entity, (transform, global, mesh_comp, animator)) in world.query::<(&Transform, Option<&GlobalTransform>, &MeshRender, Option<&Animator>)>().iter() {
             let Some((mesh, handle)) = mesh_comp.get_mesh_and_handle(&scenes) else {continue};
-            instancer.add_mesh(handle.index(), mesh, transform);
+            let MeshSource::Scene(_, mesh_ix) = mesh_comp.source else {continue};
+            let Some(mesh_transform) = mesh_comp.get_mesh_transform(&scenes) else {continue};
+            let key = (handle.index(), mesh_ix);
+            let transform = util::world_matrix(transform, global) * mesh_transform.get_matrix();
+
+            let mut joint_offset = NO_JOINTS;
+            if let Some(palette) = animator.map(|animator| animator.palette()).filter(|palette| !palette.is_empty()) {
+                if joints_written + palette.len() <= joint_ssbo.len() {
+                    joint_ssbo[joints_written..joints_written + palette.len()].copy_from_slice(palette);
+                    joint_offset = joints_written as u32;
+                    joints_written += palette.len();
+                } else {
+                    log::warn!("More than {} joints in the world, the rest are rendered in their bind pose", MAX_JOINTS);
+                }
+            }
+
+            res.mesh_instancer.add_mesh(key, mesh, transform, joint_offset);
+            if has_shadows {
+                res.shadow_instancer.add_mesh(key, mesh, transform, joint_offset);
+            }
+            if has_local_shadows {
+                res.local_shadow_instancer.add_mesh(key, mesh, transform, joint_offset);
+            }
         }
 
-        instancer.write_instance_buffer(instance_ssbo);
+        // All instancers share the instance buffer, one after the other
+        let instance_ssbo = res.instance_ssbo.mapped_slice_mut();
+        let mut written = 0;
+        for instancer in [&mut res.mesh_instancer, &mut res.shadow_instancer, &mut res.local_shadow_instancer] {
+            written += instancer.write_instance_buffer(&mut instance_ssbo[written..], written);
+        }
+
+        res.culling_report = CullingReport {
+            camera: res.mesh_instancer.stats(),
+            directional_shadows: res.shadow_instancer.stats(),
+            local_shadows: res.local_shadow_instancer.stats(),
+        };
     }
     fn prepare_ibl(&self, world: &World, assets: &AssetManager, ubo_data: &mut WorldUBO) {
         let environment_textures = assets.read_assets::<EnvironmentTexture>().expect("Environment Textures pool not found");
@@ -200,9 +268,62 @@ impl WorldRenderer {
         ubo_data.env_map_irradiance_ix = diffuse_irradiance.index() as u32;
         ubo_data.env_map_prefiltered_ix = specular_prefiltered.index() as u32;
     }
-    fn prepare(&mut self, world: &World, assets: &AssetManager, camera: Option<Entity>) {
-        self.write_instances(world, assets);
+    /// Returns the bounding spheres of point and spot lights which were assigned shadow maps
+    fn prepare_local_lights(&mut self, world: &World, ubo_data: &mut WorldUBO) -> Vec<(Vec3, f32)> {
+        let mut lights = Vec::new();
+
+        for (_, (transform, global, light)) in world.query::<(&Transform, Option<&GlobalTransform>, &Light)>().iter() {
+            let kind = match light.kind {
+                LightKind::Point => LOCAL_LIGHT_POINT,
+                LightKind::Spot => LOCAL_LIGHT_SPOT,
+                LightKind::Directional => continue,
+            };
+            if lights.len() == MAX_LOCAL_LIGHTS {
+                log::warn!("More than {} point and spot lights in the world, the rest are ignored", MAX_LOCAL_LIGHTS);
+                break;
+            }
+
+            let light_transform = GlobalTransform::from_matrix(util::world_matrix(transform, global));
+            let outer_cone_angle = light.outer_cone_angle.clamp(0.0, 89.0f32.to_radians());
+            let inner_cone_angle = light.inner_cone_angle.clamp(0.0, outer_cone_angle);
+
+            let local_light = LocalLight {
+                position: light_transform.position(),
+                range: light.range.max(0.0),
+                color: light.color.truncate(),
+                intensity: light.intensity,
+                direction: light_transform.forward(),
+                kind,
+                cos_inner_cone: inner_cone_angle.cos(),
+                cos_outer_cone: outer_cone_angle.cos(),
+                shadow_tile: -1,
+                normal_bias: 0.0,
+            };
+            lights.push((local_light, light.shadow.enabled.then_some(light.shadow)));
+        }
+
+        let res = &mut self.res;
+        passes::shadow::compute_local_shadows(
+            &mut lights,
+            ubo_data.camera_position.into(),
+            &res.settings,
+            res.local_shadow_ssbo.mapped_slice_mut(),
+            &mut res.local_shadow_casters,
+        );
+
+        let local_light_ssbo = res.local_light_ssbo.mapped_slice_mut();
+        for (ix, (light, _)) in lights.iter().enumerate() {
+            local_light_ssbo[ix] = *light;
+        }
+        ubo_data.n_local_lights = lights.len() as u32;
 
+        lights
+            .iter()
+            .filter(|(light, _)| light.shadow_tile >= 0)
+            .map(|(light, _)| (light.position, light.range))
+            .collect()
+    }
+    fn prepare(&mut self, world: &World, assets: &AssetManager, camera: Option<Entity>) {
         let mut ubo_data = WorldUBO::default();
         self.prepare_ibl(world, assets, &mut ubo_data);
         
@@ -213,15 +334,19 @@ impl WorldRenderer {
         res.camera = camera;
         res.directional_light = directional_light;
 
+        let mut camera_culling = Culling::Disabled;
+        let mut shadow_culling = None;
+
         if let Some(entity) = camera {
-            let mut query = world.query_one::<(&Transform, &Camera)>(entity).unwrap();
-            let (transform, camera) = query.get().unwrap();
+            let mut query = world.query_one::<(&Transform, Option<&GlobalTransform>, &Camera)>(entity).unwrap();
+            let (transform, global, camera) = query.get().unwrap();
+            let camera_matrix = util::world_matrix(transform, global);
 
             let projection = camera.get_projection_matrix(res.viewport.0, res.viewport.1);
-            let view = transform.get_matrix().inverse();
+            let view = camera_matrix.inverse();
 
             let camera_view_proj = projection * view;
-            ubo_data.camera_position = transform.position.into();
+            ubo_data.camera_position = camera_matrix.w_axis.truncate().into();
             ubo_data.proj = projection;
             ubo_data.view = view;
             ubo_data.view_proj = camera_view_proj;
@@ -230,6 +355,8 @@ impl WorldRenderer {
             ubo_data.viewport_size = res.viewport.into();
             ubo_data.exposure = camera.exposure;
 
+            camera_culling = Culling::Frustum(Frustum::from_view_proj(&camera_view_proj));
+
             if let Some((_, (transform, environment))) =
                 world.query::<(&Transform, &Environment)>().iter().next()
             {
@@ -238,11 +365,12 @@ impl WorldRenderer {
             }
 
             if let Some(entity) = directional_light {
-                let mut query = world.query_one::<(&Transform, &Light)>(entity).unwrap();
-                let (transform, light) = query.get().unwrap();
+                let mut query = world.query_one::<(&Transform, Option<&GlobalTransform>, &Light)>(entity).unwrap();
+                let (transform, global, light) = query.get().unwrap();
+                let light_transform = GlobalTransform::from_matrix(util::world_matrix(transform, global));
 
-                let direction = transform.forward();
-                let up_direction = transform.up();
+                let direction = light_transform.forward();
+                let up_direction = light_transform.up();
 
                 ubo_data.dir_light.intensity = light.intensity;
                 ubo_data.dir_light.size = light.size;
@@ -258,11 +386,26 @@ impl WorldRenderer {
                     ubo_data.dir_light.shadow_fade = shadow.fade;
                     ubo_data.dir_light.max_shadow_distance = shadow.max_shadow_distance;
                     passes::shadow::compute_cascades(&mut ubo_data, &res.settings);
+
+                    // Cascade splits are fitted on the GPU to the depth range of the scene,
+                    // which lies within the camera frustum truncated at the max shadow distance
+                    let shadow_camera = Camera {
+                        far: camera.far.min(shadow.max_shadow_distance).max(camera.near + 0.01),
+                        ..*camera
+                    };
+                    let shadow_view_proj = shadow_camera.get_projection_matrix(res.viewport.0, res.viewport.1) * view;
+                    shadow_culling = Some(Culling::ShadowCasters {
+                        frustum: Frustum::from_view_proj(&shadow_view_proj),
+                        direction,
+                    });
                 }
             }
         }
 
-        let world_ubo = &mut res.world_ubo;
+        let local_shadow_spheres = self.prepare_local_lights(world, &mut ubo_data);
+        self.write_instances(world, assets, camera_culling, shadow_culling, local_shadow_spheres);
+
+        let world_ubo = &mut self.res.world_ubo;
         world_ubo.mapped_slice_mut()[0] = ubo_data;
     }
     fn reset(&mut self) {
@@ -270,7 +413,13 @@ impl WorldRenderer {
         self.res.directional_light = None;
         self.res.world_ubo.new_frame();
         self.res.instance_ssbo.new_frame();
+        self.res.joint_ssbo.new_frame();
+        self.res.local_light_ssbo.new_frame();
+        self.res.local_shadow_ssbo.new_frame();
+        self.res.local_shadow_casters.clear();
         self.res.mesh_instancer.new_frame();
+        self.res.shadow_instancer.new_frame();
+        self.res.local_shadow_instancer.new_frame();
     }
     pub fn render(
         &mut self,
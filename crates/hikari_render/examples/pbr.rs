@@ -1107,6 +1107,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 size: 1.0,
                 shadow: ShadowInfo::default(),
                 kind: hikari_3d::LightKind::Directional,
+                ..Default::default()
             },
             transform: Transform::default(),
         },
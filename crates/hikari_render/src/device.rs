@@ -317,6 +317,9 @@ impl Device {
             .map(|&x| x.as_ptr())
             .collect::<Vec<_>>();
 
+        // Block compressed textures are used whenever the device supports them, see Features::TEXTURE_COMPRESSION_BC
+        let enable_features =
+            enable_features | (physical_device.features & Features::TEXTURE_COMPRESSION_BC);
         let enabled_features = enable_features.into();
 
         let mut sync2 =
@@ -261,7 +261,7 @@ impl<'a> CommandBuffer<'a> {
                 0,
                 &[vk::Viewport {
                     x,
-                    y: height - y,
+                    y: y + height,
                     width,
                     height: -height,
                     min_depth: 0.0,
@@ -30,6 +30,36 @@ pub fn format_size(format: vk::Format) -> u32 {
         _ => todo!(),
     }
 }
+/// Size in bytes of a 4x4 block of a block compressed format, `None` for formats which aren't block compressed
+pub fn format_block_size(format: vk::Format) -> Option<u32> {
+    match format {
+        vk::Format::BC1_RGB_UNORM_BLOCK
+        | vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_UNORM_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC4_UNORM_BLOCK
+        | vk::Format::BC4_SNORM_BLOCK => Some(8),
+        vk::Format::BC2_UNORM_BLOCK
+        | vk::Format::BC2_SRGB_BLOCK
+        | vk::Format::BC3_UNORM_BLOCK
+        | vk::Format::BC3_SRGB_BLOCK
+        | vk::Format::BC5_UNORM_BLOCK
+        | vk::Format::BC5_SNORM_BLOCK
+        | vk::Format::BC6H_UFLOAT_BLOCK
+        | vk::Format::BC6H_SFLOAT_BLOCK
+        | vk::Format::BC7_UNORM_BLOCK
+        | vk::Format::BC7_SRGB_BLOCK => Some(16),
+        _ => None,
+    }
+}
+/// Size in bytes of a single mip level of the given dimensions, block compressed formats are padded to whole blocks
+pub fn mip_level_size(format: vk::Format, width: u32, height: u32, depth: u32) -> usize {
+    let (width, height, depth) = (width.max(1), height.max(1), depth.max(1));
+    match format_block_size(format) {
+        Some(block_size) => (width.div_ceil(4) * height.div_ceil(4) * depth * block_size) as usize,
+        None => (width * height * depth * format_size(format)) as usize,
+    }
+}
 pub(crate) fn format_to_aspect_flags(format: vk::Format) -> vk::ImageAspectFlags {
     match format {
         vk::Format::D16_UNORM | vk::Format::D32_SFLOAT => vk::ImageAspectFlags::DEPTH,
@@ -293,6 +323,12 @@ impl RawSampledImage {
         layers: u32,
         mut vkconfig: ImageConfig,
     ) -> anyhow::Result<Self> {
+        if format_block_size(vkconfig.format).is_some() {
+            return Err(anyhow::anyhow!(
+                "Cannot create gpu image, block compressed format {:?} needs pre-built mips",
+                vkconfig.format
+            ));
+        }
         vkconfig.usage |= vk::ImageUsageFlags::TRANSFER_DST;
 
         if vkconfig.host_readable {
@@ -464,6 +500,158 @@ impl RawSampledImage {
             config: vkconfig,
         })
     }
+    /// Creates a 2D image from pre-built mip levels, starting with the full resolution image.
+    /// Nothing is generated on the GPU, so this also works for block compressed formats which can't be blitted
+    pub fn with_mips(
+        device: &crate::Device,
+        mips: &[&[u8]],
+        width: u32,
+        height: u32,
+        mut vkconfig: ImageConfig,
+    ) -> anyhow::Result<Self> {
+        if mips.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Cannot create gpu image without any mip levels"
+            ));
+        }
+        vkconfig.usage |= vk::ImageUsageFlags::TRANSFER_DST;
+        vkconfig.mip_levels = mips.len() as u32;
+
+        let mut offsets = Vec::with_capacity(mips.len());
+        let mut total_size = 0;
+        for (level, mip) in mips.iter().enumerate() {
+            let expected_size = mip_level_size(vkconfig.format, width >> level, height >> level, 1);
+            if mip.len() != expected_size {
+                return Err(anyhow::anyhow!(
+                    "Cannot create gpu image, mip level {} is {} bytes but expected {} bytes, format is {:?}",
+                    level,
+                    mip.len(),
+                    expected_size,
+                    vkconfig.format
+                ));
+            }
+            offsets.push(total_size);
+            // Buffer offsets of copies to images must be a multiple of the texel block size
+            total_size += (mip.len() + 15) & !15;
+        }
+
+        let mut basic_image_views = Vec::new();
+        let mut shader_resource_views = Vec::new();
+        let mut render_target_views = Vec::new();
+
+        let (image, allocation, sampler) = Self::create_image_with_sampler_and_views(
+            device,
+            width,
+            height,
+            1,
+            1,
+            &vkconfig,
+            &mut basic_image_views,
+            &mut shader_resource_views,
+            &mut render_target_views,
+        )?;
+
+        let subresource_range = vk::ImageSubresourceRange::default()
+            .aspect_mask(format_to_aspect_flags(vkconfig.format))
+            .base_mip_level(0)
+            .level_count(vkconfig.mip_levels)
+            .layer_count(1);
+
+        let mut staging_buffer = crate::buffer::RawBuffer::new(
+            device,
+            "staging_buffer",
+            total_size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            gpu_allocator::MemoryLocation::CpuToGpu,
+        )?;
+
+        unsafe {
+            let slice = staging_buffer.mapped_slice_mut();
+
+            for (mip, &offset) in mips.iter().zip(&offsets) {
+                slice[offset..offset + mip.len()].copy_from_slice(mip);
+            }
+        }
+
+        let buffer_copy_regions: Vec<_> = offsets
+            .iter()
+            .enumerate()
+            .map(|(level, &offset)| {
+                vk::BufferImageCopy::default()
+                    .buffer_offset(offset as u64)
+                    .image_subresource(
+                        vk::ImageSubresourceLayers::default()
+                            .aspect_mask(format_to_aspect_flags(vkconfig.format))
+                            .mip_level(level as u32)
+                            .base_array_layer(0)
+                            .layer_count(1),
+                    )
+                    .image_extent(vk::Extent3D {
+                        width: (width >> level).max(1),
+                        height: (height >> level).max(1),
+                        depth: 1,
+                    })
+            })
+            .collect();
+
+        unsafe {
+            hikari_dev::profile_scope!("Image Upload");
+            device.submit_commands_immediate(|cmd| {
+                let device = device.raw();
+
+                crate::barrier::image_memory_barrier(
+                    device,
+                    cmd,
+                    image,
+                    subresource_range,
+                    vk::AccessFlags::empty(),
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::TRANSFER,
+                );
+
+                device.cmd_copy_buffer_to_image(
+                    cmd,
+                    staging_buffer.buffer(),
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &buffer_copy_regions,
+                );
+
+                crate::barrier::image_memory_barrier(
+                    device,
+                    cmd,
+                    image,
+                    subresource_range,
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::AccessFlags::SHADER_READ,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::FRAGMENT_SHADER,
+                );
+                Ok(())
+            })?;
+        }
+
+        staging_buffer.delete(device);
+        Ok(Self {
+            image,
+            basic_image_views,
+            shader_resource_views,
+            render_target_views,
+            arbitrary_image_views: Mutex::new(Default::default()),
+            sampler,
+            allocation: Some(allocation),
+            width,
+            height,
+            depth: 1,
+            layers: 1,
+            config: vkconfig,
+        })
+    }
     /// Assumes that image is in TRANSFER_SRC_OPTIMAL layout
     pub fn generate_mips(&self, device: &crate::Device, cmd: vk::CommandBuffer) {
         Self::generate_mips_(
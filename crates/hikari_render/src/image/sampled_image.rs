@@ -88,6 +88,25 @@ impl SampledImage {
             bindless_handles,
         })
     }
+    /// Creates a 2D image from pre-built mip levels, starting with the full resolution image
+    /// Mips aren't generated, so this works for block compressed formats as well
+    pub fn with_mips(
+        device: &Arc<crate::Device>,
+        mips: &[&[u8]],
+        width: u32,
+        height: u32,
+        vkconfig: ImageConfig,
+    ) -> anyhow::Result<Self> {
+        let raw = RawSampledImage::with_mips(device, mips, width, height, vkconfig)?;
+
+        let bindless_handles = Self::create_bindless(device, &raw);
+        Ok(Self {
+            device: device.clone(),
+            raw,
+            download_buffer: None,
+            bindless_handles,
+        })
+    }
     fn create_bindless(device: &Arc<crate::Device>, raw: &RawSampledImage) -> Vec<BindlessHandle<vk::ImageView>> {
         let mut handles = Vec::new();
         for &view_ix in &raw.shader_resource_views {
@@ -11,6 +11,7 @@ pub enum ShaderDataType {
     Vec2f,
     Vec3f,
     Vec4f,
+    Vec4u,
 }
 impl ShaderDataType {
     pub const fn size(self) -> usize {
@@ -19,6 +20,7 @@ impl ShaderDataType {
             ShaderDataType::Vec2f => 4 * 2,
             ShaderDataType::Vec3f => 4 * 3,
             ShaderDataType::Vec4f => 4 * 4,
+            ShaderDataType::Vec4u => 4 * 4,
         }
     }
     pub const fn shape(self) -> u32 {
@@ -27,6 +29,7 @@ impl ShaderDataType {
             ShaderDataType::Vec2f => 2,
             ShaderDataType::Vec3f => 3,
             ShaderDataType::Vec4f => 4,
+            ShaderDataType::Vec4u => 4,
         }
     }
 
@@ -36,6 +39,7 @@ impl ShaderDataType {
             ShaderDataType::Vec2f => vk::Format::R32G32_SFLOAT,
             ShaderDataType::Vec3f => vk::Format::R32G32B32_SFLOAT,
             ShaderDataType::Vec4f => vk::Format::R32G32B32A32_SFLOAT,
+            ShaderDataType::Vec4u => vk::Format::R32G32B32A32_UINT,
         }
     }
 }
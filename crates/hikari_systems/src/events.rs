@@ -0,0 +1,218 @@
+use std::{marker::PhantomData, pin::Pin};
+
+use crate::{
+    global::UnsafeGlobalState,
+    query::{Borrows, Fetch, FetchState, Query},
+    State,
+};
+
+/// A queue of events of type `T` sent between functions.
+/// Events are kept for two calls of [`Events::update`], which normally happens once per frame,
+/// so every reader sees an event regardless of whether it runs before or after the writer
+pub struct Events<T> {
+    /// Events sent before the last update
+    previous: Vec<(usize, T)>,
+    /// Events sent since the last update
+    current: Vec<(usize, T)>,
+    event_count: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            event_count: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn send(&mut self, event: T) {
+        self.current.push((self.event_count, event));
+        self.event_count += 1;
+    }
+    /// Drops the events which have been kept for two updates and starts a new buffer
+    pub fn update(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+    /// Removes all events without them being read
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+    /// Number of events currently kept
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Events sent after the event with id `start`, oldest first
+    fn iter_from(&self, start: usize) -> impl Iterator<Item = &T> {
+        self.previous
+            .iter()
+            .chain(self.current.iter())
+            .filter(move |(id, _)| *id >= start)
+            .map(|(_, event)| event)
+    }
+}
+
+/// Sends events of type `T`, requires [`Events<T>`] to be added to the state
+pub struct EventWriter<'a, T> {
+    events: &'a mut Events<T>,
+}
+
+impl<'a, T> EventWriter<'a, T> {
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        for event in events {
+            self.events.send(event);
+        }
+    }
+}
+
+/// Reads the events of type `T` which were sent since the function last ran.
+/// Every function has its own position in the queue, so events are seen once by every reader
+pub struct EventReader<'a, T> {
+    events: &'a Events<T>,
+    last_read: &'a mut usize,
+}
+
+impl<'a, T> EventReader<'a, T> {
+    /// Returns the unread events, oldest first, and marks them as read
+    pub fn iter(&mut self) -> impl Iterator<Item = &T> {
+        let start = std::mem::replace(self.last_read, self.events.event_count);
+        self.events.iter_from(start)
+    }
+    /// Number of unread events
+    pub fn len(&self) -> usize {
+        self.events.iter_from(*self.last_read).count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Marks all events as read without looking at them
+    pub fn clear(&mut self) {
+        *self.last_read = self.events.event_count;
+    }
+}
+
+pub struct EventWriterFetch<T> {
+    _phantom: PhantomData<T>,
+}
+impl<'a, T: State> Query for EventWriter<'a, T> {
+    type Fetch = EventWriterFetch<T>;
+}
+impl<T: State> FetchState for EventWriterFetch<T> {
+    type State = ();
+}
+unsafe impl<'a, T: State> Fetch<'a> for EventWriterFetch<T> {
+    type Item = EventWriter<'a, T>;
+
+    fn get(_: &'a mut (), g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+        let result = unsafe { UnsafeGlobalState::get_unchecked_mut::<Events<T>>(g_state) };
+
+        match result {
+            Some(events) => EventWriter { events },
+            None => panic!("No events of type: {}", std::any::type_name::<T>()),
+        }
+    }
+
+    fn borrow_check(borrows: &mut Borrows) {
+        borrows.borrow_mut::<Events<T>>()
+    }
+}
+
+pub struct EventReaderFetch<T> {
+    _phantom: PhantomData<T>,
+}
+impl<'a, T: State> Query for EventReader<'a, T> {
+    type Fetch = EventReaderFetch<T>;
+}
+impl<T: State> FetchState for EventReaderFetch<T> {
+    type State = usize;
+}
+unsafe impl<'a, T: State> Fetch<'a> for EventReaderFetch<T> {
+    type Item = EventReader<'a, T>;
+
+    fn get(last_read: &'a mut usize, g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+        let result = unsafe { g_state.get_unchecked::<Events<T>>() };
+
+        match result {
+            Some(events) => EventReader { events, last_read },
+            None => panic!("No events of type: {}", std::any::type_name::<T>()),
+        }
+    }
+
+    fn borrow_check(borrows: &mut Borrows) {
+        borrows.borrow::<Events<T>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Schedule, StateBuilder, Task};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Collision(u32);
+
+    #[test]
+    fn events_expire() {
+        let mut events = Events::new();
+        events.send(Collision(0));
+        events.update();
+        events.send(Collision(1));
+        assert_eq!(events.len(), 2);
+
+        events.update();
+        assert_eq!(events.iter_from(0).collect::<Vec<_>>(), [&Collision(1)]);
+
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn readers_see_events_once() {
+        fn write(mut writer: EventWriter<Collision>, frame: &mut u32) {
+            writer.send(Collision(*frame));
+            *frame += 1;
+        }
+        // Runs before the writer, so it sees events from the previous frame
+        fn read_early(mut reader: EventReader<Collision>, seen: &mut Vec<u32>) {
+            seen.extend(reader.iter().map(|collision| collision.0));
+        }
+        fn update_events(events: &mut Events<Collision>) {
+            events.update();
+        }
+
+        let mut global = StateBuilder::new();
+        global.add_state(Events::<Collision>::new());
+        global.add_state(0_u32);
+        global.add_state(Vec::<u32>::new());
+        let mut global = global.build();
+
+        let mut schedule = Schedule::new();
+        schedule.create_stage("First");
+        schedule.create_stage("Update");
+        schedule.add_task("First", Task::new("Update Events", update_events));
+        schedule.add_task("Update", Task::new("Read", read_early).before("Write"));
+        schedule.add_task("Update", Task::new("Write", write));
+
+        let mut schedule = schedule.build().unwrap();
+        for _ in 0..4 {
+            schedule.execute(&mut global);
+        }
+
+        let seen = global.get::<Vec<u32>>().unwrap();
+        assert_eq!(*seen, [0, 1, 2]);
+    }
+}
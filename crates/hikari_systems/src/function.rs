@@ -3,18 +3,33 @@ use crate::query::Query;
 
 pub type VoidFunction = Function<()>;
 
-#[allow(dead_code)]
 pub struct Function<R> {
     borrows: Borrows,
-    exec: Box<dyn FnMut(Pin<&UnsafeGlobalState>) -> R + 'static>,
+    exclusive: bool,
+    exec: Box<dyn FnMut(Pin<&UnsafeGlobalState>) -> R + Send + Sync + 'static>,
 }
 impl<R> Function<R> {
-    pub unsafe fn from_raw(exec: Box<dyn FnMut(Pin<&UnsafeGlobalState>) -> R + 'static>) -> Self {
+    /// Raw functions don't declare what they access, so they are treated as
+    /// exclusive and never run alongside other functions
+    pub unsafe fn from_raw(
+        exec: Box<dyn FnMut(Pin<&UnsafeGlobalState>) -> R + Send + Sync + 'static>,
+    ) -> Self {
         Self {
             borrows: Borrows::default(),
+            exclusive: true,
             exec,
         }
     }
+    pub fn borrows(&self) -> &Borrows {
+        &self.borrows
+    }
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+    /// Returns true if `self` and `other` cannot be run at the same time
+    pub fn conflicts_with(&self, other: &Function<R>) -> bool {
+        self.exclusive || other.exclusive || self.borrows.conflicts_with(&other.borrows)
+    }
     #[inline]
     pub(crate) unsafe fn run(&mut self, g_state: Pin<&UnsafeGlobalState>) -> R {
         (self.exec)(g_state)
@@ -27,7 +42,7 @@ pub trait IntoFunction<Params, R> {
 use std::pin::Pin;
 
 use crate::global::UnsafeGlobalState;
-use crate::query::Fetch;
+use crate::query::{Fetch, FetchState};
 
 macro_rules! impl_into_function {
     ($($name: ident),*) => {
@@ -43,15 +58,18 @@ macro_rules! impl_into_function {
                 let mut borrows = Borrows::default();
                 ($(<<$name as Query>::Fetch as Fetch>::borrow_check(&mut borrows),)*);
 
+                let mut fetch_state = <<($($name,)*) as Query>::Fetch as FetchState>::State::default();
+
                 Function {
                     exec: Box::new(move |g_state| {
                         unsafe {
-                            let ($($name,)*) = g_state.query::<($($name,)*)>();
+                            let ($($name,)*) = g_state.query::<($($name,)*)>(&mut fetch_state);
 
                             self($($name,)*)
                         }
                     }),
-                    borrows
+                    borrows,
+                    exclusive: false,
                 }
             }
         }
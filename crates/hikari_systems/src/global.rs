@@ -3,7 +3,7 @@ use std::{any::{TypeId, type_name}, marker::PhantomPinned, pin::Pin};
 use fxhash::FxHashMap;
 
 use crate::{
-    query::{Fetch, Query},
+    query::{Fetch, FetchState, Query},
     State,
 };
 
@@ -107,10 +107,11 @@ impl UnsafeGlobalState {
             .get(&TypeId::of::<S>())
             .map(|cell| cell.borrow_cast_unchecked_mut())
     }
-    pub(crate) unsafe fn query<Q: Query>(
-        self: Pin<&Self>,
-    ) -> <<Q as Query>::Fetch as Fetch<'_>>::Item {
-        Q::Fetch::get(self)
+    pub(crate) unsafe fn query<'a, Q: Query>(
+        self: Pin<&'a Self>,
+        state: &'a mut <<Q as Query>::Fetch as FetchState>::State,
+    ) -> <<Q as Query>::Fetch as Fetch<'a>>::Item {
+        Q::Fetch::get(state, self)
     }
 }
 impl Drop for UnsafeGlobalState {
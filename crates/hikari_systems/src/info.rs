@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+/// A snapshot of how a [`crate::Schedule`] was resolved, see [`crate::Schedule::info`]
+#[derive(Clone, Debug, Default)]
+pub struct ScheduleInfo {
+    pub stages: Vec<StageInfo>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StageInfo {
+    pub name: String,
+    /// Tasks in the order they run when the schedule is executed serially
+    pub tasks: Vec<TaskInfo>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskInfo {
+    pub name: String,
+    /// State accessed by the task, sorted by type name
+    pub borrows: Vec<BorrowInfo>,
+    /// Exclusive tasks don't declare their borrows and never run alongside other tasks
+    pub exclusive: bool,
+    pub sets: Vec<String>,
+    pub has_conditions: bool,
+    /// Tasks of the same stage which have to finish before this one starts
+    pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BorrowInfo {
+    pub type_name: &'static str,
+    pub mutable: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DependencyKind {
+    /// Requested through `before`/`after` on a task or set
+    Ordering,
+    /// The tasks borrow the same state and at least one of them mutably
+    Conflict,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    /// Index of the task in [`StageInfo::tasks`]
+    pub task: usize,
+    pub kind: DependencyKind,
+}
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl ScheduleInfo {
+    /// Exports the dependency graph in Graphviz DOT format, with one cluster per stage.
+    /// Ordering constraints are drawn as solid edges and borrow conflicts as dashed edges
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph Schedule {\n    rankdir=LR;\n    node [shape=box];\n");
+
+        for (stage_ix, stage) in self.stages.iter().enumerate() {
+            let node = |task: &TaskInfo| format!("\"{}/{}\"", escape(&stage.name), escape(&task.name));
+
+            writeln!(dot, "    subgraph cluster_{} {{", stage_ix).unwrap();
+            writeln!(dot, "        label=\"{}\";", escape(&stage.name)).unwrap();
+            for task in &stage.tasks {
+                let mut label = escape(&task.name);
+                for borrow in &task.borrows {
+                    let kind = if borrow.mutable { "&mut" } else { "&" };
+                    write!(label, "\\n{} {}", kind, escape(borrow.type_name)).unwrap();
+                }
+                if task.exclusive {
+                    label.push_str("\\n(exclusive)");
+                }
+                writeln!(dot, "        {} [label=\"{}\"];", node(task), label).unwrap();
+            }
+            writeln!(dot, "    }}").unwrap();
+
+            for task in &stage.tasks {
+                for dependency in &task.dependencies {
+                    let style = match dependency.kind {
+                        DependencyKind::Ordering => "solid",
+                        DependencyKind::Conflict => "dashed",
+                    };
+                    writeln!(
+                        dot,
+                        "    {} -> {} [style={}];",
+                        node(&stage.tasks[dependency.task]),
+                        node(task),
+                        style
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
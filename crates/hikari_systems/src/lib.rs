@@ -1,9 +1,17 @@
+pub mod events;
 pub mod function;
 pub mod global;
+pub mod info;
 pub mod query;
 pub mod stage;
 pub mod state;
 
+pub use events::EventReader;
+pub use events::EventWriter;
+pub use events::Events;
+
+pub use info::ScheduleInfo;
+
 pub use global::GlobalState;
 pub use global::StateBuilder;
 pub use state::State;
@@ -11,6 +19,8 @@ pub use state::State;
 pub use stage::Schedule;
 pub use stage::ScheduleBuilder;
 pub use stage::Task;
+pub use stage::TaskControl;
+pub use stage::TaskSet;
 
 pub use function::Function;
 pub use function::IntoFunction;
@@ -2,7 +2,7 @@ use std::{marker::PhantomData, pin::Pin};
 
 use fxhash::FxHashMap;
 
-use crate::{global::UnsafeGlobalState, State};
+use crate::{global::UnsafeGlobalState, info::BorrowInfo, State};
 #[allow(dead_code)]
 pub(crate) struct Type {
     pub name: &'static str,
@@ -50,22 +50,57 @@ impl Borrows {
             );
         }
     }
-    fn borrow<T: 'static>(&mut self) {
+    pub(crate) fn borrow<T: 'static>(&mut self) {
         self.borrow_int::<T>(BorrowKind::Shared)
     }
-    fn borrow_mut<T: 'static>(&mut self) {
+    pub(crate) fn borrow_mut<T: 'static>(&mut self) {
         self.borrow_int::<T>(BorrowKind::Mutable)
     }
+    /// The state accessed, sorted by type name
+    pub fn iter(&self) -> impl Iterator<Item = BorrowInfo> {
+        let mut borrows: Vec<_> = self
+            .map
+            .values()
+            .map(|borrow| BorrowInfo {
+                type_name: borrow.ty.name,
+                mutable: borrow.kind == BorrowKind::Mutable,
+            })
+            .collect();
+        borrows.sort_by_key(|borrow| borrow.type_name);
+
+        borrows.into_iter()
+    }
+    /// Returns true if `self` and `other` cannot be accessed at the same time,
+    /// i.e. both touch the same state and at least one of them mutably
+    pub fn conflicts_with(&self, other: &Borrows) -> bool {
+        let (smaller, larger) = if self.map.len() <= other.map.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        smaller.map.iter().any(|(id, borrow)| match larger.map.get(id) {
+            Some(other_borrow) => {
+                borrow.kind == BorrowKind::Mutable || other_borrow.kind == BorrowKind::Mutable
+            }
+            None => false,
+        })
+    }
 }
 
 pub trait Query {
     type Fetch: for<'a> Fetch<'a>;
 }
 
-pub unsafe trait Fetch<'a>: Sized {
+/// Data kept by a function between runs for one of its parameters, e.g. how far an [`crate::EventReader`] has read
+pub trait FetchState {
+    type State: Default + Send + Sync + 'static;
+}
+
+pub unsafe trait Fetch<'a>: FetchState + Sized {
     type Item;
 
-    fn get(g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item;
+    fn get(state: &'a mut Self::State, g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item;
 
     fn borrow_check(borrows: &mut Borrows);
 }
@@ -78,10 +113,13 @@ impl<'a, S: State> Query for &'a S {
     type Fetch = RefFetch<S>;
 }
 
+impl<S: State> FetchState for RefFetch<S> {
+    type State = ();
+}
 unsafe impl<'a, S: State> Fetch<'a> for RefFetch<S> {
     type Item = &'a S;
 
-    fn get(g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+    fn get(_: &'a mut (), g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
         let result = unsafe {
             g_state
                 .get_unchecked::<S>()
@@ -103,10 +141,13 @@ impl<'a, S: State> Query for &'a mut S {
     type Fetch = RefMutFetch<S>;
 }
 
+impl<S: State> FetchState for RefMutFetch<S> {
+    type State = ();
+}
 unsafe impl<'a, S: State> Fetch<'a> for RefMutFetch<S> {
     type Item = &'a mut S;
 
-    fn get(g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+    fn get(_: &'a mut (), g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
         let result = unsafe {
             UnsafeGlobalState::get_unchecked_mut::<S>(g_state)
         };
@@ -128,10 +169,13 @@ impl<'a, S: State> Query for Option<&'a S> {
 pub struct MaybeRefFetch<T> {
     _phantom: PhantomData<T>,
 }
+impl<S: State> FetchState for MaybeRefFetch<S> {
+    type State = ();
+}
 unsafe impl<'a, S: State> Fetch<'a> for MaybeRefFetch<S> {
     type Item = Option<&'a S>;
 
-    fn get(g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+    fn get(_: &'a mut (), g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
         unsafe { g_state.get_unchecked::<S>() }
     }
 
@@ -146,10 +190,13 @@ impl<'a, S: State> Query for Option<&'a mut S> {
 pub struct MaybeRefMutFetch<T> {
     _phantom: PhantomData<T>,
 }
+impl<S: State> FetchState for MaybeRefMutFetch<S> {
+    type State = ();
+}
 unsafe impl<'a, S: State> Fetch<'a> for MaybeRefMutFetch<S> {
     type Item = Option<&'a mut S>;
 
-    fn get(g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+    fn get(_: &'a mut (), g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
         unsafe { g_state.get_unchecked_mut::<S>() }
     }
 
@@ -161,10 +208,13 @@ unsafe impl<'a, S: State> Fetch<'a> for MaybeRefMutFetch<S> {
 impl Query for () {
     type Fetch = ();
 }
+impl FetchState for () {
+    type State = ();
+}
 unsafe impl<'a> Fetch<'a> for () {
     type Item = ();
 
-    fn get(_: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+    fn get(_: &'a mut (), _: Pin<&'a UnsafeGlobalState>) -> Self::Item {
         ()
     }
     fn borrow_check(_: &mut Borrows) {}
@@ -176,11 +226,17 @@ macro_rules! impl_query {
             type Fetch = ($($name::Fetch,)*);
         }
 
+        impl<$($name: FetchState),*> FetchState for ($($name,)*) {
+            type State = ($($name::State,)*);
+        }
+
+        #[allow(non_snake_case)]
         unsafe impl<'a, $($name: Fetch<'a>),*> Fetch<'a> for ($($name,)*) {
             type Item = ($($name::Item,)*);
 
-            fn get(g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
-                ($($name::get(g_state),)*)
+            fn get(state: &'a mut Self::State, g_state: Pin<&'a UnsafeGlobalState>) -> Self::Item {
+                let ($($name,)*) = state;
+                ($(<$name as Fetch<'a>>::get($name, g_state),)*)
             }
             fn borrow_check(borrows: &mut Borrows) {
                 ($($name::borrow_check(borrows),)*);
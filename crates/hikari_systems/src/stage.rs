@@ -1,7 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
 
 use crate::{
     function::{Function, IntoFunction},
+    global::UnsafeGlobalState,
+    info::{Dependency, DependencyKind, ScheduleInfo, StageInfo, TaskInfo},
     GlobalState,
 };
 pub struct Task<Return> {
@@ -9,15 +19,12 @@ pub struct Task<Return> {
     function: Function<Return>,
     before: HashSet<String>,
     after: HashSet<String>,
+    sets: HashSet<String>,
+    conditions: Vec<Function<bool>>,
 }
 impl<Return> Task<Return> {
     pub fn new<Params>(name: &str, function: impl IntoFunction<Params, Return>) -> Self {
-        Self {
-            name: name.to_owned(),
-            function: function.into_function(),
-            before: HashSet::new(),
-            after: HashSet::new(),
-        }
+        unsafe { Self::with_raw_function(name, function.into_function()) }
     }
     pub unsafe fn with_raw_function(name: &str, function: Function<Return>) -> Self {
         Self {
@@ -25,21 +32,39 @@ impl<Return> Task<Return> {
             function,
             before: HashSet::new(),
             after: HashSet::new(),
+            sets: HashSet::new(),
+            conditions: Vec::new(),
         }
     }
     pub fn name(&self) -> &str {
         &self.name
     }
+    /// `task_name` can also be the name of a [`TaskSet`], in which case the task runs before every task in the set
     pub fn before(mut self, task_name: &str) -> Self {
         self.before.insert(task_name.to_owned());
 
         self
     }
+    /// `task_name` can also be the name of a [`TaskSet`], in which case the task runs after every task in the set
     pub fn after(mut self, task_name: &str) -> Self {
         self.after.insert(task_name.to_owned());
 
         self
     }
+    /// Adds the task to a set, the ordering and conditions of the set configured with [`ScheduleBuilder::configure_set`] apply to the task
+    pub fn in_set(mut self, set_name: &str) -> Self {
+        self.sets.insert(set_name.to_owned());
+
+        self
+    }
+    /// The task is skipped unless `condition` returns true.
+    /// Conditions of all tasks in a stage are evaluated before any of them runs, so they don't
+    /// see the effects of tasks in the same stage. This holds for both serial and parallel execution
+    pub fn run_if<Params>(mut self, condition: impl IntoFunction<Params, bool>) -> Self {
+        self.conditions.push(condition.into_function());
+
+        self
+    }
     fn validate(&self) -> Result<(), String> {
         let intersection = self.before.intersection(&self.after);
 
@@ -52,30 +77,365 @@ impl<Return> Task<Return> {
         }
     }
 }
+
+/// A named group of tasks, tasks join it with [`Task::in_set`].
+/// Ordering and run conditions of the set apply to all of its tasks, within each stage
+pub struct TaskSet {
+    name: String,
+    before: HashSet<String>,
+    after: HashSet<String>,
+    conditions: Vec<Function<bool>>,
+}
+impl TaskSet {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            before: HashSet::new(),
+            after: HashSet::new(),
+            conditions: Vec::new(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// All tasks of the set run before the task or set named `name`
+    pub fn before(mut self, name: &str) -> Self {
+        self.before.insert(name.to_owned());
+
+        self
+    }
+    /// All tasks of the set run after the task or set named `name`
+    pub fn after(mut self, name: &str) -> Self {
+        self.after.insert(name.to_owned());
+
+        self
+    }
+    /// Tasks of the set are skipped unless `condition` returns true.
+    /// It is evaluated once per stage, together with the conditions of [`Task::run_if`]
+    pub fn run_if<Params>(mut self, condition: impl IntoFunction<Params, bool>) -> Self {
+        self.conditions.push(condition.into_function());
+
+        self
+    }
+    fn validate(&self) -> Result<(), String> {
+        if self.before.intersection(&self.after).count() > 0 {
+            Err(format!(
+                "Set {:?} cannot run both before and after another task or set!",
+                self.name
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Turns tasks and sets on and off at runtime by name.
+/// If it is part of the state, it is checked every time a stage runs
+#[derive(Debug, Default)]
+pub struct TaskControl {
+    disabled: HashSet<String>,
+}
+impl TaskControl {
+    pub fn enable(&mut self, name: &str) {
+        self.disabled.remove(name);
+    }
+    pub fn disable(&mut self, name: &str) {
+        self.disabled.insert(name.to_owned());
+    }
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        if enabled {
+            self.enable(name)
+        } else {
+            self.disable(name)
+        }
+    }
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name)
+    }
+}
+
+struct ScheduledTask<Return> {
+    name: String,
+    function: Function<Return>,
+    conditions: Vec<Function<bool>>,
+    /// Indices into [`Schedule::sets`]
+    sets: Vec<usize>,
+}
+
+struct ScheduledSet {
+    name: String,
+    conditions: Vec<Function<bool>>,
+}
+
+/// Checks whether `task` is enabled and all of its conditions hold.
+/// The conditions of sets are only evaluated once, their results are kept in `set_results`
+fn should_run<Return>(
+    task: &mut ScheduledTask<Return>,
+    sets: &mut [ScheduledSet],
+    set_results: &mut [Option<bool>],
+    g_state: Pin<&UnsafeGlobalState>,
+) -> bool {
+    if let Some(control) = g_state.get::<TaskControl>() {
+        let disabled = !control.is_enabled(&task.name)
+            || task.sets.iter().any(|&set| !control.is_enabled(&sets[set].name));
+        if disabled {
+            return false;
+        }
+    }
+
+    for &set in &task.sets {
+        let result = *set_results[set].get_or_insert_with(|| {
+            sets[set]
+                .conditions
+                .iter_mut()
+                .all(|condition| unsafe { condition.run(g_state) })
+        });
+
+        if !result {
+            return false;
+        }
+    }
+
+    task.conditions
+        .iter_mut()
+        .all(|condition| unsafe { condition.run(g_state) })
+}
+
+/// Decides which tasks of a stage run, before any of them does
+fn evaluate_stage<Return>(
+    tasks: &mut [ScheduledTask<Return>],
+    sets: &mut [ScheduledSet],
+    g_state: Pin<&UnsafeGlobalState>,
+) -> Vec<bool> {
+    let mut set_results = vec![None; sets.len()];
+
+    tasks
+        .iter_mut()
+        .map(|task| should_run(task, sets, &mut set_results, g_state))
+        .collect()
+}
+
 pub struct Schedule<Return> {
-    functions: Vec<(String, Function<Return>)>,
+    tasks: Vec<ScheduledTask<Return>>,
+    sets: Vec<ScheduledSet>,
+    stages: Vec<StageGraph>,
 }
 
 impl<Return> Schedule<Return> {
     pub fn new() -> ScheduleBuilder<Return> {
-        ScheduleBuilder { stages: Vec::new() }
+        ScheduleBuilder::new()
+    }
+    /// Describes the resolved order of the tasks in every stage and what they depend on
+    pub fn info(&self) -> ScheduleInfo {
+        let stages = self
+            .stages
+            .iter()
+            .map(|stage| StageInfo {
+                name: stage.name.clone(),
+                tasks: self.tasks[stage.range.clone()]
+                    .iter()
+                    .zip(&stage.dependencies)
+                    .map(|(task, dependencies)| TaskInfo {
+                        name: task.name.clone(),
+                        borrows: task.function.borrows().iter().collect(),
+                        exclusive: task.function.is_exclusive(),
+                        sets: task.sets.iter().map(|&set| self.sets[set].name.clone()).collect(),
+                        has_conditions: !task.conditions.is_empty()
+                            || task.sets.iter().any(|&set| !self.sets[set].conditions.is_empty()),
+                        dependencies: dependencies.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        ScheduleInfo { stages }
     }
     #[inline]
     pub fn execute(&mut self, state: &mut GlobalState) {
         for _ret in self.execute_iter(state) {}
     }
+    /// Runs every task one after another, skipped tasks don't produce an item
     #[inline]
     pub fn execute_iter<'a>(&'a mut self, state: &'a mut GlobalState) -> impl Iterator<Item = Return> + 'a {
-        self.functions.iter_mut().map(|(_name, function)| {
+        let g_state = state.raw();
+        let Self { tasks, sets, stages } = self;
+        let mut stage = 0;
+        let mut enabled = Vec::new();
+
+        (0..tasks.len()).filter_map(move |ix| {
+            while ix >= stages[stage].range.end {
+                stage += 1;
+            }
+
+            // Conditions are evaluated when the stage starts, like in `execute_parallel`
+            let range = stages[stage].range.clone();
+            if ix == range.start {
+                enabled = evaluate_stage(&mut tasks[range.clone()], sets, g_state);
+            }
+
+            if !enabled[ix - range.start] {
+                return None;
+            }
+
+            let task = &mut tasks[ix];
+            let _name = &task.name;
             hikari_dev::profile_scope!(_name);
 
             unsafe {
-                function.run(state.raw())
+                Some(task.function.run(g_state))
             }
         })
     }
-    pub fn execute_parallel(&mut self, _state: &mut GlobalState) {
-        todo!()
+    /// Executes the schedule on the current rayon thread pool.
+    /// Stages still run one after another, but tasks within a stage run concurrently
+    /// as long as their borrows don't conflict and their `before`/`after` constraints are met.
+    /// Conflicting tasks run in the same order as they would in [`Schedule::execute`]
+    pub fn execute_parallel(&mut self, state: &mut GlobalState) {
+        self.execute_parallel_with(state, |_, _| 1)
+    }
+    /// Like [`Schedule::execute_parallel`], but every stage runs as many times as `repetitions` returns for it.
+    /// `repetitions` is called with the name of the stage right before the stage would run,
+    /// so it sees the effects of all previous stages. Returning 0 skips the stage
+    pub fn execute_parallel_with(
+        &mut self,
+        state: &mut GlobalState,
+        mut repetitions: impl FnMut(&str, &GlobalState) -> usize,
+    ) {
+        for stage_ix in 0..self.stages.len() {
+            for _ in 0..repetitions(&self.stages[stage_ix].name, state) {
+                self.execute_stage_parallel(stage_ix, state);
+            }
+        }
+    }
+    fn execute_stage_parallel(&mut self, stage_ix: usize, state: &mut GlobalState) {
+        let g_state = state.raw();
+        let stage = &self.stages[stage_ix];
+
+        let tasks = &mut self.tasks[stage.range.clone()];
+
+        // Conditions are evaluated up front, as they may conflict with the tasks
+        let enabled = evaluate_stage(tasks, &mut self.sets, g_state);
+
+        if tasks.len() == 1 {
+            let task = &mut tasks[0];
+            let _name = &task.name;
+            hikari_dev::profile_scope!(_name);
+
+            if enabled[0] {
+                unsafe {
+                    task.function.run(g_state);
+                }
+            }
+            return;
+        }
+
+        let ctx = ParallelContext {
+            functions: tasks
+                .iter_mut()
+                .map(|task| Mutex::new((task.name.as_str(), &mut task.function)))
+                .collect(),
+            enabled,
+            remaining: stage
+                .dependency_counts
+                .iter()
+                .map(|&count| AtomicUsize::new(count))
+                .collect(),
+            dependents: &stage.dependents,
+            g_state,
+        };
+
+        rayon::in_place_scope(|scope| {
+            for (ix, &count) in stage.dependency_counts.iter().enumerate() {
+                if count == 0 {
+                    let ctx = &ctx;
+                    scope.spawn(move |scope| ctx.run(scope, ix));
+                }
+            }
+        });
+    }
+}
+
+/// Dependencies between the tasks of a single stage, indices are relative to the start of the stage
+struct StageGraph {
+    name: String,
+    range: Range<usize>,
+    dependents: Vec<Vec<usize>>,
+    dependency_counts: Vec<usize>,
+    /// Predecessors of every task and why it has to wait for them, only used for introspection
+    dependencies: Vec<Vec<Dependency>>,
+}
+
+impl StageGraph {
+    /// `functions` must be in topological order so that every explicit edge and every
+    /// implicit edge created due to a borrow conflict points forward
+    fn new<Return>(
+        name: String,
+        range: Range<usize>,
+        tasks: &[ScheduledTask<Return>],
+        edges: &HashMap<String, HashSet<String>>,
+    ) -> Self {
+        let n = tasks.len();
+        let mut dependents = vec![Vec::new(); n];
+        let mut dependency_counts = vec![0; n];
+        let mut dependencies = vec![Vec::new(); n];
+
+        for (i, task_i) in tasks.iter().enumerate() {
+            let successors = edges.get(&task_i.name);
+
+            for (j, task_j) in tasks.iter().enumerate().skip(i + 1) {
+                let ordered = successors.is_some_and(|successors| successors.contains(&task_j.name));
+
+                let kind = if ordered {
+                    DependencyKind::Ordering
+                } else if task_i.function.conflicts_with(&task_j.function) {
+                    DependencyKind::Conflict
+                } else {
+                    continue;
+                };
+
+                dependents[i].push(j);
+                dependency_counts[j] += 1;
+                dependencies[j].push(Dependency { task: i, kind });
+            }
+        }
+
+        Self {
+            name,
+            range,
+            dependents,
+            dependency_counts,
+            dependencies,
+        }
+    }
+}
+
+struct ParallelContext<'a, Return> {
+    functions: Vec<Mutex<(&'a str, &'a mut Function<Return>)>>,
+    /// Disabled tasks are skipped, but still unblock their dependents
+    enabled: Vec<bool>,
+    remaining: Vec<AtomicUsize>,
+    dependents: &'a [Vec<usize>],
+    g_state: Pin<&'a UnsafeGlobalState>,
+}
+
+impl<'a, Return> ParallelContext<'a, Return> {
+    fn run<'s>(&'s self, scope: &rayon::Scope<'s>, ix: usize) {
+        if self.enabled[ix] {
+            let mut guard = self.functions[ix].lock().unwrap();
+            let (_name, function) = &mut *guard;
+            hikari_dev::profile_scope!(_name);
+
+            // Safety: Tasks which run concurrently never have conflicting borrows
+            unsafe {
+                function.run(self.g_state);
+            }
+        }
+
+        for &dependent in &self.dependents[ix] {
+            if self.remaining[dependent].fetch_sub(1, Ordering::AcqRel) == 1 {
+                scope.spawn(move |scope| self.run(scope, dependent));
+            }
+        }
     }
 }
 
@@ -94,12 +454,16 @@ impl<Return> Stage<Return> {
 }
 
 pub struct ScheduleBuilder<Return> {
-    stages: Vec<Stage<Return>>
+    stages: Vec<Stage<Return>>,
+    sets: Vec<TaskSet>,
 }
 
 impl<Return> ScheduleBuilder<Return> {
     pub fn new() -> Self {
-        Self { stages: Vec::new() }
+        Self {
+            stages: Vec::new(),
+            sets: Vec::new(),
+        }
     }
     pub fn create_stage(&mut self, name: &str) -> &mut Self {
         if self.stages.iter().find(|st| st.name == name).is_none() {
@@ -123,10 +487,22 @@ impl<Return> ScheduleBuilder<Return> {
 
         self
     }
+    /// Sets the ordering and run conditions of a set, which apply to the tasks of the set in every stage
+    pub fn configure_set(&mut self, set: TaskSet) -> &mut Self {
+        if self.sets.iter().any(|existing| existing.name == set.name) {
+            panic!("Set with name {} is already configured", set.name);
+        }
+        self.sets.push(set);
+
+        self
+    }
     fn validate(&self) -> Result<(), String> {
         for stage in &self.stages {
             stage.validate()?;
         }
+        for set in &self.sets {
+            set.validate()?;
+        }
 
         for stage in &self.stages {
             let mut task_names: HashSet<String> = HashSet::new();
@@ -140,53 +516,127 @@ impl<Return> ScheduleBuilder<Return> {
                     task_names.insert(task.name.clone());
                 }
             }
+
+            let clash = stage.tasks.iter().flat_map(|task| &task.sets).find(|&set| task_names.contains(set));
+            if let Some(set) = clash {
+                return Err(format!("Set {:?} has the same name as a task in stage {:?}", set, stage.name));
+            }
         }
 
         Ok(())
     }
-    fn build_graph(mut tasks: Vec<Task<Return>>) -> TaskGraph<Return> {
+    fn build_graph(tasks: Vec<Task<Return>>, sets: &[TaskSet]) -> TaskGraph<Return> {
+        // Orderings can name either a task or a set, which stands for all of its tasks in this stage
+        let mut members: HashMap<String, Vec<String>> = HashMap::new();
+        for task in &tasks {
+            members.entry(task.name.clone()).or_default().push(task.name.clone());
+            for set in &task.sets {
+                members.entry(set.clone()).or_default().push(task.name.clone());
+            }
+        }
+
         let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut add_edges = |from: &str, to: &str| {
+            let (Some(from_nodes), Some(to_nodes)) = (members.get(from), members.get(to)) else {
+                return;
+            };
+
+            for from_node in from_nodes {
+                for to_node in to_nodes.iter().filter(|&to_node| to_node != from_node) {
+                    edges
+                        .entry(from_node.clone())
+                        .or_default()
+                        .insert(to_node.clone());
+                }
+            }
+        };
 
         for task in &tasks {
             for from_node in &task.after {
-                edges
-                    .entry(from_node.clone())
-                    .or_default()
-                    .insert(task.name().to_string());
+                add_edges(from_node, &task.name);
             }
 
             for to_node in &task.before {
-                edges
-                    .entry(task.name().to_string())
-                    .or_default()
-                    .insert(to_node.to_string());
+                add_edges(&task.name, to_node);
+            }
+        }
+        for set in sets {
+            for from_node in &set.after {
+                add_edges(from_node, &set.name);
+            }
+
+            for to_node in &set.before {
+                add_edges(&set.name, to_node);
             }
         }
 
         let nodes = tasks
-            .drain(..)
+            .into_iter()
             .map(|task| (task.name().to_string(), task))
             .collect();
 
         TaskGraph { nodes, edges }
     }
-    pub fn build(self) -> Result<Schedule<Return>, String> {
+    pub fn build(mut self) -> Result<Schedule<Return>, String> {
         self.validate()?;
 
-        let mut functions = Vec::new();
+        let mut sets = Vec::new();
+        let mut set_ixs = HashMap::new();
+        for set in &mut self.sets {
+            set_ixs.insert(set.name.clone(), sets.len());
+            sets.push(ScheduledSet {
+                name: set.name.clone(),
+                conditions: std::mem::take(&mut set.conditions),
+            });
+        }
+
+        let mut scheduled = Vec::new();
+        let mut stages = Vec::new();
         print!("Exec order: ");
         for stage in self.stages {
-            let graph = Self::build_graph(stage.tasks);
-            let mut tasks = graph.into_topological_order();
-
-            tasks.drain(..).for_each(|task| {
+            let graph = Self::build_graph(stage.tasks, &self.sets);
+            let edges = graph.edges.clone();
+            let tasks = graph
+                .into_topological_order()
+                .map_err(|cycle| format!("Tasks in stage {:?} depend on each other: {}", stage.name, cycle.join(" -> ")))?;
+
+            let start = scheduled.len();
+            tasks.into_iter().for_each(|task| {
                 print!("{} ", task.name());
-                functions.push((task.name, task.function));
+
+                // Sets which were never configured still exist, so they can be disabled
+                let task_sets = task
+                    .sets
+                    .into_iter()
+                    .map(|set| {
+                        *set_ixs.entry(set.clone()).or_insert_with(|| {
+                            sets.push(ScheduledSet {
+                                name: set,
+                                conditions: Vec::new(),
+                            });
+                            sets.len() - 1
+                        })
+                    })
+                    .collect();
+
+                scheduled.push(ScheduledTask {
+                    name: task.name,
+                    function: task.function,
+                    conditions: task.conditions,
+                    sets: task_sets,
+                });
             });
+            let range = start..scheduled.len();
+
+            stages.push(StageGraph::new(stage.name, range.clone(), &scheduled[range], &edges));
         }
         println!();
 
-        Ok(Schedule { functions })
+        Ok(Schedule {
+            tasks: scheduled,
+            sets,
+            stages,
+        })
     }
 }
 
@@ -195,34 +645,52 @@ struct TaskGraph<Return> {
     edges: HashMap<String, HashSet<String>>,
 }
 impl<Return> TaskGraph<Return> {
+    /// Depth first search which pushes nodes after all of their successors.
+    /// Returns the nodes forming a cycle if one is found
     fn topo_sort_(
         node_name: &str,
         visited: &mut HashSet<String>,
+        path: &mut Vec<String>,
         adj_list: &HashMap<String, HashSet<String>>,
         stack: &mut Vec<String>,
-    ) {
+    ) -> Result<(), Vec<String>> {
         visited.insert(node_name.to_string());
+        path.push(node_name.to_string());
 
         if let Some(connected_nodes) = adj_list.get(node_name) {
             for node_name in connected_nodes {
+                if let Some(start) = path.iter().position(|on_path| on_path == node_name) {
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(node_name.clone());
+                    return Err(cycle);
+                }
                 if !visited.contains(node_name) {
-                    Self::topo_sort_(node_name, visited, adj_list, stack);
+                    Self::topo_sort_(node_name, visited, path, adj_list, stack)?;
                 }
             }
         }
 
+        path.pop();
         stack.push(node_name.to_string());
+        Ok(())
     }
-    fn into_topological_order(mut self) -> Vec<Task<Return>> {
+    /// Orders the tasks so that every task comes after the tasks it depends on.
+    /// Fails with the names of the tasks forming a cycle, the first name is repeated at the end
+    fn into_topological_order(mut self) -> Result<Vec<Task<Return>>, Vec<String>> {
         let mut order = Vec::new();
 
         let mut visited = HashSet::new();
 
         let adj_list = &self.edges;
 
-        for node in self.nodes.values() {
-            if !visited.contains(node.name()) {
-                Self::topo_sort_(node.name(), &mut visited, &adj_list, &mut order)
+        // Visiting in a fixed order keeps the execution order stable between runs,
+        // independent tasks end up sorted by name as the order is reversed at the end
+        let mut names: Vec<&String> = self.nodes.keys().collect();
+        names.sort_by(|a, b| b.cmp(a));
+
+        for name in names {
+            if !visited.contains(name) {
+                Self::topo_sort_(name, &mut visited, &mut Vec::new(), adj_list, &mut order)?;
             }
         }
 
@@ -234,7 +702,7 @@ impl<Return> TaskGraph<Return> {
             topo.push(self.nodes.remove(&node_name).unwrap());
         }
 
-        topo
+        Ok(topo)
     }
 }
 
@@ -280,4 +748,270 @@ mod tests {
             schedule.execute(&mut global);
         }
     }
+
+    #[test]
+    fn parallel_respects_ordering() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Only the counter is shared and it is borrowed immutably, so the tasks don't conflict
+        struct Sequence(AtomicUsize);
+        struct FirstRuns(Vec<usize>);
+        struct SecondRuns(Vec<usize>);
+        struct ThirdRuns(Vec<usize>);
+
+        fn first(sequence: &Sequence, runs: &mut FirstRuns) {
+            runs.0.push(sequence.0.fetch_add(1, Ordering::SeqCst));
+        }
+        fn second(sequence: &Sequence, runs: &mut SecondRuns) {
+            runs.0.push(sequence.0.fetch_add(1, Ordering::SeqCst));
+        }
+        fn third(sequence: &Sequence, runs: &mut ThirdRuns) {
+            runs.0.push(sequence.0.fetch_add(1, Ordering::SeqCst));
+        }
+
+        let mut global = StateBuilder::new();
+        global.add_state(Sequence(AtomicUsize::new(0)));
+        global.add_state(FirstRuns(Vec::new()));
+        global.add_state(SecondRuns(Vec::new()));
+        global.add_state(ThirdRuns(Vec::new()));
+        let mut global = global.build();
+
+        let mut schedule = Schedule::new();
+        schedule.create_stage("Update");
+        schedule.create_stage("Render");
+
+        schedule.add_task("Render", Task::new("Third", &third));
+        schedule.add_task("Update", Task::new("Second", &second).after("First"));
+        schedule.add_task("Update", Task::new("First", &first));
+
+        let mut schedule = schedule.build().unwrap();
+
+        for _ in 0..50 {
+            schedule.execute_parallel(&mut global);
+        }
+
+        let first = global.get::<FirstRuns>().unwrap();
+        let second = global.get::<SecondRuns>().unwrap();
+        let third = global.get::<ThirdRuns>().unwrap();
+        assert_eq!(first.0.len(), 50);
+        for frame in 0..50 {
+            assert_eq!(
+                [first.0[frame], second.0[frame], third.0[frame]],
+                [frame * 3, frame * 3 + 1, frame * 3 + 2]
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_repeats_stages() {
+        fn count(counter: &mut Vec<&'static str>) {
+            counter.push("Fixed");
+        }
+        fn render(counter: &mut Vec<&'static str>) {
+            counter.push("Render");
+        }
+
+        let mut global = StateBuilder::new();
+        global.add_state(Vec::<&'static str>::new());
+        let mut global = global.build();
+
+        let mut schedule = Schedule::new();
+        schedule.create_stage("Fixed");
+        schedule.create_stage("Render");
+        schedule.add_task("Fixed", Task::new("Count", &count));
+        schedule.add_task("Render", Task::new("Render", &render));
+
+        let mut schedule = schedule.build().unwrap();
+
+        for repetitions in [3, 0] {
+            schedule.execute_parallel_with(&mut global, |stage, _| if stage == "Fixed" { repetitions } else { 1 });
+        }
+
+        let order = global.get::<Vec<&'static str>>().unwrap();
+        assert_eq!(*order, ["Fixed", "Fixed", "Fixed", "Render", "Render"]);
+    }
+
+    #[test]
+    fn conditions_and_sets() {
+        use crate::{TaskControl, TaskSet};
+
+        struct Playing(bool);
+
+        fn physics(order: &mut Vec<&'static str>) {
+            order.push("Physics");
+        }
+        fn scripts(order: &mut Vec<&'static str>) {
+            order.push("Scripts");
+        }
+        fn gizmos(order: &mut Vec<&'static str>) {
+            order.push("Gizmos");
+        }
+
+        let mut global = StateBuilder::new();
+        global.add_state(Vec::<&'static str>::new());
+        global.add_state(Playing(false));
+        global.add_state(TaskControl::default());
+        let mut global = global.build();
+
+        let mut schedule = Schedule::new();
+        schedule.create_stage("Update");
+        schedule.configure_set(
+            TaskSet::new("Gameplay")
+                .after("Gizmos")
+                .run_if(|playing: &Playing| playing.0),
+        );
+        schedule.add_task("Update", Task::new("Scripts", scripts).in_set("Gameplay").after("Physics"));
+        schedule.add_task("Update", Task::new("Physics", physics).in_set("Gameplay"));
+        schedule.add_task("Update", Task::new("Gizmos", gizmos));
+
+        let mut schedule = schedule.build().unwrap();
+
+        schedule.execute_parallel(&mut global);
+        global.get_mut::<Playing>().unwrap().0 = true;
+        schedule.execute_parallel(&mut global);
+        global.get_mut::<TaskControl>().unwrap().disable("Physics");
+        schedule.execute(&mut global);
+        global.get_mut::<TaskControl>().unwrap().disable("Gameplay");
+        schedule.execute_parallel(&mut global);
+
+        let order = global.get::<Vec<&'static str>>().unwrap();
+        assert_eq!(
+            *order,
+            ["Gizmos", "Gizmos", "Physics", "Scripts", "Gizmos", "Scripts", "Gizmos"]
+        );
+    }
+
+    #[test]
+    fn conditions_are_evaluated_per_stage() {
+        struct Playing(bool);
+
+        fn start(playing: &mut Playing) {
+            playing.0 = true;
+        }
+        fn scripts(counter: &mut usize) {
+            *counter += 1;
+        }
+
+        let mut global = StateBuilder::new();
+        global.add_state(0usize);
+        global.add_state(Playing(false));
+        let mut global = global.build();
+
+        let mut schedule = Schedule::new();
+        schedule.create_stage("Update");
+        schedule.add_task("Update", Task::new("Start", start));
+        schedule.add_task(
+            "Update",
+            Task::new("Scripts", scripts)
+                .after("Start")
+                .run_if(|playing: &Playing| playing.0),
+        );
+        let mut schedule = schedule.build().unwrap();
+
+        // Both ways of running the schedule only see the state from before the stage
+        schedule.execute(&mut global);
+        assert_eq!(*global.get::<usize>().unwrap(), 0);
+
+        global.get_mut::<Playing>().unwrap().0 = false;
+        schedule.execute_parallel(&mut global);
+        assert_eq!(*global.get::<usize>().unwrap(), 0);
+
+        schedule.execute(&mut global);
+        assert_eq!(*global.get::<usize>().unwrap(), 1);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        fn noop() {}
+
+        let mut schedule = Schedule::<()>::new();
+        schedule.create_stage("Update");
+        schedule.add_task("Update", Task::new("A", noop).after("C"));
+        schedule.add_task("Update", Task::new("B", noop).after("A"));
+        schedule.add_task("Update", Task::new("C", noop).after("B"));
+
+        let err = schedule.build().err().unwrap();
+        assert!(err.contains("\"Update\""));
+        for name in ["A", "B", "C"] {
+            assert!(err.contains(name), "{}", err);
+        }
+    }
+
+    #[test]
+    fn schedule_info() {
+        use crate::info::{BorrowInfo, Dependency, DependencyKind};
+
+        fn read(_x: &f32) {}
+        fn write(_x: &mut f32) {}
+        fn other(_y: &i32) {}
+
+        let mut schedule = Schedule::<()>::new();
+        schedule.create_stage("Update");
+        schedule.add_task("Update", Task::new("Write", write));
+        schedule.add_task("Update", Task::new("Read", read).after("Other"));
+        schedule.add_task("Update", Task::new("Other", other));
+
+        let info = schedule.build().unwrap().info();
+        let stage = &info.stages[0];
+        let names: Vec<_> = stage.tasks.iter().map(|task| task.name.as_str()).collect();
+        assert_eq!(names, ["Other", "Read", "Write"]);
+
+        assert_eq!(
+            stage.tasks[2].borrows,
+            [BorrowInfo {
+                type_name: "f32",
+                mutable: true
+            }]
+        );
+        assert_eq!(
+            stage.tasks[1].dependencies,
+            [Dependency {
+                task: 0,
+                kind: DependencyKind::Ordering
+            }]
+        );
+        assert_eq!(
+            stage.tasks[2].dependencies,
+            [Dependency {
+                task: 1,
+                kind: DependencyKind::Conflict
+            }]
+        );
+
+        let dot = info.to_dot();
+        assert!(dot.contains("\"Update/Other\" -> \"Update/Read\" [style=solid]"));
+        assert!(dot.contains("\"Update/Read\" -> \"Update/Write\" [style=dashed]"));
+    }
+
+    #[test]
+    fn parallel_runs_concurrently() {
+        use std::sync::Barrier;
+
+        // Both tasks wait on the barrier, so this only finishes if they run at the same time
+        fn wait_a(barrier: &Barrier, _x: &f32) {
+            barrier.wait();
+        }
+        fn wait_b(barrier: &Barrier, _x: &f32) {
+            barrier.wait();
+        }
+
+        let mut global = StateBuilder::new();
+        global.add_state(Barrier::new(2));
+        global.add_state(69_f32);
+        let mut global = global.build();
+
+        let mut schedule = Schedule::new();
+        schedule.create_stage("Update");
+        schedule.add_task("Update", Task::new("A", &wait_a));
+        schedule.add_task("Update", Task::new("B", &wait_b));
+
+        let mut schedule = schedule.build().unwrap();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+
+        pool.install(|| schedule.execute_parallel(&mut global));
+    }
 }
@@ -0,0 +1,33 @@
+#ifndef CLUSTER_GLSL
+#define CLUSTER_GLSL
+
+// Must match the constants in passes/light_culling.rs
+#define CLUSTER_X 16
+#define CLUSTER_Y 9
+#define CLUSTER_Z 24
+#define N_CLUSTERS (CLUSTER_X * CLUSTER_Y * CLUSTER_Z)
+#define MAX_LIGHTS_PER_CLUSTER 64
+
+// Clusters are sliced logarithmically in view space depth between depthRange.x and depthRange.y
+float getClusterSliceDepth(uint slice, vec2 depthRange) {
+    return depthRange.x * pow(depthRange.y / depthRange.x, float(slice) / float(CLUSTER_Z));
+}
+uint getClusterSlice(float viewZ, vec2 depthRange) {
+    float slice = log(max(viewZ, depthRange.x) / depthRange.x) / log(depthRange.y / depthRange.x) * float(CLUSTER_Z);
+    return uint(clamp(slice, 0.0, float(CLUSTER_Z - 1)));
+}
+uint getClusterIndex(uvec3 cluster) {
+    return cluster.x + cluster.y * CLUSTER_X + cluster.z * CLUSTER_X * CLUSTER_Y;
+}
+// Screen tiles are laid out from the top left corner of the screen
+uvec2 getClusterTile(vec2 ndc) {
+    vec2 uv = vec2(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    uvec2 tile = uvec2(clamp(uv * vec2(CLUSTER_X, CLUSTER_Y), vec2(0.0), vec2(CLUSTER_X - 1, CLUSTER_Y - 1)));
+    return tile;
+}
+vec2 getClusterTileNDC(vec2 tile) {
+    vec2 uv = tile / vec2(CLUSTER_X, CLUSTER_Y);
+    return vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+}
+
+#endif
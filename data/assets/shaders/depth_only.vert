@@ -2,11 +2,14 @@
 
 #include <world.glsl>
 #include <forward_pass_global_set.glsl>
+#include <skinning.glsl>
 
 layout(location = 0) in vec3 position;
+layout(location = 1) in uvec4 joints;
+layout(location = 2) in vec4 weights;
 
 void main() {
-    mat4 transform = perInstanceData[gl_InstanceIndex].transform;
+    mat4 transform = perInstanceData[gl_InstanceIndex].transform * skinMatrix(gl_InstanceIndex, joints, weights);
     vec3 worldPosition = vec3(transform * vec4(position, 1.0));
     gl_Position = world.viewProj * vec4(worldPosition, 1.0);
 }
\ No newline at end of file
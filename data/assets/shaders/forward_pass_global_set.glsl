@@ -12,6 +12,16 @@ layout(std140, set = 1, binding = 1) readonly buffer InstanceSSBO {
     PerInstanceData perInstanceData[];
 };
 
+layout(std140, set = 1, binding = 2) readonly buffer LocalLightSSBO {
+    LocalLight localLights[];
+};
+layout(std140, set = 1, binding = 3) readonly buffer LocalShadowSSBO {
+    LocalShadowTile localShadowTiles[];
+};
+layout(std140, set = 1, binding = 4) readonly buffer JointSSBO {
+    mat4 jointPalette[];
+};
+
 layout(push_constant) uniform Constants {
     mat4 transform;
     MaterialInputs mat;
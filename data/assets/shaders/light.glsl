@@ -30,6 +30,28 @@ struct DirectionalLight {
     ShadowCascade cascades[N_CASCADES];
 };
 
+#define LOCAL_LIGHT_POINT 0
+#define LOCAL_LIGHT_SPOT 1
+
+struct LocalLight {
+    vec3 position;
+    float range;
+    vec3 color;
+    float intensity;
+    vec3 direction;
+    uint kind;
+    float cosInnerCone;
+    float cosOuterCone;
+    int shadowTile;
+    float normalBias;
+};
+struct LocalShadowTile {
+    mat4 viewProj;
+    vec2 atlasUVOffset;
+    vec2 atlasSizeRatio;
+    float mapTexelSize;
+};
+
 struct LightInfo {
     float intensity;
     vec3 color;
@@ -9,12 +9,14 @@
 #include <pcss.glsl>
 #include <tonemap.glsl>
 #include <utils.glsl>
+#include <cluster.glsl>
 #include <forward_pass_global_set.glsl>
 layout(location = 0) in vec3 worldPosition;
 layout(location = 1) in vec3 normalFs;
 layout(location = 2) in vec2 tc0Fs;
 layout(location = 3) in vec2 tc1Fs;
 layout(location = 4) in vec3 viewPosition;
+layout(location = 5) in vec4 tangentFs;
 
 layout(location = 0) out vec4 outColor;
 
@@ -22,6 +24,13 @@ layout(std140, set = 2, binding = 0) readonly buffer cascadeRenderInfoSSBO {
     CascadeRenderInfo cascades[];
 };
 layout(set = 2, binding = 1) uniform sampler2D shadowMap;
+layout(set = 2, binding = 2) uniform sampler2D localShadowMap;
+layout(std430, set = 2, binding = 3) readonly buffer LightClusterSSBO {
+    vec2 clusterDepthRange;
+    vec2 _pad;
+    uint clusterLightCounts[N_CLUSTERS];
+    uint clusterLightIndices[];
+};
 
 #define ALBEDO_OFFSET 0
 #define ROUGHNESS_OFFSET 1
@@ -40,7 +49,7 @@ Surface getSurface() {
 
     vec3 normal;
     if(mat.normalIx > 0) {
-        normal = getNormalScreen(normalFs, worldPosition, uv, GLOBAL_TEXTURES(mat.normalIx));
+        normal = getNormalTangent(normalFs, tangentFs, uv, GLOBAL_TEXTURES(mat.normalIx));
     } else
      {
         normal = normalize(normalFs);
@@ -117,6 +126,96 @@ float getDirectionalShadow(Surface surface, LightInfo lightInfo, out uint cascad
 
     return mix(1.0, shadow, intensity);
 }
+// Windowed inverse square falloff, reaches zero at the light's range
+// Karis 2013, "Real Shading in Unreal Engine 4"
+float getRangeAttenuation(float distance, float range) {
+    float distanceSq = distance * distance;
+    float ratio = distance / max(range, 0.0001);
+    float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / max(distanceSq, 0.0001);
+}
+float getSpotAttenuation(LocalLight light, vec3 lightToSurface) {
+    float cd = dot(normalize(light.direction), lightToSurface);
+    return smoothstep(light.cosOuterCone, light.cosInnerCone, cd);
+}
+// Matches the face order used when rendering point light shadows: +X, -X, +Y, -Y, +Z, -Z
+uint getCubeFace(vec3 v) {
+    vec3 a = abs(v);
+    if(a.x >= a.y && a.x >= a.z) {
+        return v.x > 0.0 ? 0 : 1;
+    }
+    if(a.y >= a.z) {
+        return v.y > 0.0 ? 2 : 3;
+    }
+    return v.z > 0.0 ? 4 : 5;
+}
+float getLocalShadow(Surface surface, LightInfo lightInfo, LocalLight light, vec3 lightToSurface) {
+    uint tileIx = uint(light.shadowTile);
+    if(light.kind == LOCAL_LIGHT_POINT) {
+        tileIx += getCubeFace(lightToSurface);
+    }
+
+    LocalShadowTile tile = localShadowTiles[tileIx];
+    ShadowInfo shadowInfo = getShadowInfo(surface,
+                                lightInfo,
+                                tile.viewProj,
+                                tile.atlasUVOffset,
+                                tile.atlasSizeRatio,
+                                tile.mapTexelSize,
+                                light.normalBias);
+
+    return PCFWitness3x3(localShadowMap, shadowInfo);
+}
+uint getClusterIndex(Surface surface) {
+    vec4 clipPosition = world.viewProj * vec4(surface.worldPosition, 1.0);
+    uvec2 tile = getClusterTile(clipPosition.xy / clipPosition.w);
+    uint slice = getClusterSlice(surface.viewPosition.z, clusterDepthRange);
+
+    return getClusterIndex(uvec3(tile, slice));
+}
+vec3 getLocalLights(Surface surface, PBRMaterialParameters materialParams) {
+    vec3 color = vec3(0.0);
+
+    uint clusterIndex = getClusterIndex(surface);
+    uint offset = clusterIndex * MAX_LIGHTS_PER_CLUSTER;
+    uint count = clusterLightCounts[clusterIndex];
+
+    for(uint i = 0; i < count; i++) {
+        LocalLight light = localLights[clusterLightIndices[offset + i]];
+
+        vec3 lightToSurface = surface.worldPosition - light.position;
+        float distance = length(lightToSurface);
+        if(distance >= light.range) {
+            continue;
+        }
+        lightToSurface /= max(distance, 0.0001);
+
+        float attenuation = getRangeAttenuation(distance, light.range);
+        if(light.kind == LOCAL_LIGHT_SPOT) {
+            attenuation *= getSpotAttenuation(light, lightToSurface);
+        }
+        if(attenuation <= 0.0) {
+            continue;
+        }
+
+        LightInfo lightInfo = LightInfo(light.intensity * attenuation, light.color, lightToSurface, light.shadowTile >= 0 ? 1 : 0);
+
+        float shadow = lightInfo.castShadows == 1 ? getLocalShadow(surface, lightInfo, light, lightToSurface) : 1.0;
+        color += BRDF(surface, lightInfo, materialParams, GLOBAL_TEXTURES_CUBE(world.envMapIrradianceIx)) * shadow;
+    }
+
+    return color;
+}
+// Blue for clusters with a single light, through green and yellow to red for full clusters
+vec3 lightClusterDebug(uint count) {
+    if(count == 0) {
+        return vec3(0.0);
+    }
+    float t = clamp(float(count) / float(MAX_LIGHTS_PER_CLUSTER / 4), 0.0, 1.0);
+    vec3 low = mix(vec3(0.0, 0.0, 1.0), vec3(0.0, 1.0, 0.0), clamp(t * 2.0, 0.0, 1.0));
+    vec3 high = mix(vec3(1.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0), clamp(t * 2.0 - 1.0, 0.0, 1.0));
+    return t < 0.5 ? low : high;
+}
 vec3 shadowCascadeDebug(uint cascadeIndex) {
     switch(cascadeIndex) {
         		case 0:
@@ -146,6 +245,7 @@ void main() {
     float shadow = dirLightInfo.castShadows == 1 ? getDirectionalShadow(surface, dirLightInfo, cascadeIndex) : 1.0;
 
     color = direct * shadow;
+    color += getLocalLights(surface, materialParams);
     
     vec3 indirect = IBL(
                     materialParams, 
@@ -172,5 +272,10 @@ void main() {
     //color = color * (1.0f / tonemapACES(vec3(11.2f)));
     color = tonemapFilmic(color);
     //color = pow(color, vec3(1.0/2.2));
+
+#ifdef DEBUG_LIGHT_CLUSTERS
+    uint clusterCount = clusterLightCounts[getClusterIndex(surface)];
+    color = mix(color, lightClusterDebug(clusterCount), clusterCount > 0 ? 0.6 : 0.0);
+#endif
     outColor = vec4(color, 1.0);
 }
\ No newline at end of file
@@ -2,25 +2,34 @@
 #include <world.glsl>
 #include <material.glsl>
 #include <forward_pass_global_set.glsl>
+#include <skinning.glsl>
 
 layout(location = 0) in vec3 position;
 layout(location = 1) in vec3 normal;
 layout(location = 2) in vec2 tc0;
 layout(location = 3) in vec2 tc1;
+layout(location = 4) in vec4 tangent;
+layout(location = 5) in uvec4 joints;
+layout(location = 6) in vec4 weights;
 
 layout(location = 0) out vec3 worldPosition;
 layout(location = 1) out vec3 normalFs;
 layout(location = 2) out vec2 tc0Fs;
 layout(location = 3) out vec2 tc1Fs;
 layout(location = 4) out vec3 viewPosition;
+layout(location = 5) out vec4 tangentFs;
 
 void main() {
-    mat4 transform = perInstanceData[gl_InstanceIndex].transform;
+    mat4 transform = perInstanceData[gl_InstanceIndex].transform * skinMatrix(gl_InstanceIndex, joints, weights);
 
     vec4 transPos = transform * vec4(position, 1.0);
     worldPosition = transPos.xyz;
     normalFs = mat3(transpose(inverse(transform))) * normal;
 
+    // Mirroring transforms flip the handedness of the tangent space
+    float handedness = determinant(mat3(transform)) < 0.0 ? -1.0 : 1.0;
+    tangentFs = vec4(mat3(transform) * tangent.xyz, tangent.w * handedness);
+
     tc0Fs = tc0;
     tc1Fs = tc1;
 
@@ -3,8 +3,11 @@
 #include <light.glsl>
 #include <world.glsl>
 #include <forward_pass_global_set.glsl>
+#include <skinning.glsl>
 
 layout(location = 0) in vec3 position;
+layout(location = 1) in uvec4 joints;
+layout(location = 2) in vec4 weights;
 
 layout(std140, set = 2, binding = 0) readonly buffer cascadeRenderInfoSSBO {
     CascadeRenderInfo cascades[];
@@ -12,9 +15,14 @@ layout(std140, set = 2, binding = 0) readonly buffer cascadeRenderInfoSSBO {
 
 
 void main() {
+    mat4 transform = perInstanceData[gl_InstanceIndex].transform * skinMatrix(gl_InstanceIndex, joints, weights);
+#ifdef LOCAL_LIGHT
+    uint tileIx = pc.mat.uvSet;
+    gl_Position = localShadowTiles[tileIx].viewProj * transform * vec4(position, 1.0);
+#else
     uint cascadeIx = pc.mat.uvSet;
-    mat4 transform = perInstanceData[gl_InstanceIndex].transform;
     gl_Position = cascades[cascadeIx].viewProj * transform * vec4(position, 1.0);
+#endif
     
     //Pancaking
     //https://www.gamedev.net/forums/topic/639036-shadow-mapping-and-high-up-objects/
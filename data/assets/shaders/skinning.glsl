@@ -0,0 +1,21 @@
+#ifndef SKINNING_GLSL
+#define SKINNING_GLSL
+
+#include <forward_pass_global_set.glsl>
+
+const uint NO_JOINTS = 0xFFFFFFFFu;
+
+// Blends the joint palette of the instance, identity for meshes without a skin
+mat4 skinMatrix(uint instanceIx, uvec4 joints, vec4 weights) {
+    uint jointOffset = perInstanceData[instanceIx].jointOffset;
+    if (jointOffset == NO_JOINTS) {
+        return mat4(1.0);
+    }
+
+    return weights.x * jointPalette[jointOffset + joints.x] +
+           weights.y * jointPalette[jointOffset + joints.y] +
+           weights.z * jointPalette[jointOffset + joints.z] +
+           weights.w * jointPalette[jointOffset + joints.w];
+}
+
+#endif
@@ -20,6 +20,20 @@ vec3 getNormalScreen(const vec3 normal, const vec3 worldPosition, const vec2 uv,
     }
 	return normalize(TBN * tangentNormal);
 }
+// Tangent space normal mapping with per vertex tangents, w holds the handedness of the bitangent
+vec3 getNormalTangent(const vec3 normal, const vec4 tangent, const vec2 uv, in sampler2D normalMap) {
+	vec3 tangentNormal = texture(normalMap, uv).xyz * 2.0 - 1.0;
+
+	vec3 N = normalize(normal);
+	// Re-orthogonalize as the interpolated vectors drift apart
+	vec3 T = normalize(tangent.xyz - N * dot(N, tangent.xyz));
+	vec3 B = cross(N, T) * tangent.w;
+	mat3 TBN = mat3(T, B, N);
+    if (!gl_FrontFacing) {
+            tangentNormal *= -1.0;
+    }
+	return normalize(TBN * tangentNormal);
+}
 #endif
 // mat3 cotangentFrame( vec3 N, vec3 p, vec2 uv )
 // {
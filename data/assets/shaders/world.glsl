@@ -20,11 +20,13 @@ struct World {
     uint BRDFLutIx;
     DirectionalLight dirLight;
     uint showCascades;
+    uint nLocalLights;
 };
 
 
 struct PerInstanceData {
     mat4 transform;
+    uint jointOffset;
 };
 
 #endif
\ No newline at end of file
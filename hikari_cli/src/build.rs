@@ -1,3 +1,876 @@
-pub fn run(_release: bool) -> anyhow::Result<()> {
-    todo!()
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use fs_extra::dir::CopyOptions;
+use hikari_3d::{IMPORTED_SCENE_EXTENSIONS, IMPORTED_TEXTURE_EXTENSIONS, SUPPORTED_ENV_TEXTURE_EXTENSIONS};
+use hikari_asset::{PhysicalIO, ARTIFACT_DIR};
+use hikari_editor::{Project, PROJECT_EXTENSION};
+use hikari_runtime::{GameDescription, GAME_DESCRIPTION_FILE};
+use serde_yaml::Value;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+const ASSET_DB_FILENAME: &str = "assets.db";
+const BINARY_WORLD_EXTENSION: &str = "hworldb";
+const OUTPUT_ASSET_DIR: &str = "assets";
+
+#[cfg(target_os = "linux")]
+const RUNTIME_BINARY: &str = "hikari_runtime";
+#[cfg(target_os = "windows")]
+const RUNTIME_BINARY: &str = "hikari_runtime.exe";
+
+struct DbRecord {
+    path: PathBuf,
+    /// The record exactly as it appears in the asset database, settings are kept opaque
+    entry: Value,
+}
+
+/// A read only view of the asset database which does not require the asset types to be registered
+struct AssetDatabase {
+    records: Vec<DbRecord>,
+    uuid_to_record: HashMap<Uuid, usize>,
+}
+impl AssetDatabase {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut db = Self {
+            records: Vec::new(),
+            uuid_to_record: HashMap::new(),
+        };
+
+        if !path.is_file() {
+            log::warn!("No asset database found at {}", path.display());
+            return Ok(db);
+        }
+
+        let entries: Vec<Value> = serde_yaml::from_reader(std::fs::File::open(path)?)?;
+        for entry in entries {
+            let uuid = entry
+                .get("uuid")
+                .and_then(Value::as_str)
+                .and_then(|uuid| Uuid::parse_str(uuid).ok());
+            let path = entry.get("path").and_then(Value::as_str).map(PathBuf::from);
+
+            let (Some(uuid), Some(path)) = (uuid, path) else {
+                return Err(anyhow::anyhow!("Malformed record in asset database: {:?}", entry));
+            };
+
+            db.uuid_to_record.insert(uuid, db.records.len());
+            db.records.push(DbRecord { path, entry });
+        }
+
+        Ok(db)
+    }
+    fn uuid_to_path(&self, uuid: &Uuid) -> Option<&Path> {
+        self.uuid_to_record
+            .get(uuid)
+            .map(|&ix| self.records[ix].path.as_path())
+    }
+    /// Hash of the import settings of the asset at `path`, which selects its artifact.
+    /// Settings are stored under the uuid of their type, next to the uuid and path of the asset
+    fn settings_hash(&self, path: &Path) -> Option<u64> {
+        let record = self.records.iter().find(|record| record.path == path)?;
+        let (_, settings) = record.entry.as_mapping()?.iter().find(|(key, _)| {
+            !matches!(key.as_str(), Some("uuid") | Some("path"))
+        })?;
+
+        hikari_asset::hash_settings(settings).ok()
+    }
+}
+
+enum Reference {
+    File(PathBuf),
+    Handle { uuid: Uuid, path: PathBuf },
+}
+
+#[derive(Default)]
+struct Collected {
+    /// Every file that needs to be shipped, relative to the project directory
+    files: Vec<PathBuf>,
+    /// Files which are referenced but don't exist, along with the file referencing them
+    missing: Vec<(PathBuf, Option<PathBuf>)>,
+    /// Handles whose uuid is not present in the asset database, along with the file referencing them
+    dangling: Vec<(Uuid, PathBuf, PathBuf)>,
+}
+
+enum Artifact {
+    /// Imported from the current contents of the source file, path is relative to the project directory
+    Current(PathBuf),
+    /// Imported from an older version of the source file
+    Stale(PathBuf),
+}
+
+/// Finds the artifact the editor imported `path` into with the settings of its asset database record, see [`hikari_asset::Importer`].
+/// Only the contents of the source file and its dependencies are compared, the importer versions aren't known here
+fn find_artifact(
+    project_dir: &Path,
+    path: &Path,
+    db: &AssetDatabase,
+) -> anyhow::Result<Option<Artifact>> {
+    let Some(settings_hash) = db.settings_hash(path) else { return Ok(None) };
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else { return Ok(None) };
+    let artifact_dir = project_dir
+        .join(ARTIFACT_DIR)
+        .join(path.parent().unwrap_or_else(|| Path::new("")));
+    if !artifact_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let prefix = format!("{}.{:016x}.", file_name, settings_hash);
+    for entry in std::fs::read_dir(&artifact_dir)? {
+        let artifact_path = entry?.path();
+        let Some(artifact_name) = artifact_path.file_name().and_then(|name| name.to_str()) else { continue };
+
+        // a.png.<settings hash>.ktx2 belongs to a.png but a.png.bak.<settings hash>.ktx2 does not
+        match artifact_name.strip_prefix(&prefix) {
+            Some(artifact_ext) if !artifact_ext.is_empty() && !artifact_ext.contains('.') => {}
+            _ => continue,
+        }
+
+        let rel_path = artifact_path.strip_prefix(project_dir)?.to_owned();
+
+        let artifact = if hikari_asset::is_artifact_current(&PhysicalIO, project_dir, path, &rel_path)? {
+            Artifact::Current(rel_path)
+        } else {
+            Artifact::Stale(rel_path)
+        };
+        return Ok(Some(artifact));
+    }
+
+    Ok(None)
+}
+
+/// Whether the editor imports `path` into an artifact, see the importers registered by `Plugin3D`
+fn is_imported(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else { return false };
+    let ext = ext.to_lowercase();
+
+    IMPORTED_TEXTURE_EXTENSIONS
+        .iter()
+        .chain(&SUPPORTED_ENV_TEXTURE_EXTENSIONS)
+        .chain(&IMPORTED_SCENE_EXTENSIONS)
+        .any(|&imported| imported == ext)
+}
+
+/// Picks the file to ship for every collected asset, artifacts replace their source files
+fn resolve_artifacts(
+    project_dir: &Path,
+    files: &[PathBuf],
+    db: &AssetDatabase,
+    release: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut shipped = Vec::with_capacity(files.len());
+    let mut stale = 0;
+    let mut not_imported = 0;
+    let log_level = if release {
+        log::Level::Error
+    } else {
+        log::Level::Warn
+    };
+
+    for file in files {
+        match find_artifact(project_dir, file, db)? {
+            Some(Artifact::Current(artifact)) => shipped.push(artifact),
+            Some(Artifact::Stale(artifact)) => {
+                log::log!(
+                    log_level,
+                    "Artifact {} is out of date with {}",
+                    artifact.display(),
+                    file.display()
+                );
+                stale += 1;
+                shipped.push(file.clone());
+            }
+            // The runtime would import it on first load, writing into the build
+            None if is_imported(file) => {
+                log::log!(log_level, "{} has not been imported", file.display());
+                not_imported += 1;
+                shipped.push(file.clone());
+            }
+            None => shipped.push(file.clone()),
+        }
+    }
+
+    if release && stale + not_imported > 0 {
+        return Err(anyhow::anyhow!(
+            "Build failed, {} artifact(s) are out of date and {} asset(s) have not been imported. Open the project in the editor to import them",
+            stale,
+            not_imported
+        ));
+    }
+
+    Ok(shipped)
+}
+
+fn find_project_file(dir: &Path) -> anyhow::Result<PathBuf> {
+    let mut projects = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some(PROJECT_EXTENSION) {
+            projects.push(path);
+        }
+    }
+
+    match projects.len() {
+        0 => Err(anyhow::anyhow!(
+            "No .{} project file found in {}",
+            PROJECT_EXTENSION,
+            dir.display()
+        )),
+        1 => Ok(projects.pop().unwrap()),
+        _ => Err(anyhow::anyhow!(
+            "Multiple project files found in {}: {:?}",
+            dir.display(),
+            projects
+        )),
+    }
+}
+
+/// Serialized handles are maps with exactly two fields, `uuid` and `path`
+fn collect_handles(value: &Value, references: &mut Vec<Reference>) {
+    match value {
+        Value::Mapping(map) => {
+            if map.len() == 2 {
+                let uuid = map
+                    .get("uuid")
+                    .and_then(Value::as_str)
+                    .and_then(|uuid| Uuid::parse_str(uuid).ok());
+                let path = map.get("path").and_then(Value::as_str);
+
+                if let (Some(uuid), Some(path)) = (uuid, path) {
+                    references.push(Reference::Handle {
+                        uuid,
+                        path: PathBuf::from(path),
+                    });
+                    return;
+                }
+            }
+
+            for value in map.values() {
+                collect_handles(value, references);
+            }
+        }
+        Value::Sequence(seq) => {
+            for value in seq {
+                collect_handles(value, references);
+            }
+        }
+        Value::Tagged(tagged) => collect_handles(&tagged.value, references),
+        _ => {}
+    }
+}
+
+/// Buffers and images stored next to a .gltf file
+fn collect_gltf_uris(gltf: &Value, parent: &Path, references: &mut Vec<Reference>) {
+    for key in ["buffers", "images"] {
+        let Some(Value::Sequence(items)) = gltf.get(key) else { continue };
+
+        for item in items {
+            let Some(uri) = item.get("uri").and_then(Value::as_str) else { continue };
+            if uri.starts_with("data:") {
+                continue;
+            }
+            references.push(Reference::File(parent.join(uri)));
+        }
+    }
+}
+
+/// Reads a bincode varint: values below 251 are stored in one byte, larger ones behind a marker byte selecting their width.
+/// Returns the value and the number of bytes read
+fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let (&marker, rest) = bytes.split_first()?;
+    let width = match marker {
+        0..=250 => return Some((marker as u64, 1)),
+        251 => 2,
+        252 => 4,
+        253 => 8,
+        _ => return None,
+    };
+
+    let mut value = [0; 8];
+    value[..width].copy_from_slice(rest.get(..width)?);
+    Some((u64::from_le_bytes(value), 1 + width))
+}
+
+/// Components of binary worlds are encoded with bincode, see `World::to_binary`, and decoding them would require every component type to be registered.
+/// Instead the file is scanned for the encoding of a handle: its uuid as a byte string of length 16 directly followed by its path as a string.
+/// Only uuids of the asset database are considered, so dangling handles of binary worlds are not reported.
+/// This is a heuristic, a uuid of the asset database stored outside of a handle is only taken for a reference if it happens to be followed by something that reads as a path
+fn collect_binary_handles(bytes: &[u8], db: &AssetDatabase, references: &mut Vec<Reference>) {
+    const UUID_LEN: usize = 16;
+    let mut found = HashSet::new();
+
+    for start in 0..bytes.len().saturating_sub(UUID_LEN) {
+        if bytes[start] != UUID_LEN as u8 {
+            continue;
+        }
+        let uuid = Uuid::from_slice(&bytes[start + 1..start + 1 + UUID_LEN]).unwrap();
+        if db.uuid_to_path(&uuid).is_none() || found.contains(&uuid) {
+            continue;
+        }
+
+        let path_start = start + 1 + UUID_LEN;
+        let Some((len, len_size)) = read_varint(&bytes[path_start..]) else { continue };
+        let path = usize::try_from(len)
+            .ok()
+            .and_then(|len| bytes.get(path_start + len_size..)?.get(..len))
+            .and_then(|path| std::str::from_utf8(path).ok());
+
+        // Padding and other binary data decode as control characters, paths never contain them
+        match path {
+            Some(path) if !path.is_empty() && !path.chars().any(char::is_control) => {
+                found.insert(uuid);
+                references.push(Reference::Handle {
+                    uuid,
+                    path: PathBuf::from(path),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn find_references(project_dir: &Path, path: &Path, db: &AssetDatabase) -> Vec<Reference> {
+    let mut references = Vec::new();
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    // Textures, materials and animations extracted by the glTF importer are loaded by path, so they never show up as handles
+    if matches!(ext.as_deref(), Some("gltf") | Some("glb")) {
+        if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+            let texture_prefix = format!("{}_texture_", stem);
+            let material_prefix = format!("{}_material_", stem);
+            let animation_prefix = format!("{}_animation_", stem);
+
+            for record in &db.records {
+                let Some(file_name) = record.path.file_name().and_then(|name| name.to_str()) else { continue };
+
+                if record.path.parent() == Some(parent)
+                    && [&texture_prefix, &material_prefix, &animation_prefix]
+                        .iter()
+                        .any(|prefix| file_name.starts_with(prefix.as_str()))
+                {
+                    references.push(Reference::File(record.path.clone()));
+                }
+            }
+        }
+    }
+
+    if ext.as_deref() == Some(BINARY_WORLD_EXTENSION) {
+        if let Ok(bytes) = std::fs::read(project_dir.join(path)) {
+            collect_binary_handles(&bytes, db, &mut references);
+        }
+        return references;
+    }
+
+    // Binary files fail to parse and are treated as leaves
+    let Ok(contents) = std::fs::read_to_string(project_dir.join(path)) else {
+        return references;
+    };
+    let Ok(value) = serde_yaml::from_str::<Value>(&contents) else {
+        return references;
+    };
+
+    // Scene artifacts embed the buffers and the images are extracted next to the .gltf file
+    if ext.as_deref() == Some("gltf")
+        && !matches!(find_artifact(project_dir, path, db), Ok(Some(Artifact::Current(_))))
+    {
+        collect_gltf_uris(&value, parent, &mut references);
+    }
+    collect_handles(&value, &mut references);
+
+    references
+}
+
+fn collect_assets(project_dir: &Path, project: &Project, db: &AssetDatabase) -> Collected {
+    let mut collected = Collected::default();
+    let mut visited = HashSet::new();
+    let mut queue: VecDeque<(PathBuf, Option<PathBuf>)> = project
+        .worlds()
+        .iter()
+        .map(|world| (world.clone(), None))
+        .collect();
+
+    while let Some((path, referrer)) = queue.pop_front() {
+        if !visited.insert(path.clone()) {
+            continue;
+        }
+
+        if !project_dir.join(&path).is_file() {
+            collected.missing.push((path, referrer));
+            continue;
+        }
+
+        for reference in find_references(project_dir, &path, db) {
+            match reference {
+                Reference::File(file) => queue.push_back((file, Some(path.clone()))),
+                Reference::Handle { uuid, path: handle_path } => match db.uuid_to_path(&uuid) {
+                    Some(record_path) => {
+                        queue.push_back((record_path.to_owned(), Some(path.clone())))
+                    }
+                    None => collected.dangling.push((uuid, handle_path, path.clone())),
+                },
+            }
+        }
+
+        collected.files.push(path);
+    }
+
+    collected
+}
+
+fn find_runtime_binary(config: &Config) -> Option<PathBuf> {
+    let install_path = config.engine_path.join(RUNTIME_BINARY);
+    if install_path.is_file() {
+        return Some(install_path);
+    }
+
+    // For when we are running in a dev environment
+    let exec_path = std::env::current_exe().ok()?.parent()?.join(RUNTIME_BINARY);
+    exec_path.is_file().then_some(exec_path)
+}
+
+fn copy_assets(
+    project_dir: &Path,
+    output_dir: &Path,
+    files: &[PathBuf],
+    shipped_files: &[PathBuf],
+    db: &AssetDatabase,
+) -> anyhow::Result<()> {
+    let asset_dir = output_dir.join(OUTPUT_ASSET_DIR);
+
+    for file in shipped_files {
+        let dest = asset_dir.join(file);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(project_dir.join(file), dest)?;
+    }
+
+    // Only ship records of the assets which were copied, either as source or as artifact
+    let shipped: HashSet<&PathBuf> = files.iter().collect();
+    let records: Vec<&Value> = db
+        .records
+        .iter()
+        .filter(|record| shipped.contains(&record.path))
+        .map(|record| &record.entry)
+        .collect();
+
+    std::fs::create_dir_all(&asset_dir)?;
+    let db_file = std::fs::File::create(asset_dir.join(ASSET_DB_FILENAME))?;
+    serde_yaml::to_writer(db_file, &records)?;
+
+    Ok(())
+}
+
+fn copy_engine_data(config: &Config, output_dir: &Path) -> anyhow::Result<()> {
+    let engine_assets = config.engine_path.join("data/assets");
+    if !engine_assets.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Engine assets not found at {}",
+            engine_assets.display()
+        ));
+    }
+
+    let data_dir = output_dir.join("data");
+    std::fs::create_dir_all(&data_dir)?;
+    fs_extra::dir::copy(
+        engine_assets,
+        data_dir,
+        &CopyOptions {
+            overwrite: true,
+            ..Default::default()
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn run(config: &Config, release: bool) -> anyhow::Result<()> {
+    let project_dir = std::env::current_dir()?;
+    let project_file = find_project_file(&project_dir)?;
+    let project = Project::open(&project_file)?;
+
+    let db = AssetDatabase::open(&project_dir.join(ASSET_DB_FILENAME))?;
+    let collected = collect_assets(&project_dir, &project, &db);
+
+    for (uuid, path, referrer) in &collected.dangling {
+        log::warn!(
+            "Dangling reference in {}: {} ({}) is not in the asset database",
+            referrer.display(),
+            uuid,
+            path.display()
+        );
+    }
+
+    let used: HashSet<&PathBuf> = collected.files.iter().collect();
+    let unused: Vec<&Path> = db
+        .records
+        .iter()
+        .filter(|record| !used.contains(&record.path))
+        .map(|record| record.path.as_path())
+        .collect();
+    for path in &unused {
+        log::info!("Unused asset: {}", path.display());
+    }
+
+    for (path, referrer) in &collected.missing {
+        match referrer {
+            Some(referrer) => log::error!(
+                "Missing file: {} referenced by {}",
+                path.display(),
+                referrer.display()
+            ),
+            None => log::error!("Missing world: {}", path.display()),
+        }
+    }
+    if !collected.missing.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Build failed, {} referenced file(s) are missing",
+            collected.missing.len()
+        ));
+    }
+    if release && !collected.dangling.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Build failed, {} dangling reference(s) found. Dangling references are only allowed in debug builds",
+            collected.dangling.len()
+        ));
+    }
+
+    let shipped_files = resolve_artifacts(&project_dir, &collected.files, &db, release)?;
+    let artifacts = shipped_files
+        .iter()
+        .filter(|file| file.starts_with(ARTIFACT_DIR))
+        .count();
+
+    let profile = if release { "release" } else { "debug" };
+    let output_dir = project_dir.join("build").join(profile);
+    if output_dir.exists() {
+        std::fs::remove_dir_all(&output_dir)?;
+    }
+    std::fs::create_dir_all(&output_dir)?;
+
+    copy_assets(&project_dir, &output_dir, &collected.files, &shipped_files, &db)?;
+    copy_engine_data(config, &output_dir)?;
+
+    match find_runtime_binary(config) {
+        Some(runtime) => {
+            let mut exe_name = PathBuf::from(&project.name);
+            if let Some(ext) = Path::new(RUNTIME_BINARY).extension() {
+                exe_name.set_extension(ext);
+            }
+            std::fs::copy(runtime, output_dir.join(exe_name))?;
+        }
+        None => log::warn!("Runtime binary not found, only game data was packaged"),
+    }
+
+    let worlds = project.worlds().to_vec();
+    let desc = GameDescription {
+        name: project.name.clone(),
+        asset_dir: Some(PathBuf::from(OUTPUT_ASSET_DIR)),
+        starting_world_ix: (!worlds.is_empty()).then_some(0),
+        worlds,
+        ..Default::default()
+    };
+    desc.save(output_dir.join(GAME_DESCRIPTION_FILE))?;
+
+    println!(
+        "Built {} ({}): {} asset(s) ({} imported), {} unused, {} dangling reference(s) -> {}",
+        project.name,
+        profile,
+        collected.files.len(),
+        artifacts,
+        unused.len(),
+        collected.dangling.len(),
+        output_dir.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use hikari_asset::ArtifactHeader;
+    use serde::{Deserialize, Serialize};
+    use type_uuid::TypeUuid;
+
+    use super::*;
+
+    const MAIN_WORLD: Uuid = Uuid::from_u128(0x8f0c6f0e3a554a8eb1a42f7d6d0b9c01);
+    const MATERIAL: Uuid = Uuid::from_u128(0x0b7f0f527b434a0c9f0e5f3c8a1d2e02);
+    const ALBEDO: Uuid = Uuid::from_u128(0xc3d2a1b09e8f4d7c8b6a5f4e3d2c1b03);
+    const TREE: Uuid = Uuid::from_u128(0x5a6b7c8d9e0f4a1b8c2d3e4f5a6b7c04);
+    const TREE_TEXTURE: Uuid = Uuid::from_u128(0xd4c3b2a10f9e4d8c9b7a6f5e4d3c2b05);
+    const TREE_MATERIAL: Uuid = Uuid::from_u128(0xe5d4c3b21a0f4e9d8c7b6a5f4e3d2c06);
+    const UNUSED: Uuid = Uuid::from_u128(0xf6e5d4c3b2a14f0e9d8c7b6a5f4e3d07);
+    const DANGLING: Uuid = Uuid::from_u128(0xa7b8c9d0e1f24a3b8c4d5e6f7a8b9c08);
+    const IMPORT_SETTINGS: Uuid = Uuid::from_u128(0x2c0d1f4e8a7b4c6d9e5f1a2b3c4d5e09);
+
+    struct TempProject(PathBuf);
+    impl TempProject {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("hikari_build_{}_{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+
+            Self(dir)
+        }
+        fn write(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) {
+            let path = self.0.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        /// Writes an artifact of `source` as the importer would, stale artifacts store a hash of other contents
+        fn write_artifact(&self, source: &str, extension: &str, db: &AssetDatabase, current: bool) -> PathBuf {
+            let source = Path::new(source);
+            let settings_hash = db.settings_hash(source).unwrap();
+            let source_hash = if current {
+                let data = std::fs::read(self.0.join(source)).unwrap();
+                hikari_asset::hash_sources(&PhysicalIO, &self.0, hikari_asset::hash_source(&data), &[]).unwrap()
+            } else {
+                0
+            };
+
+            let mut bytes = Vec::new();
+            ArtifactHeader {
+                version: 1,
+                source_hash,
+                settings_hash,
+                dependencies: vec![],
+            }
+            .write(&mut bytes)
+            .unwrap();
+            bytes.extend_from_slice(b"imported");
+
+            let artifact = hikari_asset::artifact_path(source, settings_hash, extension);
+            self.write(&artifact, bytes);
+            artifact
+        }
+        fn open_db(&self) -> AssetDatabase {
+            AssetDatabase::open(&self.0.join(ASSET_DB_FILENAME)).unwrap()
+        }
+    }
+    impl Drop for TempProject {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn project(worlds: &[&str]) -> Project {
+        serde_yaml::from_str(&format!("name: test\nengine_version: '0.1.0'\nworlds: {:?}", worlds)).unwrap()
+    }
+
+    #[test]
+    fn collects_referenced_assets() {
+        let project_dir = TempProject::new("collect");
+        project_dir.write(
+            ASSET_DB_FILENAME,
+            format!(
+                "- uuid: {MAIN_WORLD}\n  path: main.hworld\n\
+                 - uuid: {MATERIAL}\n  path: materials/bark.hmat\n\
+                 - uuid: {ALBEDO}\n  path: textures/albedo.png\n\
+                 - uuid: {TREE}\n  path: models/tree.gltf\n\
+                 - uuid: {TREE_TEXTURE}\n  path: models/tree_texture_0.png\n\
+                 - uuid: {TREE_MATERIAL}\n  path: models/tree_material_0.hmat\n\
+                 - uuid: {UNUSED}\n  path: textures/unused.png\n"
+            ),
+        );
+        project_dir.write(
+            "main.hworld",
+            format!(
+                "entities:\n\
+                 - components:\n    Material:\n      uuid: {MATERIAL}\n      path: materials/bark.hmat\n\
+                 - components:\n    Scene:\n      uuid: {TREE}\n      path: models/tree.gltf\n\
+                 - components:\n    Sound:\n      uuid: {DANGLING}\n      path: sounds/wind.ogg\n"
+            ),
+        );
+        project_dir.write(
+            "materials/bark.hmat",
+            format!("albedo_map:\n  uuid: {ALBEDO}\n  path: textures/albedo.png\nroughness: 0.5\n"),
+        );
+        project_dir.write("textures/albedo.png", b"albedo");
+        project_dir.write("textures/unused.png", b"unused");
+        project_dir.write(
+            "models/tree.gltf",
+            r#"{"buffers": [{"uri": "tree.bin"}, {"uri": "data:application/octet-stream;base64,AAAA"}], "images": [{"uri": "bark.png"}]}"#,
+        );
+        project_dir.write("models/tree.bin", b"buffer");
+        project_dir.write("models/tree_texture_0.png", b"texture");
+        project_dir.write("models/tree_material_0.hmat", "roughness: 1.0\n");
+
+        let db = project_dir.open_db();
+        let collected = collect_assets(&project_dir.0, &project(&["main.hworld"]), &db);
+
+        let files: HashSet<&Path> = collected.files.iter().map(PathBuf::as_path).collect();
+        let expected: HashSet<&Path> = [
+            "main.hworld",
+            "materials/bark.hmat",
+            "textures/albedo.png",
+            "models/tree.gltf",
+            "models/tree.bin",
+            "models/tree_texture_0.png",
+            "models/tree_material_0.hmat",
+        ]
+        .into_iter()
+        .map(Path::new)
+        .collect();
+        assert_eq!(files, expected);
+
+        assert_eq!(
+            collected.missing,
+            vec![(PathBuf::from("models/bark.png"), Some(PathBuf::from("models/tree.gltf")))]
+        );
+        assert_eq!(
+            collected.dangling,
+            vec![(DANGLING, PathBuf::from("sounds/wind.ogg"), PathBuf::from("main.hworld"))]
+        );
+    }
+
+    #[test]
+    fn reports_missing_worlds() {
+        let project_dir = TempProject::new("missing_world");
+        let db = project_dir.open_db();
+
+        let collected = collect_assets(&project_dir.0, &project(&["main.hworld"]), &db);
+
+        assert!(collected.files.is_empty());
+        assert_eq!(collected.missing, vec![(PathBuf::from("main.hworld"), None)]);
+    }
+
+    #[test]
+    fn current_gltf_artifacts_replace_uris() {
+        let project_dir = TempProject::new("gltf_artifact");
+        project_dir.write(
+            ASSET_DB_FILENAME,
+            format!("- uuid: {TREE}\n  path: models/tree.gltf\n  {IMPORT_SETTINGS}:\n    format: Rgba8\n"),
+        );
+        project_dir.write("models/tree.gltf", r#"{"buffers": [{"uri": "tree.bin"}]}"#);
+
+        let db = project_dir.open_db();
+        let tree = Path::new("models/tree.gltf");
+        assert_eq!(find_references(&project_dir.0, tree, &db).len(), 1);
+
+        // The buffers are embedded in the artifact
+        project_dir.write_artifact("models/tree.gltf", hikari_3d::SCENE_ARTIFACT_EXTENSION, &db, true);
+        assert!(find_references(&project_dir.0, tree, &db).is_empty());
+    }
+
+    #[test]
+    fn resolves_artifacts() {
+        let project_dir = TempProject::new("artifacts");
+        project_dir.write(
+            ASSET_DB_FILENAME,
+            format!(
+                "- uuid: {ALBEDO}\n  path: textures/albedo.png\n  {IMPORT_SETTINGS}:\n    format: Rgba8\n\
+                 - uuid: {TREE_TEXTURE}\n  path: textures/stale.png\n  {IMPORT_SETTINGS}:\n    format: Rgba8\n\
+                 - uuid: {UNUSED}\n  path: textures/new.png\n  {IMPORT_SETTINGS}:\n    format: Rgba8\n\
+                 - uuid: {MATERIAL}\n  path: materials/bark.hmat\n"
+            ),
+        );
+        for file in ["textures/albedo.png", "textures/stale.png", "textures/new.png", "materials/bark.hmat"] {
+            project_dir.write(file, file);
+        }
+
+        let db = project_dir.open_db();
+        let albedo_artifact = project_dir.write_artifact("textures/albedo.png", "ktx2", &db, true);
+        project_dir.write_artifact("textures/stale.png", "ktx2", &db, false);
+
+        let files: Vec<PathBuf> = ["textures/albedo.png", "textures/stale.png", "textures/new.png", "materials/bark.hmat"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+
+        // Debug builds ship the sources of stale and missing artifacts
+        let shipped = resolve_artifacts(&project_dir.0, &files, &db, false).unwrap();
+        assert_eq!(
+            shipped,
+            vec![
+                albedo_artifact.clone(),
+                PathBuf::from("textures/stale.png"),
+                PathBuf::from("textures/new.png"),
+                PathBuf::from("materials/bark.hmat"),
+            ]
+        );
+
+        let error = resolve_artifacts(&project_dir.0, &files, &db, true).unwrap_err();
+        assert!(error.to_string().contains("1 artifact(s) are out of date and 1 asset(s) have not been imported"));
+
+        // Assets which are not imported don't fail release builds
+        let shipped = resolve_artifacts(&project_dir.0, &[files[0].clone(), files[3].clone()], &db, true).unwrap();
+        assert_eq!(shipped, vec![albedo_artifact, PathBuf::from("materials/bark.hmat")]);
+    }
+
+    /// Serializes like a `Handle`, which can't be serialized without an asset manager
+    #[derive(Serialize, Deserialize)]
+    struct HandleData {
+        uuid: Uuid,
+        path: PathBuf,
+    }
+
+    #[derive(Serialize, Deserialize, TypeUuid)]
+    #[uuid = "3b1f0c9a-6d2e-4f8b-a7c5-9e0d1f2a3b10"]
+    struct MeshRenderer {
+        mesh: HandleData,
+    }
+
+    /// Stores a uuid of the asset database outside of a handle
+    #[derive(Serialize, Deserialize, TypeUuid)]
+    #[uuid = "4c2a1d0b-7e3f-4a9c-b8d6-0f1e2a3b4c11"]
+    struct AssetTag {
+        asset: Uuid,
+        bytes: Vec<u8>,
+    }
+
+    #[test]
+    fn finds_handles_in_binary_worlds() {
+        let project_dir = TempProject::new("binary");
+        project_dir.write(
+            ASSET_DB_FILENAME,
+            format!(
+                "- uuid: {TREE}\n  path: models/tree.gltf\n\
+                 - uuid: {ALBEDO}\n  path: textures/albedo.png\n"
+            ),
+        );
+
+        let mut registry = hikari_core::Registry::builder();
+        registry.register_serde::<MeshRenderer>();
+        registry.register_serde::<AssetTag>();
+        let registry = registry.build();
+
+        let mut world = hikari_core::World::new();
+        world.create_entity_with((MeshRenderer {
+            mesh: HandleData {
+                uuid: TREE,
+                path: PathBuf::from("models/tree.gltf"),
+            },
+        },));
+        world.create_entity_with((AssetTag {
+            asset: ALBEDO,
+            bytes: vec![1, 2, 3],
+        },));
+        project_dir.write("main.hworldb", world.to_binary(&registry).unwrap().as_slice());
+
+        let db = project_dir.open_db();
+        let references = find_references(&project_dir.0, Path::new("main.hworldb"), &db);
+
+        assert_eq!(references.len(), 1);
+        assert!(matches!(
+            &references[0],
+            Reference::Handle { uuid: handle_uuid, path } if *handle_uuid == TREE && path == Path::new("models/tree.gltf")
+        ));
+    }
+
+    #[test]
+    fn reads_varints() {
+        assert_eq!(read_varint(&[16]), Some((16, 1)));
+        assert_eq!(read_varint(&[251, 0x2c, 0x01]), Some((300, 3)));
+        assert_eq!(read_varint(&[252, 0, 0, 1, 0]), Some((65536, 5)));
+        assert_eq!(read_varint(&[251, 0x2c]), None);
+        assert_eq!(read_varint(&[]), None);
+    }
 }
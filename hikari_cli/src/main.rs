@@ -23,12 +23,12 @@ enum Command {
 }
 fn run() -> anyhow::Result<()> {
     let cmd = Command::parse();
-    let _config = config::Config::new()?;
+    let config = config::Config::new()?;
 
     match cmd {
         Command::New { path } => new::run(path),
         Command::Open { path } => open::run(path),
-        Command::Build { release } => build::run(release),
+        Command::Build { release } => build::run(&config, release),
     }
 }
 fn main() -> anyhow::Result<()> {
@@ -40,15 +40,38 @@ impl EditorComponent for Light {
 
         ui.checkbox("Cast shadows", &mut self.shadow.enabled);
 
-        ui.combo(
+        let kinds = [LightKind::Directional, LightKind::Point, LightKind::Spot];
+        let mut current_kind = kinds.iter().position(|&kind| kind == self.kind).unwrap_or(0);
+        if ui.combo(
             "Type",
-            &mut 0,
-            &[LightKind::Directional],
+            &mut current_kind,
+            &kinds,
             |kind| match kind {
                 LightKind::Point => std::borrow::Cow::Borrowed("Point"),
                 LightKind::Directional => std::borrow::Cow::Borrowed("Directional"),
+                LightKind::Spot => std::borrow::Cow::Borrowed("Spot"),
             },
-        );
+        ) {
+            self.kind = kinds[current_kind];
+        }
+
+        if self.kind != LightKind::Directional {
+            imgui::Drag::new("Range")
+                .range(0.0, f32::MAX)
+                .speed(0.1)
+                .build(ui, &mut self.range);
+        }
+        if self.kind == LightKind::Spot {
+            let mut inner = self.inner_cone_angle.to_degrees();
+            let mut outer = self.outer_cone_angle.to_degrees();
+            ui.slider_config("Inner Cone Angle", 0.0, 89.0)
+                .build(&mut inner);
+            ui.slider_config("Outer Cone Angle", 0.0, 89.0)
+                .build(&mut outer);
+
+            self.outer_cone_angle = outer.to_radians();
+            self.inner_cone_angle = inner.min(outer).to_radians();
+        }
 
         ui.enabled(self.shadow.enabled, || {
             let shadow_info = &mut self.shadow;
@@ -24,11 +24,18 @@ fn register_serde_and_clone<C: SerializeComponent + CloneComponent>(registry: &m
 pub fn register_components(components: &mut EditorComponents, registry: &mut RegistryBuilder) {
     register_editor_serde_clone::<hikari::math::Transform>(components, registry);
     register_editor_serde_clone::<hikari::g3d::Camera>(components, registry);
-    register_editor_serde_clone::<hikari::g3d::MeshRender>(components, registry);
+    components.register::<hikari::g3d::MeshRender>();
+    registry.register_serde_versioned::<hikari::g3d::MeshRender>(hikari::g3d::MeshRender::VERSION);
+    registry.register_migration::<hikari::g3d::MeshRender>(0, hikari::g3d::MeshRender::migrate_v0);
+    registry.register_clone::<hikari::g3d::MeshRender>();
     register_editor_serde_clone::<hikari::g3d::Light>(components, registry);
     register_editor_serde_clone::<hikari::g3d::Environment>(components, registry);
 
+    register_serde_and_clone::<hikari::g3d::Animator>(registry);
     register_serde_and_clone::<editor::meta::EditorOnly>(registry);
-    register_serde_and_clone::<editor::meta::EditorOutlinerInfo>(registry);
+    registry.register_serde_versioned::<editor::meta::EditorOutlinerInfo>(editor::meta::EditorOutlinerInfo::VERSION);
+    registry.register_migration::<editor::meta::EditorOutlinerInfo>(0, editor::meta::EditorOutlinerInfo::migrate_v0);
+    registry.register_clone::<editor::meta::EditorOutlinerInfo>();
+    registry.register_entity_links::<editor::meta::EditorOutlinerInfo>();
     register_serde_and_clone::<editor::camera::ViewportCamera>(registry);
 }
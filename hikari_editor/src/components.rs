@@ -49,6 +49,10 @@ impl ComponentDispatch {
         (self.name)()
     }
     #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+    #[inline]
     pub fn sort_key(&self) -> usize {
         (self.sort_key)()
     }
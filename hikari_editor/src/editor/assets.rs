@@ -9,6 +9,7 @@ pub fn save_all(asset_manager: &AssetManager, only_unsaved: bool) -> anyhow::Res
     asset_manager.save_all::<Texture2D>(only_unsaved)?;
     asset_manager.save_all::<TextureCube>(only_unsaved)?;
     asset_manager.save_all::<Material>(only_unsaved)?;
+    asset_manager.save_all::<hikari::g3d::AnimationClip>(only_unsaved)?;
     asset_manager.save_all::<EnvironmentTexture>(only_unsaved)?;
 
     Ok(())
@@ -1,9 +1,8 @@
 use hikari::core::{World, Entity};
 use hikari::g3d::Camera;
+use hikari::input::{Actions, AnalogAxis, Axis2dBinding, AxisBinding, InputMap, KeyCode, MouseButton};
 use hikari::math::{Quat, Transform, Vec2, Vec3};
 
-use hikari::imgui::*;
-
 use super::meta::EditorOnly;
 
 #[derive(Copy, Clone, serde::Serialize, serde::Deserialize, type_uuid::TypeUuid)]
@@ -43,65 +42,52 @@ impl ViewportCamera {
         }
     } 
 
-    pub fn manipulate(&mut self, ui: &Ui, transform: &mut Transform, dt: f32) {
+    /// Adds the actions used to fly the camera around
+    pub fn bind_actions(map: &mut InputMap) {
+        map.bind_axis2d(
+            "Viewport Move",
+            Axis2dBinding::new(
+                AxisBinding::buttons(KeyCode::A, KeyCode::D),
+                AxisBinding::buttons(KeyCode::S, KeyCode::W),
+            ),
+        )
+        .bind_axis("Viewport Elevate", AxisBinding::buttons(KeyCode::Q, KeyCode::E))
+        .bind_button("Viewport Fast", KeyCode::LShift)
+        .bind_button("Viewport Look", MouseButton::Middle)
+        .bind_axis2d(
+            "Viewport Look Delta",
+            Axis2dBinding::new(
+                AxisBinding::analog(AnalogAxis::MouseX),
+                AxisBinding::analog(AnalogAxis::MouseY),
+            ),
+        );
+    }
+    pub fn manipulate(&mut self, actions: &Actions, transform: &mut Transform, dt: f32) {
         hikari::dev::profile_function!();
 
         let speed = self.speed;
         let sensitivity = self.angular_speed;
 
-        //let externally_changed = !as_quat.abs_diff_eq(transform.rotation, 0.001)
-
-        //dbg!(x.to_degrees(), y.to_degrees(), z.to_degrees());
-
-        // if externally_changed {
-        //     let (x, y, z) =  transform.rotation.to_euler(EulerRot::XYZ);
-        //     self.rotation.x = -y;
-        //     self.rotation.y = z;
-        // }
-
-        let fast_multiplier = if ui.is_key_down(Key::LeftShift) {
+        let fast_multiplier = if actions.pressed("Viewport Fast") {
             3.0
         } else {
             1.0
         };
+        let step = speed * fast_multiplier * dt;
 
-        if ui.is_key_down(Key::A) {
-            transform.position += -transform.right() * speed * fast_multiplier * dt;
-        } else if ui.is_key_down(Key::D) {
-            transform.position += transform.right() * speed * fast_multiplier * dt;
-        }
-        if ui.is_key_down(Key::W) {
-            transform.position += transform.forward() * speed * fast_multiplier * dt;
-        } else if ui.is_key_down(Key::S) {
-            transform.position += -transform.forward() * speed * fast_multiplier * dt;
-        }
-
-        if ui.is_key_down(Key::E) {
-            transform.position += transform.up() * speed * fast_multiplier * dt;
-        } else if ui.is_key_down(Key::Q) {
-            transform.position += -transform.up() * speed * fast_multiplier * dt;
-        }
+        let movement = actions.axis2d("Viewport Move");
+        transform.position += transform.right() * movement.x * step;
+        transform.position += transform.forward() * movement.y * step;
+        transform.position += transform.up() * actions.axis("Viewport Elevate") * step;
 
-        if ui.is_mouse_down(MouseButton::Middle) {
+        if actions.pressed("Viewport Look") {
             let rotation = &mut self.rotation;
-            let mouse_delta: Vec2 = ui.io().mouse_delta.into();
-            let delta: Vec2 = mouse_delta * sensitivity * dt;
+            let delta: Vec2 = actions.axis2d("Viewport Look Delta") * sensitivity * dt;
 
             *rotation += delta;
 
-            let delta_quat = Quat::from_axis_angle(Vec3::Y, rotation.x)
+            transform.rotation = Quat::from_axis_angle(Vec3::Y, rotation.x)
                 * Quat::from_axis_angle(Vec3::X, rotation.y);
-
-            //*rotation *= delta_quat;
-
-            //let (mut rot_x, rot_y, rot_z) =rotation.to_euler(EulerRot::XYZ);
-            //if rot_x > 180.0 {
-            //    rot_x -= 360.0;
-            //}
-            //let mut rot_x = rot_x.clamp(-FRAC_PI_2 + 10.0f32.to_radians(), FRAC_PI_2 - 10.0f32.to_radians());
-            //*rotation = Quat::from_euler(EulerRot::XYZ, rot_x, rot_y, 0.0).normalize();
-
-            transform.rotation = delta_quat;
         }
     }
 }
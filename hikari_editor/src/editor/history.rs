@@ -0,0 +1,682 @@
+use std::any::TypeId;
+
+use hikari::{
+    asset::{AssetManager, Handle},
+    core::{Entity, EntityId, EntityLink, Parent, PrefabInstance, Registry, World},
+    g3d::Material,
+};
+use serde_yaml::Value;
+
+use crate::components::EditorComponents;
+
+use super::Outliner;
+
+const MAX_HISTORY_LEN: usize = 256;
+
+/// Serializes the component of type `type_id` on `entity`.
+/// Returns `None` if the entity doesn't have the component or it isn't serializable
+pub fn snapshot_component(
+    world: &World,
+    registry: &Registry,
+    entity: Entity,
+    type_id: TypeId,
+) -> Option<Value> {
+    let entity_ref = world.entity(entity).ok()?;
+
+    match registry.serialize_component_of_type(type_id, entity_ref, serde_yaml::value::Serializer)? {
+        Ok(value) => Some(value),
+        Err(err) => {
+            log::error!("Failed to snapshot component: {}", err);
+            None
+        }
+    }
+}
+
+/// Marks the fields of `entity` which differ from its prefab as overridden, should be called after every edit of an entity
+pub fn record_prefab_overrides(
+    world: &World,
+    registry: &Registry,
+    asset_manager: &AssetManager,
+    entity: Entity,
+) {
+    if let Err(err) = PrefabInstance::record_overrides(world, registry, asset_manager, entity) {
+        log::error!("Failed to record prefab overrides: {}", err);
+    }
+}
+
+/// Entities which were restored under a different handle, as old and new handle
+type Remapped = Vec<(Entity, Entity)>;
+
+/// `spawn_at` despawns any live entity using the same id, whatever its generation
+fn is_id_taken(world: &World, entity: Entity) -> bool {
+    world
+        .entities()
+        .any(|entity_ref| entity_ref.entity().id() == entity.id())
+}
+
+/// Everything needed to bring back a removed entity,
+/// under the same handle unless its id has been reused in the meantime
+pub struct EntitySnapshot {
+    entity: Entity,
+    id: EntityId,
+    parent: Option<Entity>,
+    components: Vec<(TypeId, Value)>,
+    /// Position of the entity in the outliner
+    order_ix: Option<usize>,
+}
+
+impl EntitySnapshot {
+    /// Captures `entity` followed by all of its descendants, parents always come before their children
+    pub fn capture_recursive(
+        world: &mut World,
+        registry: &Registry,
+        entity: Entity,
+    ) -> anyhow::Result<Vec<Self>> {
+        let order = Outliner::outliner_info(world).order.clone();
+
+        let mut entities = vec![entity];
+        entities.extend(world.descendants(entity));
+
+        entities
+            .into_iter()
+            .map(|entity| Self::capture(world, registry, entity, &order))
+            .collect()
+    }
+    fn capture(
+        world: &World,
+        registry: &Registry,
+        entity: Entity,
+        order: &[EntityLink],
+    ) -> anyhow::Result<Self> {
+        let entity_ref = world.entity(entity)?;
+        let id = (*world.entity_id(entity)?).clone();
+        let order_ix = order.iter().position(|ordered| ordered.uuid() == id.uuid);
+
+        let mut components = Vec::new();
+        for type_id in entity_ref.component_types() {
+            // The hierarchy is relinked separately when restoring
+            if type_id == TypeId::of::<Parent>() {
+                continue;
+            }
+
+            if let Some(value) =
+                registry.serialize_component_of_type(type_id, entity_ref, serde_yaml::value::Serializer)
+            {
+                components.push((type_id, value?));
+            }
+        }
+
+        Ok(Self {
+            entity,
+            id,
+            parent: world.parent(entity),
+            components,
+            order_ix,
+        })
+    }
+    /// Restores the snapshots and returns the entities which got a new handle, the snapshots are updated to the new handles
+    fn restore_all(snapshots: &mut [Self], ctx: &mut EditContext) -> anyhow::Result<Remapped> {
+        let world = &mut *ctx.world;
+
+        let mut remapped = Vec::new();
+        for snapshot in snapshots.iter_mut() {
+            if is_id_taken(world, snapshot.entity) {
+                let entity = world.create_entity_with((snapshot.id.clone(),));
+                remapped.push((snapshot.entity, entity));
+                snapshot.entity = entity;
+            } else {
+                world.create_entity_at(snapshot.entity, (snapshot.id.clone(),));
+            }
+        }
+        // Links between components are resolved by uuid, only the handles kept by the snapshots need remapping
+        for snapshot in snapshots.iter_mut() {
+            snapshot.remap(&remapped);
+        }
+
+        // Parents are linked before the components are restored,
+        // as linking adjusts the local transform to keep the world position of the child
+        for snapshot in snapshots.iter() {
+            if let Some(parent) = snapshot.parent.filter(|&parent| world.contains(parent)) {
+                world.set_parent(snapshot.entity, parent)?;
+            }
+        }
+
+        for snapshot in snapshots {
+            for (type_id, value) in &snapshot.components {
+                ctx.registry.deserialize_component_of_type(
+                    *type_id,
+                    snapshot.entity,
+                    world,
+                    value.clone(),
+                )?;
+            }
+        }
+
+        let mut ordered: Vec<_> = snapshots
+            .iter()
+            .filter_map(|snapshot| snapshot.order_ix.map(|ix| (ix, snapshot.entity)))
+            .collect();
+        ordered.sort_by_key(|&(ix, _)| ix);
+
+        let links: Vec<_> = ordered
+            .into_iter()
+            .map(|(ix, entity)| Ok((ix, EntityLink::new(world, entity)?)))
+            .collect::<anyhow::Result<_>>()?;
+
+        let order = &mut Outliner::outliner_info(world).order;
+        for (ix, link) in links {
+            order.insert(ix.min(order.len()), link);
+        }
+        world.resolve_entity_links(ctx.registry);
+
+        Ok(remapped)
+    }
+    fn remap(&mut self, remapped: &[(Entity, Entity)]) {
+        remap_entity(&mut self.entity, remapped);
+        if let Some(parent) = &mut self.parent {
+            remap_entity(parent, remapped);
+        }
+    }
+    fn remove_all(snapshots: &[Self], ctx: &mut EditContext) -> anyhow::Result<()> {
+        // The first snapshot is the root, removing it removes its descendants as well
+        if let Some(root) = snapshots.first() {
+            ctx.outliner.remove_entity(ctx.world, root.entity)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn remap_entity(entity: &mut Entity, remapped: &[(Entity, Entity)]) {
+    if let Some(&(_, new)) = remapped.iter().find(|(old, _)| old == entity) {
+        *entity = new;
+    }
+}
+
+/// A reversible edit made in the editor
+pub enum EditCommand {
+    /// A component was added, changed or removed. `None` means the entity didn't have the component
+    Component {
+        entity: Entity,
+        type_id: TypeId,
+        before: Option<Value>,
+        after: Option<Value>,
+    },
+    Rename {
+        entity: Entity,
+        before: String,
+        after: String,
+    },
+    Reparent {
+        entity: Entity,
+        before: Option<Entity>,
+        after: Option<Entity>,
+    },
+    /// Entities were created, the first snapshot is the root
+    Spawn(Vec<EntitySnapshot>),
+    /// Entities were removed, the first snapshot is the root
+    Despawn(Vec<EntitySnapshot>),
+    Material {
+        handle: Handle<Material>,
+        before: Value,
+        after: Value,
+    },
+}
+
+impl EditCommand {
+    /// Folds `next` into this command if both edit the same thing, otherwise hands it back
+    fn merge(&mut self, next: EditCommand) -> Result<(), EditCommand> {
+        match (self, next) {
+            (
+                EditCommand::Component {
+                    entity,
+                    type_id,
+                    after,
+                    ..
+                },
+                EditCommand::Component {
+                    entity: next_entity,
+                    type_id: next_type_id,
+                    after: next_after,
+                    ..
+                },
+            ) if *entity == next_entity && *type_id == next_type_id => {
+                *after = next_after;
+                Ok(())
+            }
+            (
+                EditCommand::Material { handle, after, .. },
+                EditCommand::Material {
+                    handle: next_handle,
+                    after: next_after,
+                    ..
+                },
+            ) if *handle == next_handle => {
+                *after = next_after;
+                Ok(())
+            }
+            (_, next) => Err(next),
+        }
+    }
+    /// Points the command at the new handles of restored entities
+    fn remap(&mut self, remapped: &[(Entity, Entity)]) {
+        match self {
+            EditCommand::Component { entity, .. } | EditCommand::Rename { entity, .. } => {
+                remap_entity(entity, remapped)
+            }
+            EditCommand::Reparent {
+                entity,
+                before,
+                after,
+            } => {
+                remap_entity(entity, remapped);
+                for parent in [before, after].into_iter().flatten() {
+                    remap_entity(parent, remapped);
+                }
+            }
+            EditCommand::Spawn(snapshots) | EditCommand::Despawn(snapshots) => {
+                for snapshot in snapshots {
+                    snapshot.remap(remapped);
+                }
+            }
+            EditCommand::Material { .. } => {}
+        }
+    }
+    /// Returns the entities which were restored under a different handle
+    fn apply(&mut self, ctx: &mut EditContext, undo: bool) -> anyhow::Result<Remapped> {
+        let mut remapped = Vec::new();
+
+        match self {
+            EditCommand::Component {
+                entity,
+                type_id,
+                before,
+                after,
+            } => {
+                if !ctx.world.contains(*entity) {
+                    return Err(anyhow::anyhow!("Entity {:?} no longer exists", entity));
+                }
+
+                match if undo { before } else { after } {
+                    Some(value) => ctx.registry.deserialize_component_of_type(
+                        *type_id,
+                        *entity,
+                        ctx.world,
+                        value.clone(),
+                    )?,
+                    None => {
+                        let dispatch = ctx.components.get(*type_id).ok_or_else(|| {
+                            anyhow::anyhow!("Component can't be removed from the editor")
+                        })?;
+                        dispatch.remove_component(*entity, ctx.world)?;
+                    }
+                }
+
+                // Otherwise the next sync of a prefab instance would bring back the value of the prefab
+                record_prefab_overrides(ctx.world, ctx.registry, ctx.asset_manager, *entity);
+            }
+            EditCommand::Rename {
+                entity,
+                before,
+                after,
+            } => {
+                let mut id = ctx.world.get_component::<&mut EntityId>(*entity)?;
+                id.name = if undo { before } else { after }.clone();
+            }
+            EditCommand::Reparent {
+                entity,
+                before,
+                after,
+            } => match if undo { before } else { after } {
+                Some(parent) => ctx.world.set_parent(*entity, *parent)?,
+                None => ctx.world.detach(*entity)?,
+            },
+            EditCommand::Spawn(snapshots) => {
+                if undo {
+                    EntitySnapshot::remove_all(snapshots, ctx)?;
+                } else {
+                    remapped = EntitySnapshot::restore_all(snapshots, ctx)?;
+                }
+            }
+            EditCommand::Despawn(snapshots) => {
+                if undo {
+                    remapped = EntitySnapshot::restore_all(snapshots, ctx)?;
+                } else {
+                    EntitySnapshot::remove_all(snapshots, ctx)?;
+                }
+            }
+            EditCommand::Material {
+                handle,
+                before,
+                after,
+            } => {
+                // Deserializing loads the referenced textures, so do it before locking the pool
+                let material: Material =
+                    serde_yaml::from_value(if undo { before } else { after }.clone())?;
+
+                let mut materials = ctx.asset_manager.write_assets::<Material>().unwrap();
+                if let Some(current) = materials.get_mut(handle) {
+                    *current = material;
+                }
+            }
+        }
+
+        Ok(remapped)
+    }
+}
+
+pub struct EditContext<'a> {
+    pub world: &'a mut World,
+    pub registry: &'a Registry,
+    pub components: &'a EditorComponents,
+    pub asset_manager: &'a AssetManager,
+    pub outliner: &'a mut Outliner,
+}
+
+/// Undo and redo stacks of the edits made to the current world
+#[derive(Default)]
+pub struct History {
+    undo: Vec<EditCommand>,
+    redo: Vec<EditCommand>,
+    /// Whether the next command may be merged into the last one
+    merging: bool,
+}
+
+impl History {
+    /// Records an edit which has already been applied.
+    /// Consecutive edits of the same component or material are merged until [`History::seal`] is called,
+    /// so that dragging a value over several frames produces a single entry
+    pub fn push(&mut self, command: EditCommand) {
+        self.redo.clear();
+
+        let command = match self.undo.last_mut() {
+            Some(last) if self.merging => match last.merge(command) {
+                Ok(()) => return,
+                Err(command) => command,
+            },
+            _ => command,
+        };
+
+        if self.undo.len() == MAX_HISTORY_LEN {
+            self.undo.remove(0);
+        }
+        self.undo.push(command);
+        self.merging = true;
+    }
+    /// Ends the current interaction, the next edit gets its own entry
+    pub fn seal(&mut self) {
+        self.merging = false;
+    }
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.merging = false;
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    pub fn undo(&mut self, ctx: &mut EditContext) -> anyhow::Result<()> {
+        self.merging = false;
+        let Some(mut command) = self.undo.pop() else {
+            return Ok(());
+        };
+
+        let result = command.apply(ctx, true);
+        ctx.outliner.deselect_removed(ctx.world);
+
+        // A command which failed to apply leaves the world in an unknown state, so it's dropped
+        let remapped = result?;
+        self.redo.push(command);
+        self.remap(&remapped);
+
+        Ok(())
+    }
+    pub fn redo(&mut self, ctx: &mut EditContext) -> anyhow::Result<()> {
+        self.merging = false;
+        let Some(mut command) = self.redo.pop() else {
+            return Ok(());
+        };
+
+        let result = command.apply(ctx, false);
+        ctx.outliner.deselect_removed(ctx.world);
+
+        let remapped = result?;
+        self.undo.push(command);
+        self.remap(&remapped);
+
+        Ok(())
+    }
+    fn remap(&mut self, remapped: &[(Entity, Entity)]) {
+        if remapped.is_empty() {
+            return;
+        }
+
+        for command in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            command.remap(remapped);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use hikari::core::Prefab;
+    use serde::{Deserialize, Serialize};
+    use type_uuid::TypeUuid;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TypeUuid)]
+    #[uuid = "9d4e2b71-3c5a-4f08-a6e1-7b2c0d9f8e14"]
+    struct Health {
+        current: f32,
+        max: f32,
+    }
+
+    /// Only one asset manager may exist per process, so the tests share it
+    fn asset_manager() -> &'static AssetManager {
+        static ASSET_MANAGER: OnceLock<AssetManager> = OnceLock::new();
+
+        ASSET_MANAGER.get_or_init(|| {
+            let asset_dir = std::env::temp_dir().join(format!("hikari_history_{}", std::process::id()));
+            std::fs::create_dir_all(&asset_dir).unwrap();
+
+            let mut builder = AssetManager::builder();
+            builder.register_asset_type::<Prefab>();
+            builder.set_asset_dir(asset_dir);
+            builder.build().unwrap()
+        })
+    }
+
+    struct TestEditor {
+        world: World,
+        registry: Registry,
+        components: EditorComponents,
+        outliner: Outliner,
+        history: History,
+    }
+    impl TestEditor {
+        fn new() -> Self {
+            let mut registry = Registry::builder();
+            registry.register_serde::<Health>();
+            registry.register_clone::<Health>();
+
+            let mut world = World::new();
+            let mut outliner = Outliner::default();
+            outliner.on_world_loaded(&mut world);
+
+            Self {
+                world,
+                registry: registry.build(),
+                components: EditorComponents::default(),
+                outliner,
+                history: History::default(),
+            }
+        }
+        fn spawn(&mut self, health: Health) -> Entity {
+            let entity = self.outliner.add_entity(&mut self.world, "Enemy");
+            self.world.add_component(entity, health).unwrap();
+            entity
+        }
+        /// Changes the health of `entity` the way the properties window does
+        fn set_health(&mut self, entity: Entity, health: Health) {
+            let type_id = TypeId::of::<Health>();
+            let before = snapshot_component(&self.world, &self.registry, entity, type_id);
+            *self.world.get_component::<&mut Health>(entity).unwrap() = health;
+
+            self.history.push(EditCommand::Component {
+                entity,
+                type_id,
+                before,
+                after: snapshot_component(&self.world, &self.registry, entity, type_id),
+            });
+        }
+        fn despawn(&mut self, entity: Entity) {
+            let snapshots = EntitySnapshot::capture_recursive(&mut self.world, &self.registry, entity).unwrap();
+            self.outliner.remove_entity(&mut self.world, entity).unwrap();
+            self.history.push(EditCommand::Despawn(snapshots));
+        }
+        fn health(&self, entity: Entity) -> Health {
+            self.world.get_component::<&Health>(entity).unwrap().clone()
+        }
+        fn undo(&mut self) {
+            let mut ctx = EditContext {
+                world: &mut self.world,
+                registry: &self.registry,
+                components: &self.components,
+                asset_manager: asset_manager(),
+                outliner: &mut self.outliner,
+            };
+            self.history.undo(&mut ctx).unwrap();
+        }
+        fn redo(&mut self) {
+            let mut ctx = EditContext {
+                world: &mut self.world,
+                registry: &self.registry,
+                components: &self.components,
+                asset_manager: asset_manager(),
+                outliner: &mut self.outliner,
+            };
+            self.history.redo(&mut ctx).unwrap();
+        }
+    }
+
+    fn health(current: f32) -> Health {
+        Health { current, max: 10.0 }
+    }
+
+    #[test]
+    fn merged_drag_undoes_in_one_step() {
+        let mut editor = TestEditor::new();
+        let entity = editor.spawn(health(10.0));
+
+        for current in [9.0, 8.0, 7.0] {
+            editor.set_health(entity, health(current));
+        }
+        editor.history.seal();
+        editor.set_health(entity, health(1.0));
+        assert_eq!(editor.history.undo.len(), 2);
+
+        editor.undo();
+        assert_eq!(editor.health(entity), health(7.0));
+        editor.undo();
+        assert_eq!(editor.health(entity), health(10.0));
+        assert!(!editor.history.can_undo());
+
+        editor.redo();
+        assert_eq!(editor.health(entity), health(7.0));
+        editor.redo();
+        assert_eq!(editor.health(entity), health(1.0));
+        assert!(!editor.history.can_redo());
+    }
+
+    #[test]
+    fn push_clears_redo() {
+        let mut editor = TestEditor::new();
+        let entity = editor.spawn(health(10.0));
+
+        editor.set_health(entity, health(5.0));
+        editor.undo();
+        assert!(editor.history.can_redo());
+
+        editor.set_health(entity, health(3.0));
+        assert!(!editor.history.can_redo());
+    }
+
+    #[test]
+    fn undo_despawn_remaps_reused_id() {
+        let mut editor = TestEditor::new();
+        let entity = editor.spawn(health(10.0));
+
+        editor.set_health(entity, health(5.0));
+        editor.history.seal();
+        editor.despawn(entity);
+
+        // The id of the removed entity is handed out again
+        let reused = editor.spawn(health(2.0));
+        assert_eq!(reused.id(), entity.id());
+
+        editor.undo();
+        let restored = editor
+            .world
+            .entities()
+            .map(|entity_ref| entity_ref.entity())
+            .find(|&candidate| candidate != reused && editor.world.get_component::<&Health>(candidate).is_ok())
+            .unwrap();
+        assert_ne!(restored, entity);
+        assert_eq!(editor.health(restored), health(5.0));
+
+        // The edit made before the despawn now points at the restored entity
+        editor.undo();
+        assert_eq!(editor.health(restored), health(10.0));
+        assert_eq!(editor.health(reused), health(2.0));
+
+        editor.redo();
+        editor.redo();
+        assert!(!editor.world.contains(restored));
+        assert_eq!(editor.health(reused), health(2.0));
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut editor = TestEditor::new();
+        let entity = editor.spawn(health(10.0));
+
+        for ix in 0..MAX_HISTORY_LEN + 10 {
+            editor.history.push(EditCommand::Rename {
+                entity,
+                before: ix.to_string(),
+                after: (ix + 1).to_string(),
+            });
+        }
+
+        assert_eq!(editor.history.undo.len(), MAX_HISTORY_LEN);
+        assert!(matches!(&editor.history.undo[0], EditCommand::Rename { before, .. } if before == "10"));
+    }
+
+    #[test]
+    fn redo_records_prefab_overrides() {
+        let mut editor = TestEditor::new();
+        let entity = editor.spawn(health(10.0));
+
+        let prefab = Prefab::from_hierarchy(&editor.world, &editor.registry, entity).unwrap();
+        let revision = prefab.revision();
+        let handle = asset_manager().write_assets::<Prefab>().unwrap().insert(prefab);
+        PrefabInstance::link(&mut editor.world, entity, &handle, revision).unwrap();
+
+        editor.set_health(entity, health(5.0));
+        editor.undo();
+        editor.redo();
+        let health_uuid = uuid::Uuid::from_bytes(Health::UUID);
+        assert!(editor
+            .world
+            .get_component::<&PrefabInstance>(entity)
+            .unwrap()
+            .overrides()
+            .contains_key(&health_uuid));
+    }
+}
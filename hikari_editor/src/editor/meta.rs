@@ -1,4 +1,4 @@
-use hikari::core::Entity;
+use hikari::core::{Entity, EntityLink, MapEntityLinks};
 use serde::{Deserialize, Serialize};
 use type_uuid::TypeUuid;
 
@@ -10,5 +10,28 @@ pub struct EditorOnly;
 #[serde(default)]
 #[uuid = "9215dd05-f049-4e8f-8899-6ad92aeead47"]
 pub struct EditorOutlinerInfo {
-    pub order: Vec<Entity>
+    pub order: Vec<EntityLink>,
+    /// Order saved by worlds from before entities were linked by uuid, converted once the world is loaded
+    pub legacy_order: Vec<Entity>,
+}
+
+impl EditorOutlinerInfo {
+    pub const VERSION: u32 = 1;
+
+    /// Version 0 stored the order as entity handles, which are kept in `legacy_order` until they can be looked up
+    pub fn migrate_v0(mut value: serde_yaml::Value) -> anyhow::Result<serde_yaml::Value> {
+        if let serde_yaml::Value::Mapping(mapping) = &mut value {
+            if let Some(order) = mapping.remove("order") {
+                mapping.insert("legacy_order".into(), order);
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+impl MapEntityLinks for EditorOutlinerInfo {
+    fn map_entity_links(&mut self, f: &mut dyn FnMut(&mut EntityLink)) {
+        self.order.map_entity_links(f)
+    }
 }
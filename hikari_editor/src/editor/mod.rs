@@ -1,12 +1,14 @@
 use crate::{component_impls, components::EditorComponents, widgets::RenameState};
 use clipboard::ClipboardProvider;
 use hikari::{
-    core::{Game, Registry},
-    input::KeyCode,
+    asset::AssetManager,
+    core::{Game, Registry, World},
+    input::{Actions, KeyCode},
 };
 use hikari_editor::*;
 
 mod font;
+pub mod history;
 mod icons;
 pub(crate) mod logging;
 pub mod meta;
@@ -16,6 +18,7 @@ mod style;
 mod windows;
 mod assets;
 
+use history::{EditContext, History};
 use windows::*;
 
 pub use logging::*;
@@ -64,6 +67,8 @@ impl Editor {
         let registry = registry.build();
         game.add_state(registry);
         game.add_plugin(hikari::core::load_save::WorldLoaderPlugin);
+
+        camera::ViewportCamera::bind_actions(game.get_mut::<Actions>().map_mut());
         //game.create_asset::<Scene>();
         // let loader = SceneLoader { registry };
         // game.register_asset_loader::<Scene, SceneLoader>(loader.clone());
@@ -85,6 +90,7 @@ impl Editor {
             save_and_exit: SaveAndExit::default(),
             editor_settings: EditorSettings::default(),
             render_settings: RenderSettings::default(),
+            history: History::default(),
         };
 
         game.add_state(editor);
@@ -108,9 +114,7 @@ impl Editor {
                     //project::draw(ui, self, state).unwrap();
                     self.file_menu(ui, state).unwrap();
 
-                    ui.menu("Edit", || {
-                        ui.menu_item_config("Preferences").enabled(false).build();
-                    });
+                    self.edit_menu(ui, state);
                     ui.menu("Windows", || {
                         if ui.menu_item("Material Editor") {
                             MaterialEditor::open(self);
@@ -160,6 +164,75 @@ impl Editor {
         if let Err(err) = result {
             log::error!("{}", err);
         }
+
+        // An interaction is over once nothing is being dragged or typed into
+        if !ui.is_any_item_active() && !ui.is_mouse_down(imgui::MouseButton::Left) {
+            self.history.seal();
+        }
+    }
+    pub fn edit_menu(&mut self, ui: &hikari::imgui::Ui, state: EngineState) {
+        let mut undo = false;
+        let mut redo = false;
+
+        ui.menu("Edit", || {
+            undo |= ui
+                .menu_item_config("Undo")
+                .shortcut("Ctrl + Z")
+                .enabled(self.history.can_undo())
+                .build();
+            redo |= ui
+                .menu_item_config("Redo")
+                .shortcut("Ctrl + Shift + Z")
+                .enabled(self.history.can_redo())
+                .build();
+            ui.separator();
+            ui.menu_item_config("Preferences").enabled(false).build();
+        });
+
+        {
+            let input = state.get::<hikari::input::Input>().unwrap();
+            let keyboard = input.keyboard();
+
+            // Text fields handle undo themselves while they are being edited
+            if ui.io().key_ctrl && !ui.io().want_text_input && keyboard.was_just_pressed(KeyCode::Z) {
+                if ui.io().key_shift {
+                    redo = true; // Ctrl + Shift + Z
+                } else {
+                    undo = true; // Ctrl + Z
+                }
+            }
+        }
+
+        if undo {
+            if let Err(err) = self.with_edit_context(state, History::undo) {
+                log::error!("Failed to undo: {}", err);
+            }
+        }
+        if redo {
+            if let Err(err) = self.with_edit_context(state, History::redo) {
+                log::error!("Failed to redo: {}", err);
+            }
+        }
+    }
+    fn with_edit_context(
+        &mut self,
+        state: EngineState,
+        f: impl FnOnce(&mut History, &mut EditContext) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let mut world = state.get_mut::<World>().unwrap();
+        let registry = state.get::<Registry>().unwrap();
+        let components = state.get::<EditorComponents>().unwrap();
+        let asset_manager = state.get::<AssetManager>().unwrap();
+
+        let mut ctx = EditContext {
+            world: &mut world,
+            registry: &registry,
+            components: &components,
+            asset_manager: &asset_manager,
+            outliner: &mut self.outliner,
+        };
+
+        f(&mut self.history, &mut ctx)
     }
     pub fn file_menu(&mut self, ui: &hikari::imgui::Ui, state: EngineState) -> anyhow::Result<()> {
         let mut open = false;
@@ -235,6 +308,7 @@ impl Editor {
     }
     pub fn load(&mut self, project_file: &std::path::Path, state: EngineState) -> anyhow::Result<()> {
         self.project_manager.open(project_file, state)?;
+        self.history.clear();
         self.load_state()
     }
     pub fn save_all(&mut self, state: EngineState) -> anyhow::Result<()> {
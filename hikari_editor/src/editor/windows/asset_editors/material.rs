@@ -1,3 +1,4 @@
+use crate::editor::history::{EditCommand, History};
 use crate::editor::windows::Editor;
 use crate::{editor::EditorWindow, widgets::AssetSelector};
 use hikari::imgui::*;
@@ -32,6 +33,7 @@ impl EditorWindow for MaterialEditor {
     fn draw(ui: &Ui, editor: &mut Editor, state: EngineState) -> anyhow::Result<()> {
         let asset_manager = state.get::<AssetManager>().unwrap();
         let material_editor = &mut editor.material_editor;
+        let history = &mut editor.history;
 
         let mut outer_result = Ok(());
 
@@ -59,7 +61,7 @@ impl EditorWindow for MaterialEditor {
                 //     ui.text_disabled("Preview, Doesn't Do Anything for now...");
 
                 //     ui.table_next_column();
-                outer_result = material_edit(ui, &mut material_editor.current, &asset_manager);
+                outer_result = material_edit(ui, &mut material_editor.current, &asset_manager, history);
                 // }
             });
 
@@ -115,6 +117,7 @@ fn material_edit(
     ui: &Ui,
     current: &mut Option<Handle<Material>>,
     asset_manager: &AssetManager,
+    history: &mut History,
 ) -> anyhow::Result<()> {
     if let Some(_table_token) = ui.begin_table_with_sizing(
         "MaterialEditor",
@@ -150,6 +153,8 @@ fn material_edit(
         let mut materials = asset_manager.write_assets::<Material>().unwrap();
         let material = materials.get_mut(&handle);
         if let Some(material) = material {
+            let before = serde_yaml::to_value(&*material)?;
+
             parameter_edit(
                 ui,
                 "Albedo",
@@ -213,6 +218,15 @@ fn material_edit(
 
             parameter_edit(ui, "Normal", &mut material.normal, || {}, asset_manager)?;
 
+            let after = serde_yaml::to_value(&*material)?;
+            if after != before {
+                history.push(EditCommand::Material {
+                    handle: handle.clone(),
+                    before,
+                    after,
+                });
+            }
+
             drop(materials);
             ui.new_line();
 
@@ -4,6 +4,7 @@ use hikari::{
     asset::{AssetDB, AssetManager},
     pbr::WorldRenderer,
 };
+use hikari::core::{info::DependencyKind, ScheduleInfo};
 use hikari_editor::EngineState;
 use parking_lot::{lock_api::RwLockUpgradableReadGuard, RwLockWriteGuard};
 
@@ -67,6 +68,49 @@ fn draw_asset_db(ui: &Ui, record: &hikari::asset::Record, asset_db: &AssetDB) {
         }
     });
 }
+/// Lists the tasks of every stage in the order they run, with the state they borrow and the tasks they wait for
+fn draw_schedule(ui: &Ui, info: &ScheduleInfo) -> anyhow::Result<()> {
+    if ui.button("Export DOT") {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Graphviz", &["dot"])
+            .set_file_name("schedule.dot")
+            .save_file()
+        {
+            std::fs::write(path, info.to_dot())?;
+        }
+    }
+
+    for stage in &info.stages {
+        let Some(_token) = ui.tree_node(&stage.name) else { continue };
+
+        for task in &stage.tasks {
+            let Some(_token) = ui.tree_node(&task.name) else { continue };
+
+            if task.exclusive {
+                ui.text("Exclusive");
+            }
+            for borrow in &task.borrows {
+                let kind = if borrow.mutable { "&mut" } else { "&" };
+                ui.text(format!("{} {}", kind, borrow.type_name));
+            }
+            if !task.sets.is_empty() {
+                ui.text(format!("Sets: {}", task.sets.join(", ")));
+            }
+            if task.has_conditions {
+                ui.text("Has run conditions");
+            }
+            for dependency in &task.dependencies {
+                let reason = match dependency.kind {
+                    DependencyKind::Ordering => "ordering",
+                    DependencyKind::Conflict => "borrow conflict",
+                };
+                ui.text_disabled(format!("After {} ({})", stage.tasks[dependency.task].name, reason));
+            }
+        }
+    }
+
+    Ok(())
+}
 
 impl EditorWindow for Debugger {
     fn open(editor: &mut Editor) {
@@ -110,6 +154,23 @@ impl EditorWindow for Debugger {
                         }
                     }
 
+                    if let Some(_token) = ui.tab_item("Culling") {
+                        let report = renderer.culling_report();
+                        for (name, stats) in [
+                            ("Camera", report.camera),
+                            ("Directional Shadows", report.directional_shadows),
+                            ("Point/Spot Shadows", report.local_shadows),
+                        ] {
+                            ui.text(format!(
+                                "{}: {} total, {} culled, {} drawn",
+                                name,
+                                stats.submitted + stats.culled,
+                                stats.culled,
+                                stats.submitted
+                            ));
+                        }
+                    }
+
                     if let Some(_token) = ui.tab_item("Asset DB") {
                         let asset_manager = state.get::<AssetManager>().unwrap();
 
@@ -223,6 +284,11 @@ impl EditorWindow for Debugger {
                         }
                     }
 
+                    if let Some(_token) = ui.tab_item("Schedule") {
+                        let info = state.get::<ScheduleInfo>().unwrap();
+                        draw_schedule(ui, &info)?;
+                    }
+
                     // if let Some(_token) = ui.tab_item("Render Target Debug") {
                     //     ui.text("Shadow Map Atlas");
                     //     let shadow_map = renderer.graph_resources().get_image_by_name("ShadowMapAtlasDebug").unwrap();
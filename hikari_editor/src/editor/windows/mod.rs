@@ -27,6 +27,8 @@ pub use editor_settings::*;
 
 use crate::widgets::RenameState;
 
+use super::history::History;
+
 pub trait EditorWindow {
     fn draw(ui: &hikari::imgui::Ui, editor: &mut Editor, state: EngineState) -> anyhow::Result<()>;
     fn draw_if_open(
@@ -58,6 +60,7 @@ pub struct Editor {
     pub save_and_exit: SaveAndExit,
     pub editor_settings: EditorSettings,
     pub render_settings: RenderSettings,
+    pub history: History,
     pub show_demo: bool,
 }
 impl Editor {
@@ -1,18 +1,33 @@
-use hikari::core::*;
+use std::path::Path;
+
+use hikari::asset::{AssetManager, Handle, LoadStatus};
+use hikari::core::{prefab::SUPPORTED_PREFAB_EXTENSIONS, *};
 use hikari::g3d::{MeshRender, Outline};
 use hikari::math::*;
 
+use crate::editor::history::{EditCommand, EntitySnapshot, History};
 use crate::editor::meta::{EditorOutlinerInfo};
-use crate::widgets::RenameInput;
+use crate::widgets::{RenameInput, RenameState};
 use hikari::imgui::*;
 use hikari_editor::*;
 
 use crate::editor::{Editor, EditorWindow};
 
+const OUTLINER_ENTITY_PAYLOAD: &str = "OUTLINER_ENTITY";
+const PREFAB_INSTANCE_COLOR: [f32; 4] = [0.45, 0.7, 1.0, 1.0];
+
+enum HierarchyAction {
+    Attach { child: Entity, parent: Entity },
+    Detach(Entity),
+}
+
 #[derive(Default, serde::Serialize, serde::Deserialize)]
 pub struct Outliner {
     #[serde(skip)]
     selected: Option<Entity>,
+    /// Prefabs waiting to finish loading before they are instantiated
+    #[serde(skip)]
+    pending_prefabs: Vec<Handle<Prefab>>,
 }
 impl Outliner {
     pub fn on_world_loaded(&mut self, world: &mut World) {
@@ -39,50 +54,173 @@ impl Outliner {
 
             outliner_info.order = Vec::new();
             world.create_entity_with((outliner_info, ));
+        } else {
+            // Entity handles of old worlds are still valid right after loading, so they can be turned into links
+            let legacy_order = std::mem::take(&mut Self::outliner_info(world).legacy_order);
+            let links: Vec<_> = legacy_order
+                .into_iter()
+                .filter_map(|entity| EntityLink::new(world, entity).ok())
+                .collect();
+            Self::outliner_info(world).order.extend(links);
         }
     }
     pub fn add_entity(&mut self, world: &mut World, name: &str) -> Entity {
         let entity = world.create_entity_with_name(name);
 
-        Self::outliner_info(world).order.push(entity);
+        Self::push_ordered(world, entity);
         entity
     }
-    pub fn duplicate_entity(&mut self, world: &mut World, entity: Entity, registry: &Registry) -> Result<(), NoSuchEntity> {
+    pub fn duplicate_entity(&mut self, world: &mut World, entity: Entity, registry: &Registry) -> Result<Entity, NoSuchEntity> {
         let dup_entity = world.duplicate_entity(entity, registry)?;
         
-        let outliner_info = Self::outliner_info(world);
-        outliner_info.order.push(dup_entity);
+        Self::push_ordered(world, dup_entity);
+        Ok(dup_entity)
+    }
+    /// Shows `entity` last among the root entities
+    pub(crate) fn push_ordered(world: &mut World, entity: Entity) {
+        if let Ok(link) = EntityLink::new(world, entity) {
+            Self::outliner_info(world).order.push(link);
+        }
+    }
+    /// Turns `entity` and its descendants into a new prefab saved at `path`, the entities become its first instance
+    pub fn create_prefab(
+        world: &mut World,
+        registry: &Registry,
+        asset_manager: &AssetManager,
+        entity: Entity,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        let prefab = Prefab::from_hierarchy(world, registry, entity)?;
+        let revision = prefab.revision();
+
+        let handle = asset_manager.create(path, prefab, None)?;
+        asset_manager.save(&handle)?;
+
+        PrefabInstance::link(world, entity, &handle, revision)?;
         Ok(())
     }
-    fn outliner_info(world: &mut World) -> &mut EditorOutlinerInfo {
+    /// Loads the prefab at `path`, it is instantiated as a root entity once loaded
+    pub fn instantiate_prefab(&mut self, asset_manager: &AssetManager, path: &Path) -> anyhow::Result<()> {
+        let handle = asset_manager.load::<Prefab>(path, None, false)?;
+        self.pending_prefabs.push(handle);
+
+        Ok(())
+    }
+    fn spawn_pending_prefabs(
+        &mut self,
+        world: &mut World,
+        registry: &Registry,
+        asset_manager: &AssetManager,
+        history: &mut History,
+    ) {
+        if self.pending_prefabs.is_empty() {
+            return;
+        }
+
+        let prefabs = asset_manager.read_assets::<Prefab>().unwrap();
+        let mut spawned = Vec::new();
+
+        self.pending_prefabs.retain(|handle| {
+            if let Some(prefab) = prefabs.get(handle) {
+                spawned.push(prefab.instantiate(handle, world, registry));
+                return false;
+            }
+
+            match asset_manager.status(&handle.clone_erased_as_weak()) {
+                Some(LoadStatus::Failed) => {
+                    log::error!("Failed to load prefab");
+                    false
+                }
+                _ => true,
+            }
+        });
+        drop(prefabs);
+
+        for entity in spawned {
+            Self::push_ordered(world, entity);
+            Self::record_spawn(world, registry, history, entity);
+        }
+    }
+    pub(crate) fn outliner_info(world: &mut World) -> &mut EditorOutlinerInfo {
         let query =  world.query_mut::<&mut EditorOutlinerInfo>();
         let (_, info) = query.into_iter().next().unwrap();
 
         info
     }
+    /// Returns the root entities in the order authored by the user, children are drawn under their parents
     pub fn ordered_entities(&mut self, world: &mut World) -> Vec<Entity> {
         hikari::dev::profile_scope!("Outliner Entity sorting");
 
-        let outliner_info = Self::outliner_info(world);
+        let order = Self::outliner_info(world).order.clone();
         let mut ordered_entities = Vec::new();
 
         //Put entities authored by user
-        for entity in &outliner_info.order {
-            ordered_entities.push(*entity);
+        for link in order {
+            if let Some(entity) = link.resolve(world).filter(|&entity| world.parent(entity).is_none()) {
+                ordered_entities.push(entity);
+            }
         }
 
         ordered_entities
     }
+    /// Removes `entity` along with all of its children
     pub fn remove_entity(&mut self, world: &mut World, entity: Entity) -> Result<(), NoSuchEntity> {
-        world.remove_entity(entity)?;
+        let mut removed = world.descendants(entity);
+        removed.push(entity);
+        let removed: Vec<_> = removed
+            .into_iter()
+            .filter_map(|entity| world.entity_uuid(entity).ok())
+            .collect();
+
+        world.remove_entity_recursive(entity)?;
 
-        Self::outliner_info(world).order.retain(|&x| x != entity);
+        Self::outliner_info(world).order.retain(|link| !removed.contains(&link.uuid()));
 
         Ok(())
     }
+    fn apply_hierarchy_action(world: &mut World, action: HierarchyAction) -> Option<EditCommand> {
+        let (child, parent) = match action {
+            HierarchyAction::Attach { child, parent } => (child, Some(parent)),
+            HierarchyAction::Detach(child) => (child, None),
+        };
+
+        let before = world.parent(child);
+        if before == parent {
+            return None;
+        }
+
+        let result = match parent {
+            Some(parent) => world.set_parent(child, parent),
+            None => world.detach(child),
+        };
+
+        match result {
+            Ok(()) => Some(EditCommand::Reparent {
+                entity: child,
+                before,
+                after: parent,
+            }),
+            Err(err) => {
+                log::warn!("Failed to change parent: {}", err);
+                None
+            }
+        }
+    }
+    fn record_spawn(world: &mut World, registry: &Registry, history: &mut History, entity: Entity) {
+        match EntitySnapshot::capture_recursive(world, registry, entity) {
+            Ok(snapshots) => history.push(EditCommand::Spawn(snapshots)),
+            Err(err) => log::error!("Failed to record created entity: {}", err),
+        }
+    }
     pub fn selected(&self) -> Option<Entity> {
         self.selected
     }
+    /// Clears the selection if the selected entity no longer exists
+    pub fn deselect_removed(&mut self, world: &World) {
+        if self.selected.map_or(false, |entity| !world.contains(entity)) {
+            self.selected = None;
+        }
+    }
     pub fn set_selected(&mut self, entity: Entity, world: &mut World) {
         if let Some(current_entity) = self.selected {
             let _res = world.remove_component::<Outline>(current_entity);
@@ -101,6 +239,8 @@ impl Outliner {
 impl EditorWindow for Outliner {
     fn draw(ui: &Ui, editor: &mut Editor, state: EngineState) -> anyhow::Result<()> {
         let mut world = state.get_mut::<World>().unwrap();
+        let registry = state.get::<Registry>().unwrap();
+        let asset_manager = state.get::<AssetManager>().unwrap();
 
         ui.window("Outliner")
             .size([300.0, 400.0], Condition::FirstUseEver)
@@ -108,23 +248,81 @@ impl EditorWindow for Outliner {
             .build(|| {
                 let outliner = &mut editor.outliner;
                 let rename_state = &mut editor.rename_state;
+                let history = &mut editor.history;
 
                 if !editor.project_manager.current_world().is_some() {
                     ui.text("No World");
                     return;
                 };
 
+                outliner.spawn_pending_prefabs(&mut world, &registry, &asset_manager, history);
+
                 if ui.button("+") {
-                    outliner.add_entity(&mut world, "untitled");
+                    let entity = outliner.add_entity(&mut world, "untitled");
+                    Outliner::record_spawn(&mut world, &registry, history, entity);
+                }
+
+                ui.same_line();
+                if ui.button("Instantiate Prefab") {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Hikari Prefab", &SUPPORTED_PREFAB_EXTENSIONS)
+                        .set_directory(asset_manager.get_asset_dir())
+                        .pick_file()
+                    {
+                        let result = path
+                            .strip_prefix(asset_manager.get_asset_dir())
+                            .map_err(anyhow::Error::from)
+                            .and_then(|path| outliner.instantiate_prefab(&asset_manager, path));
+
+                        if let Err(err) = result {
+                            log::error!("Failed to instantiate prefab: {}", err);
+                        }
+                    }
                 }
 
+                ui.same_line();
+                ui.disabled(outliner.selected.is_none(), || {
+                    if ui.button("Create Prefab from Selection") {
+                        let Some(entity) = outliner.selected else { return };
+
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Hikari Prefab", &SUPPORTED_PREFAB_EXTENSIONS)
+                            .set_directory(asset_manager.get_asset_dir())
+                            .save_file()
+                        {
+                            let path = path.with_extension(SUPPORTED_PREFAB_EXTENSIONS[0]);
+                            let result = path
+                                .strip_prefix(asset_manager.get_asset_dir())
+                                .map_err(anyhow::Error::from)
+                                .and_then(|path| {
+                                    Outliner::create_prefab(&mut world, &registry, &asset_manager, entity, path)
+                                });
+
+                            if let Err(err) = result {
+                                log::error!("Failed to create prefab: {}", err);
+                            }
+                        }
+                    }
+                });
+
                 if ui.is_window_focused() && ui.is_key_down(Key::Delete) {
                     if let Some(entity) = outliner.selected {
+                        match EntitySnapshot::capture_recursive(&mut world, &registry, entity) {
+                            Ok(snapshots) => history.push(EditCommand::Despawn(snapshots)),
+                            Err(err) => log::error!("Failed to record removed entity: {}", err),
+                        }
                         outliner.remove_entity(&mut world, entity).unwrap();
                         outliner.selected = None;
                     }
                 }
 
+                if ui.is_window_focused() && ui.io().key_ctrl && ui.is_key_pressed_no_repeat(Key::D) {
+                    if let Some(entity) = outliner.selected {
+                        let duplicate = outliner.duplicate_entity(&mut world, entity, &registry).unwrap();
+                        Outliner::record_spawn(&mut world, &registry, history, duplicate);
+                    }
+                }
+
                 let ordered_entities;
                 {
                     hikari::dev::profile_scope!("Outliner Entity sorting");
@@ -132,27 +330,35 @@ impl EditorWindow for Outliner {
                 }
 
                 let mut selected = None;
+                let mut action = None;
                 for entity in ordered_entities {
-                    let mut entity_info = world.get_component::<&mut EntityId>(entity).unwrap();
-
-                    let entity_id = ui.new_id(entity.id() as usize);
-                    let _id = ui.push_id_int(entity.id() as i32);
-
-                    RenameInput::new(entity_id, &mut entity_info.name).build(
+                    draw_entity(
                         ui,
+                        &world,
+                        entity,
+                        outliner,
                         rename_state,
-                        |current| {
-                            let clicked = ui
-                                .selectable_config(&current)
-                                .selected(outliner.selected() == Some(entity))
-                                .build();
-
-                            if clicked {
-                                selected = Some(entity);
-                            }
-                        },
+                        history,
+                        &mut selected,
+                        &mut action,
                     );
                 }
+
+                // Dropping an entity on the empty space below the tree makes it a root entity
+                let available = ui.content_region_avail();
+                ui.dummy([available[0], available[1].max(20.0)]);
+                if let Some(target) = ui.drag_drop_target() {
+                    if let Some(Ok(payload)) = target
+                        .accept_payload::<Entity, _>(OUTLINER_ENTITY_PAYLOAD, DragDropFlags::empty())
+                    {
+                        action = Some(HierarchyAction::Detach(payload.data));
+                    }
+                    target.pop();
+                }
+
+                if let Some(command) = action.and_then(|action| Outliner::apply_hierarchy_action(&mut world, action)) {
+                    history.push(command);
+                }
                 if let Some(selected) = selected {
                     outliner.set_selected(selected, &mut world);
                 }
@@ -161,3 +367,83 @@ impl EditorWindow for Outliner {
         Ok(())
     }
 }
+
+#[allow(clippy::too_many_arguments)]
+fn draw_entity(
+    ui: &Ui,
+    world: &World,
+    entity: Entity,
+    outliner: &Outliner,
+    rename_state: &mut RenameState,
+    history: &mut History,
+    selected: &mut Option<Entity>,
+    action: &mut Option<HierarchyAction>,
+) {
+    let children = world.children(entity);
+    let Ok(mut entity_info) = world.get_component::<&mut EntityId>(entity) else {
+        return;
+    };
+
+    let entity_id = ui.new_id(entity.id() as usize);
+    let _id = ui.push_id_int(entity.id() as i32);
+
+    let is_prefab_instance = world.has_component::<PrefabInstance>(entity);
+
+    let mut node = None;
+    let old_name = RenameInput::new(entity_id, &mut entity_info.name).build(ui, rename_state, |current| {
+        let mut flags = TreeNodeFlags::OPEN_ON_ARROW | TreeNodeFlags::SPAN_AVAIL_WIDTH;
+        if children.is_empty() {
+            flags |= TreeNodeFlags::LEAF;
+        }
+        if outliner.selected() == Some(entity) {
+            flags |= TreeNodeFlags::SELECTED;
+        }
+
+        let color_token = is_prefab_instance.then(|| ui.push_style_color(StyleColor::Text, PREFAB_INSTANCE_COLOR));
+        node = ui
+            .tree_node_config(&format!("{}###entity", current))
+            .flags(flags)
+            .push();
+        drop(color_token);
+
+        if ui.is_item_clicked() && !ui.is_item_toggled_open() {
+            *selected = Some(entity);
+        }
+
+        if let Some(_tooltip) = ui
+            .drag_drop_source_config(OUTLINER_ENTITY_PAYLOAD)
+            .begin_payload(entity)
+        {
+            ui.text(current);
+        }
+
+        if let Some(target) = ui.drag_drop_target() {
+            if let Some(Ok(payload)) =
+                target.accept_payload::<Entity, _>(OUTLINER_ENTITY_PAYLOAD, DragDropFlags::empty())
+            {
+                *action = Some(HierarchyAction::Attach {
+                    child: payload.data,
+                    parent: entity,
+                });
+            }
+            target.pop();
+        }
+    });
+
+    if let Some(before) = old_name.filter(|before| *before != entity_info.name) {
+        history.push(EditCommand::Rename {
+            entity,
+            before,
+            after: entity_info.name.clone(),
+        });
+    }
+
+    // Children may share the same archetype, so the borrow must be released before drawing them
+    drop(entity_info);
+
+    if let Some(_node) = node {
+        for child in children {
+            draw_entity(ui, world, child, outliner, rename_state, history, selected, action);
+        }
+    }
+}
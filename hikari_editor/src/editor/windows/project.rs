@@ -2,7 +2,7 @@ use std::path::Path;
 
 use hikari::{
     asset::{AssetManager, Handle, LoadStatus},
-    core::{Registry, World},
+    core::{load_save, Registry, World},
     g3d::Camera
 };
 use hikari_editor::{project::Project};
@@ -91,6 +91,8 @@ pub struct ProjectManager {
     current_world: Option<Handle<World>>,
     current_world_ix: Option<usize>,
     new_world_scratch: Option<usize>,
+    /// World whose context menu is open
+    context_world: Option<usize>,
     world_creator: WorldCreator,
 }
 
@@ -103,6 +105,7 @@ impl ProjectManager {
         let asset_manager = state.get::<AssetManager>().unwrap();
 
         asset_manager.set_asset_dir(proj_dir)?;
+        asset_manager.set_hot_reload(true)?;
         std::env::set_current_dir(proj_dir)?;
 
         match Project::open(&file) {
@@ -296,8 +299,42 @@ impl EditorWindow for ProjectManager {
                                 project_manager.new_world_scratch = Some(ix);
                                 ui.open_popup("Open World");
                             }
+                            if ui.is_item_clicked_with_button(MouseButton::Right) {
+                                project_manager.context_world = Some(ix);
+                                ui.open_popup("World Options");
+                            }
                         }
 
+                        ui.popup("World Options", || {
+                            let Some(ix) = project_manager.context_world else { return };
+                            let world_path = project.worlds()[ix].clone();
+
+                            let is_binary = world_path
+                                .extension()
+                                .and_then(|ext| ext.to_str())
+                                .map_or(false, |ext| load_save::SUPPORTED_BINARY_WORLD_EXTENSIONS.contains(&ext));
+                            let (label, extension) = if is_binary {
+                                ("Convert to YAML", load_save::SUPPORTED_WORLD_EXTENSIONS[0])
+                            } else {
+                                ("Convert to Binary", load_save::SUPPORTED_BINARY_WORLD_EXTENSIONS[0])
+                            };
+
+                            // The open world stays bound to its file and may have unsaved changes, so it can't be converted
+                            let is_open = current_world_ix == Some(ix);
+                            if ui.menu_item_config(label).enabled(!is_open).build() {
+                                let converted = world_path.with_extension(extension);
+                                let registry = state.get::<Registry>().unwrap();
+
+                                match load_save::convert_world(&world_path, &converted, &registry) {
+                                    Ok(()) => {
+                                        log::info!("Converted {:?} to {:?}", world_path, converted);
+                                        project.set_world_path(ix, converted);
+                                    }
+                                    Err(err) => log::error!("Failed to convert world: {}", err),
+                                }
+                            }
+                        });
+
                         ui.modal_popup_config("Open World")
                         .resizable(false)
                         .save_settings(false)
@@ -325,6 +362,7 @@ impl EditorWindow for ProjectManager {
                 project_manager.set_world(new_world, state)?;
                 let mut world = state.get_mut::<World>().unwrap();
                 editor.outliner.on_world_loaded(&mut world);
+                editor.history.clear();
             }
         }
         Ok(())
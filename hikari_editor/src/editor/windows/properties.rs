@@ -1,13 +1,18 @@
-use std::any::TypeId;
+use std::{any::TypeId, collections::HashMap};
 
 use crate::{
     components::{ComponentDispatch, EditorComponents},
-    editor::meta::{EditorOnly, EditorOutlinerInfo},
+    editor::{
+        history::{record_prefab_overrides, snapshot_component, EditCommand, History},
+        meta::{EditorOnly, EditorOutlinerInfo},
+    },
     imgui,
 };
+use hikari::asset::AssetManager;
 use hikari::core::*;
 use hikari_editor::*;
-use imgui::{ImguiUiExt, TreeNodeFlags};
+use imgui::{ImguiUiExt, ItemHoveredFlags, TreeNodeFlags};
+use serde_yaml::Value;
 
 use super::{Editor, EditorWindow};
 
@@ -16,14 +21,33 @@ pub struct Properties {
     pub position_locked: bool,
     pub scale_locked: bool,
     pub rotation_locked: bool,
+    /// Components of `edited_entity` the user is interacting with, along with their state before the last recorded edit.
+    /// Components are only serialized during an interaction instead of every frame
+    edits: HashMap<TypeId, Option<Value>>,
+    edited_entity: Option<Entity>,
 }
 
+/// Whether any popup is open, edits made through popups such as combo boxes happen outside of the component's widgets
+fn is_any_popup_open() -> bool {
+    unsafe {
+        imgui::sys::igIsPopupOpen_Str(
+            std::ptr::null(),
+            (imgui::sys::ImGuiPopupFlags_AnyPopupId | imgui::sys::ImGuiPopupFlags_AnyPopupLevel)
+                as i32,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn component_selection(
     ui: &imgui::Ui,
     entity: Entity,
     world: &mut World,
     components: &EditorComponents,
     filtered_types: &[TypeId],
+    registry: &Registry,
+    asset_manager: &AssetManager,
+    history: &mut History,
 ) {
     ui.popup("ComponentSelection", || {
         let mut sorted_components: Vec<_> = components
@@ -42,6 +66,13 @@ fn component_selection(
         for component in sorted_components {
             if ui.selectable(component.name()) {
                 component.add_component(entity, world).unwrap();
+                history.push(EditCommand::Component {
+                    entity,
+                    type_id: component.type_id(),
+                    before: None,
+                    after: snapshot_component(world, registry, entity, component.type_id()),
+                });
+                record_prefab_overrides(world, registry, asset_manager, entity);
             }
         }
     });
@@ -57,6 +88,7 @@ fn component_selection(
         ui.calc_text_size("Add Component")[0],
     );
 }
+#[allow(clippy::too_many_arguments)]
 fn draw_component(
     ui: &imgui::Ui,
     entity: Entity,
@@ -64,6 +96,8 @@ fn draw_component(
     world: &mut World,
     editor: &mut Editor,
     state: EngineState,
+    registry: &Registry,
+    edits: &mut HashMap<TypeId, Option<Value>>,
 ) -> anyhow::Result<()> {
     // let token = ui
     //                         .tree_node_config(component.name())
@@ -85,19 +119,120 @@ fn draw_component(
     // }
 
     let mut open = true;
+    let type_id = component.type_id();
 
     if ui.collapsing_header_with_close_button(
         component.name(),
         TreeNodeFlags::DEFAULT_OPEN,
         &mut open,
     ) {
-        component.draw_component(ui, entity, world, editor, state)?;
+        ui.group(|| component.draw_component(ui, entity, world, editor, state))?;
+
+        let engaged = ui.is_item_active()
+            || ui.is_item_hovered_with_flags(
+                ItemHoveredFlags::ALLOW_WHEN_BLOCKED_BY_POPUP
+                    | ItemHoveredFlags::ALLOW_WHEN_BLOCKED_BY_ACTIVE_ITEM,
+            );
+
+        match edits.get_mut(&type_id) {
+            Some(before) => {
+                let after = snapshot_component(world, registry, entity, type_id);
+                if after != *before {
+                    editor.history.push(EditCommand::Component {
+                        entity,
+                        type_id,
+                        before: std::mem::replace(before, after.clone()),
+                        after,
+                    });
+
+                    let asset_manager = state.get::<AssetManager>().unwrap();
+                    record_prefab_overrides(world, registry, &asset_manager, entity);
+                }
+
+                let interacting = ui.is_any_item_active()
+                    || ui.is_mouse_down(imgui::MouseButton::Left)
+                    || is_any_popup_open();
+                if !engaged && !interacting {
+                    edits.remove(&type_id);
+                }
+            }
+            // The interaction starts when the pointer reaches the component, before any of its values can change
+            None if engaged => {
+                edits.insert(type_id, snapshot_component(world, registry, entity, type_id));
+            }
+            None => {}
+        }
     }
 
     if !open {
+        let before = snapshot_component(world, registry, entity, type_id);
         component.remove_component(entity, world).unwrap();
+        editor.history.push(EditCommand::Component {
+            entity,
+            type_id,
+            before,
+            after: None,
+        });
+
+        let asset_manager = state.get::<AssetManager>().unwrap();
+        record_prefab_overrides(world, registry, &asset_manager, entity);
+    }
+
+    Ok(())
+}
+/// Shows which prefab `entity` was created from and lets the instance be applied to or reset to the prefab
+fn draw_prefab_instance(
+    ui: &imgui::Ui,
+    entity: Entity,
+    world: &mut World,
+    registry: &Registry,
+    asset_manager: &AssetManager,
+) -> anyhow::Result<()> {
+    let Ok(instance) = world.get_component::<&PrefabInstance>(entity) else {
+        return Ok(());
+    };
+    let handle = instance.prefab().clone();
+    let override_count = instance.overrides().len();
+    drop(instance);
+
+    let path = asset_manager
+        .asset_db()
+        .read()
+        .handle_to_path(&handle.clone_erased_as_weak())
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "<unsaved>".into());
+
+    ui.text(format!("Prefab: {}", path));
+    ui.text(format!("Overridden Components: {}", override_count));
+
+    if ui.button("Apply to Prefab") {
+        // Changes are applied from the outermost entity belonging to the same instance
+        let mut root = entity;
+        while let Some(parent) = world.parent(root) {
+            let same_prefab = world
+                .get_component::<&PrefabInstance>(parent)
+                .map_or(false, |instance| *instance.prefab() == handle);
+            if !same_prefab {
+                break;
+            }
+            root = parent;
+        }
+
+        {
+            let mut prefabs = asset_manager.write_assets::<Prefab>().unwrap();
+            let prefab = prefabs
+                .get_mut(&handle)
+                .ok_or_else(|| anyhow::anyhow!("Prefab isn't loaded"))?;
+            prefab.apply_instance(world, registry, root)?;
+        }
+        asset_manager.save(&handle)?;
+    }
+    ui.same_line();
+    if ui.button("Revert Overrides") {
+        PrefabInstance::revert(world, entity)?;
     }
 
+    ui.separator();
     Ok(())
 }
 impl EditorWindow for Properties {
@@ -110,14 +245,23 @@ impl EditorWindow for Properties {
             .resizable(true)
             .build(|| -> anyhow::Result<()> {
                 let components = state.get::<EditorComponents>().unwrap();
-                let outliner = &editor.outliner;
+                let registry = state.get::<Registry>().unwrap();
 
-                if let Some(entity) = outliner.selected() {
+                if let Some(entity) = editor.outliner.selected() {
                     let mut world = state.get_mut::<World>().unwrap();
 
+                    if editor.properties.edited_entity != Some(entity) {
+                        editor.properties.edits.clear();
+                        editor.properties.edited_entity = Some(entity);
+                    }
+
                     {
                         hikari::dev::profile_scope!("Draw Components");
                         let _id = ui.push_id_int(entity.id() as i32);
+
+                        let asset_manager = state.get::<AssetManager>().unwrap();
+                        draw_prefab_instance(ui, entity, &mut world, &registry, &asset_manager)?;
+
                         let entity_ref = world.entity(entity).unwrap();
 
                         let entity_ty_ids = entity_ref
@@ -133,11 +277,32 @@ impl EditorWindow for Properties {
                         }
                         entity_components.sort_by_key(|component| component.sort_key());
 
-                        for component in entity_components {
-                            draw_component(ui, entity, component, &mut world, editor, state)?;
-                        }
+                        let mut edits = std::mem::take(&mut editor.properties.edits);
+                        let result = entity_components.into_iter().try_for_each(|component| {
+                            draw_component(
+                                ui,
+                                entity,
+                                component,
+                                &mut world,
+                                editor,
+                                state,
+                                &registry,
+                                &mut edits,
+                            )
+                        });
+                        editor.properties.edits = edits;
+                        result?;
                     }
-                    component_selection(ui, entity, &mut world, &components, &filtered_types);
+                    component_selection(
+                        ui,
+                        entity,
+                        &mut world,
+                        &components,
+                        &filtered_types,
+                        &registry,
+                        &state.get::<AssetManager>().unwrap(),
+                        &mut editor.history,
+                    );
                 }
                 Ok(())
             });
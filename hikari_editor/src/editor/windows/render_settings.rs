@@ -103,6 +103,33 @@ impl EditorWindow for RenderSettings {
                             4 => ShadowResolution::D4096,
                             _ => unreachable!(),
                         };
+
+                        let mut current_res = (settings.local_shadow_map_resolution as usize).min(3);
+                        ui.combo(
+                            "Point/Spot Shadow Map Resolution",
+                            &mut current_res,
+                            &[
+                                ShadowResolution::D256,
+                                ShadowResolution::D512,
+                                ShadowResolution::D1024,
+                                ShadowResolution::D2048,
+                            ],
+                            |kind| match kind {
+                                ShadowResolution::D256 => std::borrow::Cow::Borrowed("256"),
+                                ShadowResolution::D512 => std::borrow::Cow::Borrowed("512"),
+                                ShadowResolution::D1024 => std::borrow::Cow::Borrowed("1024"),
+                                ShadowResolution::D2048 => std::borrow::Cow::Borrowed("2048"),
+                                ShadowResolution::D4096 => std::borrow::Cow::Borrowed("4096"),
+                            },
+                        );
+
+                        settings.local_shadow_map_resolution = match current_res {
+                            0 => ShadowResolution::D256,
+                            1 => ShadowResolution::D512,
+                            2 => ShadowResolution::D1024,
+                            3 => ShadowResolution::D2048,
+                            _ => unreachable!(),
+                        };
                         ui.separator();
 
                         let mut current_view = settings.debug.view as usize;
@@ -113,11 +140,13 @@ impl EditorWindow for RenderSettings {
                                 DebugView::None,
                                 DebugView::Unlit,
                                 DebugView::Wireframe,
+                                DebugView::LightClusters,
                             ],
                             |kind| match kind {
                                 DebugView::None => std::borrow::Cow::Borrowed("None"),
                                 DebugView::Unlit => std::borrow::Cow::Borrowed("Unlit"),
                                 DebugView::Wireframe => std::borrow::Cow::Borrowed("Wireframe"),
+                                DebugView::LightClusters => std::borrow::Cow::Borrowed("Light Clusters"),
                             },
                         );
 
@@ -125,6 +154,7 @@ impl EditorWindow for RenderSettings {
                             0 => DebugView::None,
                             1 => DebugView::Unlit,
                             2 => DebugView::Wireframe,
+                            3 => DebugView::LightClusters,
                             _ => unreachable!(),
                         };
 
@@ -2,7 +2,8 @@
 use crate::imgui;
 use crate::imgui::gizmo::*;
 use hikari::asset::AssetManager;
-use hikari::core::{Time};
+use hikari::core::{Registry, Time};
+use hikari::input::Actions;
 use hikari::g3d::{Light, LightKind};
 use hikari::math::*;
 use hikari::{
@@ -15,6 +16,7 @@ use hikari::{
 use hikari_editor::*;
 
 use crate::editor::camera::ViewportCamera;
+use crate::editor::history::{record_prefab_overrides, snapshot_component, EditCommand};
 use crate::editor::{icons, Editor, EditorWindow};
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -250,8 +252,11 @@ impl EditorWindow for Viewport {
         let mut world = state.get_mut::<World>().unwrap();
         let shader_lib = state.get_mut::<ShaderLibrary>().unwrap();
         let asset_manager = state.get::<AssetManager>().unwrap();
+        let registry = state.get::<Registry>().unwrap();
         
-        let dt = state.get::<Time>().unwrap().dt();
+        // The editor camera keeps moving while the game is paused or slowed down
+        let dt = state.get::<Time>().unwrap().unscaled_dt();
+        let actions = state.get::<Actions>().unwrap();
         ui.window("Viewport")
             .size([950.0, 200.0], imgui::Condition::FirstUseEver)
             .resizable(true)
@@ -260,6 +265,7 @@ impl EditorWindow for Viewport {
                 
                 let viewport = &mut editor.viewport;
                 let outliner = &mut editor.outliner;
+                let history = &mut editor.history;
 
                 let window_size_float = ui.content_region_avail();
 
@@ -288,11 +294,7 @@ impl EditorWindow for Viewport {
                     let mut query = world.query_one::<(&mut Transform, &mut ViewportCamera)>(editor_camera).unwrap();
                     let (transform, viewport_camera) = query.get().unwrap();
 
-                    viewport_camera.manipulate(
-                        ui,
-                        transform,
-                        dt,
-                    );
+                    viewport_camera.manipulate(&actions, transform, dt);
 
                     ui.get_window_draw_list()
                         .add_rect(viewport_min, viewport_max, imgui::ImColor32::WHITE)
@@ -321,12 +323,19 @@ impl EditorWindow for Viewport {
                 }
 
                 if let Some(entity) = outliner.selected() {
+                    // The gizmo works in world space, so the local transform of child entities needs to be converted
+                    let parent_matrix = world
+                        .parent(entity)
+                        .and_then(|parent| world.world_matrix(parent).ok())
+                        .unwrap_or(Mat4::IDENTITY);
+
+                    let mut new_transform = None;
                     if let Ok(mut query) =
-                        world.query_one::<(&Camera, &mut Transform)>(editor_camera)
+                        world.query_one::<(&Camera, &Transform)>(editor_camera)
                     {
                         let (camera, cam_transform) = query.get().unwrap();
 
-                        if let Ok(mut transform) = world.get_component::<&mut Transform>(entity) {
+                        if let Ok(transform) = world.get_component::<&Transform>(entity) {
                             let projection =
                                 camera.get_projection_matrix(window_size.0, window_size.1);
                             let view = cam_transform.get_matrix().inverse();
@@ -340,13 +349,35 @@ impl EditorWindow for Viewport {
                                     .operation(operation)
                                     .mode(viewport.gizmo_state.mode)
                                     .viewport(viewport_min, viewport_max)
-                                    .manipulate(*transform, projection, view)
+                                    .manipulate(
+                                        Transform::from_matrix(parent_matrix * transform.get_matrix()),
+                                        projection,
+                                        view,
+                                    )
                                 {
-                                    *transform = changed_transform;
+                                    new_transform = Some(Transform::from_matrix(
+                                        parent_matrix.inverse() * changed_transform.get_matrix(),
+                                    ));
                                 }
                             }
                         }
                     }
+
+                    // Every frame of a drag is merged into a single history entry
+                    if let Some(new_transform) = new_transform {
+                        let type_id = std::any::TypeId::of::<Transform>();
+                        let before = snapshot_component(&world, &registry, entity, type_id);
+                        *world.get_component::<&mut Transform>(entity).unwrap() = new_transform;
+                        let after = snapshot_component(&world, &registry, entity, type_id);
+
+                        history.push(EditCommand::Component {
+                            entity,
+                            type_id,
+                            before,
+                            after,
+                        });
+                        record_prefab_overrides(&world, &registry, &asset_manager, entity);
+                    }
                 }
             });
         Ok(())
@@ -66,6 +66,11 @@ impl Project {
         remove_ix.map(|ix| self.worlds.remove(ix));
     }
 
+    /// Points the world at `ix` to a different file, used after converting it to another format
+    pub fn set_world_path(&mut self, ix: usize, path: impl AsRef<Path>) {
+        self.worlds[ix] = path.as_ref().to_owned();
+    }
+
     pub fn worlds(&self) -> &[PathBuf] {
         &self.worlds
     }
@@ -64,6 +64,7 @@ impl<'r, 'de> Visitor<'de> for SceneVisitor<'r> {
                         registry: self.registry,
                         world: &mut world,
                     })?;
+                    world.resolve_entity_links(self.registry);
 
                     world_fd = Some(world);
                 }
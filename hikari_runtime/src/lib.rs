@@ -1,7 +1,5 @@
 use std::path::Path;
 use std::path::PathBuf;
-use std::time::Duration;
-use std::time::Instant;
 
 use hikari::asset::*;
 use hikari::core::*;
@@ -12,12 +10,19 @@ use hikari::g3d::*;
 use hikari::render::*;
 use hikari::pbr::*;
 use hikari::core::winit::dpi::*;
+use serde::{Deserialize, Serialize};
 
 pub mod registry;
 pub mod settings;
+pub mod world_manager;
 
 pub use settings::*;
+pub use world_manager::*;
 
+pub const GAME_DESCRIPTION_FILE: &str = "game.yaml";
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameDescription {
     pub name: String,
     pub asset_dir: Option<PathBuf>,
@@ -43,31 +48,34 @@ impl Default for GameDescription {
         }
     }
 }
+impl GameDescription {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(serde_yaml::from_reader(std::fs::File::open(path)?)?)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(serde_yaml::to_writer(file, self)?)
+    }
+}
+
+/// States of the [`DefaultRuntime`]. The game is [`RuntimeState::Loading`] until the [`WorldManager`] has loaded every requested world.
+/// Worlds requested while [`RuntimeState::Playing`] are loaded in the background instead,
+/// a game can transition to [`RuntimeState::Loading`] itself to show a loading screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RuntimeState {
+    Loading,
+    Playing,
+}
 
 pub struct DefaultRuntime {
     game: Game,
 }
 
 impl DefaultRuntime {
-    fn load_world(world_path: &Path, asset_manager: &AssetManager) -> anyhow::Result<World> {
-        log::debug!("Load world");
-        let starting_world = asset_manager.load::<World>(world_path, None, false)?;
-
-        println!("Handle index {:?}", starting_world.index());
-        let status = asset_manager.wait_for_load(&starting_world);
-
-        println!("{:?}", status);
-        if status != LoadStatus::Loaded {
-            return Err(anyhow::anyhow!("Failed to load starting world"));
-        }
-        let mut world_pool = asset_manager.write_assets::<World>().unwrap();
-
-        assert!(starting_world.strong_count() == 1);
-        // assert!(world_pool.get(&starting_world).is_some());
-        let new_world = world_pool.take(&starting_world).unwrap();
-        println!("Got world");
-        Ok(new_world)
-    }
     pub fn new(desc: GameDescription) -> anyhow::Result<Self> {   
         let (width, height) = desc.initial_window_size;
 
@@ -107,27 +115,40 @@ impl DefaultRuntime {
             std::env::set_current_dir(asset_dir)?;
         }
 
+        let mut world_manager = WorldManager::new(desc.worlds.clone());
+        if let Some(starting_world_ix) = desc.starting_world_ix {
+            world_manager.request(starting_world_ix, WorldLoadMode::Replace)?;
+        }
+        game.add_state(world_manager);
         game.add_state(desc);
-        game.add_state(Instant::now());
-        game.create_init_stage("Load Dem Assets");
-        game.add_init_task("Load Dem Assets", 
-        Task::new("Load first world",
-        |game_world: &mut World, desc: &GameDescription, asset_manager: &AssetManager| {
-            if let Some(starting_world_ix) = desc.starting_world_ix {
-                let world_path = &desc.worlds[starting_world_ix];
-                *game_world = Self::load_world(world_path, asset_manager)?;
-            }
-
-            Ok(())
-        }));
 
-        game.add_task(hikari::core::UPDATE, Task::new("Switch world", |game_world: &mut World, now: &Instant, asset_manager: &AssetManager| {
-            //let world_path = &desc.worlds[starting_world_ix];
-            if now.elapsed() < Duration::new(5, 0) {
-                return;
-            }
-            *game_world = Self::load_world(Path::new("Sponza2.hworld"), asset_manager).expect("Failed to load world");
-        }));
+        game.add_task(
+            FIRST,
+            Task::new(
+                "Update World Manager",
+                |world_manager: &mut WorldManager,
+                 world: &mut World,
+                 registry: &Registry,
+                 asset_manager: &AssetManager| {
+                    world_manager.update(world, registry, asset_manager);
+                },
+            ),
+        );
+
+        game.add_states(RuntimeState::Loading);
+        game.add_task_in_state(
+            FIRST,
+            RuntimeState::Loading,
+            Task::new(
+                "Finish Loading",
+                |world_manager: &WorldManager, states: &mut States<RuntimeState>| {
+                    if !world_manager.is_loading() {
+                        states.set(RuntimeState::Playing);
+                    }
+                },
+            )
+            .after("Update World Manager"),
+        );
 
         game.add_platform_event_hook(|_, _, event, control_flow| {
             match event {   
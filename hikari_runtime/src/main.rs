@@ -0,0 +1,15 @@
+use hikari_runtime::*;
+
+/// Launches a game packaged by `hikari build`, the game description is expected next to the executable
+fn main() -> anyhow::Result<()> {
+    let exe_dir = hikari::utils::engine_dir();
+
+    let mut desc = GameDescription::open(exe_dir.join(GAME_DESCRIPTION_FILE))?;
+    if let Some(asset_dir) = &mut desc.asset_dir {
+        if asset_dir.is_relative() {
+            *asset_dir = exe_dir.join(&*asset_dir);
+        }
+    }
+
+    DefaultRuntime::new(desc)?.run()
+}
@@ -11,9 +11,12 @@ pub fn default_registry() -> Registry {
 
     register_serde_and_clone::<hikari::math::Transform>(&mut registry);
     register_serde_and_clone::<hikari::g3d::Camera>(&mut registry);
-    register_serde_and_clone::<hikari::g3d::MeshRender>(&mut registry);
+    registry.register_serde_versioned::<hikari::g3d::MeshRender>(hikari::g3d::MeshRender::VERSION);
+    registry.register_migration::<hikari::g3d::MeshRender>(0, hikari::g3d::MeshRender::migrate_v0);
+    registry.register_clone::<hikari::g3d::MeshRender>();
     register_serde_and_clone::<hikari::g3d::Light>(&mut registry);
     register_serde_and_clone::<hikari::g3d::Environment>(&mut registry);
+    register_serde_and_clone::<hikari::g3d::Animator>(&mut registry);
 
     registry.build()
 }
\ No newline at end of file
@@ -1,9 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
 pub enum Quality {
     Low,
     Medium,
     High,
     Ultra,
 }
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct GraphicsSettings {
     pub vsync: bool,
     pub fxaa: bool,
@@ -53,11 +58,17 @@ impl GraphicsSettings {
             Quality::High => ShadowResolution::D2048,
             Quality::Ultra => ShadowResolution::D4096
         };
+        let local_shadow_map_resolution = match self.shadow_quality {
+            Quality::Low => ShadowResolution::D256,
+            Quality::Medium | Quality::High => ShadowResolution::D512,
+            Quality::Ultra => ShadowResolution::D1024
+        };
 
         hikari::pbr::Settings {
             vsync: self.vsync,
             fxaa: self.fxaa,
             directional_shadow_map_resolution,
+            local_shadow_map_resolution,
             ..Default::default()
         }
     }
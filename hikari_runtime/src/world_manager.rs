@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use hikari::asset::*;
+use hikari::core::*;
+
+/// How a requested world is combined with the active one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorldLoadMode {
+    /// The requested world replaces the active world
+    Replace,
+    /// The entities of the requested world are added to the active world
+    Additive,
+}
+
+/// Progress of the world being loaded, counting the world file and every asset it references
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl LoadProgress {
+    /// Fraction of assets which have finished loading, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+}
+
+struct PendingWorld {
+    path: PathBuf,
+    mode: WorldLoadMode,
+    handle: Handle<World>,
+    /// Known once the world file itself is loaded
+    references: Option<Vec<ErasedHandle>>,
+    progress: LoadProgress,
+}
+
+/// Loads worlds in the background while the active world keeps running.
+/// Requested worlds are loaded one after another along with the assets they reference,
+/// and are swapped in at the start of the frame after they finish loading
+pub struct WorldManager {
+    worlds: Vec<PathBuf>,
+    active: Option<PathBuf>,
+    requests: VecDeque<(PathBuf, WorldLoadMode)>,
+    pending: Option<PendingWorld>,
+}
+
+impl WorldManager {
+    /// `worlds` are the worlds of the game which can be requested by index, see [`crate::GameDescription::worlds`]
+    pub fn new(worlds: Vec<PathBuf>) -> Self {
+        Self {
+            worlds,
+            active: None,
+            requests: VecDeque::new(),
+            pending: None,
+        }
+    }
+    pub fn worlds(&self) -> &[PathBuf] {
+        &self.worlds
+    }
+    /// Path of the world which last replaced the active world
+    pub fn active(&self) -> Option<&Path> {
+        self.active.as_deref()
+    }
+    /// Requests the world at `ix` in [`WorldManager::worlds`]
+    pub fn request(&mut self, ix: usize, mode: WorldLoadMode) -> anyhow::Result<()> {
+        let path = self
+            .worlds
+            .get(ix)
+            .ok_or_else(|| anyhow::anyhow!("No world with index {}, the game has {} worlds", ix, self.worlds.len()))?
+            .clone();
+        self.request_path(path, mode);
+
+        Ok(())
+    }
+    /// Requests the world at `path`, relative to the asset directory
+    pub fn request_path(&mut self, path: impl Into<PathBuf>, mode: WorldLoadMode) {
+        self.requests.push_back((path.into(), mode));
+    }
+    /// Returns true while requested worlds haven't been swapped in yet
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some() || !self.requests.is_empty()
+    }
+    /// Progress of the world currently being loaded, for loading screens
+    pub fn progress(&self) -> Option<LoadProgress> {
+        self.pending.as_ref().map(|pending| pending.progress)
+    }
+    /// Path and mode of the world currently being loaded
+    pub fn loading(&self) -> Option<(&Path, WorldLoadMode)> {
+        self.pending.as_ref().map(|pending| (pending.path.as_path(), pending.mode))
+    }
+    pub(crate) fn update(&mut self, world: &mut World, registry: &Registry, asset_manager: &AssetManager) {
+        if self.pending.is_none() {
+            let Some((path, mode)) = self.requests.pop_front() else { return };
+
+            log::info!("Loading world {:?}", path);
+            match asset_manager.load::<World>(&path, None, false) {
+                Ok(handle) => {
+                    self.pending = Some(PendingWorld {
+                        path,
+                        mode,
+                        handle,
+                        references: None,
+                        progress: LoadProgress { loaded: 0, total: 1 },
+                    });
+                }
+                Err(err) => {
+                    log::error!("Failed to load world {:?}: {}", path, err);
+                    return;
+                }
+            }
+        }
+
+        let pending = self.pending.as_mut().unwrap();
+        match asset_manager.status(&pending.handle.clone_erased()) {
+            Some(LoadStatus::Loaded) => {}
+            Some(LoadStatus::Loading) => return,
+            _ => {
+                log::error!("Failed to load world {:?}", pending.path);
+                self.pending = None;
+                return;
+            }
+        }
+
+        let references = pending
+            .references
+            .get_or_insert_with(|| asset_manager.references(&pending.handle.clone_erased()));
+        // Assets which failed to load or have already been dropped won't make any more progress
+        let finished = references
+            .iter()
+            .filter(|reference| !matches!(asset_manager.status(reference), Some(LoadStatus::Loading)))
+            .count();
+        pending.progress = LoadProgress {
+            loaded: 1 + finished,
+            total: 1 + references.len(),
+        };
+
+        if finished < references.len() {
+            return;
+        }
+
+        let pending = self.pending.take().unwrap();
+        let mut worlds = asset_manager.write_assets::<World>().unwrap();
+        if worlds.get(&pending.handle).is_none() {
+            log::error!("World {:?} was unloaded before it could be swapped in", pending.path);
+            return;
+        }
+
+        match pending.mode {
+            WorldLoadMode::Replace => {
+                // The loaded world is moved out of the pool, unless another handle still refers to it
+                *world = match worlds.take(&pending.handle) {
+                    Some(loaded) => loaded,
+                    None => worlds.get(&pending.handle).unwrap().clone(registry),
+                };
+                log::info!("Switched to world {:?}", pending.path);
+                self.active = Some(pending.path);
+            }
+            WorldLoadMode::Additive => {
+                worlds.get(&pending.handle).unwrap().merge_into(registry, world);
+                log::info!("Merged world {:?}", pending.path);
+            }
+        }
+    }
+}